@@ -0,0 +1,334 @@
+//! Clipboard access for the extension's helper binaries. Both talk to the
+//! clipboard through [`ClipboardBackend`], so the system clipboard can be
+//! swapped for [`MockBackend`] in tests and other backends added later.
+
+use anyhow::{anyhow, Context, Result};
+use arboard::{Clipboard, ImageData};
+use std::borrow::Cow;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+mod native;
+pub use native::display_server;
+
+/// Returned by a getter when the clipboard holds nothing of that kind. Any
+/// other error is a real failure to read the clipboard.
+#[derive(Debug)]
+pub struct NoContent(pub &'static str);
+
+impl fmt::Display for NoContent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The clipboard contains no {}", self.0)
+    }
+}
+
+impl std::error::Error for NoContent {}
+
+/// Formats password managers add to mark a copy as secret: Windows'
+/// monitor exclusion, the nspasteboard.org concealed type, and KDE's hint.
+pub const CONCEALED_FORMATS: [&str; 3] = [
+    "ExcludeClipboardContentFromMonitorProcessing",
+    "org.nspasteboard.ConcealedType",
+    "x-kde-passwordManagerHint",
+];
+
+/// The concealment hint among `formats`, if any.
+pub fn concealment_hint(formats: &[String]) -> Option<String> {
+    formats.iter().find(|format| CONCEALED_FORMATS.contains(&format.as_str())).cloned()
+}
+
+/// The process that put the current content on the clipboard.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Owner {
+    pub pid: Option<u32>,
+    pub name: String,
+}
+
+/// An RGBA image, 4 bytes per pixel, row-major.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+}
+
+/// Reads and writes one clipboard. Getters fail with [`NoContent`] when the
+/// clipboard holds no content of the requested kind.
+pub trait ClipboardBackend: Send {
+    /// Short name reported in diagnostics, e.g. `arboard` or `mock`.
+    fn name(&self) -> &'static str;
+    fn get_text(&mut self) -> Result<String>;
+    fn get_image(&mut self) -> Result<Image>;
+    fn get_files(&mut self) -> Result<Vec<PathBuf>>;
+    fn set_text(&mut self, text: &str) -> Result<()>;
+    fn set_image(&mut self, image: &Image) -> Result<()>;
+    /// Offers `html` together with `alt_text`, for targets that only take
+    /// plain text, in one write.
+    fn set_html(&mut self, html: &str, alt_text: &str) -> Result<()>;
+    fn set_files(&mut self, files: &[PathBuf]) -> Result<()>;
+    fn clear(&mut self) -> Result<()>;
+    /// Names of the formats on offer, as the platform calls them: MIME types
+    /// and TARGETS on X11, format names on Windows, pasteboard types on macOS.
+    fn formats(&mut self) -> Result<Vec<String>>;
+    /// The process that owns the content, when the platform can tell.
+    fn owner(&mut self) -> Option<Owner>;
+    /// The hint marking the current content as secret, if its owner set one.
+    fn concealment(&mut self) -> Option<String> {
+        concealment_hint(&self.formats().ok()?)
+    }
+}
+
+/// The system clipboard through arboard.
+pub struct ArboardBackend {
+    clipboard: Clipboard,
+}
+
+impl ArboardBackend {
+    pub fn new() -> Result<Self> {
+        let clipboard = Clipboard::new().context("Failed to access system clipboard")?;
+        Ok(ArboardBackend { clipboard })
+    }
+}
+
+impl ClipboardBackend for ArboardBackend {
+    fn name(&self) -> &'static str {
+        "arboard"
+    }
+
+    fn get_text(&mut self) -> Result<String> {
+        self.clipboard.get_text().map_err(|e| unavailable(e, "text"))
+    }
+
+    fn get_image(&mut self) -> Result<Image> {
+        let image = self.clipboard.get_image().map_err(|e| unavailable(e, "image"))?;
+        Ok(Image { width: image.width, height: image.height, rgba: image.bytes.into_owned() })
+    }
+
+    fn get_files(&mut self) -> Result<Vec<PathBuf>> {
+        self.clipboard.get().file_list().map_err(|e| unavailable(e, "files"))
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<()> {
+        Ok(self.clipboard.set_text(text)?)
+    }
+
+    fn set_image(&mut self, image: &Image) -> Result<()> {
+        Ok(self.clipboard.set_image(ImageData {
+            width: image.width,
+            height: image.height,
+            bytes: Cow::Borrowed(&image.rgba),
+        })?)
+    }
+
+    fn set_html(&mut self, html: &str, alt_text: &str) -> Result<()> {
+        Ok(self.clipboard.set_html(html, Some(alt_text))?)
+    }
+
+    fn set_files(&mut self, files: &[PathBuf]) -> Result<()> {
+        Ok(self.clipboard.set().file_list(files)?)
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        Ok(self.clipboard.clear()?)
+    }
+
+    fn formats(&mut self) -> Result<Vec<String>> {
+        native::formats()
+    }
+
+    fn owner(&mut self) -> Option<Owner> {
+        native::owner()
+    }
+
+    fn concealment(&mut self) -> Option<String> {
+        native::concealment()
+    }
+}
+
+fn unavailable(error: arboard::Error, kind: &'static str) -> anyhow::Error {
+    match error {
+        arboard::Error::ContentNotAvailable => NoContent(kind).into(),
+        other => other.into(),
+    }
+}
+
+/// What a [`MockBackend`] currently holds.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Content {
+    #[default]
+    Empty,
+    Text(String),
+    Image(Image),
+    Files(Vec<PathBuf>),
+    /// HTML with its plain-text alternative, which is what `get_text` reads.
+    Html { html: String, text: String },
+}
+
+/// An in-memory clipboard. Clones share their content, so a test can keep
+/// one handle while the code under test owns another.
+#[derive(Debug, Clone, Default)]
+pub struct MockBackend {
+    content: Arc<Mutex<Content>>,
+    /// Formats offered alongside the content's own, such as concealment hints.
+    extra_formats: Arc<Mutex<Vec<String>>>,
+    owner: Arc<Mutex<Option<Owner>>>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn content(&self) -> Content {
+        self.content.lock().unwrap().clone()
+    }
+
+    pub fn put(&self, content: Content) {
+        self.put_with_formats(content, &[]);
+    }
+
+    /// Replaces the content, offering `formats` besides its own.
+    pub fn put_with_formats(&self, content: Content, formats: &[&str]) {
+        *self.content.lock().unwrap() = content;
+        *self.extra_formats.lock().unwrap() = formats.iter().map(|f| f.to_string()).collect();
+    }
+
+    pub fn set_owner(&self, owner: Option<Owner>) {
+        *self.owner.lock().unwrap() = owner;
+    }
+}
+
+impl ClipboardBackend for MockBackend {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn get_text(&mut self) -> Result<String> {
+        match self.content() {
+            Content::Text(text) | Content::Html { text, .. } => Ok(text),
+            _ => Err(NoContent("text").into()),
+        }
+    }
+
+    fn get_image(&mut self) -> Result<Image> {
+        match self.content() {
+            Content::Image(image) => Ok(image),
+            _ => Err(NoContent("image").into()),
+        }
+    }
+
+    fn get_files(&mut self) -> Result<Vec<PathBuf>> {
+        match self.content() {
+            Content::Files(files) => Ok(files),
+            _ => Err(NoContent("files").into()),
+        }
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<()> {
+        self.put(Content::Text(text.to_string()));
+        Ok(())
+    }
+
+    fn set_image(&mut self, image: &Image) -> Result<()> {
+        self.put(Content::Image(image.clone()));
+        Ok(())
+    }
+
+    fn set_html(&mut self, html: &str, alt_text: &str) -> Result<()> {
+        self.put(Content::Html { html: html.to_string(), text: alt_text.to_string() });
+        Ok(())
+    }
+
+    fn set_files(&mut self, files: &[PathBuf]) -> Result<()> {
+        self.put(Content::Files(files.to_vec()));
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.put(Content::Empty);
+        Ok(())
+    }
+
+    fn formats(&mut self) -> Result<Vec<String>> {
+        let mut formats = match self.content() {
+            Content::Empty => vec![],
+            Content::Text(_) => vec!["text/plain".to_string()],
+            Content::Image(_) => vec!["image/png".to_string()],
+            Content::Files(_) => vec!["text/uri-list".to_string()],
+            Content::Html { .. } => vec!["text/html".to_string(), "text/plain".to_string()],
+        };
+        formats.extend(self.extra_formats.lock().unwrap().iter().cloned());
+        Ok(formats)
+    }
+
+    fn owner(&mut self) -> Option<Owner> {
+        self.owner.lock().unwrap().clone()
+    }
+}
+
+/// Validates that every path is an existing file, then puts the list on
+/// the clipboard. Shared by `clipboard-files` and the monitor's
+/// `copy_files` command.
+pub fn copy_files(clipboard: &mut dyn ClipboardBackend, file_paths: &[String]) -> Result<()> {
+    for path in file_paths {
+        let metadata = std::fs::metadata(path).with_context(|| format!("File not found or inaccessible: {}", path))?;
+        if !metadata.is_file() {
+            anyhow::bail!("Path is not a file: {}", path);
+        }
+    }
+
+    let paths: Vec<PathBuf> = file_paths.iter().map(PathBuf::from).collect();
+    clipboard.set_files(&paths).context("Failed to copy files to clipboard")
+}
+
+/// Opens the backend named on the command line: `arboard` (the default)
+/// or `mock`, which starts empty.
+pub fn open(name: &str) -> Result<Box<dyn ClipboardBackend>> {
+    match name {
+        "arboard" => Ok(Box::new(ArboardBackend::new()?)),
+        "mock" => Ok(Box::new(MockBackend::new())),
+        other => Err(anyhow!("Unknown clipboard backend: {} (expected arboard or mock)", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clones_share_content() {
+        let handle = MockBackend::new();
+        let mut backend: Box<dyn ClipboardBackend> = Box::new(handle.clone());
+        assert!(backend.get_text().unwrap_err().is::<NoContent>());
+
+        handle.put(Content::Text("copied".to_string()));
+        assert_eq!(backend.get_text().unwrap(), "copied");
+        assert!(backend.get_files().is_err());
+
+        backend.set_files(&[PathBuf::from("/tmp/a.txt")]).unwrap();
+        assert_eq!(handle.content(), Content::Files(vec![PathBuf::from("/tmp/a.txt")]));
+        assert_eq!(backend.formats().unwrap(), ["text/uri-list"]);
+        backend.clear().unwrap();
+        assert_eq!(handle.content(), Content::Empty);
+
+        handle.put_with_formats(Content::Text("hunter2".to_string()), &["org.nspasteboard.ConcealedType"]);
+        assert_eq!(backend.concealment().as_deref(), Some("org.nspasteboard.ConcealedType"));
+        handle.put(Content::Text("public".to_string()));
+        assert_eq!(backend.concealment(), None);
+    }
+
+    #[test]
+    fn test_copy_files_puts_the_list_on_the_clipboard() {
+        let file = std::env::temp_dir().join(format!("clipboard-core-files-{}.txt", std::process::id()));
+        std::fs::write(&file, "x").unwrap();
+        let clipboard = MockBackend::new();
+        let path = file.to_string_lossy().to_string();
+
+        let missing = format!("{}.missing", path);
+        copy_files(&mut clipboard.clone(), &[path]).unwrap();
+        assert_eq!(clipboard.content(), Content::Files(vec![file.clone()]));
+        assert!(copy_files(&mut clipboard.clone(), &[missing]).is_err());
+        let _ = std::fs::remove_file(file);
+    }
+}
@@ -0,0 +1,266 @@
+//! Platform clipboard details arboard does not expose: the raw formats on
+//! offer and the process that owns them.
+
+use std::env;
+
+/// The windowing system the clipboard belongs to: `windows`, `macos`,
+/// `wayland`, `x11`, or `none` when no display is reachable.
+pub fn display_server() -> &'static str {
+    if cfg!(windows) {
+        "windows"
+    } else if cfg!(target_os = "macos") {
+        "macos"
+    } else if env::var_os("WAYLAND_DISPLAY").is_some() {
+        "wayland"
+    } else if env::var_os("DISPLAY").is_some() {
+        "x11"
+    } else {
+        "none"
+    }
+}
+
+pub use platform::{concealment, formats, owner};
+
+/// X11, and Wayland sessions through XWayland.
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use anyhow::{bail, Context, Result};
+    use std::fs;
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use x11rb::connection::Connection;
+    use x11rb::protocol::res::{query_client_ids, ClientIdMask, ClientIdSpec};
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt as _, CreateWindowAux, WindowClass};
+    use x11rb::protocol::Event;
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::{COPY_DEPTH_FROM_PARENT, CURRENT_TIME, NONE};
+
+    use crate::Owner;
+
+    /// How long the selection owner gets to answer a TARGETS request.
+    const TIMEOUT: Duration = Duration::from_millis(500);
+
+    fn atom(conn: &RustConnection, name: &str) -> Result<u32> {
+        Ok(conn.intern_atom(false, name.as_bytes())?.reply()?.atom)
+    }
+
+    /// Asks the CLIPBOARD owner for its TARGETS.
+    pub fn formats() -> Result<Vec<String>> {
+        let (conn, screen) = x11rb::connect(None).context("Failed to connect to the X server")?;
+        let root = conn.setup().roots[screen].root;
+        let window = conn.generate_id()?;
+        conn.create_window(COPY_DEPTH_FROM_PARENT, window, root, 0, 0, 1, 1, 0, WindowClass::INPUT_OUTPUT, 0, &CreateWindowAux::new())?;
+        let clipboard = atom(&conn, "CLIPBOARD")?;
+        let targets = atom(&conn, "TARGETS")?;
+        let property = atom(&conn, "CLIPBOARD_MONITOR_TARGETS")?;
+        conn.convert_selection(window, clipboard, targets, property, CURRENT_TIME)?;
+        conn.flush()?;
+
+        let deadline = Instant::now() + TIMEOUT;
+        let stored = loop {
+            match conn.poll_for_event()? {
+                Some(Event::SelectionNotify(event)) if event.requestor == window => break event.property,
+                Some(_) => continue,
+                None if Instant::now() > deadline => bail!("The clipboard owner did not answer a TARGETS request"),
+                None => thread::sleep(Duration::from_millis(5)),
+            }
+        };
+        // Nobody owns the clipboard.
+        if stored == NONE {
+            return Ok(Vec::new());
+        }
+        let reply = conn.get_property(true, window, property, AtomEnum::ATOM, 0, 1024)?.reply()?;
+        let atoms: Vec<u32> = reply.value32().map(|atoms| atoms.collect()).unwrap_or_default();
+        atoms
+            .into_iter()
+            .map(|atom| Ok(String::from_utf8_lossy(&conn.get_atom_name(atom)?.reply()?.name).into_owned()))
+            .collect()
+    }
+
+    /// Finds the owner's pid through the X-Resource extension, which knows
+    /// it for every local client whether or not the window sets _NET_WM_PID.
+    pub fn owner() -> Option<Owner> {
+        let (conn, _) = x11rb::connect(None).ok()?;
+        let clipboard = atom(&conn, "CLIPBOARD").ok()?;
+        let window = conn.get_selection_owner(clipboard).ok()?.reply().ok()?.owner;
+        if window == NONE {
+            return None;
+        }
+        let spec = ClientIdSpec { client: window, mask: ClientIdMask::LOCAL_CLIENT_PID };
+        let reply = query_client_ids(&conn, &[spec]).ok()?.reply().ok()?;
+        let pid = reply.ids.iter().find_map(|id| id.value.first().copied())?;
+        let name = fs::read_to_string(format!("/proc/{}/comm", pid))
+            .map(|name| name.trim().to_string())
+            .unwrap_or_else(|_| pid.to_string());
+        Some(Owner { pid: Some(pid), name })
+    }
+
+    pub fn concealment() -> Option<String> {
+        crate::concealment_hint(&formats().ok()?)
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use anyhow::{bail, Result};
+    use std::path::Path;
+    use std::thread;
+    use std::time::Duration;
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::DataExchange::{
+        CloseClipboard, EnumClipboardFormats, GetClipboardData, GetClipboardFormatNameW, GetClipboardOwner,
+        OpenClipboard, RegisterClipboardFormatW,
+    };
+    use windows_sys::Win32::System::Memory::{GlobalLock, GlobalSize, GlobalUnlock};
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+
+    use crate::Owner;
+
+    const HISTORY_FORMAT: &str = "CanIncludeInClipboardHistory";
+
+    /// Predefined formats, which have no registered name.
+    fn standard_name(format: u32) -> Option<&'static str> {
+        Some(match format {
+            1 => "CF_TEXT",
+            2 => "CF_BITMAP",
+            7 => "CF_OEMTEXT",
+            8 => "CF_DIB",
+            13 => "CF_UNICODETEXT",
+            15 => "CF_HDROP",
+            16 => "CF_LOCALE",
+            17 => "CF_DIBV5",
+            _ => return None,
+        })
+    }
+
+    fn name_of(format: u32) -> String {
+        if let Some(name) = standard_name(format) {
+            return name.to_string();
+        }
+        let mut buffer = [0u16; 256];
+        let len = unsafe { GetClipboardFormatNameW(format, buffer.as_mut_ptr(), buffer.len() as i32) };
+        match len {
+            1.. => String::from_utf16_lossy(&buffer[..len as usize]),
+            _ => format!("#{}", format),
+        }
+    }
+
+    pub fn formats() -> Result<Vec<String>> {
+        // Another process may hold the clipboard open for a moment.
+        let mut attempts = 0;
+        while unsafe { OpenClipboard(std::ptr::null_mut()) } == 0 {
+            attempts += 1;
+            if attempts == 10 {
+                bail!("The clipboard is held open by another process");
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let mut names = Vec::new();
+        let mut format = 0;
+        loop {
+            format = unsafe { EnumClipboardFormats(format) };
+            if format == 0 {
+                break;
+            }
+            names.push(name_of(format));
+        }
+        unsafe { CloseClipboard() };
+        Ok(names)
+    }
+
+    pub fn owner() -> Option<Owner> {
+        let window = unsafe { GetClipboardOwner() };
+        if window.is_null() {
+            return None;
+        }
+        let mut pid = 0u32;
+        unsafe { GetWindowThreadProcessId(window, &mut pid) };
+        if pid == 0 {
+            return None;
+        }
+        let mut name = pid.to_string();
+        let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid) };
+        if !process.is_null() {
+            let mut buffer = [0u16; 1024];
+            let mut len = buffer.len() as u32;
+            if unsafe { QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, buffer.as_mut_ptr(), &mut len) } != 0 {
+                let path = String::from_utf16_lossy(&buffer[..len as usize]);
+                name = Path::new(&path).file_name().map_or(path.clone(), |n| n.to_string_lossy().into_owned());
+            }
+            unsafe { CloseHandle(process) };
+        }
+        Some(Owner { pid: Some(pid), name })
+    }
+
+    /// Besides the exclusion format, Windows lets the owner opt out of
+    /// history with a `CanIncludeInClipboardHistory` value of 0.
+    pub fn concealment() -> Option<String> {
+        let formats = formats().ok()?;
+        if let Some(hint) = crate::concealment_hint(&formats) {
+            return Some(hint);
+        }
+        if !formats.iter().any(|f| f == HISTORY_FORMAT) || unsafe { OpenClipboard(std::ptr::null_mut()) } == 0 {
+            return None;
+        }
+        let name: Vec<u16> = HISTORY_FORMAT.encode_utf16().chain(Some(0)).collect();
+        let mut excluded = false;
+        unsafe {
+            let format = RegisterClipboardFormatW(name.as_ptr());
+            let data = GetClipboardData(format);
+            if !data.is_null() && GlobalSize(data) >= 4 {
+                let value = GlobalLock(data) as *const u32;
+                if !value.is_null() {
+                    excluded = *value == 0;
+                    GlobalUnlock(data);
+                }
+            }
+            CloseClipboard();
+        }
+        excluded.then(|| HISTORY_FORMAT.to_string())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use anyhow::Result;
+    use objc2_app_kit::NSPasteboard;
+
+    use crate::Owner;
+
+    #[allow(unused_unsafe)]
+    pub fn formats() -> Result<Vec<String>> {
+        let types = unsafe { NSPasteboard::generalPasteboard().types() };
+        Ok(types.map(|types| types.iter().map(|t| t.to_string()).collect()).unwrap_or_default())
+    }
+
+    /// The pasteboard does not record which process wrote it.
+    pub fn owner() -> Option<Owner> {
+        None
+    }
+
+    pub fn concealment() -> Option<String> {
+        crate::concealment_hint(&formats().ok()?)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use anyhow::{bail, Result};
+
+    use crate::Owner;
+
+    pub fn formats() -> Result<Vec<String>> {
+        bail!("Listing clipboard formats is not supported on this platform")
+    }
+
+    pub fn owner() -> Option<Owner> {
+        None
+    }
+
+    pub fn concealment() -> Option<String> {
+        None
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::{Context, Result};
-use arboard::Clipboard;
+use clipboard_core::{copy_files, ArboardBackend};
 use std::env;
 use std::io::{self, Read};
 
@@ -29,28 +29,8 @@ fn main() -> Result<()> {
         request.files
     };
 
-    // Validate all files exist
-    for path in &file_paths {
-        let metadata = std::fs::metadata(path)
-            .with_context(|| format!("File not found or inaccessible: {}", path))?;
-
-        if !metadata.is_file() {
-            anyhow::bail!("Path is not a file: {}", path);
-        }
-    }
-
-    let mut clipboard = Clipboard::new()
-        .context("Failed to access system clipboard")?;
-
-    // Use arboard's cross-platform file_list method
-    // Convert String paths to Path references
-    let paths: Vec<&std::path::Path> = file_paths
-        .iter()
-        .map(|p| std::path::Path::new(p))
-        .collect();
-
-    clipboard.set().file_list(&paths)
-        .context("Failed to copy files to clipboard")?;
+    let mut clipboard = ArboardBackend::new()?;
+    copy_files(&mut clipboard, &file_paths)?;
 
     println!("✅ Successfully copied {} files to clipboard", file_paths.len());
     Ok(())
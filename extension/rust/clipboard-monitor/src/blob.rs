@@ -0,0 +1,116 @@
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+
+/// Shortest run taken for a base64 payload; anything shorter is more likely
+/// an identifier or a hash.
+const MIN_LEN: usize = 64;
+
+/// How much of a payload is decoded to tell what it holds.
+const SNIFF_CHARS: usize = 4096;
+
+/// Most payloads reported for one copy.
+const MAX_BLOBS: usize = 8;
+
+static BASE64_RUN: Lazy<Regex> = Lazy::new(|| Regex::new(&format!(r"[A-Za-z0-9+/_-]{{{},}}={{0,2}}", MIN_LEN)).unwrap());
+
+fn engine(url_safe: bool) -> GeneralPurpose {
+    let config = GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+    match url_safe {
+        true => GeneralPurpose::new(&alphabet::URL_SAFE, config),
+        false => GeneralPurpose::new(&alphabet::STANDARD, config),
+    }
+}
+
+/// A base64 payload found in copied text, by its byte range there.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Base64Blob {
+    pub offset: usize,
+    pub length: usize,
+    /// Size of the whole payload once decoded.
+    pub decoded_bytes: usize,
+    /// `png`, `jpeg`, `gif`, `pdf`, `zip`, `gzip`, `text`, or `binary`.
+    pub kind: &'static str,
+}
+
+/// Base64 payloads in `text` that decode cleanly. Only the start of each is
+/// decoded, so a multi-megabyte blob costs no more than a small one.
+pub fn find(text: &str) -> Vec<Base64Blob> {
+    BASE64_RUN
+        .find_iter(text)
+        .filter_map(|run| {
+            let body = run.as_str().trim_end_matches('=');
+            // Real payloads mix cases and digits; long words and hex do not.
+            let has = |test: fn(&char) -> bool| body.chars().any(|c| test(&c));
+            if !(has(char::is_ascii_uppercase) && has(char::is_ascii_lowercase) && has(char::is_ascii_digit)) {
+                return None;
+            }
+            let url_safe = body.contains(['-', '_']);
+            if url_safe && body.contains(['+', '/']) {
+                return None;
+            }
+            let sniffed = &body[..body.len().min(SNIFF_CHARS)];
+            let whole = body.len() <= SNIFF_CHARS;
+            // A prefix must end on a four-character boundary to decode.
+            let sniffed = if whole { sniffed } else { &sniffed[..sniffed.len() / 4 * 4] };
+            let decoded = engine(url_safe).decode(sniffed).ok()?;
+            Some(Base64Blob {
+                offset: run.start(),
+                length: run.len(),
+                decoded_bytes: body.len() * 3 / 4,
+                kind: sniff(&decoded, whole),
+            })
+        })
+        .take(MAX_BLOBS)
+        .collect()
+}
+
+/// What `bytes`, the start of a payload, is. A prefix may end inside a UTF-8
+/// character, which is not held against it.
+fn sniff(bytes: &[u8], whole: bool) -> &'static str {
+    const MAGIC: [(&[u8], &str); 6] = [
+        (b"\x89PNG\r\n\x1a\n", "png"),
+        (b"\xff\xd8\xff", "jpeg"),
+        (b"GIF8", "gif"),
+        (b"%PDF-", "pdf"),
+        (b"PK\x03\x04", "zip"),
+        (b"\x1f\x8b", "gzip"),
+    ];
+    if let Some((_, kind)) = MAGIC.iter().find(|(magic, _)| bytes.starts_with(magic)) {
+        return kind;
+    }
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) if !whole && e.error_len().is_none() => std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap(),
+        Err(_) => return "binary",
+    };
+    match text.chars().all(|c| !c.is_control() || c.is_whitespace()) {
+        true => "text",
+        false => "binary",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::general_purpose::STANDARD;
+
+    #[test]
+    fn test_find_reports_decodable_payloads_with_their_type() {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend([0u8; 100]);
+        let png = STANDARD.encode(&png);
+        let text = STANDARD.encode("The quick brown fox jumps over the lazy dog, 1234567890 times.");
+        let copy = format!("img: data:image/png;base64,{}\nmsg={}\nsha={}", png, text, "ab12".repeat(16));
+
+        let blobs = find(&copy);
+        assert_eq!(blobs.len(), 2);
+        assert_eq!((blobs[0].offset, blobs[0].length, blobs[0].kind), (copy.find(&png).unwrap(), png.len(), "png"));
+        assert_eq!(blobs[0].decoded_bytes, 108);
+        assert_eq!(blobs[1].kind, "text");
+        assert!(find(&"path/to/some/module_name".repeat(4)).is_empty());
+    }
+}
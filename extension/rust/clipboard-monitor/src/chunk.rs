@@ -0,0 +1,124 @@
+use crate::tokens;
+
+/// Splits `text` into chunks of at most about `max_tokens` tokens for pasting
+/// into a model one at a time. Chunks break between paragraphs, never inside
+/// a fenced code block unless the block alone is too large; then between
+/// lines, and only as a last resort inside a line. Each chunk after the
+/// first starts with the last paragraph of the one before, when that
+/// paragraph is short, so that no chunk begins without context.
+pub fn chunk(text: &str, max_tokens: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    for block in blocks(text) {
+        split_oversized(&block, max_tokens, &mut pieces);
+    }
+
+    let mut chunks: Vec<Vec<&str>> = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut current_tokens = 0;
+    for piece in &pieces {
+        let piece_tokens = tokens::estimate(piece);
+        if !current.is_empty() && current_tokens + piece_tokens > max_tokens {
+            let overlap = current.last().copied().filter(|last| {
+                let last_tokens = tokens::estimate(last);
+                last_tokens <= max_tokens / 4 && last_tokens + piece_tokens <= max_tokens
+            });
+            chunks.push(std::mem::take(&mut current));
+            current_tokens = 0;
+            if let Some(overlap) = overlap {
+                current.push(overlap);
+                current_tokens = tokens::estimate(overlap);
+            }
+        }
+        current.push(piece);
+        current_tokens += piece_tokens;
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks.into_iter().map(|parts| parts.join("\n\n")).collect()
+}
+
+/// Paragraphs, separated by blank lines, with each fenced code block kept
+/// whole.
+fn blocks(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut in_fence = false;
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        if line.trim().is_empty() && !in_fence {
+            if !current.is_empty() {
+                blocks.push(current.join("\n"));
+                current.clear();
+            }
+            continue;
+        }
+        current.push(line);
+    }
+    if !current.is_empty() {
+        blocks.push(current.join("\n"));
+    }
+    blocks
+}
+
+/// Pushes `block` to `out`, first cut between lines, then within lines, until
+/// every piece fits in `max_tokens`.
+fn split_oversized(block: &str, max_tokens: usize, out: &mut Vec<String>) {
+    if tokens::estimate(block) <= max_tokens {
+        out.push(block.to_string());
+        return;
+    }
+    let mut current = String::new();
+    for line in block.lines() {
+        let candidate = match current.is_empty() {
+            true => line.to_string(),
+            false => format!("{}\n{}", current, line),
+        };
+        if tokens::estimate(&candidate) <= max_tokens {
+            current = candidate;
+            continue;
+        }
+        if !current.is_empty() {
+            out.push(std::mem::take(&mut current));
+        }
+        if tokens::estimate(line) <= max_tokens {
+            current = line.to_string();
+            continue;
+        }
+        // Four characters to a token leaves room for the estimate's error.
+        let chars: Vec<char> = line.chars().collect();
+        for part in chars.chunks(max_tokens * 4) {
+            out.push(part.iter().collect());
+        }
+    }
+    if !current.is_empty() {
+        out.push(current);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_break_between_paragraphs_and_keep_code_blocks() {
+        let code = "```rust\nfn main() {\n\n    run();\n}\n```";
+        let text = format!("First paragraph here.\n\n{}\n\nLast words.", code);
+        assert_eq!(chunk(&text, 1000), [text.as_str()]);
+        assert_eq!(chunk(&text, 20), [format!("First paragraph here.\n\n{}", code), "Last words.".to_string()]);
+
+        let text = "Alpha beta gamma delta epsilon.\n\nShort.\n\nAnother paragraph follows.";
+        assert_eq!(
+            chunk(text, 10),
+            ["Alpha beta gamma delta epsilon.\n\nShort.", "Short.\n\nAnother paragraph follows."]
+        );
+
+        let long = "word ".repeat(100);
+        let chunks = chunk(&long, 10);
+        assert!(chunks.len() > 5);
+        assert!(chunks.iter().all(|chunk| tokens::estimate(chunk) <= 10));
+        assert_eq!(chunks.concat().replace("\n\n", ""), long);
+    }
+}
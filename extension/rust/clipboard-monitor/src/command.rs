@@ -0,0 +1,172 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Matches the opening tag of a qdrant command and captures its type and attributes.
+static OPEN_TAG: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)<qdrant-(file|search|read)([^>]*?)/?>").unwrap());
+
+/// Matches `key="value"` (or single-quoted) attributes inside an opening tag.
+static ATTRIBUTE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(\w+)\s*=\s*['"]([^'"]*)['"]"#).unwrap());
+
+static SEARCH_BLOCK: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<search>(.*?)</search>").unwrap());
+static REPLACE_BLOCK: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<replace>(.*?)</replace>").unwrap());
+
+/// A qdrant command parsed from a raw XML payload, mirroring the extension's XmlParser.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    File {
+        path: String,
+        /// `create`, `update`, `replace` or `delete`. Defaults to `replace`.
+        action: String,
+        content: Option<String>,
+        search_block: Option<String>,
+        replace_block: Option<String>,
+    },
+    Search {
+        query: String,
+    },
+    Read {
+        path: String,
+        /// 1-based inclusive line range from `startLine`/`endLine` or `lines="a-b"`.
+        start_line: Option<usize>,
+        end_line: Option<usize>,
+    },
+}
+
+/// Strips a surrounding markdown code fence from content, if present.
+fn sanitize_content(content: &str) -> String {
+    let content = content.trim();
+    if content.starts_with("```") && content.ends_with("```") && content.len() > 3 {
+        let mut lines: Vec<&str> = content.lines().collect();
+        lines.remove(0);
+        lines.pop();
+        return lines.join("\n").trim().to_string();
+    }
+    content.to_string()
+}
+
+/// Parses a single raw payload as emitted in `TriggerXml`.
+pub fn parse(raw: &str) -> Result<Command, String> {
+    let open = OPEN_TAG
+        .captures(raw)
+        .ok_or_else(|| "Payload is not a qdrant command".to_string())?;
+    let kind = open[1].to_lowercase();
+    let mut attributes: HashMap<String, String> = ATTRIBUTE
+        .captures_iter(&open[2])
+        .map(|c| (c[1].to_string(), c[2].to_string()))
+        .collect();
+
+    // Inner text sits between the end of the opening tag and the last closing tag.
+    let tag_end = open.get(0).unwrap().end();
+    let inner = if open[0].ends_with("/>") {
+        ""
+    } else {
+        match raw.rfind("</") {
+            Some(close) if close >= tag_end => &raw[tag_end..close],
+            _ => "",
+        }
+    };
+
+    match kind.as_str() {
+        "file" => {
+            let path = attributes
+                .remove("path")
+                .filter(|p| !p.trim().is_empty())
+                .ok_or_else(|| "Missing required \"path\" attribute for file action.".to_string())?;
+            let action = attributes
+                .remove("action")
+                .map(|a| a.to_lowercase())
+                .unwrap_or_else(|| "replace".to_string());
+            let search = SEARCH_BLOCK.captures(inner);
+            let replace = REPLACE_BLOCK.captures(inner);
+            // A search/replace pair edits the file; anything else is whole-file content.
+            let (content, search_block, replace_block) = match (search, replace) {
+                (Some(s), Some(r)) => (None, Some(sanitize_content(&s[1])), Some(sanitize_content(&r[1]))),
+                _ if inner.trim().is_empty() => (None, None, None),
+                _ => (Some(sanitize_content(inner)), None, None),
+            };
+            Ok(Command::File {
+                path,
+                action,
+                content,
+                search_block,
+                replace_block,
+            })
+        }
+        "search" => {
+            let query = inner.trim().to_string();
+            if query.is_empty() {
+                return Err("Search command has no query".to_string());
+            }
+            Ok(Command::Search { query })
+        }
+        _ => {
+            let path = attributes
+                .remove("path")
+                .filter(|p| !p.trim().is_empty())
+                .ok_or_else(|| "Missing required \"path\" attribute for read action.".to_string())?;
+            let number = |key: &str| -> Result<Option<usize>, String> {
+                attributes
+                    .get(key)
+                    .map(|v| v.trim().parse().map_err(|_| format!("Invalid {} attribute: {}", key, v)))
+                    .transpose()
+            };
+            let (mut start_line, mut end_line) = (number("startLine")?, number("endLine")?);
+            if let Some(lines) = attributes.get("lines") {
+                let (start, end) = lines.split_once('-').unwrap_or((lines, lines));
+                start_line = Some(start.trim().parse().map_err(|_| format!("Invalid lines attribute: {}", lines))?);
+                end_line = Some(end.trim().parse().map_err(|_| format!("Invalid lines attribute: {}", lines))?);
+            }
+            Ok(Command::Read { path, start_line, end_line })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_commands() {
+        let create = parse("<qdrant-file path=\"a.ts\" action=\"create\">\n```ts\nconst x = 1;\n```\n</qdrant-file>").unwrap();
+        assert_eq!(
+            create,
+            Command::File {
+                path: "a.ts".to_string(),
+                action: "create".to_string(),
+                content: Some("const x = 1;".to_string()),
+                search_block: None,
+                replace_block: None,
+            }
+        );
+
+        let edit = parse("<qdrant-file path=\"b.rs\" action=\"replace\"><search>old</search><replace>new</replace></qdrant-file>").unwrap();
+        match edit {
+            Command::File { content, search_block, replace_block, .. } => {
+                assert_eq!(content, None);
+                assert_eq!(search_block.as_deref(), Some("old"));
+                assert_eq!(replace_block.as_deref(), Some("new"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        assert!(parse("<qdrant-file action=\"delete\" />").is_err());
+    }
+
+    #[test]
+    fn test_parse_read_line_ranges() {
+        assert_eq!(
+            parse("<qdrant-read path=\"src/config.ts\" lines=\"10-20\" />").unwrap(),
+            Command::Read { path: "src/config.ts".to_string(), start_line: Some(10), end_line: Some(20) }
+        );
+        assert_eq!(
+            parse("<qdrant-read path=\"a.rs\" startLine=\"5\">why</qdrant-read>").unwrap(),
+            Command::Read { path: "a.rs".to_string(), start_line: Some(5), end_line: None }
+        );
+        assert!(parse("<qdrant-read path=\"a.rs\" lines=\"x-2\" />").is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::transform::Transforms;
+
+/// The `--config` file, TOML:
+///
+/// ```toml
+/// [transforms]
+/// trim = true
+/// wsl_paths = true
+///
+/// [triggers]
+/// duplicate_window_ms = 10000
+/// split_batches = true
+/// suppress_updates = true
+///
+/// [updates]
+/// max_inline_bytes = 1048576
+/// max_history_bytes = 8388608
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub transforms: Transforms,
+    #[serde(default)]
+    pub triggers: TriggerConfig,
+    #[serde(default)]
+    pub updates: UpdateConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateConfig {
+    /// Copies larger than this are written to a temp file and sent as a
+    /// `clipboard_update_ref`.
+    #[serde(default = "default_max_inline_bytes")]
+    pub max_inline_bytes: usize,
+    /// Most bytes of recent copies held in memory.
+    #[serde(default = "default_max_history_bytes")]
+    pub max_history_bytes: usize,
+}
+
+fn default_max_inline_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_max_history_bytes() -> usize {
+    8 * 1024 * 1024
+}
+
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        UpdateConfig { max_inline_bytes: default_max_inline_bytes(), max_history_bytes: default_max_history_bytes() }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TriggerConfig {
+    /// A qdrant command seen again within this many milliseconds is not run
+    /// again; 0 runs every copy.
+    #[serde(default = "default_duplicate_window_ms")]
+    pub duplicate_window_ms: u64,
+    /// Send one `trigger_command` per qdrant command instead of a single
+    /// `trigger_xml` for the whole copy.
+    #[serde(default)]
+    pub split_batches: bool,
+    /// Copies holding a qdrant command send only the trigger: no
+    /// `clipboard_update`, and they are kept out of the history.
+    #[serde(default)]
+    pub suppress_updates: bool,
+}
+
+fn default_duplicate_window_ms() -> u64 {
+    10_000
+}
+
+impl Default for TriggerConfig {
+    fn default() -> Self {
+        TriggerConfig { duplicate_window_ms: default_duplicate_window_ms(), split_batches: false, suppress_updates: false }
+    }
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Invalid config {}", path.display()))
+    }
+}
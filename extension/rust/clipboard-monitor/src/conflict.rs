@@ -0,0 +1,87 @@
+/// One Git conflict block: the two sides and the labels after their markers,
+/// e.g. `HEAD` and a branch name. A diff3 base section is left out.
+#[derive(Debug, PartialEq)]
+pub struct Conflict {
+    pub ours: String,
+    pub theirs: String,
+    pub labels: Vec<String>,
+}
+
+#[derive(PartialEq)]
+enum Section {
+    Outside,
+    Ours,
+    Base,
+    Theirs,
+}
+
+/// Every complete conflict block in `text`, in order. Markers must start a
+/// line and be exactly seven characters long, as Git writes them.
+pub fn find(text: &str) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    let mut section = Section::Outside;
+    let (mut ours, mut theirs, mut labels) = (Vec::new(), Vec::new(), Vec::new());
+    for line in text.lines() {
+        if let Some(label) = marker(line, '<') {
+            section = Section::Ours;
+            ours.clear();
+            theirs.clear();
+            labels = vec![label.to_string()];
+            continue;
+        }
+        match section {
+            Section::Outside => {}
+            Section::Ours | Section::Base if line == "=======" => section = Section::Theirs,
+            Section::Ours if marker(line, '|').is_some() => section = Section::Base,
+            Section::Ours => ours.push(line),
+            Section::Base => {}
+            Section::Theirs => match marker(line, '>') {
+                Some(label) => {
+                    labels.push(label.to_string());
+                    conflicts.push(Conflict {
+                        ours: ours.join("\n"),
+                        theirs: theirs.join("\n"),
+                        labels: std::mem::take(&mut labels),
+                    });
+                    section = Section::Outside;
+                }
+                None => theirs.push(line),
+            },
+        }
+    }
+    conflicts
+}
+
+/// The label after a seven-character run of `c` starting `line`, if it is
+/// one.
+fn marker(line: &str, c: char) -> Option<&str> {
+    let rest = line.strip_prefix(&c.to_string().repeat(7))?;
+    match rest.chars().next() {
+        None => Some(""),
+        Some(' ') => Some(rest.trim()),
+        Some(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_reads_both_styles_of_conflict() {
+        let text = "fn a() {}\n<<<<<<< HEAD\nlet x = 1;\n=======\nlet x = 2;\nlet y = 3;\n>>>>>>> feature/x\n\
+                    <<<<<<< ours\nkeep\n||||||| base\nold\n=======\n>>>>>>> theirs\n<<<<<<< HEAD\nunfinished";
+        assert_eq!(
+            find(text),
+            [
+                Conflict {
+                    ours: "let x = 1;".to_string(),
+                    theirs: "let x = 2;\nlet y = 3;".to_string(),
+                    labels: vec!["HEAD".to_string(), "feature/x".to_string()],
+                },
+                Conflict { ours: "keep".to_string(), theirs: String::new(), labels: vec!["ours".to_string(), "theirs".to_string()] },
+            ]
+        );
+        assert!(find("<<<<<<<< not a marker\n=======\n>>>>>>>").is_empty());
+    }
+}
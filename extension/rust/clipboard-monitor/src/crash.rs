@@ -0,0 +1,70 @@
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::PathBuf;
+use std::process;
+
+use crate::protocol::OutputMessage;
+
+/// Where the last crash report is kept, for bug reports after the
+/// extension has restarted the monitor.
+pub fn crash_file() -> PathBuf {
+    std::env::temp_dir().join("clipboard-monitor-crash.log")
+}
+
+/// Reports any panic, on any thread, as a final `error` message with code
+/// `panic` and writes it to [`crash_file`], then aborts so a panicking
+/// worker thread cannot leave the monitor running half-dead.
+pub fn install_hook() {
+    panic::set_hook(Box::new(|info| {
+        let report = crash_report(info, Backtrace::force_capture().to_string());
+        if let OutputMessage::Error { message, backtrace, .. } = &report {
+            let _ = fs::write(crash_file(), format!("{}\n\n{}", message, backtrace.as_deref().unwrap_or_default()));
+            eprintln!("{}", message);
+        }
+        let _ = crate::send_json(&report);
+        process::abort();
+    }));
+}
+
+fn crash_report(info: &PanicHookInfo, backtrace: String) -> OutputMessage {
+    let payload = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic payload".to_string());
+    let thread = std::thread::current().name().unwrap_or("unnamed").to_string();
+    let location = info.location().map(|l| format!(" at {}:{}", l.file(), l.line())).unwrap_or_default();
+    OutputMessage::Error {
+        code: Some("panic".to_string()),
+        message: format!("Clipboard monitor panicked in thread '{}'{}: {}", thread, location, payload),
+        backtrace: Some(backtrace),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_crash_report_names_the_panic() {
+        let report = Arc::new(Mutex::new(None));
+        let captured = Arc::clone(&report);
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            *captured.lock().unwrap() = Some(crash_report(info, "frames".to_string()));
+        }));
+        let _ = std::thread::Builder::new()
+            .name("poller".to_string())
+            .spawn(|| panic!("boom {}", 42))
+            .unwrap()
+            .join();
+        panic::set_hook(previous);
+
+        let json = serde_json::to_string(&report.lock().unwrap().take().unwrap()).unwrap();
+        assert!(json.starts_with(r#"{"type":"error","code":"panic","message":"Clipboard monitor panicked in thread 'poller' at src/crash.rs:"#));
+        assert!(json.ends_with(r#": boom 42","backtrace":"frames"}"#));
+    }
+}
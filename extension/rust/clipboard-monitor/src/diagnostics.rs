@@ -0,0 +1,82 @@
+use clipboard_core::ClipboardBackend;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::protocol::{ClipboardOwner, OutputMessage};
+
+/// Errors older than this drop out of the counts.
+const RECENT: Duration = Duration::from_secs(10 * 60);
+/// At most this many errors are remembered.
+const MAX_ERRORS: usize = 1000;
+
+/// Recent failures by kind, for `diagnose`.
+#[derive(Default)]
+pub struct ErrorLog {
+    entries: VecDeque<(Instant, &'static str)>,
+    last: Option<String>,
+}
+
+impl ErrorLog {
+    pub fn note(&mut self, kind: &'static str, message: String) {
+        self.entries.push_back((Instant::now(), kind));
+        if self.entries.len() > MAX_ERRORS {
+            self.entries.pop_front();
+        }
+        self.last = Some(message);
+    }
+
+    fn counts(&mut self) -> BTreeMap<String, usize> {
+        while self.entries.front().is_some_and(|(at, _)| at.elapsed() > RECENT) {
+            self.entries.pop_front();
+        }
+        let mut counts = BTreeMap::new();
+        for (_, kind) in &self.entries {
+            *counts.entry(kind.to_string()).or_default() += 1;
+        }
+        counts
+    }
+}
+
+/// Describes the clipboard environment and the monitor's recent trouble.
+pub fn report(clipboard: &mut dyn ClipboardBackend, errors: &mut ErrorLog, monitoring: bool, capture_all: bool) -> OutputMessage {
+    let (formats, formats_error) = match clipboard.formats() {
+        Ok(formats) => (formats, None),
+        Err(e) => (Vec::new(), Some(format!("{:#}", e))),
+    };
+    OutputMessage::Diagnostics {
+        display_server: clipboard_core::display_server().to_string(),
+        backend: clipboard.name().to_string(),
+        clipboard_owner: clipboard.owner().map(|owner| ClipboardOwner { pid: owner.pid, name: owner.name }),
+        formats,
+        formats_error,
+        monitoring,
+        capture_all,
+        recent_errors: errors.counts(),
+        last_error: errors.last.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clipboard_core::{Content, MockBackend, Owner};
+
+    #[test]
+    fn test_report_describes_the_clipboard() {
+        let clipboard = MockBackend::new();
+        clipboard.put(Content::Text("copied".to_string()));
+        clipboard.set_owner(Some(Owner { pid: Some(42), name: "code".to_string() }));
+        let mut errors = ErrorLog::default();
+        errors.note("read", "first".to_string());
+        errors.note("read", "second".to_string());
+        errors.note("command", "third".to_string());
+
+        let json = serde_json::to_value(report(&mut clipboard.clone(), &mut errors, true, false)).unwrap();
+        assert_eq!(json["type"], "diagnostics");
+        assert_eq!(json["backend"], "mock");
+        assert_eq!(json["clipboard_owner"], serde_json::json!({ "pid": 42, "name": "code" }));
+        assert_eq!(json["formats"], serde_json::json!(["text/plain"]));
+        assert_eq!(json["recent_errors"], serde_json::json!({ "command": 1, "read": 2 }));
+        assert_eq!(json["last_error"], "third");
+    }
+}
@@ -0,0 +1,371 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use crate::command::Command;
+use crate::protocol::{OutputMessage, WriteMode};
+
+/// The directories clipboard commands may touch. Relative paths resolve
+/// against the first root; anything that lands outside every root is refused.
+#[derive(Debug, Clone, Default)]
+pub struct Sandbox {
+    roots: Vec<PathBuf>,
+}
+
+impl Sandbox {
+    pub fn new(roots: Vec<PathBuf>) -> Result<Self> {
+        let roots = roots
+            .into_iter()
+            .map(|root| {
+                root.canonicalize()
+                    .with_context(|| format!("Root does not exist: {}", root.display()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Sandbox { roots })
+    }
+
+    /// Commands are only executed when at least one root was configured.
+    pub fn is_enabled(&self) -> bool {
+        !self.roots.is_empty()
+    }
+
+    /// Resolves `path` to an absolute location inside a root. Symlinks in the
+    /// existing part of the path are followed before the check, so a link
+    /// cannot be used to escape.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf> {
+        let first = self.roots.first().ok_or_else(|| anyhow!("No workspace root configured"))?;
+        let requested = Path::new(path);
+        let joined = if requested.is_absolute() { requested.to_path_buf() } else { first.join(requested) };
+
+        // Canonicalize the deepest existing ancestor, then re-append the rest.
+        // A dangling symlink is not a missing path: writing through it would
+        // create its target, wherever that is.
+        let mut existing = joined.as_path();
+        let mut rest = Vec::new();
+        while !existing.exists() {
+            if existing.symlink_metadata().is_ok() {
+                bail!("Refusing to follow dangling symlink: {}", existing.display());
+            }
+            match (existing.parent(), existing.file_name()) {
+                (Some(parent), Some(name)) => {
+                    rest.push(name.to_os_string());
+                    existing = parent;
+                }
+                _ => bail!("Invalid path: {}", path),
+            }
+        }
+        let mut resolved = existing.canonicalize()?;
+        for name in rest.into_iter().rev() {
+            resolved.push(name);
+        }
+        if resolved.components().any(|c| c == Component::ParentDir) {
+            bail!("Path escapes the workspace: {}", path);
+        }
+
+        if self.roots.iter().any(|root| resolved.starts_with(root)) {
+            Ok(resolved)
+        } else {
+            bail!("Path is outside the workspace: {}", path)
+        }
+    }
+}
+
+/// Files larger than this are not returned by `<qdrant-read>`.
+pub const MAX_READ_BYTES: u64 = 1024 * 1024;
+
+/// Reads the file named by a `<qdrant-read>` command, limited to its line range.
+pub fn execute_read(sandbox: &Sandbox, id: &str, command: &Command) -> OutputMessage {
+    let (content, error) = match read_file(sandbox, command) {
+        Ok(content) => (content, None),
+        Err(e) => (String::new(), Some(format!("{:#}", e))),
+    };
+    OutputMessage::CommandResult { id: id.to_string(), kind: "read".to_string(), content, error }
+}
+
+fn read_file(sandbox: &Sandbox, command: &Command) -> Result<String> {
+    let Command::Read { path, start_line, end_line } = command else {
+        bail!("Not a read command");
+    };
+    let target = sandbox.resolve(path)?;
+    let size = fs::metadata(&target).with_context(|| format!("Failed to read {}", path))?.len();
+    if size > MAX_READ_BYTES {
+        bail!("{} is {} bytes, over the {} byte read limit", path, size, MAX_READ_BYTES);
+    }
+    let text = fs::read_to_string(&target).with_context(|| format!("Failed to read {}", path))?;
+    if start_line.is_none() && end_line.is_none() {
+        return Ok(text);
+    }
+    let start = start_line.unwrap_or(1).max(1);
+    let end = end_line.unwrap_or(usize::MAX);
+    if end < start {
+        bail!("Line range {}-{} is empty", start, end);
+    }
+    Ok(text
+        .lines()
+        .skip(start - 1)
+        .take(end - start + 1)
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// Performs a `<qdrant-file>` action and describes the outcome.
+pub fn execute_file(sandbox: &Sandbox, id: &str, command: &Command) -> OutputMessage {
+    let Command::File { path, action, .. } = command else {
+        return OutputMessage::Error { code: None, message: "Not a file command".to_string(), backtrace: None };
+    };
+    match apply_file(sandbox, command) {
+        Ok(bytes_written) => OutputMessage::FileActionResult {
+            id: id.to_string(),
+            path: path.clone(),
+            action: action.clone(),
+            success: true,
+            bytes_written,
+            error: None,
+        },
+        Err(e) => OutputMessage::FileActionResult {
+            id: id.to_string(),
+            path: path.clone(),
+            action: action.clone(),
+            success: false,
+            bytes_written: None,
+            error: Some(format!("{:#}", e)),
+        },
+    }
+}
+
+fn apply_file(sandbox: &Sandbox, command: &Command) -> Result<Option<usize>> {
+    let Command::File { path, action, content, search_block, replace_block } = command else {
+        bail!("Not a file command");
+    };
+    let target = sandbox.resolve(path)?;
+    if target.is_dir() {
+        bail!("{} is a directory", path);
+    }
+
+    let new_content = match action.as_str() {
+        "delete" => {
+            fs::remove_file(&target).with_context(|| format!("Failed to delete {}", path))?;
+            return Ok(None);
+        }
+        "create" => {
+            if target.exists() {
+                bail!("{} already exists", path);
+            }
+            content.clone().unwrap_or_default()
+        }
+        "update" | "replace" => match (search_block, replace_block) {
+            (Some(search), Some(replace)) => {
+                let current = fs::read_to_string(&target).with_context(|| format!("Failed to read {}", path))?;
+                match current.matches(search.as_str()).count() {
+                    0 => bail!("Search block not found in {}", path),
+                    1 => current.replacen(search.as_str(), replace, 1),
+                    n => bail!("Search block matches {} times in {}", n, path),
+                }
+            }
+            _ => content
+                .clone()
+                .ok_or_else(|| anyhow!("No content given for {}", path))?,
+        },
+        other => bail!("Unknown file action: {}", other),
+    };
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&target, new_content.as_bytes()).with_context(|| format!("Failed to write {}", path))?;
+    Ok(Some(new_content.len()))
+}
+
+/// Reads up to `max_bytes` of `path` for the `read_file` command, cut back
+/// to the last whole character.
+pub fn execute_read_file(sandbox: &Sandbox, path: &str, max_bytes: Option<u64>) -> OutputMessage {
+    let limit = max_bytes.unwrap_or(MAX_READ_BYTES).min(MAX_READ_BYTES);
+    let result = sandbox.resolve(path).and_then(|target| {
+        let size = fs::metadata(&target).with_context(|| format!("Failed to read {}", path))?.len();
+        let mut bytes = Vec::new();
+        fs::File::open(&target)
+            .and_then(|file| file.take(limit).read_to_end(&mut bytes))
+            .with_context(|| format!("Failed to read {}", path))?;
+        let content = match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(e) if size > limit && e.utf8_error().error_len().is_none() => {
+                let valid = e.utf8_error().valid_up_to();
+                let mut bytes = e.into_bytes();
+                bytes.truncate(valid);
+                String::from_utf8(bytes)?
+            }
+            Err(_) => bail!("{} is not UTF-8 text", path),
+        };
+        Ok((content, size))
+    });
+    match result {
+        Ok((content, size)) => OutputMessage::FileRead {
+            path: path.to_string(),
+            truncated: (content.len() as u64) < size,
+            content,
+            size,
+            error: None,
+        },
+        Err(e) => OutputMessage::FileRead {
+            path: path.to_string(),
+            content: String::new(),
+            size: 0,
+            truncated: false,
+            error: Some(format!("{:#}", e)),
+        },
+    }
+}
+
+/// Writes `content` to `path` for the `write_file` command.
+pub fn execute_write_file(sandbox: &Sandbox, path: &str, content: &str, mode: WriteMode) -> OutputMessage {
+    let result = sandbox.resolve(path).and_then(|target| {
+        if target.is_dir() {
+            bail!("{} is a directory", path);
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut options = fs::OpenOptions::new();
+        match mode {
+            WriteMode::Create => options.write(true).create_new(true),
+            WriteMode::Overwrite => options.write(true).create(true).truncate(true),
+            WriteMode::Append => options.append(true).create(true),
+        };
+        let mut file = options.open(&target).with_context(|| match mode {
+            WriteMode::Create if target.exists() => format!("{} already exists", path),
+            _ => format!("Failed to write {}", path),
+        })?;
+        file.write_all(content.as_bytes()).with_context(|| format!("Failed to write {}", path))?;
+        Ok(content.len())
+    });
+    let (bytes_written, error) = match result {
+        Ok(bytes) => (Some(bytes), None),
+        Err(e) => (None, Some(format!("{:#}", e))),
+    };
+    OutputMessage::FileWritten { path: path.to_string(), bytes_written, error }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::parse;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("clipboard-monitor-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_file_actions_stay_inside_the_sandbox() {
+        let root = temp_root("executor");
+        let sandbox = Sandbox::new(vec![root.clone()]).unwrap();
+
+        let create = parse("<qdrant-file path=\"src/a.ts\" action=\"create\">let a = 1;</qdrant-file>").unwrap();
+        assert!(matches!(execute_file(&sandbox, "f1", &create), OutputMessage::FileActionResult { success: true, .. }));
+        assert_eq!(fs::read_to_string(root.join("src/a.ts")).unwrap(), "let a = 1;");
+
+        let edit = parse("<qdrant-file path=\"src/a.ts\" action=\"update\"><search>1</search><replace>2</replace></qdrant-file>").unwrap();
+        execute_file(&sandbox, "f1", &edit);
+        assert_eq!(fs::read_to_string(root.join("src/a.ts")).unwrap(), "let a = 2;");
+
+        let escape = parse("<qdrant-file path=\"../escape.txt\" action=\"create\">x</qdrant-file>").unwrap();
+        match execute_file(&sandbox, "f1", &escape) {
+            OutputMessage::FileActionResult { success, error, .. } => {
+                assert!(!success);
+                assert!(error.unwrap().contains("outside the workspace"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let delete = parse("<qdrant-file path=\"src/a.ts\" action=\"delete\" />").unwrap();
+        execute_file(&sandbox, "f1", &delete);
+        assert!(!root.join("src/a.ts").exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dangling_symlinks_are_not_written_through() {
+        let root = temp_root("dangling");
+        let outside = temp_root("dangling-target").join("created.txt");
+        std::os::unix::fs::symlink(&outside, root.join("link.txt")).unwrap();
+        let sandbox = Sandbox::new(vec![root.clone()]).unwrap();
+
+        assert!(sandbox.resolve("link.txt").unwrap_err().to_string().contains("dangling symlink"));
+        assert!(sandbox.resolve("link.txt/below").is_err());
+        let written = execute_write_file(&sandbox, "link.txt", "x", WriteMode::Overwrite);
+        assert!(matches!(written, OutputMessage::FileWritten { error: Some(_), .. }));
+        assert!(!outside.exists());
+        let _ = fs::remove_dir_all(&root);
+        let _ = fs::remove_dir_all(outside.parent().unwrap());
+    }
+
+    #[test]
+    fn test_read_returns_requested_lines() {
+        let root = temp_root("read");
+        fs::write(root.join("notes.txt"), "one\ntwo\nthree\nfour\n").unwrap();
+        let sandbox = Sandbox::new(vec![root.clone()]).unwrap();
+
+        let read = parse("<qdrant-read path=\"notes.txt\" lines=\"2-3\" />").unwrap();
+        assert_eq!(
+            execute_read(&sandbox, "r1", &read),
+            OutputMessage::CommandResult {
+                id: "r1".to_string(),
+                kind: "read".to_string(),
+                content: "two\nthree".to_string(),
+                error: None,
+            }
+        );
+
+        let missing = parse("<qdrant-read path=\"nope.txt\" />").unwrap();
+        match execute_read(&sandbox, "r2", &missing) {
+            OutputMessage::CommandResult { error, .. } => assert!(error.is_some()),
+            other => panic!("unexpected {:?}", other),
+        }
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_bridge_reads_and_writes_inside_the_sandbox_only() {
+        let root = temp_root("bridge");
+        let sandbox = Sandbox::new(vec![root.clone()]).unwrap();
+
+        assert!(matches!(
+            execute_write_file(&sandbox, "notes/a.txt", "héllo", WriteMode::Create),
+            OutputMessage::FileWritten { bytes_written: Some(6), error: None, .. }
+        ));
+        execute_write_file(&sandbox, "notes/a.txt", "!", WriteMode::Append);
+        let exists = execute_write_file(&sandbox, "notes/a.txt", "x", WriteMode::Create);
+        assert!(matches!(exists, OutputMessage::FileWritten { error: Some(e), .. } if e.contains("already exists")));
+
+        let read = execute_read_file(&sandbox, "notes/a.txt", Some(2));
+        assert_eq!(
+            read,
+            OutputMessage::FileRead {
+                path: "notes/a.txt".to_string(),
+                content: "h".to_string(),
+                size: 7,
+                truncated: true,
+                error: None,
+            }
+        );
+        assert!(matches!(execute_read_file(&sandbox, "notes/a.txt", None), OutputMessage::FileRead { content, .. } if content == "héllo!"));
+        let escape = execute_write_file(&sandbox, "../escape.txt", "x", WriteMode::Overwrite);
+        assert!(matches!(escape, OutputMessage::FileWritten { error: Some(e), .. } if e.contains("outside the workspace")));
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_bridge_without_roots_refuses_every_path() {
+        let sandbox = Sandbox::new(Vec::new()).unwrap();
+        assert!(!sandbox.is_enabled());
+
+        let read = execute_read_file(&sandbox, "a.txt", None);
+        assert!(matches!(read, OutputMessage::FileRead { error: Some(e), .. } if e.contains("No workspace root configured")));
+        let write = execute_write_file(&sandbox, "a.txt", "x", WriteMode::Create);
+        assert!(matches!(write, OutputMessage::FileWritten { bytes_written: None, error: Some(e), .. } if e.contains("No workspace root configured")));
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::VecDeque;
+
+/// How many recent entries are kept, whatever their size.
+const MAX_ENTRIES: usize = 32;
+
+struct Entry {
+    hash: String,
+    content: String,
+    pinned: bool,
+}
+
+/// Recent copies, by hash, for commands such as `chunk_content`. Bounded by
+/// count and by total bytes; the oldest entries go first, and a copy larger
+/// than the whole budget is not kept at all. Pinned entries are exempt: they
+/// count against neither bound and survive `clear`.
+pub struct History {
+    entries: VecDeque<Entry>,
+    bytes: usize,
+    max_bytes: usize,
+}
+
+impl History {
+    pub fn new(max_bytes: usize) -> Self {
+        History { entries: VecDeque::new(), bytes: 0, max_bytes }
+    }
+
+    /// Adds a copy, unless the same content is already pinned.
+    pub fn push(&mut self, hash: String, content: String) {
+        if content.len() > self.max_bytes || self.entries.iter().any(|entry| entry.pinned && entry.hash == hash) {
+            return;
+        }
+        self.bytes += content.len();
+        self.entries.push_back(Entry { hash, content, pinned: false });
+        self.evict();
+    }
+
+    /// Pins or unpins the newest entry with `hash`; false if there is none.
+    /// Pinning drops the other copies of it.
+    pub fn set_pinned(&mut self, hash: &str, pinned: bool) -> bool {
+        let Some(index) = self.entries.iter().rposition(|entry| entry.hash == hash) else {
+            return false;
+        };
+        if self.entries[index].pinned == pinned {
+            return true;
+        }
+        self.entries[index].pinned = pinned;
+        if pinned {
+            let mut bytes = self.bytes - self.entries[index].content.len();
+            self.entries.retain(|entry| {
+                let copy = !entry.pinned && entry.hash == hash;
+                if copy {
+                    bytes -= entry.content.len();
+                }
+                !copy
+            });
+            self.bytes = bytes;
+        } else {
+            self.bytes += self.entries[index].content.len();
+            self.evict();
+        }
+        true
+    }
+
+    /// The newest entry with `hash`.
+    pub fn get(&self, hash: &str) -> Option<&str> {
+        self.entries.iter().rev().find(|entry| entry.hash == hash).map(|entry| entry.content.as_str())
+    }
+
+    /// Every entry's hash and content and whether it is pinned: the pinned
+    /// ones first, each group newest first.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str, bool)> {
+        let pinned = self.entries.iter().rev().filter(|entry| entry.pinned);
+        let unpinned = self.entries.iter().rev().filter(|entry| !entry.pinned);
+        pinned.chain(unpinned).map(|entry| (entry.hash.as_str(), entry.content.as_str(), entry.pinned))
+    }
+
+    /// Forgets every entry that is not pinned.
+    pub fn clear(&mut self) {
+        self.entries.retain(|entry| entry.pinned);
+        self.bytes = 0;
+    }
+
+    fn evict(&mut self) {
+        while self.entries.iter().filter(|entry| !entry.pinned).count() > MAX_ENTRIES || self.bytes > self.max_bytes {
+            let oldest = self.entries.iter().position(|entry| !entry.pinned).unwrap();
+            let evicted = self.entries.remove(oldest).unwrap();
+            self.bytes -= evicted.content.len();
+        }
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_evicts_the_oldest_to_stay_within_budget() {
+        let mut history = History::new(10);
+        history.push("a".to_string(), "1234".to_string());
+        history.push("b".to_string(), "5678".to_string());
+        history.push("c".to_string(), "90".to_string());
+        assert_eq!(history.len(), 3);
+        history.push("d".to_string(), "xyz".to_string());
+        assert_eq!((history.get("a"), history.get("b")), (None, Some("5678")));
+        history.push("huge".to_string(), "x".repeat(11));
+        assert_eq!((history.len(), history.get("huge")), (3, None));
+    }
+
+    #[test]
+    fn test_pinned_entries_are_listed_first_and_never_evicted() {
+        let mut history = History::new(10);
+        history.push("a".to_string(), "1234".to_string());
+        history.push("b".to_string(), "5678".to_string());
+        history.push("a".to_string(), "1234".to_string());
+        assert!(history.set_pinned("a", true) && !history.set_pinned("missing", true));
+        history.push("a".to_string(), "1234".to_string());
+        history.push("c".to_string(), "90abcd".to_string());
+        history.push("d".to_string(), "ef".to_string());
+        let hashes: Vec<(&str, bool)> = history.entries().map(|(hash, _, pinned)| (hash, pinned)).collect();
+        assert_eq!(hashes, [("a", true), ("d", false), ("c", false)]);
+        history.clear();
+        assert_eq!(history.get("a"), Some("1234"));
+        history.set_pinned("a", false);
+        history.clear();
+        assert!(history.is_empty());
+    }
+}
@@ -0,0 +1,64 @@
+//! Decoding the PNGs `set_clipboard_image` receives into the RGBA pixels
+//! the clipboard takes.
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use clipboard_core::Image;
+use png::{ColorType, Transformations};
+use std::io::Cursor;
+
+/// Decodes base64 PNG `data`, as is or as a `data:` URL. `width` and
+/// `height`, when given, must match the image's own.
+pub fn decode_png(data: &str, width: Option<usize>, height: Option<usize>) -> Result<Image> {
+    let data = data.trim();
+    let data = data.strip_prefix("data:image/png;base64,").unwrap_or(data);
+    let bytes = STANDARD.decode(data).context("Image data is not valid base64")?;
+    let mut decoder = png::Decoder::new(Cursor::new(bytes));
+    decoder.set_transformations(Transformations::normalize_to_color8() | Transformations::ALPHA);
+    let mut reader = decoder.read_info().context("Image data is not a PNG")?;
+    let size = reader.output_buffer_size().ok_or_else(|| anyhow!("PNG is too large"))?;
+    let mut pixels = vec![0; size];
+    let info = reader.next_frame(&mut pixels).context("Failed to decode PNG")?;
+    let (actual_width, actual_height) = (info.width as usize, info.height as usize);
+    if width.is_some_and(|w| w != actual_width) || height.is_some_and(|h| h != actual_height) {
+        bail!(
+            "PNG is {}x{}, not the {}x{} given",
+            actual_width,
+            actual_height,
+            width.unwrap_or(actual_width),
+            height.unwrap_or(actual_height)
+        );
+    }
+    pixels.truncate(info.line_size * actual_height);
+    let rgba = match info.color_type {
+        ColorType::Rgba => pixels,
+        ColorType::Rgb => pixels.chunks(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        ColorType::GrayscaleAlpha => pixels.chunks(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        ColorType::Grayscale => pixels.iter().flat_map(|&v| [v, v, v, 255]).collect(),
+        ColorType::Indexed => bail!("PNG palette was not expanded"),
+    };
+    Ok(Image { width: actual_width, height: actual_height, rgba })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_png_expands_to_rgba_and_checks_dimensions() {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+        encoder.set_color(ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header().unwrap().write_image_data(&[255, 0, 0, 0, 0, 255]).unwrap();
+        let data = format!("data:image/png;base64,{}", STANDARD.encode(&bytes));
+
+        let image = decode_png(&data, Some(2), Some(1)).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.rgba, [255, 0, 0, 255, 0, 0, 255, 255]);
+        let err = decode_png(&data, Some(3), None).unwrap_err();
+        assert_eq!(err.to_string(), "PNG is 2x1, not the 3x1 given");
+        assert!(decode_png("bm90IGEgcG5n", None, None).is_err());
+    }
+}
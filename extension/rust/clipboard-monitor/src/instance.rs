@@ -0,0 +1,112 @@
+use anyhow::{bail, Context, Result};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long `--takeover` waits for the old instance to exit.
+const TAKEOVER_WAIT: Duration = Duration::from_secs(5);
+
+/// Proof that this is the only monitor watching the user's clipboard; a
+/// second would double every update and run every command twice. The lock
+/// is the operating system's on a file holding the owner's pid, so it is
+/// released however the process exits.
+pub struct InstanceLock {
+    _file: File,
+}
+
+fn lock_path() -> PathBuf {
+    let user = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_default();
+    std::env::temp_dir().join(format!("clipboard-monitor-{}.lock", user))
+}
+
+/// Takes the lock, or with `takeover` stops the instance holding it first.
+pub fn acquire(takeover: bool) -> Result<InstanceLock> {
+    acquire_at(&lock_path(), takeover)
+}
+
+fn acquire_at(path: &Path, takeover: bool) -> Result<InstanceLock> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    if !try_lock(&file)? {
+        let mut pid = String::new();
+        file.read_to_string(&mut pid)?;
+        let pid = pid.trim().to_string();
+        if !takeover {
+            bail!("Another clipboard-monitor (pid {}) is already running; pass --takeover to replace it", pid);
+        }
+        terminate(&pid)?;
+        let started = Instant::now();
+        while !try_lock(&file)? {
+            if started.elapsed() > TAKEOVER_WAIT {
+                bail!("clipboard-monitor pid {} did not exit within {:?}", pid, TAKEOVER_WAIT);
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+    file.set_len(0)?;
+    file.rewind()?;
+    write!(file, "{}", std::process::id())?;
+    file.flush()?;
+    Ok(InstanceLock { _file: file })
+}
+
+fn try_lock(file: &File) -> Result<bool> {
+    match file.try_lock() {
+        Ok(()) => Ok(true),
+        Err(TryLockError::WouldBlock) => Ok(false),
+        Err(TryLockError::Error(e)) => Err(e).context("Failed to lock the instance file"),
+    }
+}
+
+/// Asks process `pid` to exit.
+fn terminate(pid: &str) -> Result<()> {
+    if pid.is_empty() || !pid.chars().all(|c| c.is_ascii_digit()) {
+        bail!("The instance lock holds no valid pid: {:?}", pid);
+    }
+    let mut command = match cfg!(windows) {
+        true => {
+            let mut command = Command::new("taskkill");
+            command.args(["/PID", pid, "/F"]);
+            command
+        }
+        false => {
+            let mut command = Command::new("kill");
+            command.args(["-TERM", pid]);
+            command
+        }
+    };
+    let status = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .context("Failed to signal the running clipboard-monitor")?;
+    if !status.success() {
+        bail!("Failed to stop clipboard-monitor pid {}", pid);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_a_second_instance_is_refused_until_the_first_exits() {
+        let path = std::env::temp_dir().join(format!("clipboard-monitor-test-{}.lock", std::process::id()));
+        let first = acquire_at(&path, false).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), std::process::id().to_string());
+        let err = acquire_at(&path, false).err().unwrap();
+        assert!(err.to_string().contains("--takeover"), "{}", err);
+        drop(first);
+        drop(acquire_at(&path, false).unwrap());
+        std::fs::remove_file(path).unwrap();
+    }
+}
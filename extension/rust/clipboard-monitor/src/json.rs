@@ -0,0 +1,69 @@
+use serde::Serialize;
+
+/// Longest `json_pretty` sent; larger documents are only validated.
+const MAX_PRETTY_BYTES: usize = 64 * 1024;
+
+/// What a copy that looks like a JSON object or array turned out to be.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct JsonInfo {
+    pub json_valid: bool,
+    /// The document re-indented, with keys sorted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_pretty: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_error: Option<JsonError>,
+}
+
+/// Where parsing failed, 1-based, with serde_json's description.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct JsonError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// Parses `text` if it starts like a JSON object or array; anything else,
+/// including bare numbers and strings, is not treated as JSON.
+pub fn check(text: &str) -> Option<JsonInfo> {
+    let trimmed = text.trim();
+    if !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+        return None;
+    }
+    Some(match serde_json::from_str::<serde_json::Value>(trimmed) {
+        Ok(value) => JsonInfo {
+            json_valid: true,
+            json_pretty: serde_json::to_string_pretty(&value).ok().filter(|pretty| pretty.len() <= MAX_PRETTY_BYTES),
+            json_error: None,
+        },
+        Err(e) => JsonInfo {
+            json_valid: false,
+            json_pretty: None,
+            json_error: Some(JsonError {
+                // Positions are in the trimmed text; count the lines trimmed off.
+                line: e.line() + text[..text.len() - text.trim_start().len()].matches('\n').count(),
+                column: e.column(),
+                message: e.to_string(),
+            }),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_pretty_prints_or_locates_the_error() {
+        let info = check(" {\"b\": 1, \"a\": [true]}\n").unwrap();
+        assert!(info.json_valid);
+        assert_eq!(info.json_pretty.unwrap(), "{\n  \"a\": [\n    true\n  ],\n  \"b\": 1\n}");
+
+        let info = check("\n{\n  \"a\": 1,\n  \"b\": 2,\n}").unwrap();
+        assert!(!info.json_valid);
+        let error = info.json_error.unwrap();
+        assert_eq!((error.line, error.column), (5, 1));
+
+        assert_eq!(check("42"), None);
+        assert_eq!(check("plain text"), None);
+    }
+}
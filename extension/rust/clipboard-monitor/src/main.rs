@@ -1,30 +1,88 @@
-use anyhow::{Result};
-use arboard::Clipboard;
+use anyhow::{Context, Result};
 use chrono::Utc;
-use md5;
+use clipboard_core::{copy_files, ClipboardBackend, Content, MockBackend, NoContent};
 use once_cell::sync::Lazy;
 use regex::Regex;
+use std::collections::HashMap;
+use std::env;
 use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+mod blob;
+mod chunk;
+mod command;
+mod config;
+mod conflict;
+mod crash;
+mod diagnostics;
+mod executor;
+mod history;
+mod image;
+mod instance;
+mod json;
+mod offload;
 mod protocol;
-use protocol::{OutputMessage, InputCommand};
+mod recording;
+mod search;
+mod service;
+mod session;
+mod system_history;
+mod tokens;
+mod transform;
+use config::{Config, TriggerConfig, UpdateConfig};
+use diagnostics::ErrorLog;
+use executor::Sandbox;
+use history::History;
+use offload::Offloads;
+use recording::Recorder;
+use search::VectorSearch;
+use transform::Transforms;
+use protocol::{Chunk, Envelope, HistoryEntry, Mode, OutputMessage, InputCommand, Reply};
 
 /// Determines the polling state: true for active, false for paused.
 static IS_MONITORING_ACTIVE: Lazy<Arc<Mutex<bool>>> = Lazy::new(|| Arc::new(Mutex::new(true)));
 
+/// Why monitoring is paused automatically, e.g. `screen_locked`; `None`
+/// while it is not. Kept apart from the user's own pause so that unlocking
+/// never resumes a monitor the user paused.
+static AUTO_PAUSE: Lazy<Arc<Mutex<Option<&'static str>>>> = Lazy::new(|| Arc::new(Mutex::new(None)));
+
+/// Most `nack`s sent per second; past this, rejected lines are only logged
+/// to stderr, so a misbehaving extension cannot flood its own stdout.
+const NACKS_PER_SECOND: u32 = 5;
+
+/// How often the session lock state is checked.
+const LOCK_POLL: Duration = Duration::from_secs(1);
+
 /// Determines if we should capture all clipboard content or just triggers.
 /// Default is false (only triggers).
 static CAPTURE_ALL: Lazy<Arc<Mutex<bool>>> = Lazy::new(|| Arc::new(Mutex::new(false)));
 
+/// Password managers whose clipboard writes are never captured, unless the
+/// extension sends its own list.
+const DEFAULT_EXCLUDED_APPS: [&str; 3] = ["KeePassXC", "1Password", "Bitwarden"];
+
+/// How much of a copy is hashed to tell whether it changed. Beyond this a
+/// change is seen by the length alone, so hashing a huge copy on every poll
+/// costs no more than hashing a small one.
+const HASH_PREFIX_BYTES: usize = 64 * 1024;
+
+/// Longest `delta` sent; beyond this a diff is no easier to read than the
+/// content itself.
+const MAX_DELTA_BYTES: usize = 16 * 1024;
+
 /// Regex for robustly detecting any qdrant XML command, capturing the entire tag block.
 /// (?s) enables dotall mode so that '.' matches newlines.
 const XML_COMMAND_REGEX: &str = r"(?s)(<qdrant-(file|search|read).*?>(.*?)</qdrant-(?:file|search|read)>|<qdrant-(file|search|read).*?/>)";
 
 /// Checks for special XML tags in content and returns specific trigger messages.
-fn check_for_triggers(content: &str) -> Option<OutputMessage> {
+/// Each payload gets an id of the form `<content hash prefix>-<index>` that
+/// every result for it echoes back.
+fn check_for_triggers(content: &str, content_hash: &str) -> Option<OutputMessage> {
     let re = Regex::new(XML_COMMAND_REGEX).unwrap();
     
     let xml_payloads: Vec<String> = re.find_iter(content)
@@ -32,21 +90,149 @@ fn check_for_triggers(content: &str) -> Option<OutputMessage> {
         .collect();
 
     if !xml_payloads.is_empty() {
+        let ids = (0..xml_payloads.len())
+            .map(|index| format!("{}-{}", &content_hash[..8], index))
+            .collect();
         return Some(OutputMessage::TriggerXml {
             xml_payloads,
+            ids,
         });
     }
 
     None
 }
 
-/// Calculates hash and returns a message if the content is new.
+/// A `trigger_xml` as one `trigger_command` per payload. The batch id is
+/// the ids' shared content hash prefix.
+fn split_trigger(trigger: OutputMessage) -> Vec<OutputMessage> {
+    let OutputMessage::TriggerXml { xml_payloads, ids } = trigger else {
+        return vec![trigger];
+    };
+    let total = xml_payloads.len();
+    xml_payloads
+        .into_iter()
+        .zip(ids)
+        .enumerate()
+        .map(|(index, (xml_payload, id))| OutputMessage::TriggerCommand {
+            batch_id: id.split('-').next().unwrap_or_default().to_string(),
+            xml_payload,
+            id,
+            index,
+            total,
+        })
+        .collect()
+}
+
+/// Executes the commands carried by a trigger when a sandbox is configured.
+/// File and read results are returned; searches contact Qdrant, so they run
+/// on their own thread and send their results when done.
+fn execute_trigger(sandbox: &Sandbox, search: &Arc<VectorSearch>, trigger: &OutputMessage) -> Vec<OutputMessage> {
+    let OutputMessage::TriggerXml { xml_payloads, ids } = trigger else {
+        return Vec::new();
+    };
+    if !sandbox.is_enabled() {
+        return Vec::new();
+    }
+    xml_payloads
+        .iter()
+        .zip(ids.iter().cloned())
+        .filter_map(|(raw, id)| match command::parse(raw) {
+            Ok(cmd @ command::Command::File { .. }) => Some(executor::execute_file(sandbox, &id, &cmd)),
+            Ok(cmd @ command::Command::Read { .. }) => Some(executor::execute_read(sandbox, &id, &cmd)),
+            Ok(command::Command::Search { query }) => {
+                let search = Arc::clone(search);
+                thread::spawn(move || {
+                    let message = match search.search(&query) {
+                        Ok(results) => OutputMessage::SearchResult { id, query, results, error: None },
+                        Err(e) => OutputMessage::SearchResult { id, query, results: Vec::new(), error: Some(format!("{:#}", e)) },
+                    };
+                    let _ = send_json(&message);
+                });
+                None
+            }
+            Err(error) => Some(OutputMessage::CommandResult {
+                id,
+                kind: "invalid".to_string(),
+                content: String::new(),
+                error: Some(error),
+            }),
+        })
+        .collect()
+}
+
+/// Command-line options.
+struct Args {
+    /// `--root DIR`, the directories clipboard commands may modify.
+    roots: Vec<PathBuf>,
+    /// `--backend NAME`, the clipboard to watch; `mock` runs headless.
+    backend: String,
+    /// `--record FILE`, where every clipboard change is logged with its timing.
+    record: Option<PathBuf>,
+    /// `--replay FILE`, a recording to process instead of the clipboard.
+    replay: Option<PathBuf>,
+    /// `--exclude-app NAME`, added to the default excluded apps.
+    exclude_apps: Vec<String>,
+    /// `--config FILE`, see `config::Config`.
+    config: Option<PathBuf>,
+    /// `--takeover`, stop an instance already running instead of exiting.
+    takeover: bool,
+}
+
+fn parse_args() -> Result<Args> {
+    let mut parsed = Args { roots: Vec::new(), backend: "arboard".to_string(), record: None, replay: None, exclude_apps: Vec::new(), config: None, takeover: false };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--root" => parsed.roots.push(PathBuf::from(args.next().context("--root requires a directory")?)),
+            "--backend" => parsed.backend = args.next().context("--backend requires a name")?,
+            "--record" => parsed.record = Some(PathBuf::from(args.next().context("--record requires a file")?)),
+            "--replay" => parsed.replay = Some(PathBuf::from(args.next().context("--replay requires a file")?)),
+            "--exclude-app" => parsed.exclude_apps.push(args.next().context("--exclude-app requires a process name")?),
+            "--takeover" => parsed.takeover = true,
+            "--config" => parsed.config = Some(PathBuf::from(args.next().context("--config requires a file")?)),
+            other => anyhow::bail!("Unknown argument: {}", other),
+        }
+    }
+    Ok(parsed)
+}
+
+fn text_hash(content: &str) -> String {
+    if content.len() <= HASH_PREFIX_BYTES {
+        return format!("{:x}", md5::compute(content.as_bytes()));
+    }
+    let mut context = md5::Context::new();
+    context.consume(&content.as_bytes()[..HASH_PREFIX_BYTES]);
+    context.consume(content.len().to_le_bytes());
+    format!("{:x}", context.compute())
+}
+
+/// Longest text diffed: diffing is quadratic at worst, and texts this long
+/// are not edits anyway.
+const MAX_DIFFED_BYTES: usize = 64 * MAX_DELTA_BYTES;
+
+/// A unified diff from `previous` to `current`, if there is one small
+/// enough to send.
+fn delta(previous: &str, current: &str) -> Option<String> {
+    if previous.len() + current.len() > MAX_DIFFED_BYTES {
+        return None;
+    }
+    let diff = similar::TextDiff::from_lines(previous, current)
+        .unified_diff()
+        .header("previous", "current")
+        .to_string();
+    (!diff.is_empty() && diff.len() <= MAX_DELTA_BYTES).then_some(diff)
+}
+
+/// Calculates hash and returns a message if the content is new. The update
+/// carries the content after `transforms`, diffed against `previous`, the
+/// last update's; triggers are found in the content as copied.
 fn process_clipboard_content(
-    content: String,
+    content: &str,
     last_hash: &Option<String>,
+    transforms: &Transforms,
+    previous: Option<&str>,
 ) -> (Option<OutputMessage>, Option<OutputMessage>, String) {
-    let digest = md5::compute(content.as_bytes());
-    let current_hash = format!("{:x}", digest);
+    let current_hash = text_hash(content);
 
     // If identical to last hash, do nothing
     if last_hash.as_deref() == Some(&current_hash) {
@@ -54,54 +240,577 @@ fn process_clipboard_content(
     }
 
     // 1. Generate Standard Update Message
+    let terminal_output = transform::has_ansi(content);
+    let (transformed, transforms_applied) = match terminal_output {
+        true => transforms.apply(&transform::strip_ansi(content)),
+        false => transforms.apply(content),
+    };
     let update_msg = Some(OutputMessage::ClipboardUpdate {
-        length: content.len(),
-        content: content.clone(),
+        length: transformed.len(),
+        estimated_tokens: tokens::estimate(&transformed),
+        delta: previous.and_then(|previous| delta(previous, &transformed)),
+        json: json::check(&transformed),
+        formats: Vec::new(),
+        base64_blobs: blob::find(&transformed),
+        content: transformed,
+        hash: current_hash.clone(),
         timestamp: Utc::now().to_rfc3339(),
+        transforms_applied,
+        terminal_output,
+        raw_content: terminal_output.then(|| content.to_string()),
     });
 
     // 2. Check for triggers (XML commands)
-    let trigger_msg = check_for_triggers(&content);
+    let trigger_msg = check_for_triggers(content, &current_hash);
 
     (update_msg, trigger_msg, current_hash)
 }
 
-fn send_json(msg: &OutputMessage) -> Result<()> {
+/// `update` listing the formats the clipboard offers.
+fn with_formats(mut update: OutputMessage, clipboard: &mut dyn ClipboardBackend) -> OutputMessage {
+    if let OutputMessage::ClipboardUpdate { formats, .. } = &mut update {
+        *formats = clipboard.formats().unwrap_or_default();
+    }
+    update
+}
+
+/// Appends the system history's `texts`, newest first, skipping any already
+/// in `entries`.
+fn merge_system_history(entries: &mut Vec<HistoryEntry>, texts: Vec<String>) {
+    let mut seen: std::collections::HashSet<String> = entries.iter().map(|entry| entry.hash.clone()).collect();
+    for text in texts {
+        let hash = text_hash(&text);
+        if seen.insert(hash.clone()) {
+            entries.push(HistoryEntry { hash, content: text, source: "system", pinned: false });
+        }
+    }
+}
+
+fn cleared(cleared: bool, reason: Option<String>) -> OutputMessage {
+    OutputMessage::ClipboardCleared { cleared, reason, timestamp: Utc::now().to_rfc3339() }
+}
+
+/// What the polling loop carries from one pass to the next.
+struct Monitor {
+    sandbox: Sandbox,
+    search: Arc<VectorSearch>,
+    last_hash: Option<String>,
+    /// Set by `--record FILE`.
+    recorder: Option<Recorder>,
+    errors: ErrorLog,
+    /// Process names whose copies are ignored entirely.
+    excluded_apps: Vec<String>,
+    /// A `clear_after` wipe: when it is due and the hash of the text it
+    /// protects.
+    pending_clear: Option<(Instant, String)>,
+    /// From the `--config` file.
+    transforms: Transforms,
+    duplicate_window: Duration,
+    split_batches: bool,
+    suppress_trigger_updates: bool,
+    /// When each recently run qdrant command, by hash, was first seen.
+    recent_payloads: HashMap<String, Instant>,
+    /// The content of the last update, which the next is diffed against.
+    last_text: Option<String>,
+    mode: Mode,
+    max_inline_bytes: usize,
+    offloads: Offloads,
+    /// Recent updates' contents by hash.
+    history: History,
+    /// Whether new copies are being appended to `accumulated`.
+    accumulating: bool,
+    accumulated: Vec<String>,
+}
+
+impl Monitor {
+    fn new(sandbox: Sandbox, search: Arc<VectorSearch>) -> Self {
+        Monitor {
+            sandbox,
+            search,
+            last_hash: None,
+            recorder: None,
+            errors: ErrorLog::default(),
+            excluded_apps: DEFAULT_EXCLUDED_APPS.iter().map(|app| app.to_string()).collect(),
+            pending_clear: None,
+            transforms: Transforms::default(),
+            duplicate_window: Duration::from_millis(TriggerConfig::default().duplicate_window_ms),
+            recent_payloads: HashMap::new(),
+            split_batches: false,
+            suppress_trigger_updates: false,
+            last_text: None,
+            mode: Mode::Normal,
+            max_inline_bytes: UpdateConfig::default().max_inline_bytes,
+            offloads: Offloads::new(),
+            history: History::new(UpdateConfig::default().max_history_bytes),
+            accumulating: false,
+            accumulated: Vec::new(),
+        }
+    }
+
+    /// One pass of the polling loop: reads the clipboard and returns the
+    /// messages to send.
+    fn poll(&mut self, clipboard: &mut dyn ClipboardBackend) -> Vec<OutputMessage> {
+        let content = match clipboard.get_text() {
+            Ok(content) if !content.is_empty() => content,
+            Ok(_) => return self.emptied(clipboard),
+            Err(e) if e.is::<NoContent>() => return self.emptied(clipboard),
+            Err(e) => {
+                // Ignore transient errors, but count them for `diagnose`.
+                self.errors.note("read", format!("{:#}", e));
+                return Vec::new();
+            }
+        };
+        let (update_msg, trigger_msg, new_hash) = process_clipboard_content(&content, &self.last_hash, &self.transforms, self.last_text.as_deref());
+        self.last_hash = Some(new_hash);
+
+        if update_msg.is_some() {
+            // Nothing below may be diffed against a skipped copy.
+            let last_text = self.last_text.take();
+            // Copies from excluded apps leave no trace at all.
+            if self.is_excluded(clipboard) {
+                return Vec::new();
+            }
+            // Password managers mark secrets; say only that something was skipped.
+            if let Some(hint) = clipboard.concealment() {
+                return vec![OutputMessage::ContentSkipped {
+                    reason: "concealed".to_string(),
+                    detail: hint,
+                    timestamp: Utc::now().to_rfc3339(),
+                }];
+            }
+            self.last_text = last_text;
+        }
+        let is_new = update_msg.is_some();
+        // Recordings keep every new copy as copied, before any transforms,
+        // and before the output filters below, so that a replay sees what
+        // the clipboard saw whatever the mode.
+        if let (Some(recorder), true) = (&mut self.recorder, is_new) {
+            if let Err(e) = recorder.record(&content) {
+                eprintln!("Recording stopped: {:#}", e);
+                self.recorder = None;
+            }
+        }
+        let update_msg = update_msg.map(|update| with_formats(update, clipboard));
+        // In triggers-only mode nothing of an ordinary copy is sent or kept,
+        // and command XML can be kept out of the history.
+        let update_msg = update_msg
+            .filter(|_| self.mode != Mode::TriggersOnly)
+            .filter(|_| !(self.suppress_trigger_updates && trigger_msg.is_some()));
+        if let Some(OutputMessage::ClipboardUpdate { content, hash, .. }) = &update_msg {
+            // Text too long to diff is not worth holding on to.
+            self.last_text = (content.len() <= MAX_DIFFED_BYTES).then(|| content.clone());
+            self.history.push(hash.clone(), content.clone());
+        }
+
+        if let (true, Some(OutputMessage::ClipboardUpdate { content, .. })) = (self.accumulating, &update_msg) {
+            self.accumulated.push(content.clone());
+        }
+
+        let mut messages = Vec::new();
+        // Only send standard updates if CAPTURE_ALL is enabled
+        // Triggers (XML, conflicts) are always sent if found.
+        if is_new {
+            messages.extend(conflict::find(&content).into_iter().map(|conflict| OutputMessage::TriggerConflict {
+                ours: conflict.ours,
+                theirs: conflict.theirs,
+                labels: conflict.labels,
+            }));
+        }
+        if *CAPTURE_ALL.lock().unwrap() {
+            messages.extend(update_msg.map(|update| self.offload(update)));
+        }
+        // A trigger (XML commands) is followed by the results of any commands
+        // executed in the sandbox.
+        let (trigger_msg, suppressed) = match trigger_msg {
+            Some(msg) => self.suppress_duplicates(msg),
+            None => (None, Vec::new()),
+        };
+        messages.extend(suppressed);
+        if let Some(msg) = trigger_msg {
+            let results = execute_trigger(&self.sandbox, &self.search, &msg);
+            for result in &results {
+                if let OutputMessage::FileActionResult { error: Some(error), .. } | OutputMessage::CommandResult { error: Some(error), .. } = result {
+                    self.errors.note("command", error.clone());
+                }
+            }
+            match self.split_batches {
+                true => messages.extend(split_trigger(msg)),
+                false => messages.push(msg),
+            }
+            messages.extend(results);
+        }
+        messages
+    }
+
+    /// `update` as a `clipboard_update_ref` if its content is too large to
+    /// send inline. It is sent inline after all if the file cannot be written.
+    fn offload(&mut self, update: OutputMessage) -> OutputMessage {
+        let OutputMessage::ClipboardUpdate { content, hash, timestamp, .. } = &update else {
+            return update;
+        };
+        if content.len() <= self.max_inline_bytes {
+            return update;
+        }
+        match self.offloads.write(hash, content) {
+            Ok(path) => OutputMessage::ClipboardUpdateRef {
+                path: path.to_string_lossy().into_owned(),
+                size: content.len(),
+                hash: hash.clone(),
+                timestamp: timestamp.clone(),
+            },
+            Err(e) => {
+                self.errors.note("offload", format!("{:#}", e));
+                update
+            }
+        }
+    }
+
+    /// The message of a failed clipboard write, noted for diagnostics.
+    fn command_error(&mut self, result: Result<()>) -> Option<String> {
+        let e = format!("{:#}", result.err()?);
+        self.errors.note("command", e.clone());
+        Some(e)
+    }
+
+    fn pin(&mut self, hash: String, pinned: bool) -> Option<OutputMessage> {
+        (!self.history.set_pinned(&hash, pinned)).then(|| OutputMessage::Error {
+            code: Some("unknown_hash".to_string()),
+            message: format!("No recent clipboard entry has hash {}", hash),
+            backtrace: None,
+        })
+    }
+
+    /// The `history` reply: the monitor's entries, then those of the system
+    /// history it does not already have.
+    fn history_reply(&self, include_system: bool) -> OutputMessage {
+        let mut entries: Vec<HistoryEntry> = self
+            .history
+            .entries()
+            .map(|(hash, content, pinned)| HistoryEntry {
+                hash: hash.to_string(),
+                content: content.to_string(),
+                source: "monitor",
+                pinned,
+            })
+            .collect();
+        let mut system_error = None;
+        if include_system {
+            match system_history::read() {
+                Ok(texts) => merge_system_history(&mut entries, texts),
+                Err(e) => system_error = Some(format!("{:#}", e)),
+            }
+        }
+        OutputMessage::History { entries, system_error }
+    }
+
+    /// Called when the clipboard holds no text: announces a clear if it held
+    /// text before and now holds nothing else either, such as an image.
+    fn emptied(&mut self, clipboard: &mut dyn ClipboardBackend) -> Vec<OutputMessage> {
+        if self.last_hash.is_none() || !clipboard.formats().map_or(true, |formats| formats.is_empty()) {
+            return Vec::new();
+        }
+        self.last_hash = None;
+        self.last_text = None;
+        vec![cleared(true, None)]
+    }
+
+    /// Drops the payloads of `trigger` already run within the duplicate
+    /// window, returning what is left of it and a notice for each dropped.
+    fn suppress_duplicates(&mut self, trigger: OutputMessage) -> (Option<OutputMessage>, Vec<OutputMessage>) {
+        let OutputMessage::TriggerXml { xml_payloads, ids } = trigger else {
+            return (Some(trigger), Vec::new());
+        };
+        let window = self.duplicate_window;
+        self.recent_payloads.retain(|_, seen| seen.elapsed() < window);
+        let mut kept = (Vec::new(), Vec::new());
+        let mut suppressed = Vec::new();
+        for (payload, id) in xml_payloads.into_iter().zip(ids) {
+            let hash = text_hash(&payload);
+            if self.recent_payloads.contains_key(&hash) {
+                suppressed.push(OutputMessage::TriggerSuppressed { reason: "duplicate".to_string(), hash });
+                continue;
+            }
+            if !window.is_zero() {
+                self.recent_payloads.insert(hash, Instant::now());
+            }
+            kept.0.push(payload);
+            kept.1.push(id);
+        }
+        let (xml_payloads, ids) = kept;
+        let trigger = (!xml_payloads.is_empty()).then_some(OutputMessage::TriggerXml { xml_payloads, ids });
+        (trigger, suppressed)
+    }
+
+    /// Whether the clipboard's owner is an excluded app. Names match without
+    /// regard to case or a `.exe` suffix.
+    fn is_excluded(&self, clipboard: &mut dyn ClipboardBackend) -> bool {
+        if self.excluded_apps.is_empty() {
+            return false;
+        }
+        let Some(owner) = clipboard.owner() else {
+            return false;
+        };
+        let normalize = |name: &str| {
+            let name = name.to_lowercase();
+            name.strip_suffix(".exe").map(str::to_string).unwrap_or(name)
+        };
+        let owner = normalize(&owner.name);
+        self.excluded_apps.iter().any(|app| normalize(app) == owner)
+    }
+
+    /// Answers a command that needs the clipboard, from the polling thread.
+    fn handle(&mut self, command: InputCommand, clipboard: &mut dyn ClipboardBackend) -> Option<OutputMessage> {
+        match command {
+            InputCommand::Diagnose => Some(diagnostics::report(
+                clipboard,
+                &mut self.errors,
+                *IS_MONITORING_ACTIVE.lock().unwrap(),
+                *CAPTURE_ALL.lock().unwrap(),
+            )),
+            InputCommand::SetExcludedApps { apps } => {
+                self.excluded_apps = apps;
+                None
+            }
+            // A later request replaces an earlier one.
+            InputCommand::ClearAfter { ms } => match clipboard.get_text() {
+                Ok(text) => {
+                    self.pending_clear = Some((Instant::now() + Duration::from_millis(ms), text_hash(&text)));
+                    None
+                }
+                Err(_) => Some(cleared(false, Some("no_text".to_string()))),
+            },
+            InputCommand::SetClipboardHtml { html, text } => {
+                // Pastes of the plain alternative are ours, not a new copy.
+                self.last_hash = Some(text_hash(&text));
+                let error = self.command_error(clipboard.set_html(&html, &text));
+                Some(OutputMessage::ClipboardWritten { format: "html", error })
+            }
+            InputCommand::SetClipboardImage { data, width, height } => {
+                let result = image::decode_png(&data, width, height).and_then(|image| clipboard.set_image(&image));
+                let error = self.command_error(result);
+                Some(OutputMessage::ClipboardWritten { format: "image", error })
+            }
+            InputCommand::ReadFile { path, max_bytes } => Some(executor::execute_read_file(&self.sandbox, &path, max_bytes)),
+            InputCommand::WriteFile { path, content, mode } => {
+                Some(executor::execute_write_file(&self.sandbox, &path, &content, mode))
+            }
+            InputCommand::CopyFiles { paths } => {
+                let error = self.command_error(copy_files(clipboard, &paths));
+                let count = if error.is_some() { 0 } else { paths.len() };
+                Some(OutputMessage::FilesCopied { count, error })
+            }
+            InputCommand::ReleaseRef { path } => self.offloads.release(&path).err().map(|e| OutputMessage::Error {
+                code: Some("release_ref".to_string()),
+                message: format!("{:#}", e),
+                backtrace: None,
+            }),
+            InputCommand::SetMode { mode } => {
+                self.mode = mode;
+                if mode == Mode::TriggersOnly {
+                    self.last_text = None;
+                    self.history.clear();
+                    self.accumulated.clear();
+                    self.accumulating = false;
+                }
+                None
+            }
+            InputCommand::StartAccumulate => {
+                self.accumulating = true;
+                None
+            }
+            InputCommand::StopAccumulate => {
+                self.accumulating = false;
+                None
+            }
+            InputCommand::FlushAccumulated { separator } => {
+                let parts = std::mem::take(&mut self.accumulated);
+                self.accumulating = false;
+                let content = parts.join(separator.as_deref().unwrap_or("\n"));
+                // The combined text is ours, not a new copy to report.
+                self.last_hash = Some(text_hash(&content));
+                let error = self.command_error(clipboard.set_text(&content));
+                Some(OutputMessage::Accumulated { content, count: parts.len(), error })
+            }
+            InputCommand::ChunkContent { hash, max_tokens } => {
+                let (chunks, error) = match (self.history.get(&hash), max_tokens) {
+                    (_, 0) => (Vec::new(), Some("max_tokens must be at least 1".to_string())),
+                    (None, _) => (Vec::new(), Some(format!("No recent clipboard entry has hash {}", hash))),
+                    (Some(content), _) => {
+                        let chunks = chunk::chunk(content, max_tokens)
+                            .into_iter()
+                            .map(|content| Chunk { estimated_tokens: tokens::estimate(&content), content })
+                            .collect();
+                        (chunks, None)
+                    }
+                };
+                Some(OutputMessage::Chunks { hash, chunks, error })
+            }
+            InputCommand::Pause | InputCommand::Resume | InputCommand::SetCaptureAll { .. } => {
+                apply_toggle(&command);
+                None
+            }
+            InputCommand::GetHistory { include_system } => Some(self.history_reply(include_system)),
+            InputCommand::PinHistoryItem { hash } => self.pin(hash, true),
+            InputCommand::UnpinHistoryItem { hash } => self.pin(hash, false),
+            InputCommand::Unsupported { name } => Some(OutputMessage::Unsupported { command: name }),
+            // Made an `Unsupported` by `parse_command`.
+            InputCommand::Unknown => None,
+            InputCommand::Batch { commands } => Some(OutputMessage::BatchResult {
+                // One reply per command, so that `replies[i]` answers `commands[i]`.
+                replies: commands
+                    .into_iter()
+                    .map(|Envelope { id, command }| Reply { id, message: self.handle(command, clipboard).unwrap_or(OutputMessage::Ack) })
+                    .collect(),
+            }),
+        }
+    }
+
+    /// Fires a due `clear_after`. The clipboard is only wiped if it still
+    /// holds the text it was scheduled for.
+    fn clear_if_due(&mut self, clipboard: &mut dyn ClipboardBackend) -> Option<OutputMessage> {
+        let (due, _) = self.pending_clear.as_ref()?;
+        if Instant::now() < *due {
+            return None;
+        }
+        let (_, hash) = self.pending_clear.take()?;
+        let unchanged = clipboard.get_text().is_ok_and(|text| text_hash(&text) == hash);
+        if !unchanged {
+            return Some(cleared(false, Some("changed".to_string())));
+        }
+        Some(match clipboard.clear() {
+            Ok(()) => {
+                // Polling is not to announce this clear a second time.
+                self.last_hash = None;
+                self.last_text = None;
+                cleared(true, Some("clear_after".to_string()))
+            }
+            Err(e) => cleared(false, Some(format!("{:#}", e))),
+        })
+    }
+}
+
+/// Feeds a `--replay` recording through the pipeline at its original pace,
+/// on a mock clipboard, then returns. Every event is processed, even ones
+/// closer together than the polling interval.
+fn replay(monitor: &mut Monitor, path: &Path) -> Result<()> {
+    let events = recording::load(path)?;
+    let clipboard = MockBackend::new();
+    let started = Instant::now();
+    for event in events {
+        if let Some(wait) = Duration::from_millis(event.at_ms).checked_sub(started.elapsed()) {
+            thread::sleep(wait);
+        }
+        clipboard.put(Content::Text(event.text));
+        for msg in monitor.poll(&mut clipboard.clone()) {
+            send_json(&msg)?;
+        }
+    }
+    Ok(())
+}
+
+fn send_json<T: serde::Serialize>(msg: &T) -> Result<()> {
     let json = serde_json::to_string(msg)?;
-    let mut out = io::stdout();
+    // Lock once so messages sent from worker threads never interleave.
+    let mut out = io::stdout().lock();
     out.write_all(json.as_bytes())?;
     out.write_all(b"\n")?;
     out.flush()?;
     Ok(())
 }
 
+/// Applies a command that only flips a flag, returning false for any other.
+fn apply_toggle(command: &InputCommand) -> bool {
+    match command {
+        InputCommand::Pause => *IS_MONITORING_ACTIVE.lock().unwrap() = false,
+        InputCommand::Resume => *IS_MONITORING_ACTIVE.lock().unwrap() = true,
+        InputCommand::SetCaptureAll { value } => *CAPTURE_ALL.lock().unwrap() = *value,
+        _ => return false,
+    }
+    true
+}
+
+/// The `nack` for a line that failed to parse, with the line's `id` if it
+/// is JSON with one.
+fn nack(line: &str, error: &serde_json::Error) -> OutputMessage {
+    const PREFIX_CHARS: usize = 120;
+    let id = serde_json::from_str::<serde_json::Value>(line)
+        .ok()
+        .and_then(|value| value.get("id")?.as_str().map(str::to_string));
+    OutputMessage::Nack { raw_prefix: line.chars().take(PREFIX_CHARS).collect(), error: error.to_string(), id }
+}
+
+/// Parses one line from the extension: a command, or an array of commands
+/// to run as a batch.
+fn parse_command(line: &str) -> serde_json::Result<Envelope> {
+    let value: serde_json::Value = serde_json::from_str(line)?;
+    if let serde_json::Value::Array(commands) = value {
+        let commands = commands.into_iter().map(parse_value).collect::<serde_json::Result<_>>()?;
+        return Ok(Envelope { id: None, command: InputCommand::Batch { commands } });
+    }
+    parse_value(value)
+}
+
+/// One command with its id, named if this version does not know it. The
+/// commands of a `batch` are parsed the same way, keeping their own ids.
+fn parse_value(mut value: serde_json::Value) -> serde_json::Result<Envelope> {
+    let name = command_name(&value);
+    let batched = match value.get_mut("commands") {
+        Some(commands @ serde_json::Value::Array(_)) if name == "batch" => Some(std::mem::replace(commands, serde_json::Value::Array(Vec::new()))),
+        _ => None,
+    };
+    let mut envelope: Envelope = serde_json::from_value(value)?;
+    match (&mut envelope.command, batched) {
+        (InputCommand::Batch { commands }, Some(serde_json::Value::Array(batched))) => {
+            *commands = batched.into_iter().map(parse_value).collect::<serde_json::Result<_>>()?;
+        }
+        (InputCommand::Unknown, _) => envelope.command = InputCommand::Unsupported { name },
+        _ => {}
+    }
+    Ok(envelope)
+}
+
+fn command_name(value: &serde_json::Value) -> String {
+    value.get("command").and_then(|name| name.as_str()).unwrap_or_default().to_string()
+}
+
+/// The reply to send for a command: its own, or an `ack` when it has none
+/// but was sent with an id.
+fn reply(id: Option<String>, message: Option<OutputMessage>) -> Option<Reply> {
+    match (id, message) {
+        (id, Some(message)) => Some(Reply { id, message }),
+        (Some(id), None) => Some(Reply { id: Some(id), message: OutputMessage::Ack }),
+        (None, None) => None,
+    }
+}
+
 /// Thread dedicated to listening for commands from the extension via stdin.
-fn input_listener() {
+/// Flags are flipped at once; commands that need the clipboard, and batches,
+/// which must run in order, are passed to the polling loop.
+fn input_listener(requests: Sender<Envelope>) {
     let stdin = io::stdin();
+    let (mut second, mut nacks) = (Instant::now(), 0);
     for line in stdin.lock().lines() {
         match line {
             Ok(json_line) => {
-                match serde_json::from_str::<InputCommand>(&json_line) {
-                    Ok(cmd) => {
-                        match cmd {
-                            InputCommand::Pause => {
-                                let mut monitoring = IS_MONITORING_ACTIVE.lock().unwrap();
-                                *monitoring = false;
-                            }
-                            InputCommand::Resume => {
-                                let mut monitoring = IS_MONITORING_ACTIVE.lock().unwrap();
-                                *monitoring = true;
-                            }
-                            InputCommand::SetCaptureAll { value } => {
-                                let mut capture_all = CAPTURE_ALL.lock().unwrap();
-                                *capture_all = value;
-                            }
+                match parse_command(&json_line) {
+                    Ok(envelope) => {
+                        if !apply_toggle(&envelope.command) {
+                            let _ = requests.send(envelope);
+                        } else if let Some(ack) = reply(envelope.id, None) {
+                            let _ = send_json(&ack);
                         }
                     }
                     Err(e) => {
                         let error_msg = format!("Rust Input Parsing Error: {} | Raw: {}", e, json_line);
-                        // Log error internally, don't flood stdout as that disrupts main flow
                         eprintln!("{}", error_msg);
+                        // Tell the extension too, unless that would flood stdout.
+                        if second.elapsed() >= Duration::from_secs(1) {
+                            (second, nacks) = (Instant::now(), 0);
+                        }
+                        if nacks < NACKS_PER_SECOND {
+                            nacks += 1;
+                            let _ = send_json(&nack(&json_line, &e));
+                        }
                     }
                 }
             }
@@ -113,61 +822,493 @@ fn input_listener() {
     }
 }
 
+/// The message announcing a change of lock state, applying it to AUTO_PAUSE.
+fn lock_transition(was_locked: bool, locked: bool) -> Option<OutputMessage> {
+    if locked == was_locked {
+        return None;
+    }
+    let reason = "screen_locked";
+    *AUTO_PAUSE.lock().unwrap() = locked.then_some(reason);
+    Some(match locked {
+        true => OutputMessage::AutoPaused { reason: reason.to_string() },
+        false => OutputMessage::AutoResumed { reason: reason.to_string() },
+    })
+}
+
+/// Thread pausing monitoring while the screen is locked, so that nothing
+/// copied on the lock screen or by another user is captured. Exits at once
+/// where the lock state cannot be read.
+fn lock_watcher() {
+    if session::is_locked().is_none() {
+        return;
+    }
+    // Starting out locked is announced like any other lock.
+    let mut was_locked = false;
+    loop {
+        let locked = session::is_locked().unwrap_or(was_locked);
+        if let Some(message) = lock_transition(was_locked, locked) {
+            let _ = send_json(&message);
+        }
+        was_locked = locked;
+        thread::sleep(LOCK_POLL);
+    }
+}
+
 fn main() -> Result<()> {
+    crash::install_hook();
+    let args = parse_args()?;
+    let sandbox = Sandbox::new(args.roots)?;
+    let search = Arc::new(VectorSearch::from_env());
+    let mut monitor = Monitor::new(sandbox, search);
+    monitor.excluded_apps.extend(args.exclude_apps);
+    if let Some(path) = &args.config {
+        let config = Config::load(path)?;
+        monitor.transforms = config.transforms;
+        monitor.duplicate_window = Duration::from_millis(config.triggers.duplicate_window_ms);
+        monitor.split_batches = config.triggers.split_batches;
+        monitor.suppress_trigger_updates = config.triggers.suppress_updates;
+        monitor.max_inline_bytes = config.updates.max_inline_bytes;
+        monitor.history = History::new(config.updates.max_history_bytes);
+    }
+    if let Some(path) = &args.record {
+        monitor.recorder = Some(Recorder::create(path)?);
+    }
+
     // 1. Initialize Clipboard
-    let mut clipboard = match Clipboard::new() {
+    let backend = if args.replay.is_some() { "mock" } else { args.backend.as_str() };
+    // Mock clipboards are private to their process, so any number may run.
+    let _instance = match backend {
+        "mock" => None,
+        _ => match instance::acquire(args.takeover) {
+            Ok(lock) => Some(lock),
+            Err(e) => {
+                let _ = send_json(&OutputMessage::Error {
+                    code: Some("already_running".to_string()),
+                    message: format!("{:#}", e),
+                    backtrace: None,
+                });
+                return Err(e);
+            }
+        },
+    };
+    let mut clipboard = match clipboard_core::open(backend) {
         Ok(cb) => cb,
         Err(e) => {
-            let error_msg = format!("Failed to init Clipboard: {}", e);
+            let error_msg = format!("Failed to init Clipboard: {:#}", e);
             let _ = send_json(&OutputMessage::Error {
+                code: None,
                 message: error_msg.clone(),
+                backtrace: None,
             });
             return Err(anyhow::anyhow!(error_msg));
         }
     };
 
     // 2. Start input listener thread
-    thread::spawn(input_listener);
+    let (requests, commands) = mpsc::channel();
+    thread::spawn(move || input_listener(requests));
+    if args.replay.is_none() && args.backend != "mock" {
+        thread::spawn(lock_watcher);
+    }
 
     // 3. Signal Ready
     send_json(&OutputMessage::Ready)?;
+    let mut notifier = service::Notifier::from_env();
+    notifier.ready();
 
-    let mut last_hash: Option<String> = None;
+    if let Some(path) = &args.replay {
+        return replay(&mut monitor, path);
+    }
 
     // 4. Main Polling Loop
-    loop {
+    'polling: loop {
         thread::sleep(Duration::from_millis(500));
+        notifier.ping();
+
+        for Envelope { id, command } in commands.try_iter() {
+            if let Some(reply) = reply(id, monitor.handle(command, clipboard.as_mut())) {
+                if send_json(&reply).is_err() { break 'polling; }
+            }
+        }
+        if let Some(notice) = monitor.clear_if_due(clipboard.as_mut()) {
+            if send_json(&notice).is_err() { break 'polling; }
+        }
         
         // Check if monitoring is paused
-        if !*IS_MONITORING_ACTIVE.lock().unwrap() {
+        if !*IS_MONITORING_ACTIVE.lock().unwrap() || AUTO_PAUSE.lock().unwrap().is_some() {
              thread::sleep(Duration::from_secs(1)); // Sleep longer while paused
              continue;
         }
 
-        match clipboard.get_text() {
-            Ok(content) => {
-                let (update_msg, trigger_msg, new_hash) = process_clipboard_content(content, &last_hash);
+        for msg in monitor.poll(clipboard.as_mut()) {
+            if send_json(&msg).is_err() { break 'polling; }
+        }
+    }
 
-                // Only send standard updates if CAPTURE_ALL is enabled
-                // Triggers (XML) are always sent if found.
-                if *CAPTURE_ALL.lock().unwrap() {
-                    if let Some(msg) = update_msg {
-                        if let Err(_) = send_json(&msg) { break; }
-                    }
-                }
+    notifier.stopping();
+    Ok(())
+}
 
-                // If a trigger was found (XML commands), send it immediately
-                if let Some(msg) = trigger_msg {
-                    if let Err(_) = send_json(&msg) { break; }
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clipboard_core::Owner;
+    use std::fs;
 
-                last_hash = Some(new_hash);
-            }
-            Err(_) => {
-                // Ignore transient errors
-            }
+    /// A monitor without a sandbox, a handle for putting content on its mock
+    /// clipboard, and the backend it polls.
+    fn test_monitor() -> (Monitor, MockBackend, MockBackend) {
+        let clipboard = MockBackend::new();
+        let backend = clipboard.clone();
+        (Monitor::new(Sandbox::default(), Arc::new(VectorSearch::from_env())), clipboard, backend)
+    }
+
+    #[test]
+    fn test_poll_reports_new_triggers_once() {
+        let (mut monitor, clipboard, mut backend) = test_monitor();
+        assert!(monitor.poll(&mut backend).is_empty());
+
+        clipboard.put(Content::Text("<qdrant-search>auth flow</qdrant-search>".to_string()));
+        let messages = monitor.poll(&mut backend);
+        assert!(matches!(&messages[..], [OutputMessage::TriggerXml { xml_payloads, .. }] if xml_payloads.len() == 1));
+        assert!(monitor.poll(&mut backend).is_empty());
+    }
+
+    #[test]
+    fn test_repeated_commands_are_suppressed_within_the_window() {
+        let (mut monitor, clipboard, mut backend) = test_monitor();
+        clipboard.put(Content::Text("<qdrant-search>auth</qdrant-search>".to_string()));
+        assert!(matches!(&monitor.poll(&mut backend)[..], [OutputMessage::TriggerXml { .. }]));
+
+        clipboard.put(Content::Text("again: <qdrant-search>auth</qdrant-search> <qdrant-search>db</qdrant-search>".to_string()));
+        let messages = monitor.poll(&mut backend);
+        assert!(matches!(&messages[..], [
+            OutputMessage::TriggerSuppressed { reason, hash },
+            OutputMessage::TriggerXml { xml_payloads, ids },
+        ] if reason == "duplicate" && *hash == text_hash("<qdrant-search>auth</qdrant-search>")
+            && xml_payloads == &["<qdrant-search>db</qdrant-search>"] && ids[0].ends_with("-1")));
+    }
+
+    #[test]
+    fn test_triggers_only_mode_keeps_no_copies() {
+        let (mut monitor, clipboard, mut backend) = test_monitor();
+        clipboard.put(Content::Text("kept".to_string()));
+        monitor.poll(&mut backend);
+        assert_eq!(monitor.history.len(), 1);
+
+        monitor.handle(serde_json::from_str(r#"{"command":"set_mode","mode":"triggers_only"}"#).unwrap(), &mut backend);
+        assert!(monitor.history.is_empty());
+        clipboard.put(Content::Text("private".to_string()));
+        assert!(monitor.poll(&mut backend).is_empty());
+        clipboard.put(Content::Text("<qdrant-search>auth</qdrant-search>".to_string()));
+        assert!(matches!(&monitor.poll(&mut backend)[..], [OutputMessage::TriggerXml { .. }]));
+        assert!(monitor.history.is_empty() && monitor.last_text.is_none());
+    }
+
+    #[test]
+    fn test_recordings_keep_copies_the_output_filters_drop() {
+        let (mut monitor, clipboard, mut backend) = test_monitor();
+        let path = std::env::temp_dir().join(format!("clipboard-monitor-record-{}.jsonl", std::process::id()));
+        monitor.recorder = Some(Recorder::create(&path).unwrap());
+        monitor.mode = Mode::TriggersOnly;
+        monitor.suppress_trigger_updates = true;
+
+        clipboard.put(Content::Text("private".to_string()));
+        monitor.poll(&mut backend);
+        clipboard.put(Content::Text("<qdrant-search>auth</qdrant-search>".to_string()));
+        monitor.poll(&mut backend);
+        monitor.poll(&mut backend);
+        let texts: Vec<String> = recording::load(&path).unwrap().into_iter().map(|event| event.text).collect();
+        assert_eq!(texts, ["private", "<qdrant-search>auth</qdrant-search>"]);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_split_trigger_numbers_each_command() {
+        let trigger = check_for_triggers("<qdrant-search>a</qdrant-search><qdrant-read path=\"x\"/>", "0cc175b9c0f1").unwrap();
+        let split = split_trigger(trigger);
+        assert_eq!(split.len(), 2);
+        assert_eq!(split[1], OutputMessage::TriggerCommand {
+            xml_payload: "<qdrant-read path=\"x\"/>".to_string(),
+            id: "0cc175b9-1".to_string(),
+            index: 1,
+            total: 2,
+            batch_id: "0cc175b9".to_string(),
+        });
+    }
+
+    #[test]
+    fn test_trigger_copies_can_be_kept_out_of_the_history() {
+        let (mut monitor, clipboard, mut backend) = test_monitor();
+        monitor.suppress_trigger_updates = true;
+        clipboard.put(Content::Text("<qdrant-search>auth</qdrant-search>".to_string()));
+        assert!(matches!(&monitor.poll(&mut backend)[..], [OutputMessage::TriggerXml { .. }]));
+        assert!(monitor.history.is_empty());
+        clipboard.put(Content::Text("plain".to_string()));
+        monitor.poll(&mut backend);
+        assert_eq!(monitor.history.len(), 1);
+    }
+
+    #[test]
+    fn test_emptied_clipboard_is_announced_once() {
+        let (mut monitor, clipboard, mut backend) = test_monitor();
+        assert!(monitor.poll(&mut backend).is_empty());
+        clipboard.put(Content::Text("text".to_string()));
+        monitor.poll(&mut backend);
+
+        clipboard.put(Content::Empty);
+        assert!(matches!(&monitor.poll(&mut backend)[..], [OutputMessage::ClipboardCleared { cleared: true, reason: None, .. }]));
+        assert!(monitor.poll(&mut backend).is_empty());
+
+        clipboard.put(Content::Text("text".to_string()));
+        monitor.poll(&mut backend);
+        clipboard.put(Content::Files(vec![PathBuf::from("/tmp/a")]));
+        assert!(monitor.poll(&mut backend).is_empty());
+    }
+
+    #[test]
+    fn test_updates_list_the_formats_on_offer() {
+        let clipboard = MockBackend::new();
+        clipboard.put_with_formats(Content::Text("cell".to_string()), &["text/html", "Biff12"]);
+        let (update, _, _) = process_clipboard_content("cell", &None, &Transforms::default(), None);
+        let update = with_formats(update.unwrap(), &mut clipboard.clone());
+        assert!(matches!(update, OutputMessage::ClipboardUpdate { formats, .. } if formats == ["text/plain", "text/html", "Biff12"]));
+    }
+
+    #[test]
+    fn test_large_updates_are_sent_by_reference() {
+        let (mut monitor, _, mut backend) = test_monitor();
+        monitor.max_inline_bytes = 4;
+        let (update, _, _) = process_clipboard_content("large copy", &None, &Transforms::default(), None);
+        let OutputMessage::ClipboardUpdateRef { path, size, .. } = monitor.offload(update.unwrap()) else {
+            panic!("expected a reference");
+        };
+        assert_eq!((fs::read_to_string(&path).unwrap(), size), ("large copy".to_string(), 10));
+
+        assert_eq!(monitor.handle(InputCommand::ReleaseRef { path: PathBuf::from(&path) }, &mut backend), None);
+        assert!(!Path::new(&path).exists());
+        let (update, _, _) = process_clipboard_content("tiny", &None, &Transforms::default(), None);
+        assert!(matches!(monitor.offload(update.unwrap()), OutputMessage::ClipboardUpdate { .. }));
+    }
+
+    #[test]
+    fn test_huge_copies_are_hashed_by_prefix_and_length() {
+        let huge = "a".repeat(HASH_PREFIX_BYTES + 10);
+        assert_eq!(text_hash(&huge), text_hash(&format!("{}b", &huge[..huge.len() - 1])));
+        assert_ne!(text_hash(&huge), text_hash(&format!("{}a", huge)));
+        assert_eq!(text_hash("a"), format!("{:x}", md5::compute("a")));
+    }
+
+    #[test]
+    fn test_an_array_line_runs_as_one_batch() {
+        let (mut monitor, _, mut backend) = test_monitor();
+        let batch = parse_command(r#"[{"command":"set_excluded_apps","apps":[]},{"command":"chunk_content","hash":"x","max_tokens":0}]"#).unwrap().command;
+        let Some(OutputMessage::BatchResult { replies }) = monitor.handle(batch, &mut backend) else {
+            panic!("expected a batch result");
+        };
+        assert!(monitor.excluded_apps.is_empty());
+        assert!(matches!(&replies[..], [Reply { message: OutputMessage::Ack, .. }, Reply { message: OutputMessage::Chunks { error: Some(_), .. }, .. }]));
+    }
+
+    #[test]
+    fn test_replies_echo_the_command_id() {
+        let (mut monitor, _, mut backend) = test_monitor();
+        let mut answer = |line: &str| {
+            let Envelope { id, command } = parse_command(line).unwrap();
+            reply(id, monitor.handle(command, &mut backend)).map(|reply| serde_json::to_string(&reply).unwrap())
+        };
+        assert_eq!(answer(r#"{"command":"start_accumulate","id":"7"}"#).unwrap(), r#"{"id":"7","type":"ack"}"#);
+        assert_eq!(answer(r#"{"command":"stop_accumulate"}"#), None);
+        let flushed = answer(r#"{"id":"8","command":"flush_accumulated"}"#).unwrap();
+        assert!(flushed.starts_with(r#"{"id":"8","type":"accumulated","#), "{}", flushed);
+    }
+
+    #[test]
+    fn test_batch_replies_carry_each_command_id() {
+        let (mut monitor, _, mut backend) = test_monitor();
+        let mut answer = |line: &str| {
+            let Envelope { id, command } = parse_command(line).unwrap();
+            serde_json::to_value(reply(id, monitor.handle(command, &mut backend)).unwrap()).unwrap()
+        };
+        let array = answer(r#"[{"command":"start_accumulate","id":"a"},{"command":"stop_accumulate"},{"command":"teleport","id":"c"}]"#);
+        assert_eq!(array["id"], serde_json::Value::Null);
+        assert_eq!(
+            array["replies"],
+            serde_json::json!([{ "id": "a", "type": "ack" }, { "type": "ack" }, { "id": "c", "type": "unsupported", "command": "teleport" }])
+        );
+        let object = answer(r#"{"command":"batch","id":"outer","commands":[{"command":"unpin_history_item","hash":"x","id":"inner"}]}"#);
+        assert_eq!(object["id"], "outer");
+        assert_eq!(object["replies"][0]["id"], "inner");
+    }
+
+    #[test]
+    fn test_invalid_lines_are_nacked_with_their_id() {
+        let line = r#"{"command":"clear_after","id":"9","ms":"soon"}"#;
+        let error = parse_command(line).unwrap_err();
+        assert!(matches!(nack(line, &error), OutputMessage::Nack { raw_prefix, id: Some(id), .. } if raw_prefix == line && id == "9"));
+        let line = "x".repeat(500);
+        let error = parse_command(&line).unwrap_err();
+        assert!(matches!(nack(&line, &error), OutputMessage::Nack { raw_prefix, id: None, .. } if raw_prefix.len() == 120));
+    }
+
+    #[test]
+    fn test_unknown_commands_are_unsupported_not_invalid() {
+        let (mut monitor, _, mut backend) = test_monitor();
+        let Envelope { id, command } = parse_command(r#"{"command":"set_theme","id":"3","theme":"dark"}"#).unwrap();
+        assert_eq!(
+            reply(id, monitor.handle(command, &mut backend)),
+            Some(Reply { id: Some("3".to_string()), message: OutputMessage::Unsupported { command: "set_theme".to_string() } })
+        );
+        let batch = parse_command(r#"[{"command":"diagnose"},{"command":"teleport"}]"#).unwrap().command;
+        assert!(matches!(monitor.handle(batch, &mut backend), Some(OutputMessage::BatchResult { replies })
+            if replies[1].message == OutputMessage::Unsupported { command: "teleport".to_string() }));
+    }
+
+    #[test]
+    fn test_system_history_is_merged_without_duplicates() {
+        let (mut monitor, _, _) = test_monitor();
+        monitor.history.push(text_hash("seen"), "seen".to_string());
+        let OutputMessage::History { mut entries, .. } = monitor.history_reply(false) else {
+            panic!("expected a history");
+        };
+        merge_system_history(&mut entries, vec!["seen".to_string(), "older".to_string(), "older".to_string()]);
+        let sources: Vec<(&str, &str)> = entries.iter().map(|entry| (entry.content.as_str(), entry.source)).collect();
+        assert_eq!(sources, [("seen", "monitor"), ("older", "system")]);
+        if !cfg!(windows) {
+            assert!(matches!(monitor.history_reply(true), OutputMessage::History { system_error: Some(_), .. }));
         }
     }
 
-    Ok(())
-}
\ No newline at end of file
+    #[test]
+    fn test_html_is_written_with_its_text_and_not_reported_back() {
+        let (mut monitor, handle, mut clipboard) = test_monitor();
+        let command = InputCommand::SetClipboardHtml { html: "<b>bold</b>".to_string(), text: "bold".to_string() };
+
+        let reply = monitor.handle(command, &mut clipboard);
+        assert_eq!(reply, Some(OutputMessage::ClipboardWritten { format: "html", error: None }));
+        assert_eq!(handle.content(), Content::Html { html: "<b>bold</b>".to_string(), text: "bold".to_string() });
+        assert!(monitor.poll(&mut clipboard).is_empty());
+    }
+
+    #[test]
+    fn test_copy_files_puts_a_file_list_on_the_clipboard() {
+        let file = std::env::temp_dir().join(format!("clipboard-monitor-copy-{}.txt", std::process::id()));
+        std::fs::write(&file, "x").unwrap();
+        let (mut monitor, handle, mut clipboard) = test_monitor();
+        let path = file.to_string_lossy().to_string();
+
+        let reply = monitor.handle(InputCommand::CopyFiles { paths: vec![path.clone()] }, &mut clipboard);
+        assert_eq!(reply, Some(OutputMessage::FilesCopied { count: 1, error: None }));
+        assert_eq!(handle.content(), Content::Files(vec![file.clone()]));
+        let reply = monitor.handle(InputCommand::CopyFiles { paths: vec![format!("{}.missing", path)] }, &mut clipboard);
+        assert!(matches!(reply, Some(OutputMessage::FilesCopied { count: 0, error: Some(_) })));
+        let _ = std::fs::remove_file(file);
+    }
+
+    #[test]
+    fn test_copies_from_excluded_apps_are_ignored() {
+        let (mut monitor, clipboard, mut backend) = test_monitor();
+        clipboard.set_owner(Some(Owner { pid: Some(7), name: "KeePassXC.exe".to_string() }));
+        clipboard.put(Content::Text("<qdrant-search>hunter2</qdrant-search>".to_string()));
+        assert!(monitor.poll(&mut backend).is_empty());
+
+        monitor.handle(InputCommand::SetExcludedApps { apps: vec!["bitwarden".to_string()] }, &mut backend);
+        clipboard.put(Content::Text("<qdrant-search>auth</qdrant-search>".to_string()));
+        assert_eq!(monitor.poll(&mut backend).len(), 1);
+    }
+
+    #[test]
+    fn test_clear_after_wipes_only_unchanged_content() {
+        let (mut monitor, clipboard, mut backend) = test_monitor();
+        clipboard.put(Content::Text("hunter2".to_string()));
+
+        assert_eq!(monitor.handle(InputCommand::ClearAfter { ms: 0 }, &mut backend), None);
+        assert!(matches!(monitor.clear_if_due(&mut backend), Some(OutputMessage::ClipboardCleared { cleared: true, .. })));
+        assert_eq!(clipboard.content(), Content::Empty);
+        assert_eq!(monitor.clear_if_due(&mut backend), None);
+        assert!(monitor.poll(&mut backend).is_empty());
+
+        clipboard.put(Content::Text("hunter2".to_string()));
+        monitor.handle(InputCommand::ClearAfter { ms: 0 }, &mut backend);
+        clipboard.put(Content::Text("something else".to_string()));
+        assert!(matches!(monitor.clear_if_due(&mut backend),
+            Some(OutputMessage::ClipboardCleared { cleared: false, reason: Some(reason), .. }) if reason == "changed"));
+        assert_eq!(clipboard.content(), Content::Text("something else".to_string()));
+    }
+
+    #[test]
+    fn test_terminal_output_is_cleaned_and_flagged() {
+        let raw = "\x1b[1;31merror\x1b[0m: build failed";
+        let (update, _, _) = process_clipboard_content(raw, &None, &Transforms::default(), None);
+        assert!(matches!(update, Some(OutputMessage::ClipboardUpdate { content, terminal_output: true, raw_content: Some(original), .. })
+            if content == "error: build failed" && original == raw));
+
+        let (update, _, _) = process_clipboard_content("plain", &None, &Transforms::default(), None);
+        let json = serde_json::to_string(&update.unwrap()).unwrap();
+        assert!(!json.contains("terminal_output") && !json.contains("raw_content"));
+    }
+
+    #[test]
+    fn test_updates_carry_a_diff_from_the_previous_copy() {
+        let delta = |previous| match process_clipboard_content("fn a() {}\nfn b() {}\n", &None, &Transforms::default(), previous) {
+            (Some(OutputMessage::ClipboardUpdate { delta, .. }), _, _) => delta,
+            other => panic!("expected an update, got {:?}", other),
+        };
+        assert_eq!(delta(None), None);
+        let diff = delta(Some("fn a() {}\n")).unwrap();
+        assert!(diff.starts_with("--- previous\n+++ current\n"));
+        assert!(diff.contains("\n+fn b() {}\n"));
+
+        let (mut monitor, clipboard, _) = test_monitor();
+        clipboard.put_with_formats(Content::Text("hunter2".to_string()), &["ExcludeClipboardContentFromMonitorProcessing"]);
+        monitor.last_text = Some("before".to_string());
+        monitor.poll(&mut clipboard.clone());
+        assert_eq!(monitor.last_text, None);
+    }
+
+    #[test]
+    fn test_flush_joins_accumulated_copies() {
+        let (mut monitor, clipboard, mut backend) = test_monitor();
+        clipboard.put(Content::Text("before".to_string()));
+        monitor.poll(&mut backend);
+
+        monitor.handle(InputCommand::StartAccumulate, &mut backend);
+        for snippet in ["one", "two"] {
+            clipboard.put(Content::Text(snippet.to_string()));
+            monitor.poll(&mut backend);
+        }
+        monitor.handle(InputCommand::StopAccumulate, &mut backend);
+        clipboard.put(Content::Text("after".to_string()));
+        monitor.poll(&mut backend);
+
+        let reply = monitor.handle(InputCommand::FlushAccumulated { separator: Some(" + ".to_string()) }, &mut backend);
+        assert_eq!(reply, Some(OutputMessage::Accumulated { content: "one + two".to_string(), count: 2, error: None }));
+        assert_eq!(clipboard.content(), Content::Text("one + two".to_string()));
+        assert!(monitor.poll(&mut backend).is_empty());
+    }
+
+    #[test]
+    fn test_lock_transitions_pause_and_resume() {
+        assert_eq!(lock_transition(false, false), None);
+        assert_eq!(lock_transition(false, true), Some(OutputMessage::AutoPaused { reason: "screen_locked".to_string() }));
+        assert_eq!(*AUTO_PAUSE.lock().unwrap(), Some("screen_locked"));
+        assert_eq!(lock_transition(true, false), Some(OutputMessage::AutoResumed { reason: "screen_locked".to_string() }));
+        assert_eq!(*AUTO_PAUSE.lock().unwrap(), None);
+    }
+
+    #[test]
+    fn test_concealed_content_is_skipped() {
+        let (mut monitor, clipboard, mut backend) = test_monitor();
+        clipboard.put_with_formats(
+            Content::Text("<qdrant-search>hunter2</qdrant-search>".to_string()),
+            &["text/plain", "ExcludeClipboardContentFromMonitorProcessing"],
+        );
+
+        let messages = monitor.poll(&mut backend);
+        assert!(matches!(&messages[..], [OutputMessage::ContentSkipped { reason, detail, .. }]
+            if reason == "concealed" && detail == "ExcludeClipboardContentFromMonitorProcessing"));
+        let json = serde_json::to_string(&messages[0]).unwrap();
+        assert!(!json.contains("hunter2"));
+        assert!(monitor.poll(&mut backend).is_empty());
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Copies too large to send inline, written to files the extension reads and
+/// then releases. Files left unreleased are removed when the monitor exits.
+pub struct Offloads {
+    dir: PathBuf,
+    paths: HashSet<PathBuf>,
+}
+
+impl Offloads {
+    pub fn new() -> Self {
+        Offloads {
+            dir: std::env::temp_dir().join(format!("clipboard-monitor-{}", std::process::id())),
+            paths: HashSet::new(),
+        }
+    }
+
+    /// Writes `content` to a file named after `hash`, readable by this user
+    /// alone, and returns its path.
+    pub fn write(&mut self, hash: &str, content: &str) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir).with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let path = self.dir.join(format!("{}.txt", hash));
+        let mut options = OpenOptions::new();
+        options.create(true).write(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        file.write_all(content.as_bytes()).with_context(|| format!("Failed to write {}", path.display()))?;
+        self.paths.insert(path.clone());
+        Ok(path)
+    }
+
+    /// Deletes a file made by `write`; any other path is refused.
+    pub fn release(&mut self, path: &Path) -> Result<()> {
+        if !self.paths.remove(path) {
+            bail!("{} is not a clipboard reference", path.display());
+        }
+        fs::remove_file(path).with_context(|| format!("Failed to remove {}", path.display()))
+    }
+}
+
+impl Drop for Offloads {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
+        let _ = fs::remove_dir(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_deletes_only_written_files() {
+        let mut offloads = Offloads::new();
+        let path = offloads.write("0cc175b9", "large").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "large");
+        assert!(offloads.release(Path::new("/etc/passwd")).is_err());
+        offloads.release(&path).unwrap();
+        assert!(!path.exists());
+        assert!(offloads.release(&path).is_err());
+    }
+}
@@ -1,23 +1,316 @@
 use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::blob::Base64Blob;
+use crate::json::JsonInfo;
+use crate::search::SearchHit;
 
 /// Messages sent from the Rust clipboard monitor to the VS Code extension.
 #[derive(Debug, Serialize, PartialEq)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum OutputMessage {
     /// Standard update message, used to populate the history list.
+    /// `transforms_applied` names the configured transforms that changed
+    /// `content`, in the order they ran. Terminal output has its ANSI escape
+    /// sequences removed from `content`, with the copy as made in
+    /// `raw_content`. `delta` is a unified diff from the previous update's
+    /// content, left out when there is none or it would be too large.
+    /// `hash` names the entry in later commands such as `chunk_content`.
     ClipboardUpdate {
         content: String,
+        hash: String,
         timestamp: String,
         length: usize,
+        /// Approximately how many tokens `content` is to a language model.
+        estimated_tokens: usize,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        transforms_applied: Vec<String>,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        terminal_output: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        raw_content: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        delta: Option<String>,
+        /// Every format on offer with the text, as the platform names them,
+        /// e.g. `text/html` alongside `text/plain` for a copy from a browser.
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        formats: Vec<String>,
+        /// Base64 payloads in the content, such as images pasted out of logs.
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        base64_blobs: Vec<Base64Blob>,
+        /// Set when the content looks like a JSON object or array.
+        #[serde(flatten)]
+        json: Option<JsonInfo>,
+    },
+    /// A copy too large to send inline: `path` holds its content, which is
+    /// `size` bytes, until `release_ref` deletes it.
+    ClipboardUpdateRef {
+        path: String,
+        size: usize,
+        hash: String,
+        timestamp: String,
     },
     /// Triggered when one or more XML commands are detected in the clipboard.
+    /// `ids[i]` identifies `xml_payloads[i]` in every later result.
     TriggerXml {
         xml_payloads: Vec<String>,
+        ids: Vec<String>,
+    },
+    /// One command of a copy, sent instead of `trigger_xml` when batches
+    /// are split: command `index` of `total`, all sharing `batch_id`.
+    TriggerCommand {
+        xml_payload: String,
+        id: String,
+        index: usize,
+        total: usize,
+        batch_id: String,
+    },
+    /// A qdrant command left out of a `trigger_xml` because the same
+    /// payload, by `hash`, was run moments ago, e.g. when a chat message is
+    /// copied twice.
+    TriggerSuppressed {
+        reason: String,
+        hash: String,
+    },
+    /// A Git conflict block found in the clipboard, one message per block.
+    /// `labels` are the texts after the `<<<<<<<` and `>>>>>>>` markers.
+    TriggerConflict {
+        ours: String,
+        theirs: String,
+        labels: Vec<String>,
+    },
+    /// Outcome of executing a `<qdrant-file>` command inside the sandbox.
+    FileActionResult {
+        id: String,
+        path: String,
+        action: String,
+        success: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        bytes_written: Option<usize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// Reply to a command that puts content on the clipboard, such as
+    /// `set_clipboard_image`; `format` names what was written.
+    ClipboardWritten {
+        format: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// Reply to `read_file`. `truncated` is set when the file is longer
+    /// than `max_bytes`, so `content` holds only its start.
+    FileRead {
+        path: String,
+        content: String,
+        size: u64,
+        truncated: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// Reply to `write_file`.
+    FileWritten {
+        path: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        bytes_written: Option<usize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// Reply to `copy_files`: how many files were put on the clipboard, or
+    /// why none were.
+    FilesCopied {
+        count: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// Result of a command executed on the extension's behalf, e.g. the
+    /// content of a file requested by `<qdrant-read>`.
+    CommandResult {
+        id: String,
+        kind: String,
+        content: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
     },
+    /// Hits from the vector index for a `<qdrant-search>` command.
+    SearchResult {
+        id: String,
+        query: String,
+        results: Vec<SearchHit>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// Clipboard content that was deliberately not captured, described by
+    /// metadata only. `reason` is `concealed` when the owner marked it
+    /// secret, with the hint it used in `detail`.
+    ContentSkipped {
+        reason: String,
+        detail: String,
+        timestamp: String,
+    },
+    /// The clipboard went from holding text to holding nothing, or a
+    /// `clear_after` fired, with `reason` `clear_after`. A `clear_after`
+    /// leaves the clipboard alone, with `cleared` false and `reason`
+    /// `changed`, if something else was copied in the meantime.
+    ClipboardCleared {
+        cleared: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+        timestamp: String,
+    },
+    /// Reply to `flush_accumulated`: the collected copies joined together,
+    /// which are also put back on the clipboard unless `error` says why not.
+    Accumulated {
+        content: String,
+        count: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// Reply to `chunk_content`, the entry's chunks in order. `error` is set,
+    /// with no chunks, when the entry is no longer held.
+    Chunks {
+        hash: String,
+        chunks: Vec<Chunk>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// Monitoring stopped by itself, e.g. with reason `screen_locked`.
+    AutoPaused {
+        reason: String,
+    },
+    /// Monitoring resumed after an `auto_paused` with the same reason.
+    AutoResumed {
+        reason: String,
+    },
+    /// A failure not tied to one command. `code` is set for failures the
+    /// extension handles specially, such as `panic`, which is followed by
+    /// the process exiting.
     Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        code: Option<String>,
         message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        backtrace: Option<String>,
     },
     Ready,
+    /// Confirms a command sent with an `id` that has no other reply.
+    Ack,
+    /// Reply to `get_history`: recent copies, newest first, the monitor's
+    /// own followed by any older ones only the system kept.
+    History {
+        entries: Vec<HistoryEntry>,
+        /// Why the system history could not be read, when it was asked for.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        system_error: Option<String>,
+    },
+    /// A command this version does not know, so that newer extensions can
+    /// probe for optional features.
+    Unsupported {
+        command: String,
+    },
+    /// A line from the extension that is not a valid command: its start,
+    /// why it was rejected, and its `id` if one could be read.
+    Nack {
+        raw_prefix: String,
+        error: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+    },
+    /// Reply to a batch of commands: one reply per command, in order, an
+    /// `ack` for those that have no other, each with its command's `id`.
+    BatchResult {
+        replies: Vec<Reply>,
+    },
+    /// Reply to `diagnose`, for triaging copies the monitor does not see.
+    Diagnostics {
+        /// `windows`, `macos`, `wayland`, `x11`, or `none`.
+        display_server: String,
+        /// The clipboard backend in use, e.g. `arboard`.
+        backend: String,
+        clipboard_owner: Option<ClipboardOwner>,
+        /// Formats currently on offer, as the platform names them.
+        formats: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        formats_error: Option<String>,
+        monitoring: bool,
+        capture_all: bool,
+        /// Failures in the last ten minutes by kind: `read` for clipboard
+        /// reads, `command` for clipboard commands, `offload` for large
+        /// copies that could not be written out.
+        recent_errors: BTreeMap<String, usize>,
+        last_error: Option<String>,
+    },
+}
+
+/// A message answering a command, carrying the command's `id` if it had one.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Reply {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(flatten)]
+    pub message: OutputMessage,
+}
+
+/// A copy in a `history` reply. `source` is `monitor` for copies the
+/// monitor saw itself and `system` for those read from the OS history.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct HistoryEntry {
+    pub hash: String,
+    pub content: String,
+    pub source: &'static str,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+}
+
+/// One piece of an entry split by `chunk_content`.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Chunk {
+    pub content: String,
+    pub estimated_tokens: usize,
+}
+
+/// The process that put the current content on the clipboard.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ClipboardOwner {
+    pub pid: Option<u32>,
+    pub name: String,
+}
+
+/// How `write_file` treats an existing file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteMode {
+    /// Fail if the file exists.
+    Create,
+    /// Replace the file's content, creating it if need be.
+    #[default]
+    Overwrite,
+    /// Add to the end of the file, creating it if need be.
+    Append,
+}
+
+/// What the monitor does with ordinary copies, set by `set_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    /// Copies are kept for commands such as `chunk_content`, and sent when
+    /// capture-all is on.
+    Normal,
+    /// Only triggers are sent; copies are neither sent, whatever
+    /// capture-all says, nor kept.
+    TriggersOnly,
+}
+
+/// A command as sent: any command may carry an `id`, which its reply echoes.
+/// A command with an `id` always gets a reply, if only an `ack`. Later
+/// messages a command causes, such as the `clipboard_cleared` of a
+/// `clear_after`, do not carry it.
+#[derive(Debug, serde::Deserialize)]
+pub struct Envelope {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(flatten)]
+    pub command: InputCommand,
 }
 
 /// Commands sent from the VS Code extension to the Rust clipboard monitor.
@@ -31,12 +324,87 @@ pub enum InputCommand {
     /// Command to enable/disable capturing of all clipboard content.
     /// If false (default), only XML triggers are sent.
     SetCaptureAll { value: bool },
+    /// Ask for a `diagnostics` message describing the clipboard environment.
+    Diagnose,
+    /// Replace the process names (e.g. `KeePassXC`) whose copies are ignored.
+    SetExcludedApps { apps: Vec<String> },
+    /// Wipe the clipboard after `ms` milliseconds unless its content changes
+    /// first, e.g. 30 seconds after copying a password.
+    ClearAfter { ms: u64 },
+    /// Start appending every new copy to a buffer, for pasting them all at
+    /// once with `flush_accumulated`.
+    StartAccumulate,
+    /// Stop appending copies; the buffer is kept until flushed.
+    StopAccumulate,
+    /// Join the buffered copies with `separator` (a newline by default),
+    /// put them on the clipboard, and empty the buffer.
+    FlushAccumulated {
+        #[serde(default)]
+        separator: Option<String>,
+    },
+    /// Read a text file inside the sandbox roots, at most `max_bytes` of it
+    /// and never more than the `<qdrant-read>` limit.
+    ReadFile {
+        path: String,
+        #[serde(default)]
+        max_bytes: Option<u64>,
+    },
+    /// Write a text file inside the sandbox roots, under the same rules as
+    /// `<qdrant-file>`.
+    WriteFile {
+        path: String,
+        content: String,
+        #[serde(default)]
+        mode: WriteMode,
+    },
+    /// Put `paths` on the clipboard as a file list, as `clipboard-files`
+    /// does. Every path must be an existing file.
+    CopyFiles { paths: Vec<String> },
+    /// Put `html` on the clipboard with `text` as its plain-text alternative,
+    /// for targets that do not take HTML.
+    SetClipboardHtml { html: String, text: String },
+    /// Put a base64 PNG, optionally a `data:` URL, on the clipboard as an
+    /// image. `width` and `height`, when given, must match the PNG's.
+    SetClipboardImage {
+        data: String,
+        #[serde(default)]
+        width: Option<usize>,
+        #[serde(default)]
+        height: Option<usize>,
+    },
+    /// Delete the file of a `clipboard_update_ref` once it has been read.
+    ReleaseRef { path: PathBuf },
+    /// Run `commands` in order and answer with one `batch_result`. A line
+    /// holding a JSON array of commands is read as a batch too.
+    Batch { commands: Vec<Envelope> },
+    /// Ask for a `history` of recent copies; with `include_system`, merged
+    /// with the Windows clipboard history.
+    GetHistory {
+        #[serde(default)]
+        include_system: bool,
+    },
+    /// Keep the recent entry with `hash` until it is unpinned: it is never
+    /// evicted and comes first in `history`. Named by hash, as `id` is the
+    /// command's own correlation id.
+    PinHistoryItem { hash: String },
+    UnpinHistoryItem { hash: String },
+    /// Switch between `normal` and `triggers_only`.
+    SetMode { mode: Mode },
+    /// Split the recent entry with `hash` into chunks of at most about
+    /// `max_tokens` tokens each.
+    ChunkContent { hash: String, max_tokens: usize },
+    /// A command this version does not know, named; never sent as such.
+    #[serde(skip_deserializing)]
+    Unsupported { name: String },
+    /// Any `command` this version does not know, before `parse_command`
+    /// makes it an `Unsupported`.
+    #[serde(other)]
+    Unknown,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde_json;
 
     #[test]
     fn test_trigger_xml_serialization() {
@@ -45,9 +413,11 @@ mod tests {
                 "<qdrant-file path=\"a.ts\" action=\"create\"></qdrant-file>".to_string(),
                 "<qdrant-search>query</qdrant-search>".to_string(),
             ],
+            ids: vec!["0cc175b9-0".to_string(), "0cc175b9-1".to_string()],
         };
         let json = serde_json::to_string(&msg).unwrap();
         assert!(json.contains(r#""type":"trigger_xml""#));
         assert!(json.contains(r#""xml_payloads":["#));
+        assert!(json.contains(r#""ids":["0cc175b9-0","0cc175b9-1"]"#));
     }
 }
\ No newline at end of file
@@ -0,0 +1,76 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+/// One clipboard change: its content and when it was seen, in milliseconds
+/// since recording started. Recordings are JSONL, one event per line.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Event {
+    pub at_ms: u64,
+    pub text: String,
+}
+
+/// Appends every clipboard change to a `--record` file as it is seen.
+pub struct Recorder {
+    out: BufWriter<File>,
+    started: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)
+            .with_context(|| format!("Failed to create recording {}", path.display()))?;
+        Ok(Recorder { out: BufWriter::new(file), started: Instant::now() })
+    }
+
+    /// Writes an event and flushes it, so a crash loses nothing recorded.
+    pub fn record(&mut self, text: &str) -> Result<()> {
+        let event = Event { at_ms: self.started.elapsed().as_millis() as u64, text: text.to_string() };
+        serde_json::to_writer(&mut self.out, &event)?;
+        self.out.write_all(b"\n")?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Reads a recording for `--replay`, in order. Blank lines are skipped.
+pub fn load(path: &Path) -> Result<Vec<Event>> {
+    let text = fs::read_to_string(path).with_context(|| format!("Failed to read recording {}", path.display()))?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).with_context(|| format!("{}:{}: invalid event", path.display(), i + 1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording_round_trips() {
+        let path = std::env::temp_dir().join(format!("clipboard-monitor-recording-{}.jsonl", std::process::id()));
+        let mut recorder = Recorder::create(&path).unwrap();
+        recorder.record("first").unwrap();
+        recorder.record("<qdrant-search>second\nline</qdrant-search>").unwrap();
+        drop(recorder);
+
+        let events = load(&path).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].text, "<qdrant-search>second\nline</qdrant-search>");
+        assert!(events[0].at_ms <= events[1].at_ms);
+
+        fs::write(&path, "{\"at_ms\":0,\"text\":\"ok\"}\n\nnot json\n").unwrap();
+        assert!(format!("{:#}", load(&path).unwrap_err()).contains(":3: invalid event"));
+        let _ = fs::remove_file(path);
+    }
+}
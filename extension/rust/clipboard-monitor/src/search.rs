@@ -0,0 +1,138 @@
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::env;
+use std::time::Duration;
+use ureq::Agent;
+
+const DEFAULT_QDRANT_URL: &str = "http://localhost:6333";
+const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+const DEFAULT_MODEL: &str = "nomic-embed-text";
+const DEFAULT_COLLECTION: &str = "codebase_context";
+/// Number of hits returned for each `<qdrant-search>`.
+const SEARCH_LIMIT: usize = 10;
+/// Snippets are cut to this many characters.
+const SNIPPET_CHARS: usize = 500;
+
+/// One chunk matching a search, as stored by the indexer.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SearchHit {
+    pub path: String,
+    pub start_line: Option<u64>,
+    pub end_line: Option<u64>,
+    pub snippet: String,
+    pub score: f64,
+}
+
+/// Embeds queries with Ollama and searches the Qdrant collection shared with
+/// the indexer. Configured through the same environment variables.
+pub struct VectorSearch {
+    agent: Agent,
+    qdrant_url: String,
+    api_key: Option<String>,
+    ollama_url: String,
+    model: String,
+    collection: String,
+}
+
+impl VectorSearch {
+    /// Reads `QDRANT_URL`, `QDRANT_API_KEY`, `QDRANT_COLLECTION`, `OLLAMA_HOST`
+    /// and `OLLAMA_MODEL`, falling back to the indexer's defaults.
+    pub fn from_env() -> Self {
+        let var = |name: &str| env::var(name).ok().filter(|v| !v.is_empty());
+        let agent = Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(30)))
+            .build()
+            .into();
+        VectorSearch {
+            agent,
+            qdrant_url: var("QDRANT_URL").unwrap_or_else(|| DEFAULT_QDRANT_URL.to_string()),
+            api_key: var("QDRANT_API_KEY"),
+            ollama_url: var("OLLAMA_HOST").unwrap_or_else(|| DEFAULT_OLLAMA_URL.to_string()),
+            model: var("OLLAMA_MODEL").unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+            collection: var("QDRANT_COLLECTION").unwrap_or_else(|| DEFAULT_COLLECTION.to_string()),
+        }
+    }
+
+    pub fn search(&self, query: &str) -> Result<Vec<SearchHit>> {
+        let url = format!("{}/api/embeddings", self.ollama_url.trim_end_matches('/'));
+        let embedding: Value = self
+            .agent
+            .post(&url)
+            .send_json(json!({ "model": self.model, "prompt": query }))
+            .with_context(|| format!("Embedding request to {} failed", self.ollama_url))?
+            .body_mut()
+            .read_json()
+            .context("Invalid embedding response")?;
+        let vector = embedding["embedding"].clone();
+        if !vector.is_array() {
+            bail!("Embedding response has no vector");
+        }
+
+        let url = format!(
+            "{}/collections/{}/points/search",
+            self.qdrant_url.trim_end_matches('/'),
+            self.collection
+        );
+        let mut request = self.agent.post(&url);
+        if let Some(key) = &self.api_key {
+            request = request.header("api-key", key);
+        }
+        let reply: Value = request
+            .send_json(json!({ "vector": vector, "limit": SEARCH_LIMIT, "with_payload": true }))
+            .with_context(|| format!("Qdrant search at {} failed", self.qdrant_url))?
+            .body_mut()
+            .read_json()
+            .context("Invalid Qdrant search response")?;
+        Ok(hits(&reply["result"]))
+    }
+}
+
+/// Maps Qdrant scored points to hits. Payload fields use the indexer's camelCase names.
+fn hits(result: &Value) -> Vec<SearchHit> {
+    result
+        .as_array()
+        .map(|points| {
+            points
+                .iter()
+                .map(|point| {
+                    let payload = &point["payload"];
+                    SearchHit {
+                        path: payload["filePath"].as_str().unwrap_or_default().to_string(),
+                        start_line: payload["startLine"].as_u64(),
+                        end_line: payload["endLine"].as_u64(),
+                        snippet: payload["content"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .chars()
+                            .take(SNIPPET_CHARS)
+                            .collect(),
+                        score: point["score"].as_f64().unwrap_or_default(),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hits_map_indexer_payloads() {
+        let result = json!([{ "id": 1, "score": 0.75, "payload": {
+            "filePath": "src/auth.ts", "startLine": 3, "endLine": 9, "content": "function login() {}"
+        } }]);
+        assert_eq!(
+            hits(&result),
+            vec![SearchHit {
+                path: "src/auth.ts".to_string(),
+                start_line: Some(3),
+                end_line: Some(9),
+                snippet: "function login() {}".to_string(),
+                score: 0.75,
+            }]
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! Readiness and liveness for systemd, alongside the `ready` message on
+//! stdout. Under a `Type=notify` unit the monitor sends `READY=1` once it is
+//! polling, and with `WatchdogSec=` a `WATCHDOG=1` from every turn of the
+//! loop, so a hung clipboard read gets the service restarted. Elsewhere, or
+//! when started by the extension, there is no notify socket and this does
+//! nothing. Windows services are not covered: the monitor has no service
+//! control entry point, so a wrapper such as a scheduled task or NSSM
+//! supervises the process itself.
+
+use std::env;
+use std::time::{Duration, Instant};
+
+pub struct Notifier {
+    socket: Option<String>,
+    watchdog: Option<Duration>,
+    last_ping: Instant,
+}
+
+impl Notifier {
+    /// Reads `NOTIFY_SOCKET` and `WATCHDOG_USEC` as systemd sets them.
+    pub fn from_env() -> Self {
+        let watchdog = watchdog_interval(
+            env::var("WATCHDOG_USEC").ok().as_deref(),
+            env::var("WATCHDOG_PID").ok().as_deref(),
+            std::process::id(),
+        );
+        Notifier::new(env::var("NOTIFY_SOCKET").ok(), watchdog)
+    }
+
+    fn new(socket: Option<String>, watchdog: Option<Duration>) -> Self {
+        Notifier { socket, watchdog, last_ping: Instant::now() }
+    }
+
+    pub fn ready(&mut self) {
+        self.notify("READY=1");
+        self.last_ping = Instant::now();
+    }
+
+    /// Pets the watchdog once half its interval has passed.
+    pub fn ping(&mut self) {
+        let Some(interval) = self.watchdog else { return };
+        if self.last_ping.elapsed() >= interval / 2 {
+            self.notify("WATCHDOG=1");
+            self.last_ping = Instant::now();
+        }
+    }
+
+    pub fn stopping(&self) {
+        self.notify("STOPPING=1");
+    }
+
+    #[cfg(unix)]
+    fn notify(&self, state: &str) {
+        use std::os::unix::net::UnixDatagram;
+        let Some(socket) = &self.socket else { return };
+        let Ok(sender) = UnixDatagram::unbound() else { return };
+        // Delivery is best effort; the service manager has its own timeouts.
+        let _ = match socket.strip_prefix('@') {
+            #[cfg(target_os = "linux")]
+            Some(name) => {
+                use std::os::linux::net::SocketAddrExt;
+                std::os::unix::net::SocketAddr::from_abstract_name(name)
+                    .and_then(|address| sender.send_to_addr(state.as_bytes(), &address))
+            }
+            _ => sender.send_to(state.as_bytes(), socket),
+        };
+    }
+
+    #[cfg(not(unix))]
+    fn notify(&self, _state: &str) {}
+}
+
+/// The watchdog interval, unless none is set or it is meant for another
+/// process.
+fn watchdog_interval(usec: Option<&str>, pid: Option<&str>, own_pid: u32) -> Option<Duration> {
+    if pid.is_some_and(|pid| pid.parse() != Ok(own_pid)) {
+        return None;
+    }
+    let usec: u64 = usec?.parse().ok()?;
+    (usec > 0).then(|| Duration::from_micros(usec))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watchdog_interval_is_only_taken_for_this_process() {
+        assert_eq!(watchdog_interval(Some("2000000"), None, 7), Some(Duration::from_secs(2)));
+        assert_eq!(watchdog_interval(Some("2000000"), Some("7"), 7), Some(Duration::from_secs(2)));
+        assert_eq!(watchdog_interval(Some("2000000"), Some("8"), 7), None);
+        assert_eq!(watchdog_interval(Some("0"), None, 7), None);
+        assert_eq!(watchdog_interval(None, None, 7), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ready_and_watchdog_reach_the_notify_socket() {
+        use std::os::unix::net::UnixDatagram;
+        let path = env::temp_dir().join(format!("clipboard-monitor-notify-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let receiver = UnixDatagram::bind(&path).unwrap();
+        receiver.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+        let mut notifier = Notifier::new(Some(path.to_string_lossy().to_string()), Some(Duration::ZERO));
+
+        let mut buffer = [0; 64];
+        notifier.ready();
+        let n = receiver.recv(&mut buffer).unwrap();
+        assert_eq!(&buffer[..n], b"READY=1");
+        notifier.ping();
+        let n = receiver.recv(&mut buffer).unwrap();
+        assert_eq!(&buffer[..n], b"WATCHDOG=1");
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,71 @@
+//! Whether the user's session is locked, polled because the lock/unlock
+//! notifications on every platform need an event loop the monitor lacks.
+//! `None` means the platform could not tell.
+
+/// systemd-logind's LockedHint, which GNOME, KDE and most lockers set.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn is_locked() -> Option<bool> {
+    let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+    let output = std::process::Command::new("loginctl")
+        .args(["show-session", &session, "--property=LockedHint", "--value"])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// The input desktop cannot be opened while the secure lock screen has it.
+#[cfg(windows)]
+pub fn is_locked() -> Option<bool> {
+    use windows_sys::Win32::System::StationsAndDesktops::{CloseDesktop, OpenInputDesktop, DESKTOP_SWITCHDESKTOP};
+
+    let desktop = unsafe { OpenInputDesktop(0, 0, DESKTOP_SWITCHDESKTOP) };
+    if desktop.is_null() {
+        return Some(true);
+    }
+    unsafe { CloseDesktop(desktop) };
+    Some(false)
+}
+
+/// `CGSSessionScreenIsLocked` in the current session's dictionary.
+#[cfg(target_os = "macos")]
+pub fn is_locked() -> Option<bool> {
+    use std::ffi::c_void;
+    use std::os::raw::c_char;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithCString(allocator: *const c_void, text: *const c_char, encoding: u32) -> *const c_void;
+        fn CFDictionaryGetValue(dictionary: *const c_void, key: *const c_void) -> *const c_void;
+        fn CFBooleanGetValue(boolean: *const c_void) -> u8;
+        fn CFRelease(object: *const c_void);
+    }
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGSessionCopyCurrentDictionary() -> *const c_void;
+    }
+    const UTF8: u32 = 0x0800_0100;
+
+    unsafe {
+        let session = CGSessionCopyCurrentDictionary();
+        if session.is_null() {
+            return None;
+        }
+        let key = CFStringCreateWithCString(std::ptr::null(), c"CGSSessionScreenIsLocked".as_ptr(), UTF8);
+        let value = CFDictionaryGetValue(session, key);
+        // The key is absent while the screen is unlocked.
+        let locked = !value.is_null() && CFBooleanGetValue(value) != 0;
+        CFRelease(key);
+        CFRelease(session);
+        Some(locked)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn is_locked() -> Option<bool> {
+    None
+}
@@ -0,0 +1,36 @@
+//! The operating system's own clipboard history, which holds copies made
+//! before the monitor started or while it was paused. Only Windows 10 and
+//! later keep one that other programs may read.
+
+use anyhow::Result;
+
+/// The text items of the system clipboard history, newest first.
+#[cfg(windows)]
+pub fn read() -> Result<Vec<String>> {
+    use anyhow::bail;
+    use windows::ApplicationModel::DataTransfer::{Clipboard, ClipboardHistoryItemsResultStatus, StandardDataFormats};
+
+    if !Clipboard::IsHistoryEnabled()? {
+        bail!("Clipboard history is turned off in Windows settings");
+    }
+    let result = Clipboard::GetHistoryItemsAsync()?.join()?;
+    let status = result.Status()?;
+    if status != ClipboardHistoryItemsResultStatus::Success {
+        bail!("Windows refused the clipboard history: {:?}", status);
+    }
+    let items = result.Items()?;
+    let text_format = StandardDataFormats::Text()?;
+    let mut texts = Vec::new();
+    for i in 0..items.Size()? {
+        let content = items.GetAt(i)?.Content()?;
+        if content.Contains(&text_format)? {
+            texts.push(content.GetTextAsync()?.join()?.to_string());
+        }
+    }
+    Ok(texts)
+}
+
+#[cfg(not(windows))]
+pub fn read() -> Result<Vec<String>> {
+    anyhow::bail!("The system clipboard history can only be read on Windows")
+}
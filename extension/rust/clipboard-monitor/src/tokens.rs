@@ -0,0 +1,46 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// The pre-tokenizer split used by GPT-style BPE vocabularies: contractions,
+/// words with their leading space, digit groups of up to three, punctuation
+/// runs, and whitespace.
+static PIECES: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"'(?:s|t|re|ve|m|ll|d)| ?\p{L}+| ?\p{N}{1,3}| ?[^\s\p{L}\p{N}]+|\s+").unwrap()
+});
+
+/// Roughly how many tokens a BPE tokenizer makes of `text`, typically within
+/// 15% for English prose and code. Each piece the pre-tokenizer splits off
+/// costs one token per five characters, not counting its leading space,
+/// except that letters outside Latin scripts, such as CJK, cost one each.
+pub fn estimate(text: &str) -> usize {
+    PIECES
+        .find_iter(text)
+        .map(|piece| {
+            let piece = piece.as_str();
+            if piece.trim().is_empty() {
+                return 1;
+            }
+            let piece = piece.strip_prefix(' ').unwrap_or(piece);
+            let wide = piece.chars().filter(|c| c.is_alphabetic() && (*c as u32) > 0x2FF).count();
+            let narrow = piece.chars().count() - wide;
+            wide + narrow.div_ceil(5)
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_is_close_for_prose_and_code() {
+        assert_eq!(estimate(""), 0);
+        // A BPE tokenizer gives 10 tokens here.
+        let prose = estimate("The quick brown fox jumps over the lazy dog.");
+        assert!((9..=12).contains(&prose), "{}", prose);
+        // And 11 here.
+        let code = estimate("fn main() { println!(\"hi\"); }");
+        assert!((9..=14).contains(&code), "{}", code);
+        assert_eq!(estimate("你好世界"), 4);
+    }
+}
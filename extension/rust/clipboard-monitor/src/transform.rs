@@ -0,0 +1,101 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+
+/// CSI sequences such as colours (`ESC [ 31 m`) and OSC sequences such as
+/// window titles and hyperlinks, ended by BEL or `ESC \`.
+static ANSI: Lazy<Regex> = Lazy::new(|| Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)").unwrap());
+
+/// A Windows drive path, e.g. `C:\Users\me\file.txt`, up to the first
+/// character that cannot appear unquoted in one.
+static WINDOWS_PATH: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\b([A-Za-z]):\\([^\s"'<>|*?:]*)"#).unwrap());
+
+/// A transform: whether it is enabled, its name, and the rewrite itself.
+type Step = (bool, &'static str, fn(&str) -> String);
+
+/// The optional rewrites applied to copied text before it is reported, each
+/// off unless enabled in the `[transforms]` table of the config file. ANSI
+/// escape sequences need no transform: terminal output is always cleaned.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Transforms {
+    /// Remove leading and trailing whitespace.
+    #[serde(default)]
+    pub trim: bool,
+    /// Replace typographic quotes with their ASCII forms.
+    #[serde(default)]
+    pub smart_quotes: bool,
+    /// Rewrite `C:\dir\file` as `/mnt/c/dir/file`.
+    #[serde(default)]
+    pub wsl_paths: bool,
+}
+
+impl Transforms {
+    /// Runs the enabled transforms in order, returning the result and the
+    /// names of those that changed something.
+    pub fn apply(&self, text: &str) -> (String, Vec<String>) {
+        let steps: [Step; 3] = [
+            (self.smart_quotes, "smart_quotes", normalize_quotes),
+            (self.wsl_paths, "wsl_paths", wsl_paths),
+            (self.trim, "trim", |text| text.trim().to_string()),
+        ];
+        let mut text = text.to_string();
+        let mut applied = Vec::new();
+        for (enabled, name, step) in steps {
+            if !enabled {
+                continue;
+            }
+            let next = step(&text);
+            if next != text {
+                applied.push(name.to_string());
+                text = next;
+            }
+        }
+        (text, applied)
+    }
+}
+
+/// Whether `text` holds ANSI escape sequences, i.e. was copied from a
+/// terminal.
+pub fn has_ansi(text: &str) -> bool {
+    ANSI.is_match(text)
+}
+
+pub fn strip_ansi(text: &str) -> String {
+    ANSI.replace_all(text, "").into_owned()
+}
+
+fn normalize_quotes(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' => '\'',
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' => '"',
+            c => c,
+        })
+        .collect()
+}
+
+fn wsl_paths(text: &str) -> String {
+    WINDOWS_PATH
+        .replace_all(text, |caps: &Captures| {
+            format!("/mnt/{}/{}", caps[1].to_lowercase(), caps[2].replace('\\', "/"))
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enabled_transforms_run_and_are_reported() {
+        let transforms = Transforms { trim: true, smart_quotes: false, wsl_paths: true };
+        let (text, applied) = transforms.apply("  error in C:\\Users\\me\\app.rs \u{201C}x\u{201D}\n");
+        assert_eq!(text, "error in /mnt/c/Users/me/app.rs \u{201C}x\u{201D}");
+        assert_eq!(applied, ["wsl_paths", "trim"]);
+
+        let (text, applied) = transforms.apply("unchanged");
+        assert_eq!(text, "unchanged");
+        assert!(applied.is_empty());
+    }
+}
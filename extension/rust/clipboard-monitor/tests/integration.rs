@@ -40,6 +40,7 @@ fn test_binary_starts_and_emits_ready() {
 
     // Clean up child process
     let _ = child.kill();
+    let _ = child.wait();
 
     // Verify JSON output
     // We trim to handle different newline characters (\r\n vs \n) across OSs
@@ -48,4 +49,58 @@ fn test_binary_starts_and_emits_ready() {
         "First message was not ready signal: {}",
         line
     );
-}
\ No newline at end of file
+}
+#[test]
+fn test_mock_backend_runs_without_a_display() {
+    compile_binary();
+
+    let mut target_path = PathBuf::from("target/release/clipboard-monitor");
+    target_path.set_extension(env::consts::EXE_EXTENSION);
+
+    let mut child = Command::new(target_path)
+        .args(["--backend", "mock"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to start child process");
+
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().expect("Failed to open stdout"))
+        .read_line(&mut line)
+        .expect("Failed to read line");
+    let _ = child.kill();
+    let _ = child.wait();
+
+    assert!(
+        line.trim().contains(r#"{"type":"ready"}"#),
+        "First message was not ready signal: {}",
+        line
+    );
+}
+
+#[test]
+fn test_replay_emits_recorded_triggers_and_exits() {
+    compile_binary();
+
+    let recording = env::temp_dir().join(format!("clipboard-monitor-replay-{}.jsonl", std::process::id()));
+    std::fs::write(
+        &recording,
+        "{\"at_ms\":0,\"text\":\"plain text\"}\n{\"at_ms\":50,\"text\":\"<qdrant-search>auth</qdrant-search>\"}\n",
+    )
+    .unwrap();
+    let mut target_path = PathBuf::from("target/release/clipboard-monitor");
+    target_path.set_extension(env::consts::EXE_EXTENSION);
+
+    let output = Command::new(target_path)
+        .arg("--replay")
+        .arg(&recording)
+        .output()
+        .expect("Failed to run replay");
+    let _ = std::fs::remove_file(&recording);
+
+    assert!(output.status.success());
+    let lines: Vec<String> = String::from_utf8_lossy(&output.stdout).lines().map(String::from).collect();
+    assert_eq!(lines.len(), 2, "unexpected output: {:?}", lines);
+    assert!(lines[0].contains(r#"{"type":"ready"}"#));
+    assert!(lines[1].contains(r#""type":"trigger_xml""#));
+    assert!(lines[1].contains("<qdrant-search>auth</qdrant-search>"));
+}
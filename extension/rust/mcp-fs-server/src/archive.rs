@@ -0,0 +1,469 @@
+//! The zip and tar.gz formats of `create_archive` and `extract_archive`,
+//! limited to what those tools need: regular files and directories, zip
+//! entries stored or deflated without zip64, and ustar tar with GNU and pax
+//! long names. Entry names are checked on the way in, so a name can never
+//! point outside the extraction directory.
+
+use anyhow::{bail, Context as _, Result};
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use flate2::read::{DeflateDecoder, GzDecoder};
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::Compression;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::error::{ErrorCode, Failure};
+
+/// Most bytes an archive may expand to, whatever it claims; guards against
+/// archives built to fill the disk.
+pub const MAX_EXTRACTED_BYTES: u64 = 1 << 30;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Zip,
+    TarGz,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Result<Format> {
+        match name {
+            "zip" => Ok(Format::Zip),
+            "tar.gz" | "tgz" => Ok(Format::TarGz),
+            other => bail!("Unknown archive format {:?}; use zip or tar.gz", other),
+        }
+    }
+
+    /// The format named by `path`'s extension.
+    pub fn from_path(path: &str) -> Result<Format> {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".zip") {
+            Ok(Format::Zip)
+        } else if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            Ok(Format::TarGz)
+        } else {
+            bail!("Cannot tell the archive format of {}; pass format", path)
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Zip => "zip",
+            Format::TarGz => "tar.gz",
+        }
+    }
+}
+
+/// A file to archive: its name inside the archive and its content.
+pub struct Source {
+    pub name: String,
+    pub content: Vec<u8>,
+    pub modified: SystemTime,
+}
+
+/// An archive member. Directories have no content.
+#[derive(Debug, PartialEq)]
+pub struct Entry {
+    pub name: PathBuf,
+    pub content: Option<Vec<u8>>,
+}
+
+/// What reading an archive found: the members it can extract and the names
+/// of those it cannot, such as symlinks.
+#[derive(Debug, Default)]
+pub struct Contents {
+    pub entries: Vec<Entry>,
+    pub skipped: Vec<String>,
+}
+
+pub fn write(format: Format, sources: &[Source]) -> Result<Vec<u8>> {
+    match format {
+        Format::Zip => write_zip(sources),
+        Format::TarGz => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&tar(sources)?)?;
+            Ok(encoder.finish()?)
+        }
+    }
+}
+
+pub fn read(format: Format, bytes: &[u8]) -> Result<Contents> {
+    match format {
+        Format::Zip => read_zip(bytes),
+        Format::TarGz => {
+            let mut tar = Vec::new();
+            GzDecoder::new(bytes)
+                .take(MAX_EXTRACTED_BYTES + 1)
+                .read_to_end(&mut tar)
+                .context("Archive is not valid gzip")?;
+            if tar.len() as u64 > MAX_EXTRACTED_BYTES {
+                bail!(Failure::new(
+                    ErrorCode::TooLarge,
+                    format!("Archive expands to more than {} bytes", MAX_EXTRACTED_BYTES)
+                ));
+            }
+            read_tar(&tar)
+        }
+    }
+}
+
+/// The relative path an entry named `name` extracts to, or an error if it
+/// is absolute or climbs out with `..`.
+pub fn safe_name(name: &str) -> Result<PathBuf> {
+    let normalized = name.replace('\\', "/");
+    let mut path = PathBuf::new();
+    for component in Path::new(&normalized).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => bail!("Archive entry {:?} points outside the destination", name),
+        }
+    }
+    if path.as_os_str().is_empty() {
+        bail!("Archive entry {:?} has no name", name);
+    }
+    Ok(path)
+}
+
+fn dos_time(time: SystemTime) -> (u16, u16) {
+    let time = DateTime::<Utc>::from(time);
+    if time.year() < 1980 {
+        return (0, 0x21);
+    }
+    let date = ((time.year() - 1980) as u16) << 9 | (time.month() as u16) << 5 | time.day() as u16;
+    let clock =
+        (time.hour() as u16) << 11 | (time.minute() as u16) << 5 | (time.second() / 2) as u16;
+    (clock, date)
+}
+
+fn write_zip(sources: &[Source]) -> Result<Vec<u8>> {
+    if sources.len() > u16::MAX as usize {
+        bail!("zip archives hold at most {} files", u16::MAX);
+    }
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+    for source in sources {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&source.content)?;
+        let compressed = encoder.finish()?;
+        let sizes = (
+            u32::try_from(compressed.len()),
+            u32::try_from(source.content.len()),
+            u32::try_from(out.len()),
+        );
+        let (Ok(compressed_size), Ok(size), Ok(offset)) = sizes else {
+            bail!("{} is too large for a zip archive", source.name);
+        };
+        let crc = crc32fast::hash(&source.content);
+        let (clock, date) = dos_time(source.modified);
+        let name = source.name.as_bytes();
+
+        // Fields shared by the local and central headers, from the version
+        // needed to the extra field length.
+        let mut common = Vec::new();
+        common.extend(20u16.to_le_bytes());
+        common.extend(0x0800u16.to_le_bytes()); // UTF-8 names
+        common.extend(8u16.to_le_bytes()); // deflate
+        common.extend(clock.to_le_bytes());
+        common.extend(date.to_le_bytes());
+        common.extend(crc.to_le_bytes());
+        common.extend(compressed_size.to_le_bytes());
+        common.extend(size.to_le_bytes());
+        common.extend((name.len() as u16).to_le_bytes());
+        common.extend(0u16.to_le_bytes());
+
+        out.extend(0x04034b50u32.to_le_bytes());
+        out.extend(&common);
+        out.extend(name);
+        out.extend(&compressed);
+
+        central.extend(0x02014b50u32.to_le_bytes());
+        central.extend((3u16 << 8 | 20).to_le_bytes()); // made by Unix
+        central.extend(&common);
+        central.extend([0; 6]); // comment length, disk, internal attributes
+        central.extend((0o100644u32 << 16).to_le_bytes());
+        central.extend(offset.to_le_bytes());
+        central.extend(name);
+    }
+    let central_offset = u32::try_from(out.len()).context("Archive is too large for zip")?;
+    out.extend(&central);
+    out.extend(0x06054b50u32.to_le_bytes());
+    out.extend([0; 4]);
+    out.extend((sources.len() as u16).to_le_bytes());
+    out.extend((sources.len() as u16).to_le_bytes());
+    out.extend((central.len() as u32).to_le_bytes());
+    out.extend(central_offset.to_le_bytes());
+    out.extend(0u16.to_le_bytes());
+    Ok(out)
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Result<u16> {
+    let field = bytes.get(at..at + 2).context("Archive is truncated")?;
+    Ok(u16::from_le_bytes([field[0], field[1]]))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Result<u32> {
+    let field = bytes.get(at..at + 4).context("Archive is truncated")?;
+    Ok(u32::from_le_bytes([field[0], field[1], field[2], field[3]]))
+}
+
+fn read_zip(bytes: &[u8]) -> Result<Contents> {
+    // The end record is the last thing in the file, followed only by a
+    // comment of at most 64 KiB.
+    let search_from = bytes.len().saturating_sub(22 + u16::MAX as usize);
+    let end = (search_from..bytes.len().saturating_sub(21))
+        .rev()
+        .find(|&at| bytes[at..].starts_with(&0x06054b50u32.to_le_bytes()))
+        .context("Archive is not a zip file")?;
+    let count = u16_at(bytes, end + 10)?;
+    let mut at = u32_at(bytes, end + 16)? as usize;
+    if count == u16::MAX || at == u32::MAX as usize {
+        bail!("zip64 archives are not supported");
+    }
+
+    let mut contents = Contents::default();
+    let mut total = 0u64;
+    for _ in 0..count {
+        if u32_at(bytes, at)? != 0x02014b50 {
+            bail!("Archive has a corrupt central directory");
+        }
+        let flags = u16_at(bytes, at + 8)?;
+        let method = u16_at(bytes, at + 10)?;
+        let crc = u32_at(bytes, at + 16)?;
+        let compressed_size = u32_at(bytes, at + 20)? as usize;
+        let size = u32_at(bytes, at + 24)? as u64;
+        let name_len = u16_at(bytes, at + 28)? as usize;
+        let extra_len = u16_at(bytes, at + 30)? as usize;
+        let comment_len = u16_at(bytes, at + 32)? as usize;
+        let external = u32_at(bytes, at + 38)?;
+        let local = u32_at(bytes, at + 42)? as usize;
+        let raw_name = bytes
+            .get(at + 46..at + 46 + name_len)
+            .context("Archive is truncated")?;
+        let name = String::from_utf8_lossy(raw_name).into_owned();
+        at += 46 + name_len + extra_len + comment_len;
+
+        if (external >> 16) & 0o170000 == 0o120000 {
+            contents.skipped.push(name);
+            continue;
+        }
+        let path = safe_name(&name)?;
+        if name.ends_with('/') {
+            contents.entries.push(Entry {
+                name: path,
+                content: None,
+            });
+            continue;
+        }
+        if flags & 1 != 0 {
+            bail!("{} is encrypted", name);
+        }
+        total += size;
+        if total > MAX_EXTRACTED_BYTES {
+            bail!(Failure::new(
+                ErrorCode::TooLarge,
+                format!("Archive expands to more than {} bytes", MAX_EXTRACTED_BYTES)
+            ));
+        }
+        let start =
+            local + 30 + u16_at(bytes, local + 26)? as usize + u16_at(bytes, local + 28)? as usize;
+        let data = bytes
+            .get(start..start + compressed_size)
+            .context("Archive is truncated")?;
+        let content = match method {
+            0 => data.to_vec(),
+            8 => {
+                let mut content = Vec::new();
+                DeflateDecoder::new(data)
+                    .take(size)
+                    .read_to_end(&mut content)
+                    .with_context(|| format!("Failed to inflate {}", name))?;
+                content
+            }
+            other => bail!("{} uses unsupported compression method {}", name, other),
+        };
+        if content.len() as u64 != size || crc32fast::hash(&content) != crc {
+            bail!("{} is corrupt: its checksum does not match", name);
+        }
+        contents.entries.push(Entry {
+            name: path,
+            content: Some(content),
+        });
+    }
+    Ok(contents)
+}
+
+/// Fills an octal tar header field, NUL-terminated.
+fn octal(field: &mut [u8], value: u64) -> Result<()> {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    if digits.len() >= field.len() {
+        bail!("Value {} does not fit a tar header", value);
+    }
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+    Ok(())
+}
+
+fn tar(sources: &[Source]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    for source in sources {
+        let mut header = [0u8; 512];
+        let name = source.name.as_bytes();
+        if name.len() <= 100 {
+            header[..name.len()].copy_from_slice(name);
+        } else {
+            // ustar splits long names at a slash into a prefix and a name.
+            let split = (name.len() - 101..name.len().min(156))
+                .rev()
+                .find(|&i| name[i] == b'/')
+                .with_context(|| format!("{} is too long a name for tar", source.name))?;
+            header[..name.len() - split - 1].copy_from_slice(&name[split + 1..]);
+            header[345..345 + split].copy_from_slice(&name[..split]);
+        }
+        let modified = source
+            .modified
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        octal(&mut header[100..108], 0o644)?;
+        octal(&mut header[108..116], 0)?;
+        octal(&mut header[116..124], 0)?;
+        octal(&mut header[124..136], source.content.len() as u64)?;
+        octal(&mut header[136..148], modified)?;
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        header[148..156].fill(b' ');
+        let checksum: u64 = header.iter().map(|&b| b as u64).sum();
+        octal(&mut header[148..155], checksum)?;
+        out.extend(header);
+        out.extend(&source.content);
+        out.resize(out.len().next_multiple_of(512), 0);
+    }
+    out.extend([0; 1024]);
+    Ok(out)
+}
+
+/// The text of a NUL-terminated tar header field.
+fn field(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn parse_octal(bytes: &[u8]) -> Result<u64> {
+    let text = field(bytes);
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).with_context(|| format!("Corrupt tar header field {:?}", text))
+}
+
+/// The `path` record of a pax extended header.
+fn pax_path(data: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(data);
+    text.lines().find_map(|record| {
+        let (_, pair) = record.split_once(' ')?;
+        pair.strip_prefix("path=").map(str::to_string)
+    })
+}
+
+fn read_tar(bytes: &[u8]) -> Result<Contents> {
+    let mut contents = Contents::default();
+    let mut long_name = None;
+    let mut at = 0;
+    while let Some(header) = bytes.get(at..at + 512) {
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let size = parse_octal(&header[124..136])? as usize;
+        let data = bytes
+            .get(at + 512..at + 512 + size)
+            .context("Archive is truncated")?;
+        at += 512 + size.next_multiple_of(512);
+
+        let kind = header[156];
+        match kind {
+            b'L' => {
+                long_name = Some(field(data));
+                continue;
+            }
+            b'x' => {
+                long_name = pax_path(data);
+                continue;
+            }
+            b'g' => continue,
+            _ => {}
+        }
+        let name = long_name.take().unwrap_or_else(|| {
+            let prefix = field(&header[345..500]);
+            let name = field(&header[..100]);
+            match prefix.is_empty() {
+                true => name,
+                false => format!("{}/{}", prefix, name),
+            }
+        });
+        let content = match kind {
+            b'0' | 0 | b'7' => Some(data.to_vec()),
+            b'5' => None,
+            _ => {
+                contents.skipped.push(name);
+                continue;
+            }
+        };
+        contents.entries.push(Entry {
+            name: safe_name(&name)?,
+            content,
+        });
+    }
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources() -> Vec<Source> {
+        let long = format!("{}/{}.txt", "nested".repeat(20), "deep");
+        ["a.txt", "dir/b.bin", long.as_str()]
+            .into_iter()
+            .map(|name| Source {
+                name: name.to_string(),
+                content: name.repeat(50).into_bytes(),
+                modified: SystemTime::now(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_both_formats_round_trip() {
+        for format in [Format::Zip, Format::TarGz] {
+            let bytes = write(format, &sources()).unwrap();
+            let contents = read(format, &bytes).unwrap();
+            let expected: Vec<Entry> = sources()
+                .into_iter()
+                .map(|source| Entry {
+                    name: PathBuf::from(source.name),
+                    content: Some(source.content),
+                })
+                .collect();
+            assert_eq!(contents.entries, expected, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_names_cannot_leave_the_destination() {
+        assert_eq!(safe_name("./a/b.txt").unwrap(), PathBuf::from("a/b.txt"));
+        assert!(safe_name("../evil.sh").is_err());
+        assert!(safe_name("a/../../evil.sh").is_err());
+        assert!(safe_name("/etc/passwd").is_err());
+        assert!(safe_name("..\\evil.exe").is_err());
+
+        let evil = [Source {
+            name: "../evil.sh".to_string(),
+            content: b"x".to_vec(),
+            modified: SystemTime::now(),
+        }];
+        let bytes = write(Format::Zip, &evil).unwrap();
+        let error = read(Format::Zip, &bytes).unwrap_err();
+        assert!(error.to_string().contains("outside the destination"));
+    }
+}
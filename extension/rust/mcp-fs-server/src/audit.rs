@@ -0,0 +1,105 @@
+//! Append-only JSONL record of every mutating tool call.
+
+use anyhow::{Context as _, Result};
+use serde_json::{json, Value};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::tools::format_time;
+
+/// Writes audit entries to a file configured with `--audit-log`. Without a
+/// file, auditing is disabled.
+#[derive(Default)]
+pub struct AuditLog {
+    path: Option<PathBuf>,
+    lock: Mutex<()>,
+}
+
+/// One mutating tool call, as recorded in the log.
+pub struct Entry<'a> {
+    pub tool: &'a str,
+    pub arguments: &'a Value,
+    pub paths: Vec<String>,
+    pub bytes_written: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl AuditLog {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// Appends one line. Arguments are stored as a hash so file contents
+    /// passed to write tools do not end up in the log.
+    pub fn record(&self, entry: Entry) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let line = json!({
+            "timestamp": format_time(std::time::SystemTime::now()),
+            "tool": entry.tool,
+            "arguments_hash": blake3::hash(entry.arguments.to_string().as_bytes()).to_hex().as_str(),
+            "paths": entry.paths,
+            "bytes_written": entry.bytes_written,
+            "result": if entry.error.is_some() { "error" } else { "ok" },
+            "error": entry.error,
+        });
+        let _guard = self.lock.lock().unwrap();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open audit log {}", path.display()))?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+
+    /// All entries, oldest first. Lines that fail to parse are skipped.
+    pub fn entries(&self) -> Result<Vec<Value>> {
+        let Some(path) = &self.path else {
+            return Ok(Vec::new());
+        };
+        let _guard = self.lock.lock().unwrap();
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        Ok(text
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+}
+
+/// Collects the paths a mutating call touched from its arguments and result.
+pub fn affected_paths(arguments: &Value, result: Option<&Value>) -> Vec<String> {
+    let mut paths = Vec::new();
+    for key in ["path", "source", "destination"] {
+        if let Some(path) = arguments.get(key).and_then(Value::as_str) {
+            paths.push(path.to_string());
+        }
+    }
+    if let Some(files) = result
+        .and_then(|r| r.get("files"))
+        .and_then(Value::as_array)
+    {
+        paths.extend(
+            files
+                .iter()
+                .filter_map(|f| f.get("path").and_then(Value::as_str))
+                .map(str::to_string),
+        );
+    }
+    paths.dedup();
+    paths
+}
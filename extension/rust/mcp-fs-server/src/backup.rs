@@ -0,0 +1,148 @@
+//! Bounded in-memory snapshots of files taken before they are modified.
+
+use anyhow::{Context as _, Result};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Oldest snapshots are evicted once either limit is exceeded.
+const MAX_BACKUPS: usize = 200;
+const MAX_TOTAL_BYTES: usize = 64 * 1024 * 1024;
+
+/// The state of one file just before a tool changed it.
+#[derive(Clone)]
+pub struct Backup {
+    pub id: u64,
+    pub path: PathBuf,
+    pub tool: &'static str,
+    pub taken_at: SystemTime,
+    /// `None` if the file did not exist, so undoing means removing it.
+    pub content: Option<Vec<u8>>,
+}
+
+#[derive(Default)]
+pub struct Backups {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    next_id: u64,
+    entries: VecDeque<Backup>,
+    total_bytes: usize,
+}
+
+impl Backups {
+    /// Records the current contents of `path` before `tool` modifies it.
+    /// Directories, symlinks and files too large to keep are not
+    /// snapshotted; a link is never followed, so its target outside the
+    /// root cannot end up in a backup that undo would restore.
+    pub fn snapshot(&self, path: &Path, tool: &'static str) -> Result<()> {
+        let content = match fs::symlink_metadata(path) {
+            Ok(metadata) if !metadata.is_file() => return Ok(()),
+            Ok(metadata) if metadata.len() > MAX_TOTAL_BYTES as u64 => return Ok(()),
+            Ok(_) => Some(
+                fs::read(path).with_context(|| format!("Failed to back up {}", path.display()))?,
+            ),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to back up {}", path.display()))
+            }
+        };
+        self.record(path, tool, content);
+        Ok(())
+    }
+
+    /// Records already-known prior contents of `path`.
+    pub fn record(&self, path: &Path, tool: &'static str, content: Option<Vec<u8>>) {
+        let size = content.as_ref().map_or(0, Vec::len);
+        if size > MAX_TOTAL_BYTES {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_id += 1;
+        let backup = Backup {
+            id: inner.next_id,
+            path: path.to_path_buf(),
+            tool,
+            taken_at: SystemTime::now(),
+            content,
+        };
+        inner.entries.push_back(backup);
+        inner.total_bytes += size;
+        while inner.entries.len() > MAX_BACKUPS || inner.total_bytes > MAX_TOTAL_BYTES {
+            let Some(evicted) = inner.entries.pop_front() else {
+                break;
+            };
+            inner.total_bytes -= evicted.content.as_ref().map_or(0, Vec::len);
+        }
+    }
+
+    /// The most recent snapshot of `path`, left in place.
+    pub fn latest(&self, path: &Path) -> Option<Backup> {
+        let inner = self.inner.lock().unwrap();
+        inner.entries.iter().rev().find(|b| b.path == path).cloned()
+    }
+
+    /// Removes and returns the most recent snapshot of `path`.
+    pub fn take_latest(&self, path: &Path) -> Option<Backup> {
+        let mut inner = self.inner.lock().unwrap();
+        let index = inner.entries.iter().rposition(|b| b.path == path)?;
+        let backup = inner.entries.remove(index)?;
+        inner.total_bytes -= backup.content.as_ref().map_or(0, Vec::len);
+        Some(backup)
+    }
+
+    /// Snapshots newest first, optionally limited to one path.
+    pub fn list(&self, path: Option<&Path>) -> Vec<Backup> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .entries
+            .iter()
+            .rev()
+            .filter(|b| path.is_none_or(|p| b.path == p))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eviction_and_latest_per_path() {
+        let backups = Backups::default();
+        let a = Path::new("/w/a.txt");
+        for i in 0..MAX_BACKUPS + 5 {
+            backups.record(a, "write_file", Some(vec![i as u8]));
+        }
+        backups.record(Path::new("/w/b.txt"), "edit_file", None);
+        assert_eq!(backups.list(None).len(), MAX_BACKUPS);
+
+        let latest = backups.take_latest(a).unwrap();
+        assert_eq!(latest.content, Some(vec![(MAX_BACKUPS + 4) as u8]));
+        assert!(backups
+            .take_latest(Path::new("/w/b.txt"))
+            .unwrap()
+            .content
+            .is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_snapshot_does_not_follow_symlinks() {
+        let env = crate::test_support::TestEnv::new("backup-link");
+        let outside = env.path().with_extension("secret");
+        fs::write(&outside, "outside").unwrap();
+        let link = env.path().join("link");
+        std::os::unix::fs::symlink(&outside, &link).unwrap();
+
+        let backups = Backups::default();
+        backups.snapshot(&link, "delete_path").unwrap();
+        assert!(backups.latest(&link).is_none());
+        let _ = fs::remove_file(&outside);
+    }
+}
@@ -0,0 +1,140 @@
+//! Honors `notifications/cancelled`. The server handles one message at a
+//! time, so transports read ahead and flag in-flight requests through
+//! [`Cancellations`] while a tool is still running; tools poll their
+//! [`CancelToken`] between units of work.
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+pub const CANCELLED_NOTIFICATION: &str = "notifications/cancelled";
+
+/// Cancellations for requests not started yet are remembered, up to this many.
+const MAX_EARLY: usize = 64;
+
+/// Returned by a tool that stopped because its request was cancelled.
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Request was cancelled by the client")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+impl Cancelled {
+    pub fn to_json(&self) -> Value {
+        json!({ "error": "cancelled", "cancelled": true })
+    }
+}
+
+/// Set once the client cancels the request a tool is serving.
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Fails with [`Cancelled`] once the request has been cancelled.
+    pub fn check(&self) -> Result<()> {
+        match self.is_cancelled() {
+            true => Err(Cancelled.into()),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Tokens of in-flight requests, keyed by JSON-RPC id.
+#[derive(Clone, Default)]
+pub struct Cancellations {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    running: HashMap<String, CancelToken>,
+    /// Ids cancelled before their request reached the server.
+    early: VecDeque<String>,
+}
+
+impl Cancellations {
+    /// Registers a request, returning its token. A cancellation that raced
+    /// ahead of the request leaves the token already set.
+    pub fn start(&self, id: &Value) -> CancelToken {
+        let key = id.to_string();
+        let mut inner = self.inner.lock().unwrap();
+        let token = CancelToken::default();
+        if let Some(position) = inner.early.iter().position(|early| *early == key) {
+            inner.early.remove(position);
+            token.0.store(true, Ordering::Relaxed);
+        }
+        inner.running.insert(key, token.clone());
+        token
+    }
+
+    pub fn finish(&self, id: &Value) {
+        self.inner.lock().unwrap().running.remove(&id.to_string());
+    }
+
+    pub fn cancel(&self, id: &Value) {
+        let key = id.to_string();
+        let mut inner = self.inner.lock().unwrap();
+        match inner.running.get(&key) {
+            Some(token) => token.0.store(true, Ordering::Relaxed),
+            None => {
+                inner.early.push_back(key);
+                while inner.early.len() > MAX_EARLY {
+                    inner.early.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Applies `line` if it is a `notifications/cancelled`, returning
+    /// whether it was one. Transports call this before queueing a message.
+    pub fn intercept(&self, line: &str) -> bool {
+        if !line.contains(CANCELLED_NOTIFICATION) {
+            return false;
+        }
+        let Ok(message) = serde_json::from_str::<Value>(line) else {
+            return false;
+        };
+        if message["method"] != CANCELLED_NOTIFICATION || message.get("id").is_some() {
+            return false;
+        }
+        if let Some(id) = message["params"].get("requestId") {
+            self.cancel(id);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_before_and_during_request() {
+        let cancellations = Cancellations::default();
+        let token = cancellations.start(&json!(1));
+        assert!(token.check().is_ok());
+        let line = json!({ "jsonrpc": "2.0", "method": CANCELLED_NOTIFICATION,
+                           "params": { "requestId": 1, "reason": "user" } });
+        assert!(cancellations.intercept(&line.to_string()));
+        assert!(token.check().unwrap_err().is::<Cancelled>());
+        cancellations.finish(&json!(1));
+
+        // The notification can overtake a request that is still queued.
+        cancellations.cancel(&json!("late"));
+        assert!(cancellations.start(&json!("late")).is_cancelled());
+        assert!(!cancellations.start(&json!(2)).is_cancelled());
+        assert!(!cancellations.intercept(r#"{"jsonrpc":"2.0","id":3,"method":"ping"}"#));
+    }
+}
@@ -0,0 +1,196 @@
+//! `completion/complete` for resource template arguments. MCP completes
+//! prompt and resource template arguments, not tool arguments, so paths are
+//! offered through the `file://{+path}` template: a client filling it in
+//! gets the files and directories under the roots that start with what has
+//! been typed, as absolute paths ready to be substituted into it.
+
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+use crate::uri::{path_to_uri, percent_decode};
+use crate::workspace::Workspace;
+
+/// Most values one completion may return, as the specification allows.
+const MAX_VALUES: usize = 100;
+
+/// Parameters of `completion/complete`.
+#[derive(Debug, Deserialize)]
+pub struct CompleteParams {
+    #[serde(rename = "ref")]
+    pub reference: Reference,
+    pub argument: Argument,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Reference {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub uri: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Argument {
+    pub name: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+pub fn complete(workspace: &Workspace, params: CompleteParams) -> Result<Value> {
+    let is_file_template = params.reference.kind == "ref/resource"
+        && params.reference.uri.as_deref() == Some("file://{+path}");
+    let mut values = match is_file_template && params.argument.name == "path" {
+        true => paths(workspace, &params.argument.value),
+        false => Vec::new(),
+    };
+    let total = values.len();
+    values.truncate(MAX_VALUES);
+    Ok(json!({
+        "completion": {
+            "values": values,
+            "total": total,
+            "hasMore": total > MAX_VALUES,
+        }
+    }))
+}
+
+/// Entries of the directory `typed` is in whose names start with its last
+/// component, directories with a trailing slash, plus the roots themselves.
+/// Values are the path part of the URI `resources/list` gives each entry,
+/// so `file://` followed by one names a readable resource.
+/// Hidden entries are left out until a `.` is typed, and entries matching
+/// the configured ignore patterns always are. A directory outside the roots
+/// completes to nothing.
+fn paths(workspace: &Workspace, typed: &str) -> Vec<String> {
+    // A value completed earlier arrives percent-encoded.
+    let typed = percent_decode(typed, typed).unwrap_or_else(|_| typed.to_string());
+    let (dir, prefix) = match typed.rfind(['/', '\\']) {
+        Some(i) => typed.split_at(i + 1),
+        None => ("", typed.as_str()),
+    };
+    let mut values: Vec<String> = workspace
+        .roots()
+        .iter()
+        .filter(|root| {
+            let root = format!("{}/", root.display());
+            root.starts_with(&typed) && root != typed
+        })
+        .map(|root| template_value(root, true))
+        .collect();
+    let Ok(resolved) = workspace.resolve(if dir.is_empty() { "." } else { dir }) else {
+        return values;
+    };
+    let Ok(entries) = fs::read_dir(&resolved) else {
+        return values;
+    };
+    let mut found: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let is_dir = entry.path().is_dir();
+            if workspace.is_excluded(&entry.path(), is_dir) {
+                return None;
+            }
+            Some(template_value(&entry.path(), is_dir))
+        })
+        .collect();
+    found.sort();
+    values.extend(found);
+    values
+}
+
+/// `path` as `file://{+path}` expects it, directories with a trailing slash.
+fn template_value(path: &Path, is_dir: bool) -> String {
+    let uri = path_to_uri(path);
+    let value = uri.strip_prefix("file://").unwrap_or(&uri);
+    match is_dir {
+        true => format!("{}/", value),
+        false => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    fn complete_path(workspace: &Workspace, value: &str) -> Vec<Value> {
+        let params = CompleteParams {
+            reference: Reference {
+                kind: "ref/resource".to_string(),
+                uri: Some("file://{+path}".to_string()),
+            },
+            argument: Argument {
+                name: "path".to_string(),
+                value: value.to_string(),
+            },
+        };
+        let result = complete(workspace, params).unwrap();
+        result["completion"]["values"].as_array().unwrap().clone()
+    }
+
+    #[test]
+    fn test_paths_complete_under_the_roots() {
+        let env = TestEnv::new("complete");
+        env.write("src/main.rs", "");
+        env.write("src/mod/lib.rs", "");
+        env.write(".env", "");
+        let workspace = &env.workspace;
+        let root = format!("{}/", env.path().display());
+        let abs = |relative: &str| json!(format!("{}{}", root, relative));
+
+        assert_eq!(complete_path(workspace, "sr"), [abs("src/")]);
+        assert_eq!(
+            complete_path(workspace, "src/m"),
+            [abs("src/main.rs"), abs("src/mod/")]
+        );
+        assert_eq!(complete_path(workspace, "."), [abs(".env")]);
+        assert_eq!(
+            complete_path(workspace, &format!("{}src/ma", root)),
+            [abs("src/main.rs")]
+        );
+        assert_eq!(
+            complete_path(workspace, &root[..root.len() - 2]),
+            [json!(root)]
+        );
+        assert!(complete_path(workspace, "/etc/").is_empty());
+    }
+
+    #[test]
+    fn test_configured_ignores_are_not_offered() {
+        let env = TestEnv::new("complete-ignore");
+        env.write("server.rs", "");
+        env.write("server.pem", "");
+        env.write("secrets/token.txt", "");
+        let workspace = env
+            .workspace
+            .clone()
+            .with_ignores(env.path(), &["*.pem".into(), "secrets/".into()])
+            .unwrap();
+
+        let server = format!("{}/server.rs", env.path().display());
+        assert_eq!(complete_path(&workspace, "se"), [json!(server)]);
+        assert!(complete_path(&workspace, "secrets/").is_empty());
+    }
+
+    #[test]
+    fn test_completed_values_read_through_the_template() {
+        let env = TestEnv::new("complete-read");
+        env.write("my notes/todo 1.md", "- ship it");
+        let workspace = &env.workspace;
+
+        let dir = complete_path(workspace, "my");
+        assert_eq!(dir.len(), 1);
+        let file = complete_path(workspace, dir[0].as_str().unwrap());
+        let uri = format!("file://{}", file[0].as_str().unwrap());
+        let content =
+            crate::resources::read(workspace, crate::resources::ReadParams { uri }).unwrap();
+        assert_eq!(content["contents"][0]["text"], "- ship it");
+    }
+}
@@ -0,0 +1,192 @@
+//! The `--config` file: roots with their permissions and ignore patterns,
+//! tools to turn off, and quota limits, checked in full at startup.
+//!
+//! ```toml
+//! audit_log = "/var/log/mcp-fs.jsonl"
+//!
+//! [[roots]]
+//! path = "~/src/app"
+//! permissions = ["read", "write"]
+//! ignore = ["*.pem", "secrets/"]
+//!
+//! [tools]
+//! disabled = ["apply_patch"]
+//!
+//! [limits]
+//! max_write_bytes = 1048576
+//! ```
+
+use anyhow::{bail, Context as _, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::quota::Limits;
+use crate::tools;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+    Read,
+    Write,
+    Delete,
+}
+
+fn all_permissions() -> Vec<Permission> {
+    vec![Permission::Read, Permission::Write, Permission::Delete]
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RootConfig {
+    /// Relative paths are taken from the config file's directory; `~/`
+    /// expands to the home directory. Canonical once loaded.
+    pub path: PathBuf,
+    /// Defaults to read, write, and delete.
+    #[serde(default = "all_permissions")]
+    pub permissions: Vec<Permission>,
+    /// Gitignore-style patterns, relative to the root, hidden from walks.
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+impl RootConfig {
+    pub fn allows(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToolsConfig {
+    #[serde(default)]
+    pub disabled: BTreeSet<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub roots: Vec<RootConfig>,
+    #[serde(default)]
+    pub tools: ToolsConfig,
+    #[serde(default)]
+    pub limits: Limits,
+    #[serde(default)]
+    pub audit_log: Option<PathBuf>,
+    #[serde(default)]
+    pub fast_search: bool,
+    /// Directory of `create_from_template` templates.
+    #[serde(default)]
+    pub templates: Option<PathBuf>,
+}
+
+impl Config {
+    /// Reads and validates `path`. Every problem names the offending key.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        let base = path.parent().unwrap_or(Path::new("."));
+        Self::parse(&text, base).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    fn parse(text: &str, base: &Path) -> Result<Self> {
+        let mut config: Config = toml::from_str(text)?;
+        let mut seen = BTreeSet::new();
+        for (i, root) in config.roots.iter_mut().enumerate() {
+            let key = format!("roots[{}]", i);
+            let path = expand(&root.path, base);
+            root.path = path
+                .canonicalize()
+                .with_context(|| format!("{}.path: {} does not exist", key, path.display()))?;
+            if !root.path.is_dir() {
+                bail!("{}.path: {} is not a directory", key, root.path.display());
+            }
+            if !seen.insert(root.path.clone()) {
+                bail!("{}.path: {} is listed twice", key, root.path.display());
+            }
+            if !root.allows(Permission::Read) {
+                bail!("{}.permissions must include \"read\"", key);
+            }
+            if root.allows(Permission::Delete) && !root.allows(Permission::Write) {
+                bail!("{}.permissions: \"delete\" requires \"write\"", key);
+            }
+        }
+        let known: BTreeSet<&str> = tools::registry().iter().map(|t| t.name).collect();
+        for name in &config.tools.disabled {
+            if !known.contains(name.as_str()) {
+                let known: Vec<&str> = known.into_iter().collect();
+                bail!(
+                    "tools.disabled: unknown tool {:?}; known tools are {}",
+                    name,
+                    known.join(", ")
+                );
+            }
+        }
+        if let Some(log) = &config.audit_log {
+            config.audit_log = Some(expand(log, base));
+        }
+        if let Some(dir) = &config.templates {
+            config.templates = Some(expand(dir, base));
+        }
+        Ok(config)
+    }
+}
+
+fn expand(path: &Path, base: &Path) -> PathBuf {
+    if let (Ok(rest), Some(home)) = (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        return PathBuf::from(home).join(rest);
+    }
+    base.join(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_parse_resolves_roots_relative_to_the_file() {
+        let env = TestEnv::new("config");
+        env.write("app/main.rs", "");
+        env.write("docs/readme.md", "");
+        let config = Config::parse(
+            r#"
+            [[roots]]
+            path = "app"
+            ignore = ["target/"]
+
+            [[roots]]
+            path = "docs"
+            permissions = ["read"]
+
+            [tools]
+            disabled = ["delete_path"]
+
+            [limits]
+            max_write_bytes = 10
+            "#,
+            env.path(),
+        )
+        .unwrap();
+        assert_eq!(config.roots[0].path, env.path().join("app"));
+        assert!(config.roots[0].allows(Permission::Delete));
+        assert!(!config.roots[1].allows(Permission::Write));
+        assert!(config.tools.disabled.contains("delete_path"));
+        assert_eq!(config.limits.max_write_bytes, Some(10));
+    }
+
+    #[test]
+    fn test_errors_name_the_offending_key() {
+        let env = TestEnv::new("config-errors");
+        env.write("app/main.rs", "");
+        let error = |text: &str| format!("{:#}", Config::parse(text, env.path()).unwrap_err());
+
+        assert!(error("[[roots]]\npath = \"missing\"").contains("roots[0].path"));
+        let delete_only = "[[roots]]\npath = \"app\"\npermissions = [\"read\", \"delete\"]";
+        assert!(error(delete_only).contains("\"delete\" requires \"write\""));
+        assert!(error("[tools]\ndisabled = [\"rm_rf\"]").contains("unknown tool \"rm_rf\""));
+        assert!(error("[limits]\nmax_bytes = 1").contains("max_bytes"));
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long an issued confirmation token stays redeemable.
+const TOKEN_TTL: Duration = Duration::from_secs(300);
+
+/// Single-use tokens that gate destructive operations behind a second call.
+/// Each token is bound to an action key describing exactly what it authorises.
+#[derive(Default)]
+pub struct Confirmations {
+    pending: Mutex<HashMap<String, (String, Instant)>>,
+    counter: AtomicU64,
+}
+
+impl Confirmations {
+    /// Issues a fresh token authorising `action`.
+    pub fn issue(&self, action: &str) -> String {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(self.counter.fetch_add(1, Ordering::Relaxed));
+        hasher.write(action.as_bytes());
+        let token = format!("confirm-{:016x}", hasher.finish());
+
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, (_, issued)| issued.elapsed() < TOKEN_TTL);
+        pending.insert(token.clone(), (action.to_string(), Instant::now()));
+        token
+    }
+
+    /// Consumes `token` if it is live and was issued for `action`.
+    pub fn redeem(&self, token: &str, action: &str) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        match pending.get(token) {
+            Some((bound, issued)) if bound == action && issued.elapsed() < TOKEN_TTL => {
+                pending.remove(token);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_are_single_use_and_action_bound() {
+        let confirmations = Confirmations::default();
+        let token = confirmations.issue("delete:/a");
+        assert!(!confirmations.redeem(&token, "delete:/b"));
+        assert!(confirmations.redeem(&token, "delete:/a"));
+        assert!(!confirmations.redeem(&token, "delete:/a"));
+    }
+}
@@ -0,0 +1,97 @@
+//! Content digests used for change detection.
+
+use serde_json::{json, Value};
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// Returned when a file no longer has the content a client last saw.
+#[derive(Debug)]
+pub struct Conflict {
+    pub path: String,
+    pub expected: String,
+    /// `None` when the file no longer exists.
+    pub current: Option<String>,
+}
+
+impl Conflict {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "error": "conflict",
+            "path": self.path,
+            "expected_hash": self.expected,
+            "current_hash": self.current,
+        })
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.current {
+            Some(current) => write!(
+                f,
+                "Conflict: {} changed on disk (expected blake3 {}, found {}); re-read it first",
+                self.path, self.expected, current
+            ),
+            None => write!(
+                f,
+                "Conflict: {} no longer exists (expected blake3 {})",
+                self.path, self.expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Conflict {}
+
+/// The hex blake3 digest of `bytes`.
+pub fn of_bytes(bytes: &[u8]) -> String {
+    blake3::hash(bytes).to_hex().to_string()
+}
+
+/// The size and hex blake3 digest of a file, streamed rather than loaded.
+pub fn of_file(path: &Path) -> io::Result<(u64, String)> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(File::open(path)?)?;
+    let size = hasher.count();
+    Ok((size, hasher.finalize().to_hex().to_string()))
+}
+
+/// Fails with [`Conflict`] unless `current` (the on-disk bytes, if any)
+/// hashes to `expected`. Nothing is checked when no hash was given.
+pub fn check_expected(
+    path: &str,
+    current: Option<&[u8]>,
+    expected: Option<&str>,
+) -> Result<(), Conflict> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    let current = current.map(of_bytes);
+    if current.as_deref() == Some(expected.to_ascii_lowercase().as_str()) {
+        return Ok(());
+    }
+    Err(Conflict {
+        path: path.to_string(),
+        expected: expected.to_string(),
+        current,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_expected() {
+        let hash = of_bytes(b"v1");
+        assert!(check_expected("a", Some(b"v1"), Some(&hash)).is_ok());
+        assert!(check_expected("a", Some(b"v2"), None).is_ok());
+
+        let conflict = check_expected("a", Some(b"v2"), Some(&hash)).unwrap_err();
+        assert_eq!(conflict.to_json()["current_hash"], of_bytes(b"v2"));
+        let missing = check_expected("a", None, Some(&hash)).unwrap_err();
+        assert!(missing.current.is_none());
+    }
+}
@@ -0,0 +1,200 @@
+//! The codes tool failures are reported with, in `structuredContent.error`
+//! next to a remediation `hint`, so clients can branch on a failure instead
+//! of parsing its message. Failures with a type of their own (sandbox,
+//! conflict, quota, cancellation) keep it and are mapped here, HTTP errors
+//! from the Qdrant and embedding services are `upstream_error`s, I/O errors
+//! are classified by kind, and the rest carry a [`Failure`] with their code.
+//! Anything unclassified is taken to be an `invalid_argument`.
+
+use serde_json::{json, Value};
+use std::fmt;
+use std::io;
+
+use crate::cancel::Cancelled;
+use crate::digest::Conflict;
+use crate::quota::QuotaExceeded;
+use crate::workspace::{AccessDenied, NotDeletable, ReadOnly};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    NotFound,
+    /// The roots' policy or the filesystem's permissions forbid the operation.
+    AccessDenied,
+    OutsideRoot,
+    TooLarge,
+    Conflict,
+    QuotaExceeded,
+    IoError,
+    /// The Qdrant or embedding service was unreachable, timed out or failed.
+    Upstream,
+    InvalidArgument,
+    Cancelled,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::AccessDenied => "access_denied",
+            ErrorCode::OutsideRoot => "outside_root",
+            ErrorCode::TooLarge => "too_large",
+            ErrorCode::Conflict => "conflict",
+            ErrorCode::QuotaExceeded => "quota_exceeded",
+            ErrorCode::IoError => "io_error",
+            ErrorCode::Upstream => "upstream_error",
+            ErrorCode::InvalidArgument => "invalid_argument",
+            ErrorCode::Cancelled => "cancelled",
+        }
+    }
+
+    /// What a client can do about the failure.
+    pub fn hint(self) -> &'static str {
+        match self {
+            ErrorCode::NotFound => "Check the path with list_directory or find_files",
+            ErrorCode::AccessDenied => {
+                "The root's policy or the file's permissions forbid this; pick another path"
+            }
+            ErrorCode::OutsideRoot => "Use a path inside one of the allowed roots",
+            ErrorCode::TooLarge => "Read or process it in smaller parts, or raise the limit",
+            ErrorCode::Conflict => "Re-read the current state, then retry or pass overwrite",
+            ErrorCode::QuotaExceeded => "Split the work into smaller calls or wait and retry",
+            ErrorCode::IoError => "Retry; if it persists the file system needs attention",
+            ErrorCode::Upstream => {
+                "Check that the Qdrant and embedding services are running, then retry"
+            }
+            ErrorCode::InvalidArgument => "Fix the arguments according to the tool's schema",
+            ErrorCode::Cancelled => "Call again if the result is still needed",
+        }
+    }
+}
+
+/// A failure that has no type of its own, with the code it is reported as.
+#[derive(Debug)]
+pub struct Failure {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl Failure {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Failure {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(what: impl fmt::Display) -> Self {
+        Failure::new(ErrorCode::NotFound, format!("Not found: {}", what))
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+pub fn classify(error: &anyhow::Error) -> ErrorCode {
+    if let Some(failure) = error.downcast_ref::<Failure>() {
+        return failure.code;
+    }
+    if error.is::<AccessDenied>() {
+        return ErrorCode::OutsideRoot;
+    }
+    if error.is::<ReadOnly>() || error.is::<NotDeletable>() {
+        return ErrorCode::AccessDenied;
+    }
+    if error.is::<Conflict>() {
+        return ErrorCode::Conflict;
+    }
+    if error.is::<QuotaExceeded>() {
+        return ErrorCode::QuotaExceeded;
+    }
+    if error.is::<Cancelled>() {
+        return ErrorCode::Cancelled;
+    }
+    // Checked before I/O errors, which a connection failure wraps.
+    if error.chain().any(|e| e.is::<ureq::Error>()) {
+        return ErrorCode::Upstream;
+    }
+    match error.chain().find_map(|e| e.downcast_ref::<io::Error>()) {
+        Some(e) if e.kind() == io::ErrorKind::NotFound => ErrorCode::NotFound,
+        Some(e) if e.kind() == io::ErrorKind::PermissionDenied => ErrorCode::AccessDenied,
+        Some(_) => ErrorCode::IoError,
+        None => ErrorCode::InvalidArgument,
+    }
+}
+
+/// The structured payload of a failed tool call: its code, hint, and
+/// whatever fields its type adds.
+pub fn details(error: &anyhow::Error) -> Value {
+    let code = classify(error);
+    let mut details = if let Some(denied) = error.downcast_ref::<AccessDenied>() {
+        denied.to_json()
+    } else if let Some(denied) = error.downcast_ref::<ReadOnly>() {
+        denied.to_json()
+    } else if let Some(denied) = error.downcast_ref::<NotDeletable>() {
+        denied.to_json()
+    } else if let Some(conflict) = error.downcast_ref::<Conflict>() {
+        conflict.to_json()
+    } else if let Some(exceeded) = error.downcast_ref::<QuotaExceeded>() {
+        exceeded.to_json()
+    } else if let Some(cancelled) = error.downcast_ref::<Cancelled>() {
+        cancelled.to_json()
+    } else {
+        json!({})
+    };
+    details["error"] = json!(code.as_str());
+    details["hint"] = json!(code.hint());
+    details
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context as _};
+
+    #[test]
+    fn test_errors_are_classified_through_context() {
+        let missing = io::Error::from(io::ErrorKind::NotFound);
+        let error = Err::<(), _>(missing)
+            .context("Failed to read a.txt")
+            .unwrap_err();
+        assert_eq!(details(&error)["error"], "not_found");
+
+        let error = anyhow::Error::new(Failure::new(ErrorCode::TooLarge, "big"));
+        assert_eq!(
+            classify(&error.context("while reading")),
+            ErrorCode::TooLarge
+        );
+
+        let error = anyhow::Error::new(ReadOnly {
+            path: "a".into(),
+            root: "/r".into(),
+        });
+        let details = details(&error);
+        assert_eq!(
+            (details["error"].as_str(), details["reason"].as_str()),
+            (Some("access_denied"), Some("read_only"))
+        );
+        assert_eq!(
+            classify(&anyhow!("Invalid regex")),
+            ErrorCode::InvalidArgument
+        );
+    }
+
+    #[test]
+    fn test_http_errors_are_upstream_failures() {
+        let refused = io::Error::from(io::ErrorKind::ConnectionRefused);
+        let error = Err::<(), _>(ureq::Error::Io(refused))
+            .context("Qdrant is not reachable at http://localhost:6333")
+            .unwrap_err();
+        assert_eq!(details(&error)["error"], "upstream_error");
+        let error = Err::<(), _>(ureq::Error::StatusCode(502))
+            .context("Embedding request failed")
+            .unwrap_err();
+        assert_eq!(classify(&error), ErrorCode::Upstream);
+    }
+}
@@ -0,0 +1,133 @@
+//! Background threads that follow growing files and push appended lines.
+
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::outbound::Outbound;
+
+/// Notification method used for lines appended to a followed file.
+pub const TAIL_NOTIFICATION: &str = "notifications/tail_file/lines";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Tracks running followers so they can be stopped by id.
+#[derive(Default)]
+pub struct Followers {
+    next_id: AtomicU64,
+    active: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+}
+
+/// What to follow and where to send updates.
+pub struct FollowRequest {
+    pub path: PathBuf,
+    pub display: String,
+    pub offset: u64,
+    pub duration: Duration,
+    pub outbound: Outbound,
+}
+
+impl Followers {
+    /// Starts following a file from `request.offset`, returning the follow id.
+    pub fn spawn(&self, request: FollowRequest) -> String {
+        let id = format!("tail-{}", self.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let stop = Arc::new(AtomicBool::new(false));
+        self.active
+            .lock()
+            .unwrap()
+            .insert(id.clone(), Arc::clone(&stop));
+
+        let active = Arc::clone(&self.active);
+        let follow_id = id.clone();
+        thread::spawn(move || {
+            run(&follow_id, request, &stop);
+            active.lock().unwrap().remove(&follow_id);
+        });
+        id
+    }
+
+    /// Signals a follower to stop. Returns false if the id is unknown.
+    pub fn stop(&self, id: &str) -> bool {
+        match self.active.lock().unwrap().remove(id) {
+            Some(flag) => {
+                flag.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Followers belong to a session; when it ends, so do they.
+impl Drop for Followers {
+    fn drop(&mut self) {
+        for flag in self.active.lock().unwrap().values() {
+            flag.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+fn run(id: &str, request: FollowRequest, stop: &AtomicBool) {
+    let deadline = Instant::now() + request.duration;
+    let mut offset = request.offset;
+    let mut partial = Vec::new();
+    let mut reason = "expired";
+
+    while Instant::now() < deadline {
+        if stop.load(Ordering::Relaxed) {
+            reason = "stopped";
+            break;
+        }
+        thread::sleep(POLL_INTERVAL);
+
+        let Ok(mut file) = File::open(&request.path) else {
+            reason = "file_removed";
+            break;
+        };
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        let mut truncated = false;
+        if len < offset {
+            // The file was truncated or rotated; start over from the top.
+            offset = 0;
+            partial.clear();
+            truncated = true;
+        }
+        if len == offset {
+            continue;
+        }
+
+        let mut chunk = Vec::new();
+        if file.seek(SeekFrom::Start(offset)).is_err()
+            || file.take(len - offset).read_to_end(&mut chunk).is_err()
+        {
+            continue;
+        }
+        offset += chunk.len() as u64;
+        partial.extend_from_slice(&chunk);
+
+        // Only complete lines are sent; the remainder waits for its newline.
+        let Some(last_newline) = partial.iter().rposition(|b| *b == b'\n') else {
+            continue;
+        };
+        let complete: Vec<u8> = partial.drain(..=last_newline).collect();
+        let lines: Vec<String> = String::from_utf8_lossy(&complete)
+            .lines()
+            .map(str::to_string)
+            .collect();
+        request.outbound.notify(
+            TAIL_NOTIFICATION,
+            json!({ "follow_id": id, "path": request.display, "lines": lines, "truncated": truncated }),
+        );
+    }
+
+    request.outbound.notify(
+        TAIL_NOTIFICATION,
+        json!({ "follow_id": id, "path": request.display, "lines": [], "finished": reason }),
+    );
+}
@@ -0,0 +1,312 @@
+//! Optional full-text index over the configured roots, kept current by a
+//! file watcher so `fast_search` never has to walk the tree.
+
+use anyhow::{Context as _, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs;
+use std::path::{Component, Path, PathBuf, MAIN_SEPARATOR_STR};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tantivy::collector::TopDocs;
+use tantivy::query::{QueryParser, RegexQuery};
+use tantivy::schema::{Field, Schema, Value as _, STORED, STRING, TEXT};
+use tantivy::{doc, Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term};
+
+use crate::text;
+use crate::workspace::Workspace;
+
+/// Files larger than this are left out of the index.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+/// Memory the index writer may use before flushing a segment.
+const WRITER_BUDGET: usize = 50 * 1024 * 1024;
+/// Changes are committed once the watcher has been quiet this long.
+const COMMIT_DELAY: Duration = Duration::from_millis(300);
+
+/// A document matching a query.
+pub struct Hit {
+    pub path: PathBuf,
+    pub score: f32,
+}
+
+pub struct SearchIndex {
+    index: Index,
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+    path: Field,
+    content: Field,
+    ready: AtomicBool,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl SearchIndex {
+    /// Creates an in-memory index of the workspace's roots. The initial
+    /// build and all later updates happen on a background thread; searches
+    /// made before the build finishes see a partial index.
+    pub fn start(workspace: Workspace) -> Result<Arc<Self>> {
+        let mut builder = Schema::builder();
+        let path = builder.add_text_field("path", STRING | STORED);
+        let content = builder.add_text_field("content", TEXT);
+        let index = Index::create_in_ram(builder.build());
+        let writer = index
+            .writer(WRITER_BUDGET)
+            .context("Failed to create index writer")?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()
+            .context("Failed to create index reader")?;
+        let this = Arc::new(Self {
+            index,
+            reader,
+            writer: Mutex::new(writer),
+            path,
+            content,
+            ready: AtomicBool::new(false),
+            watcher: Mutex::new(None),
+        });
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            if let Ok(event) = event {
+                if !matches!(event.kind, EventKind::Access(_)) {
+                    let _ = tx.send(event.paths);
+                }
+            }
+        })
+        .context("Failed to start index watcher")?;
+        for root in workspace.roots() {
+            watcher
+                .watch(root, RecursiveMode::Recursive)
+                .with_context(|| format!("Failed to watch {}", root.display()))?;
+        }
+        *this.watcher.lock().unwrap() = Some(watcher);
+
+        let worker = Arc::clone(&this);
+        thread::spawn(move || worker.run(workspace, rx));
+        Ok(this)
+    }
+
+    /// Whether the initial build has finished.
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Relaxed)
+    }
+
+    pub fn num_documents(&self) -> u64 {
+        self.reader.searcher().num_docs()
+    }
+
+    /// Runs `query` (tantivy query syntax; malformed parts are ignored) and
+    /// returns the best `limit` hits.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<Hit>> {
+        let parser = QueryParser::for_index(&self.index, vec![self.content]);
+        let (query, _errors) = parser.parse_query_lenient(query);
+        let searcher = self.reader.searcher();
+        let top = searcher.search(&query, &TopDocs::with_limit(limit).order_by_score())?;
+        let mut hits = Vec::with_capacity(top.len());
+        for (score, address) in top {
+            let document: TantivyDocument = searcher.doc(address)?;
+            if let Some(path) = document.get_first(self.path).and_then(|v| v.as_str()) {
+                hits.push(Hit {
+                    path: PathBuf::from(path),
+                    score,
+                });
+            }
+        }
+        Ok(hits)
+    }
+
+    fn run(&self, workspace: Workspace, events: Receiver<Vec<PathBuf>>) {
+        for root in workspace.roots() {
+            for entry in workspace.walker(root, false).build().filter_map(|e| e.ok()) {
+                if entry.file_type().is_some_and(|t| t.is_file()) {
+                    self.update(entry.path());
+                }
+            }
+        }
+        self.commit();
+        self.ready.store(true, Ordering::Relaxed);
+
+        let ignores: Vec<(PathBuf, Gitignore)> = workspace
+            .roots()
+            .iter()
+            .map(|root| (root.clone(), root_ignores(root)))
+            .collect();
+        let mut dirty = false;
+        loop {
+            match events.recv_timeout(COMMIT_DELAY) {
+                Ok(paths) => {
+                    for path in paths {
+                        if is_ignored(&ignores, &path)
+                            || workspace.is_excluded(&path, path.is_dir())
+                        {
+                            continue;
+                        }
+                        if path.is_dir() {
+                            // A directory moved into place brings files no event named.
+                            for entry in workspace.walker(&path, false).build().flatten() {
+                                if entry.file_type().is_some_and(|t| t.is_file()) {
+                                    self.update(entry.path());
+                                }
+                            }
+                        } else {
+                            self.update(&path);
+                        }
+                        dirty = true;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) if dirty => {
+                    self.commit();
+                    dirty = false;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    }
+
+    /// Replaces the document for `path`, or just removes it if the file is
+    /// gone or not indexable text. A path that is gone may have been a
+    /// directory, so the documents beneath it are removed too.
+    fn update(&self, path: &Path) {
+        let key = path.to_string_lossy();
+        let writer = self.writer.lock().unwrap();
+        writer.delete_term(Term::from_field_text(self.path, &key));
+        if !path.exists() {
+            let beneath = format!(
+                "{}{}.*",
+                regex::escape(&key),
+                regex::escape(MAIN_SEPARATOR_STR)
+            );
+            let deleted = RegexQuery::from_pattern(&beneath, self.path)
+                .and_then(|query| writer.delete_query(Box::new(query)));
+            if let Err(e) = deleted {
+                eprintln!("mcp-fs-server: failed to unindex {}: {}", key, e);
+            }
+            return;
+        }
+        let Some(content) = indexable_text(path) else {
+            return;
+        };
+        let _ = writer.add_document(doc!(self.path => key.as_ref(), self.content => content));
+    }
+
+    fn commit(&self) {
+        if let Err(e) = self.writer.lock().unwrap().commit() {
+            eprintln!("mcp-fs-server: index commit failed: {}", e);
+            return;
+        }
+        let _ = self.reader.reload();
+    }
+}
+
+fn indexable_text(path: &Path) -> Option<String> {
+    let metadata = fs::metadata(path).ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_FILE_BYTES {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    if text::is_binary(&bytes) {
+        return None;
+    }
+    text::decode(&bytes).map(|d| d.text)
+}
+
+/// The .gitignore and .ignore rules at the top of `root`. Nested ignore
+/// files only apply to the initial walk.
+pub(crate) fn root_ignores(root: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+    builder.add(root.join(".gitignore"));
+    builder.add(root.join(".ignore"));
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+/// Mirrors the initial walk's filters for paths reported by the watcher:
+/// hidden components and ignored paths are skipped.
+pub(crate) fn is_ignored(ignores: &[(PathBuf, Gitignore)], path: &Path) -> bool {
+    let Some((root, rules)) = ignores.iter().find(|(root, _)| path.starts_with(root)) else {
+        return true;
+    };
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let hidden = relative.components().any(|c| match c {
+        Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+        _ => false,
+    });
+    hidden
+        || rules
+            .matched_path_or_any_parents(relative, path.is_dir())
+            .is_ignore()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+    use std::time::Instant;
+
+    fn wait_for(index: &SearchIndex, query: &str) -> Vec<Hit> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let hits = index.search(query, 10).unwrap();
+            if !hits.is_empty() || Instant::now() > deadline {
+                return hits;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    #[test]
+    fn test_index_builds_and_follows_changes() {
+        let env = TestEnv::new("index");
+        env.write(".gitignore", "target/\n");
+        env.write("src/lib.rs", "pub fn frobnicate() {}\n");
+        env.write("target/out.rs", "pub fn frobnicate() {}\n");
+        let index = SearchIndex::start(env.workspace.clone()).unwrap();
+
+        let hits = wait_for(&index, "frobnicate");
+        assert!(index.is_ready());
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].path, env.path().join("src/lib.rs"));
+
+        env.write("src/new.rs", "fn quux_handler() {}\n");
+        let hits = wait_for(&index, "quux_handler");
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[test]
+    fn test_removed_directories_take_their_files_along() {
+        let env = TestEnv::new("index-dirs");
+        env.write("docs/a.md", "zanzibar notes\n");
+        env.write("docs/deep/b.md", "more zanzibar notes\n");
+        env.write("keep.md", "zanzibar kept\n");
+        let index = SearchIndex::start(env.workspace.clone()).unwrap();
+        assert_eq!(wait_for(&index, "zanzibar").len(), 3);
+
+        fs::rename(env.path().join("docs"), env.path().join("guides")).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let moved = |hits: &[Hit]| {
+            let mut paths: Vec<_> = hits.iter().map(|h| h.path.clone()).collect();
+            paths.sort();
+            paths
+                == [
+                    env.path().join("guides/a.md"),
+                    env.path().join("guides/deep/b.md"),
+                    env.path().join("keep.md"),
+                ]
+        };
+        while !moved(&index.search("zanzibar", 10).unwrap()) {
+            assert!(Instant::now() < deadline, "index still has the old paths");
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        fs::remove_dir_all(env.path().join("guides")).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while index.search("zanzibar", 10).unwrap().len() != 1 {
+            assert!(Instant::now() < deadline, "index still has removed files");
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+}
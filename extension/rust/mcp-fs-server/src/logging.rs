@@ -0,0 +1,83 @@
+//! MCP logging: `notifications/message` at or above the level the client
+//! chose with `logging/setLevel`.
+
+use serde::Deserialize;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+pub const LOG_NOTIFICATION: &str = "notifications/message";
+
+/// Syslog severities, least severe first, as named by the MCP spec.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+impl Level {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Notice => "notice",
+            Level::Warning => "warning",
+            Level::Error => "error",
+            Level::Critical => "critical",
+            Level::Alert => "alert",
+            Level::Emergency => "emergency",
+        }
+    }
+}
+
+/// Parameters of `logging/setLevel`.
+#[derive(Deserialize)]
+pub struct SetLevelParams {
+    pub level: Level,
+}
+
+/// The minimum level sent to the client, shared by every clone of a
+/// session's outbound. Warnings and worse are sent until the client says
+/// otherwise.
+#[derive(Clone)]
+pub struct Threshold(Arc<AtomicU8>);
+
+impl Default for Threshold {
+    fn default() -> Self {
+        Self(Arc::new(AtomicU8::new(Level::Warning as u8)))
+    }
+}
+
+impl Threshold {
+    pub fn set(&self, level: Level) {
+        self.0.store(level as u8, Ordering::Relaxed);
+    }
+
+    pub fn allows(&self, level: Level) -> bool {
+        level as u8 >= self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threshold_filters_by_severity() {
+        let threshold = Threshold::default();
+        assert!(!threshold.allows(Level::Info));
+        assert!(threshold.allows(Level::Warning));
+        threshold.set(Level::Debug);
+        assert!(threshold.clone().allows(Level::Debug));
+        let level: SetLevelParams = serde_json::from_str(r#"{"level":"critical"}"#).unwrap();
+        threshold.set(level.level);
+        assert!(!threshold.allows(Level::Error));
+        assert!(threshold.allows(Level::Emergency));
+    }
+}
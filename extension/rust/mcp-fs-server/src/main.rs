@@ -0,0 +1,381 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+
+mod archive;
+mod atomic;
+mod audit;
+mod backup;
+mod cancel;
+mod completion;
+mod config;
+mod confirm;
+mod digest;
+mod error;
+mod follow;
+mod index;
+mod logging;
+mod metrics;
+mod mime;
+mod outbound;
+mod patch;
+mod progress;
+mod protocol;
+mod quota;
+mod resources;
+mod semantic;
+mod server;
+mod snapshot;
+mod state;
+mod template;
+#[cfg(test)]
+mod test_support;
+mod text;
+mod tools;
+mod transfer;
+mod transport;
+mod uri;
+mod watch;
+mod workspace;
+
+use audit::AuditLog;
+use config::{Config, Permission};
+use index::SearchIndex;
+use metrics::Metrics;
+use quota::{Limits, Quotas};
+use semantic::Backend;
+use state::State;
+use template::Templates;
+use transport::tls::TlsConfig;
+use transport::{NetworkConfig, Scheduler, StateFactory};
+use workspace::Workspace;
+
+const USAGE: &str = "Usage: mcp-fs-server [OPTIONS] [ROOT...]\n\n\
+Serves the Model Context Protocol over stdio. Each ROOT is a directory\n\
+exposed to the client, and every path a tool touches must resolve inside\n\
+one of them. Clients that support MCP roots can narrow the set through\n\
+roots/list; with no ROOT given, the client's roots are used as reported.\n\n\
+Options:\n\
+  --config FILE           Read roots, per-root permissions and ignore patterns,\n\
+                          disabled tools, and limits from a TOML file; command\n\
+                          line roots and limits are added on top\n\
+  --read-only             Make every root read-only and hide mutating tools\n\
+  --read-only-root DIR    Add DIR as a root that can be read but not written\n\
+  --audit-log FILE        Append a JSONL record of every mutating tool call to FILE\n\
+  --fast-search           Keep a full-text index of the roots for fast_search\n\
+  --templates DIR         Serve each entry of DIR as a create_from_template template\n\
+  --max-write-bytes N     Refuse to write any single file larger than N bytes\n\
+  --max-files-per-call N  Refuse calls that would change more than N files\n\
+  --max-ops-per-minute N  Refuse mutating calls beyond N per minute\n\
+  --http ADDR             Serve the Streamable HTTP transport on ADDR (e.g.\n\
+                          127.0.0.1:8080) instead of stdio\n\
+  --ws ADDR               Serve JSON-RPC over WebSocket on ADDR instead of stdio\n\
+  --socket PATH           Serve newline-delimited JSON-RPC on a Unix socket at\n\
+                          PATH, readable by the current user only\n\
+  --max-concurrent-calls N  Run at most N requests at once across all network\n\
+                          sessions, admitted in arrival order (default: CPUs)\n\
+  --auth-token-file FILE  Bearer token network clients must present; defaults\n\
+                          to the MCP_FS_AUTH_TOKEN environment variable\n\
+  --no-auth               Allow unauthenticated network clients\n\
+  --tls-cert FILE         PEM certificate chain; with --tls-key, serves https/wss\n\
+  --tls-key FILE          PEM private key (PKCS#8 or RSA)\n\n\
+Semantic search reads QDRANT_URL, QDRANT_API_KEY, QDRANT_COLLECTION,\n\
+OLLAMA_HOST and OLLAMA_MODEL, like the TypeScript indexer.";
+
+/// Roots parsed from the command line.
+struct Options {
+    config: Option<PathBuf>,
+    roots: Vec<PathBuf>,
+    read_only: Vec<PathBuf>,
+    all_read_only: bool,
+    audit_log: Option<PathBuf>,
+    limits: Limits,
+    fast_search: bool,
+    templates: Option<PathBuf>,
+    http: Option<String>,
+    ws: Option<String>,
+    socket: Option<PathBuf>,
+    max_concurrent_calls: Option<usize>,
+    auth_token_file: Option<PathBuf>,
+    no_auth: bool,
+    tls_cert: Option<PathBuf>,
+    tls_key: Option<PathBuf>,
+}
+
+fn parse_root(arg: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(arg)
+        .canonicalize()
+        .with_context(|| format!("Root does not exist: {}", arg))?;
+    if !path.is_dir() {
+        anyhow::bail!("Root is not a directory: {}", arg);
+    }
+    Ok(path)
+}
+
+fn parse_limit(flag: &str, value: Option<&String>) -> Result<u64> {
+    let value = value.with_context(|| format!("{} requires a number", flag))?;
+    value
+        .parse()
+        .with_context(|| format!("{} expects a non-negative integer, got {}", flag, value))
+}
+
+/// Collects the allowed workspace roots and their write policy.
+fn parse_options(args: &[String]) -> Result<Options> {
+    let mut config = None;
+    let mut roots = Vec::new();
+    let mut read_only = Vec::new();
+    let mut all_read_only = false;
+    let mut audit_log = None;
+    let mut limits = Limits::default();
+    let mut fast_search = false;
+    let mut templates = None;
+    let mut http = None;
+    let mut ws = None;
+    let mut socket = None;
+    let mut max_concurrent_calls = None;
+    let mut auth_token_file = None;
+    let mut no_auth = false;
+    let mut tls_cert = None;
+    let mut tls_key = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => {
+                let file = args.next().context("--config requires a file")?;
+                config = Some(PathBuf::from(file));
+            }
+            "--read-only" => all_read_only = true,
+            "--read-only-root" => {
+                let dir = args
+                    .next()
+                    .context("--read-only-root requires a directory")?;
+                let root = parse_root(dir)?;
+                read_only.push(root.clone());
+                roots.push(root);
+            }
+            "--audit-log" => {
+                let file = args.next().context("--audit-log requires a file")?;
+                audit_log = Some(PathBuf::from(file));
+            }
+            "--fast-search" => fast_search = true,
+            "--templates" => {
+                let dir = args.next().context("--templates requires a directory")?;
+                templates = Some(PathBuf::from(dir));
+            }
+            "--max-write-bytes" => limits.max_write_bytes = Some(parse_limit(arg, args.next())?),
+            "--max-files-per-call" => {
+                limits.max_files_per_call = Some(parse_limit(arg, args.next())?)
+            }
+            "--max-ops-per-minute" => {
+                limits.max_ops_per_minute = Some(parse_limit(arg, args.next())?)
+            }
+            "--http" => {
+                let addr = args.next().context("--http requires an address")?;
+                http = Some(addr.clone());
+            }
+            "--ws" => {
+                let addr = args.next().context("--ws requires an address")?;
+                ws = Some(addr.clone());
+            }
+            "--socket" => {
+                let path = args.next().context("--socket requires a path")?;
+                socket = Some(PathBuf::from(path));
+            }
+            "--max-concurrent-calls" => {
+                max_concurrent_calls = Some(parse_limit(arg, args.next())?.max(1) as usize)
+            }
+            "--auth-token-file" => {
+                let file = args.next().context("--auth-token-file requires a file")?;
+                auth_token_file = Some(PathBuf::from(file));
+            }
+            "--no-auth" => no_auth = true,
+            "--tls-cert" => {
+                let file = args.next().context("--tls-cert requires a file")?;
+                tls_cert = Some(PathBuf::from(file));
+            }
+            "--tls-key" => {
+                let file = args.next().context("--tls-key requires a file")?;
+                tls_key = Some(PathBuf::from(file));
+            }
+            flag if flag.starts_with("--") => {
+                anyhow::bail!("Unknown option: {}\n\n{}", flag, USAGE)
+            }
+            _ => roots.push(parse_root(arg)?),
+        }
+    }
+    Ok(Options {
+        config,
+        roots,
+        read_only,
+        all_read_only,
+        audit_log,
+        limits,
+        fast_search,
+        templates,
+        http,
+        ws,
+        socket,
+        max_concurrent_calls,
+        auth_token_file,
+        no_auth,
+        tls_cert,
+        tls_key,
+    })
+}
+
+/// Settings for the network transports. A token is mandatory unless
+/// authentication was explicitly disabled.
+fn network_config(options: &Options) -> Result<NetworkConfig> {
+    let token = match &options.auth_token_file {
+        Some(file) => Some(
+            std::fs::read_to_string(file)
+                .with_context(|| format!("Failed to read {}", file.display()))?
+                .trim()
+                .to_string(),
+        ),
+        None => env::var("MCP_FS_AUTH_TOKEN").ok(),
+    }
+    .filter(|token| !token.is_empty());
+    if token.is_none() && !options.no_auth {
+        anyhow::bail!(
+            "Network transports require a bearer token: set MCP_FS_AUTH_TOKEN, \
+             pass --auth-token-file, or opt out with --no-auth"
+        );
+    }
+    let tls = match (&options.tls_cert, &options.tls_key) {
+        (Some(cert), Some(key)) => Some(TlsConfig::load(cert, key)?),
+        (None, None) => None,
+        _ => anyhow::bail!("--tls-cert and --tls-key must be given together"),
+    };
+    Ok(NetworkConfig { token, tls })
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let mut options = parse_options(&args)?;
+    let config = match &options.config {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    let mut undeletable = Vec::new();
+    let mut roots = Vec::new();
+    for root in &config.roots {
+        if !root.allows(Permission::Write) {
+            options.read_only.push(root.path.clone());
+        } else if !root.allows(Permission::Delete) {
+            undeletable.push(root.path.clone());
+        }
+        roots.push(root.path.clone());
+    }
+    roots.append(&mut options.roots);
+    if options.all_read_only {
+        options.read_only = roots.clone();
+    }
+    let limits = Limits {
+        max_write_bytes: options
+            .limits
+            .max_write_bytes
+            .or(config.limits.max_write_bytes),
+        max_files_per_call: options
+            .limits
+            .max_files_per_call
+            .or(config.limits.max_files_per_call),
+        max_ops_per_minute: options
+            .limits
+            .max_ops_per_minute
+            .or(config.limits.max_ops_per_minute),
+    };
+
+    let network = match options.http.is_some() || options.ws.is_some() {
+        true => network_config(&options)?,
+        false => NetworkConfig::default(),
+    };
+    let mut workspace = Workspace::new(roots)
+        .with_read_only(options.read_only)
+        .with_undeletable(undeletable);
+    for root in config.roots.iter().filter(|r| !r.ignore.is_empty()) {
+        workspace = workspace.with_ignores(&root.path, &root.ignore)?;
+    }
+    let audit_log = options.audit_log.or(config.audit_log);
+    let disabled_tools = Arc::new(config.tools.disabled);
+    let metrics = Metrics::default();
+    let session_metrics = metrics.clone();
+    let quotas = Quotas::new(limits);
+    let index = match options.fast_search || config.fast_search {
+        true => Some(SearchIndex::start(workspace.clone())?),
+        false => None,
+    };
+    let templates = options
+        .templates
+        .or(config.templates)
+        .map(|dir| Arc::new(Templates::new(dir)));
+    let semantic = Arc::new(Backend::from_env());
+    let factory: StateFactory = Arc::new(move |outbound| State {
+        outbound,
+        audit: AuditLog::new(audit_log.clone()),
+        metrics: session_metrics.clone(),
+        quotas: quotas.clone(),
+        index: index.clone(),
+        semantic: Some(Arc::clone(&semantic)),
+        disabled_tools: Arc::clone(&disabled_tools),
+        templates: templates.clone(),
+        ..State::default()
+    });
+
+    if options.http.is_none() && options.ws.is_none() && options.socket.is_none() {
+        return transport::stdio::serve(workspace, factory);
+    }
+    // Network transports share one scheduler so the limit is global. All
+    // but the last run on their own threads; any of them failing is fatal.
+    let scheduler = options
+        .max_concurrent_calls
+        .map_or_else(Scheduler::default, Scheduler::new);
+    let mut transports: Vec<Box<dyn FnOnce() -> Result<()> + Send>> = Vec::new();
+    if let Some(path) = options.socket {
+        #[cfg(unix)]
+        {
+            let (scheduler, workspace, factory) =
+                (scheduler.clone(), workspace.clone(), factory.clone());
+            transports.push(Box::new(move || {
+                transport::socket::serve(&path, scheduler, workspace, factory)
+            }));
+        }
+        #[cfg(not(unix))]
+        anyhow::bail!("--socket is only supported on Unix ({})", path.display());
+    }
+    if let Some(ws) = options.ws {
+        let (network, scheduler, workspace, factory) = (
+            network.clone(),
+            scheduler.clone(),
+            workspace.clone(),
+            factory.clone(),
+        );
+        transports.push(Box::new(move || {
+            transport::ws::serve(&ws, network, scheduler, workspace, factory)
+        }));
+    }
+    if let Some(http) = options.http {
+        transports.push(Box::new(move || {
+            transport::http::serve(&http, network, metrics, scheduler, workspace, factory)
+        }));
+    }
+    let last = transports
+        .pop()
+        .expect("at least one transport was requested");
+    for serve in transports {
+        thread::spawn(move || {
+            if let Err(e) = serve() {
+                eprintln!("mcp-fs-server: {:#}", e);
+                std::process::exit(1);
+            }
+        });
+    }
+    last()
+}
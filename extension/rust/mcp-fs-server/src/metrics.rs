@@ -0,0 +1,147 @@
+//! Per-tool counters exported in the Prometheus text format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Upper bounds, in seconds, of the latency histogram buckets.
+const BUCKETS: [f64; 10] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0];
+
+/// Metric name, help text and value of each per-tool counter.
+type Counter = (&'static str, &'static str, fn(&ToolStats) -> u64);
+
+const COUNTERS: [Counter; 4] = [
+    ("mcp_fs_tool_calls_total", "Tool calls", |s| s.calls),
+    ("mcp_fs_tool_errors_total", "Tool calls that failed", |s| {
+        s.errors
+    }),
+    ("mcp_fs_bytes_read_total", "File bytes read by tools", |s| {
+        s.bytes_read
+    }),
+    (
+        "mcp_fs_bytes_written_total",
+        "File bytes written by tools",
+        |s| s.bytes_written,
+    ),
+];
+
+#[derive(Default)]
+struct ToolStats {
+    calls: u64,
+    errors: u64,
+    bytes_read: u64,
+    bytes_written: u64,
+    seconds: f64,
+    buckets: [u64; BUCKETS.len()],
+}
+
+/// Counters shared by every session so a scrape covers the whole process.
+#[derive(Clone, Default)]
+pub struct Metrics {
+    tools: Arc<Mutex<BTreeMap<&'static str, ToolStats>>>,
+}
+
+/// The outcome of one tool call, as far as the metrics care.
+pub struct Call {
+    pub tool: &'static str,
+    pub elapsed: Duration,
+    pub succeeded: bool,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+}
+
+impl Metrics {
+    pub fn record(&self, call: Call) {
+        let mut tools = self.tools.lock().unwrap();
+        let stats = tools.entry(call.tool).or_default();
+        stats.calls += 1;
+        let seconds = call.elapsed.as_secs_f64();
+        stats.seconds += seconds;
+        for (count, bound) in stats.buckets.iter_mut().zip(BUCKETS) {
+            if seconds <= bound {
+                *count += 1;
+            }
+        }
+        if !call.succeeded {
+            stats.errors += 1;
+        }
+        stats.bytes_read += call.bytes_read;
+        stats.bytes_written += call.bytes_written;
+    }
+
+    /// Renders all counters in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let tools = self.tools.lock().unwrap();
+        let mut out = String::new();
+        for (name, help, value) in COUNTERS {
+            let _ = writeln!(out, "# HELP {} {}.", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            for (tool, stats) in tools.iter() {
+                let _ = writeln!(out, "{}{{tool=\"{}\"}} {}", name, tool, value(stats));
+            }
+        }
+
+        let name = "mcp_fs_tool_duration_seconds";
+        let _ = writeln!(out, "# HELP {} Tool call latency.", name);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (tool, stats) in tools.iter() {
+            for (count, bound) in stats.buckets.iter().zip(BUCKETS) {
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{{tool=\"{}\",le=\"{}\"}} {}",
+                    name, tool, bound, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{}_bucket{{tool=\"{}\",le=\"+Inf\"}} {}",
+                name, tool, stats.calls
+            );
+            let _ = writeln!(out, "{}_sum{{tool=\"{}\"}} {}", name, tool, stats.seconds);
+            let _ = writeln!(out, "{}_count{{tool=\"{}\"}} {}", name, tool, stats.calls);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counts_calls_bytes_and_errors() {
+        let metrics = Metrics::default();
+        metrics.record(Call {
+            tool: "read_file",
+            elapsed: Duration::from_millis(3),
+            succeeded: true,
+            bytes_read: 120,
+            bytes_written: 0,
+        });
+        metrics.record(Call {
+            tool: "read_file",
+            elapsed: Duration::from_secs(2),
+            succeeded: false,
+            bytes_read: 0,
+            bytes_written: 0,
+        });
+        metrics.record(Call {
+            tool: "write_file",
+            elapsed: Duration::from_millis(1),
+            succeeded: true,
+            bytes_read: 0,
+            bytes_written: 7,
+        });
+
+        let text = metrics.render();
+        assert!(text.contains("mcp_fs_tool_calls_total{tool=\"read_file\"} 2\n"));
+        assert!(text.contains("mcp_fs_tool_errors_total{tool=\"read_file\"} 1\n"));
+        assert!(text.contains("mcp_fs_bytes_read_total{tool=\"read_file\"} 120\n"));
+        assert!(text.contains("mcp_fs_bytes_written_total{tool=\"write_file\"} 7\n"));
+        assert!(text
+            .contains("mcp_fs_tool_duration_seconds_bucket{tool=\"read_file\",le=\"0.005\"} 1\n"));
+        assert!(text
+            .contains("mcp_fs_tool_duration_seconds_bucket{tool=\"read_file\",le=\"+Inf\"} 2\n"));
+    }
+}
@@ -0,0 +1,146 @@
+use std::path::Path;
+
+/// Identifies common binary formats from their leading magic bytes. Magics
+/// short enough to start ordinary text ("MZ", "BM", "ID3") only count with
+/// the header fields that follow them.
+pub fn sniff(bytes: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xFF\xD8\xFF", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1F\x8B\x08", "application/gzip"),
+        (b"\0asm", "application/wasm"),
+        (b"\x7FELF", "application/x-elf"),
+        (b"\0\0\x01\0", "image/x-icon"),
+        (b"OggS", "audio/ogg"),
+        (b"fLaC", "audio/flac"),
+        (b"7z\xBC\xAF\x27\x1C", "application/x-7z-compressed"),
+        (b"SQLite format 3\0", "application/vnd.sqlite3"),
+    ];
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" {
+        match &bytes[8..12] {
+            b"WEBP" => return Some("image/webp"),
+            b"WAVE" => return Some("audio/wav"),
+            _ => {}
+        }
+    }
+    if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" {
+        return Some("video/mp4");
+    }
+    if bytes.starts_with(b"MZ") && is_pe(bytes) {
+        return Some("application/vnd.microsoft.portable-executable");
+    }
+    if bytes.starts_with(b"BM") && is_bmp(bytes) {
+        return Some("image/bmp");
+    }
+    if bytes.starts_with(b"ID3") && is_id3(bytes) {
+        return Some("audio/mpeg");
+    }
+    SIGNATURES
+        .iter()
+        .find(|(magic, _)| bytes.starts_with(magic))
+        .map(|(_, mime)| *mime)
+}
+
+fn le_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    let field = bytes.get(at..at + 4)?;
+    Some(u32::from_le_bytes(field.try_into().ok()?))
+}
+
+/// An MZ stub whose `e_lfanew` points at a "PE\0\0" signature.
+fn is_pe(bytes: &[u8]) -> bool {
+    le_u32(bytes, 0x3C)
+        .and_then(|at| bytes.get(at as usize..at as usize + 4))
+        .is_some_and(|signature| signature == b"PE\0\0")
+}
+
+/// A BMP file header: zero reserved fields and a known DIB header size.
+fn is_bmp(bytes: &[u8]) -> bool {
+    bytes.get(6..10) == Some(&[0; 4])
+        && matches!(le_u32(bytes, 14), Some(12 | 40 | 52 | 56 | 64 | 108 | 124))
+}
+
+/// An ID3v2 tag header: version 2-4 and a syncsafe size.
+fn is_id3(bytes: &[u8]) -> bool {
+    bytes.len() >= 10
+        && matches!(bytes[3], 2..=4)
+        && bytes[4] != 0xFF
+        && bytes[6..10].iter().all(|b| *b < 0x80)
+}
+
+/// Guesses a MIME type from a file extension, falling back to
+/// `application/octet-stream` for anything unrecognised.
+pub fn guess_from_path(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    match ext.as_deref() {
+        Some("txt" | "log" | "ini" | "cfg" | "conf") => "text/plain",
+        Some("md" | "markdown") => "text/markdown",
+        Some("html" | "htm") => "text/html",
+        Some("css") => "text/css",
+        Some("csv") => "text/csv",
+        Some("xml") => "application/xml",
+        Some("json") => "application/json",
+        Some("yaml" | "yml") => "application/yaml",
+        Some("toml") => "application/toml",
+        Some("js" | "mjs" | "cjs") => "text/javascript",
+        Some("ts" | "tsx" | "mts" | "cts") => "text/typescript",
+        Some("jsx") => "text/jsx",
+        Some("rs") => "text/x-rust",
+        Some("py") => "text/x-python",
+        Some("go") => "text/x-go",
+        Some("java") => "text/x-java",
+        Some("kt" | "kts") => "text/x-kotlin",
+        Some("c" | "h") => "text/x-c",
+        Some("cpp" | "cc" | "hpp" | "cxx") => "text/x-c++",
+        Some("sh" | "bash") => "application/x-sh",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("pdf") => "application/pdf",
+        Some("zip") => "application/zip",
+        Some("gz") => "application/gzip",
+        Some("wasm") => "application/wasm",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_magic_bytes() {
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0"), Some("image/png"));
+        assert_eq!(sniff(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff(b"%PDF-1.7"), Some("application/pdf"));
+        assert_eq!(sniff(b"plain text"), None);
+    }
+
+    #[test]
+    fn test_short_magics_need_their_headers() {
+        assert_eq!(sniff(b"BM25 ranks documents by term frequency"), None);
+        assert_eq!(sniff(b"MZ is a stub, not a PE header"), None);
+        assert_eq!(sniff(b"ID3 tags are metadata"), None);
+
+        let mut bmp = b"BM\x46\0\0\0\0\0\0\0\x36\0\0\0\x28\0\0\0".to_vec();
+        bmp.resize(70, 0);
+        assert_eq!(sniff(&bmp), Some("image/bmp"));
+        let mut pe = vec![0u8; 0x84];
+        pe[..2].copy_from_slice(b"MZ");
+        pe[0x3C] = 0x80;
+        pe[0x80..].copy_from_slice(b"PE\0\0");
+        assert_eq!(
+            sniff(&pe),
+            Some("application/vnd.microsoft.portable-executable")
+        );
+        assert_eq!(sniff(b"ID3\x04\0\0\0\0\x01\x7F"), Some("audio/mpeg"));
+    }
+}
@@ -0,0 +1,64 @@
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::logging::{Level, Threshold, LOG_NOTIFICATION};
+
+type Sink = Arc<dyn Fn(String) + Send + Sync>;
+
+/// Sends messages to the client. Cloneable so background threads such as
+/// file followers can push notifications alongside regular responses.
+#[derive(Clone, Default)]
+pub struct Outbound {
+    sink: Option<Sink>,
+    log_level: Threshold,
+}
+
+impl Outbound {
+    /// Wraps a transport-specific writer for serialized JSON-RPC messages.
+    pub fn new(sink: impl Fn(String) + Send + Sync + 'static) -> Self {
+        Self {
+            sink: Some(Arc::new(sink)),
+            log_level: Threshold::default(),
+        }
+    }
+
+    /// An outbound whose messages can be read back from the returned receiver.
+    #[cfg(test)]
+    pub fn channel() -> (Self, std::sync::mpsc::Receiver<String>) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        (
+            Self::new(move |message| {
+                let _ = tx.send(message);
+            }),
+            rx,
+        )
+    }
+
+    /// Sends a raw, already-serialized JSON-RPC message.
+    pub fn send_raw(&self, message: String) {
+        if let Some(sink) = &self.sink {
+            sink(message);
+        }
+    }
+
+    /// Sends a JSON-RPC notification.
+    pub fn notify(&self, method: &str, params: Value) {
+        self.send_raw(json!({ "jsonrpc": "2.0", "method": method, "params": params }).to_string());
+    }
+
+    /// Applies `logging/setLevel` to this session's every outbound clone.
+    pub fn set_log_level(&self, level: Level) {
+        self.log_level.set(level);
+    }
+
+    /// Sends a log message if the client's level admits it. `logger` names
+    /// the subsystem, e.g. "sandbox".
+    pub fn log(&self, level: Level, logger: &str, data: Value) {
+        if self.log_level.allows(level) {
+            self.notify(
+                LOG_NOTIFICATION,
+                json!({ "level": level.as_str(), "logger": logger, "data": data }),
+            );
+        }
+    }
+}
@@ -0,0 +1,358 @@
+//! Unified diff generation, parsing, and in-memory application.
+
+use anyhow::{anyhow, bail, Result};
+use similar::TextDiff;
+
+/// Renders a unified diff between two versions of `path` with three lines of context.
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", path), &format!("b/{}", path))
+        .to_string()
+}
+
+/// One file section of a unified diff. `None` paths stand for `/dev/null`.
+#[derive(Debug, PartialEq)]
+pub struct FilePatch {
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FilePatch {
+    /// The path the patch operates on: the new path, or the old one for deletions.
+    pub fn target(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub lines: Vec<HunkLine>,
+    /// Set when the diff says the new side has no trailing newline.
+    pub new_missing_newline: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+impl Hunk {
+    fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Remove(s) => Some(s.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    fn new_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Add(s) => Some(s.as_str()),
+                HunkLine::Remove(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// Outcome of applying one hunk.
+#[derive(Debug, PartialEq)]
+pub struct HunkOutcome {
+    /// Line offset from the position the hunk header claimed.
+    pub offset: Option<isize>,
+    pub error: Option<String>,
+}
+
+fn parse_path(raw: &str) -> Option<String> {
+    let path = raw.split('\t').next().unwrap_or(raw).trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// The numbers of a `@@ -a,b +c,d @@` header.
+struct HunkHeader {
+    old_start: usize,
+    old_count: usize,
+    new_count: usize,
+}
+
+/// Parses `@@ -a,b +c,d @@`; an omitted count is 1.
+fn parse_hunk_header(line: &str) -> Result<HunkHeader> {
+    let malformed = || anyhow!("Malformed hunk header: {}", line);
+    let mut ranges = line
+        .strip_prefix("@@ ")
+        .ok_or_else(malformed)?
+        .split_whitespace();
+    let mut range = |sign: char| -> Result<(usize, usize)> {
+        let range = ranges
+            .next()
+            .and_then(|r| r.strip_prefix(sign))
+            .ok_or_else(malformed)?;
+        let (start, count) = range.split_once(',').unwrap_or((range, "1"));
+        Ok((
+            start.parse().map_err(|_| malformed())?,
+            count.parse().map_err(|_| malformed())?,
+        ))
+    };
+    let (old_start, old_count) = range('-')?;
+    let (_, new_count) = range('+')?;
+    Ok(HunkHeader {
+        old_start,
+        old_count,
+        new_count,
+    })
+}
+
+/// Parses a (possibly multi-file) unified diff. Each hunk is read for as
+/// many lines as its header counts, so removed or added lines that look
+/// like `--- `/`+++ ` file headers stay in the hunk.
+pub fn parse(diff: &str) -> Result<Vec<FilePatch>> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut lines = diff.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if let Some(old) = line.strip_prefix("--- ") {
+            let new = lines
+                .next()
+                .and_then(|l| l.strip_prefix("+++ "))
+                .ok_or_else(|| anyhow!("Expected '+++' line after '--- {}'", old))?;
+            files.push(FilePatch {
+                old_path: parse_path(old),
+                new_path: parse_path(new),
+                hunks: Vec::new(),
+            });
+        } else if line.starts_with("@@ ") {
+            let file = files
+                .last_mut()
+                .ok_or_else(|| anyhow!("Hunk found before any file header"))?;
+            let header = parse_hunk_header(line)?;
+            let (mut old_left, mut new_left) = (header.old_count, header.new_count);
+            let mut hunk = Hunk {
+                old_start: header.old_start,
+                lines: Vec::new(),
+                new_missing_newline: false,
+            };
+            loop {
+                // A "\ No newline at end of file" marker follows the line it is about.
+                if lines.next_if(|l| l.starts_with('\\')).is_some() {
+                    if !matches!(hunk.lines.last(), Some(HunkLine::Remove(_))) {
+                        hunk.new_missing_newline = true;
+                    }
+                    continue;
+                }
+                if old_left == 0 && new_left == 0 {
+                    break;
+                }
+                let short = || anyhow!("Hunk '{}' has fewer lines than its header counts", line);
+                let next = lines.next().ok_or_else(short)?;
+                let parsed = match next.chars().next() {
+                    // Some tools strip the single space from blank context lines.
+                    Some(' ') | None if old_left > 0 && new_left > 0 => {
+                        (old_left, new_left) = (old_left - 1, new_left - 1);
+                        HunkLine::Context(next.get(1..).unwrap_or_default().to_string())
+                    }
+                    Some('-') if old_left > 0 => {
+                        old_left -= 1;
+                        HunkLine::Remove(next[1..].to_string())
+                    }
+                    Some('+') if new_left > 0 => {
+                        new_left -= 1;
+                        HunkLine::Add(next[1..].to_string())
+                    }
+                    _ => return Err(short()),
+                };
+                hunk.lines.push(parsed);
+            }
+            file.hunks.push(hunk);
+        }
+    }
+
+    if files.is_empty() {
+        bail!("No file headers ('--- ' / '+++ ') found in patch");
+    }
+    Ok(files)
+}
+
+/// Finds where `needle` occurs in `haystack` at or after `min`, preferring
+/// the position closest to `expected`.
+fn locate(haystack: &[String], needle: &[&str], expected: usize, min: usize) -> Option<usize> {
+    let max_start = haystack.len().checked_sub(needle.len())?;
+    let matches_at = |pos: usize| {
+        haystack[pos..pos + needle.len()]
+            .iter()
+            .zip(needle)
+            .all(|(a, b)| a.trim_end_matches('\r') == b.trim_end_matches('\r'))
+    };
+    let expected = expected.clamp(min, max_start.max(min));
+    for distance in 0..=haystack.len() {
+        let candidates = [
+            expected.checked_add(distance),
+            expected.checked_sub(distance),
+        ];
+        for pos in candidates.into_iter().flatten() {
+            if pos >= min && pos <= max_start && matches_at(pos) {
+                return Some(pos);
+            }
+        }
+    }
+    None
+}
+
+/// Applies the hunks of one file patch to `original`, returning the new text
+/// (or `None` for a deletion) and per-hunk outcomes. The text is only
+/// meaningful when every hunk applied.
+pub fn apply(original: &str, patch: &FilePatch) -> (Option<String>, Vec<HunkOutcome>) {
+    let had_newline = original.is_empty() || original.ends_with('\n');
+    let body = original.strip_suffix('\n').unwrap_or(original);
+    let mut lines: Vec<String> = if original.is_empty() {
+        Vec::new()
+    } else {
+        body.split('\n').map(str::to_string).collect()
+    };
+    let mut trailing_newline = had_newline;
+
+    let mut outcomes = Vec::new();
+    // `cursor` is the first line later hunks may touch; `shift` tracks how
+    // much earlier hunks grew or shrank the file.
+    let mut cursor = 0;
+    let mut shift: isize = 0;
+    for hunk in &patch.hunks {
+        let old = hunk.old_lines();
+        let new = hunk.new_lines();
+        let claimed = if old.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let expected = (claimed as isize + shift).max(0) as usize;
+        match locate(&lines, &old, expected, cursor) {
+            Some(pos) => {
+                let replacement: Vec<String> = new.iter().map(|s| s.to_string()).collect();
+                lines.splice(pos..pos + old.len(), replacement);
+                cursor = pos + new.len();
+                shift += new.len() as isize - old.len() as isize;
+                if hunk.new_missing_newline {
+                    trailing_newline = false;
+                } else if pos + new.len() == lines.len() && !new.is_empty() {
+                    trailing_newline = true;
+                }
+                outcomes.push(HunkOutcome {
+                    offset: Some(pos as isize - expected as isize),
+                    error: None,
+                });
+            }
+            None => outcomes.push(HunkOutcome {
+                offset: None,
+                error: Some(format!(
+                    "Context not found near line {} ({} line(s) expected)",
+                    hunk.old_start,
+                    old.len()
+                )),
+            }),
+        }
+    }
+
+    if patch.new_path.is_none() {
+        return (None, outcomes);
+    }
+    let mut text = lines.join("\n");
+    if trailing_newline && !lines.is_empty() {
+        text.push('\n');
+    }
+    (Some(text), outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ fn one() {}
+-fn two() {}
++fn deux() {}
+ fn three() {}
+--- /dev/null
++++ b/NEW.md
+@@ -0,0 +1,2 @@
++# New
++file
+";
+
+    #[test]
+    fn test_parse_multi_file_diff() {
+        let files = parse(DIFF).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].target(), "src/lib.rs");
+        assert_eq!(files[0].hunks[0].lines.len(), 4);
+        assert_eq!(files[1].old_path, None);
+        assert_eq!(files[1].target(), "NEW.md");
+    }
+
+    #[test]
+    fn test_apply_with_offset_and_creation() {
+        let files = parse(DIFF).unwrap();
+        // Two extra lines at the top shift the hunk by +2.
+        let original = "// a\n// b\nfn one() {}\nfn two() {}\nfn three() {}\n";
+        let (text, outcomes) = apply(original, &files[0]);
+        assert_eq!(outcomes[0].offset, Some(2));
+        assert_eq!(
+            text.unwrap(),
+            "// a\n// b\nfn one() {}\nfn deux() {}\nfn three() {}\n"
+        );
+
+        let (text, outcomes) = apply("", &files[1]);
+        assert!(outcomes[0].error.is_none());
+        assert_eq!(text.unwrap(), "# New\nfile\n");
+    }
+
+    #[test]
+    fn test_generated_diff_round_trips() {
+        let old = "one\ntwo\nthree\n";
+        let new = "one\n2\nthree\nfour\n";
+        let diff = unified_diff("f.txt", old, new);
+        let files = parse(&diff).unwrap();
+        assert_eq!(apply(old, &files[0]).0.unwrap(), new);
+    }
+
+    #[test]
+    fn test_lines_that_look_like_file_headers_stay_in_the_hunk() {
+        let diff = "--- a/q.sql\n+++ b/q.sql\n@@ -1,2 +1,2 @@\n--- comment\n+++ x\n select 1;\n";
+        let files = parse(diff).unwrap();
+        assert_eq!(files.len(), 1);
+        let (text, outcomes) = apply("-- comment\nselect 1;\n", &files[0]);
+        assert!(outcomes[0].error.is_none());
+        assert_eq!(text.unwrap(), "++ x\nselect 1;\n");
+
+        let short = "--- a/q.sql\n+++ b/q.sql\n@@ -1,3 +1,3 @@\n select 1;\n";
+        assert!(parse(short).is_err());
+    }
+
+    #[test]
+    fn test_mismatched_context_reports_error() {
+        let files = parse(DIFF).unwrap();
+        let (_, outcomes) = apply("something else\n", &files[0]);
+        assert!(outcomes[0].error.is_some());
+    }
+}
@@ -0,0 +1,84 @@
+//! MCP progress notifications for long tool calls. A client opts in by
+//! sending `_meta.progressToken` with `tools/call`; without one, reports are
+//! dropped.
+
+use serde_json::{json, Value};
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use crate::outbound::Outbound;
+
+pub const PROGRESS_NOTIFICATION: &str = "notifications/progress";
+
+/// Reports closer together than this are dropped, except the first.
+const MIN_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Reports progress on one request to the client that asked for it.
+#[derive(Clone, Default)]
+pub struct Progress {
+    token: Option<Value>,
+    outbound: Outbound,
+    last_sent: Cell<Option<Instant>>,
+}
+
+impl Progress {
+    pub fn new(token: Option<Value>, outbound: Outbound) -> Self {
+        Self {
+            token,
+            outbound,
+            last_sent: Cell::new(None),
+        }
+    }
+
+    /// Whether the client asked for progress on this request.
+    pub fn is_requested(&self) -> bool {
+        self.token.is_some()
+    }
+
+    /// Sends `progress` out of `total` (when known), throttled so a tight
+    /// loop can call this per item. `progress` must not decrease.
+    pub fn report(&self, progress: u64, total: Option<u64>, message: &str) {
+        let Some(token) = &self.token else {
+            return;
+        };
+        if self
+            .last_sent
+            .get()
+            .is_some_and(|last| last.elapsed() < MIN_INTERVAL)
+        {
+            return;
+        }
+        let mut params = json!({ "progressToken": token, "progress": progress });
+        if let Some(total) = total {
+            params["total"] = json!(total);
+        }
+        if !message.is_empty() {
+            params["message"] = json!(message);
+        }
+        self.outbound.notify(PROGRESS_NOTIFICATION, params);
+        self.last_sent.set(Some(Instant::now()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_are_throttled_and_need_a_token() {
+        let (outbound, rx) = Outbound::channel();
+        Progress::new(None, outbound.clone()).report(1, None, "ignored");
+        assert!(rx.try_recv().is_err());
+
+        let progress = Progress::new(Some(json!("tok")), outbound);
+        progress.report(1, Some(10), "scanning");
+        progress.report(2, Some(10), "scanning");
+        let sent: Value = serde_json::from_str(&rx.try_recv().unwrap()).unwrap();
+        assert_eq!(sent["method"], PROGRESS_NOTIFICATION);
+        assert_eq!(sent["params"]["progressToken"], "tok");
+        assert_eq!(sent["params"]["progress"], 1);
+        assert_eq!(sent["params"]["total"], 10);
+        assert_eq!(sent["params"]["message"], "scanning");
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Protocol revision advertised when the client asks for one we don't know.
+pub const LATEST_PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Protocol revisions this server can speak, newest first.
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// Standard JSON-RPC 2.0 error codes.
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+
+/// MCP-specific error code for `resources/read` on an unknown URI.
+pub const RESOURCE_NOT_FOUND: i64 = -32002;
+
+/// A JSON-RPC message received from the MCP client.
+/// Requests carry an `id`; notifications do not. Responses to requests the
+/// server sent carry an `id` and a `result` or `error` but no `method`.
+#[derive(Debug, Deserialize)]
+pub struct IncomingMessage {
+    pub jsonrpc: String,
+    #[serde(default)]
+    pub id: Option<Value>,
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub params: Option<Value>,
+    #[serde(default)]
+    pub result: Option<Value>,
+    #[serde(default)]
+    pub error: Option<Value>,
+}
+
+/// A JSON-RPC response sent back to the MCP client.
+#[derive(Debug, Serialize)]
+pub struct Response {
+    pub jsonrpc: &'static str,
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl Response {
+    pub fn success(id: Value, result: Value) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    pub fn failure(id: Value, error: RpcError) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result: None,
+            error: Some(error),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Parameters of `initialize`. Only the fields the server acts on are parsed.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitializeParams {
+    #[serde(default)]
+    pub protocol_version: Option<String>,
+    #[serde(default)]
+    pub capabilities: ClientCapabilities,
+}
+
+/// Client capabilities the server reacts to.
+#[derive(Debug, Default, Deserialize)]
+pub struct ClientCapabilities {
+    #[serde(default)]
+    pub roots: Option<Value>,
+}
+
+/// A root reported by the client in reply to `roots/list`.
+#[derive(Debug, Deserialize)]
+pub struct Root {
+    pub uri: String,
+}
+
+/// Result of the client's `roots/list`.
+#[derive(Debug, Deserialize)]
+pub struct ListRootsResult {
+    pub roots: Vec<Root>,
+}
+
+/// Parameters of `tools/call`.
+#[derive(Debug, Deserialize)]
+pub struct CallToolParams {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Option<Value>,
+    #[serde(default, rename = "_meta")]
+    pub meta: Option<RequestMeta>,
+}
+
+/// Request metadata sent under `params._meta`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestMeta {
+    /// Opaque token the client wants `notifications/progress` tagged with.
+    #[serde(default)]
+    pub progress_token: Option<Value>,
+}
+
+/// Entry returned by `tools/list`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolDefinition {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub input_schema: Value,
+    pub annotations: ToolAnnotations,
+}
+
+/// Behavioural hints clients use to decide which calls need the user's
+/// approval. "Environment" means the files and the vector store, not the
+/// server's own session state such as watches or snapshots.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolAnnotations {
+    pub read_only_hint: bool,
+    /// Whether the tool may overwrite or remove existing data.
+    pub destructive_hint: bool,
+    /// Whether repeating a call with the same arguments has no further effect.
+    pub idempotent_hint: bool,
+    /// Whether the tool talks to services outside the workspace (Qdrant, Ollama).
+    pub open_world_hint: bool,
+}
+
+impl ToolAnnotations {
+    pub const READ_ONLY: Self = Self {
+        read_only_hint: true,
+        destructive_hint: false,
+        idempotent_hint: true,
+        open_world_hint: false,
+    };
+    /// Adds data without touching what is already there.
+    pub const ADDITIVE: Self = Self {
+        read_only_hint: false,
+        destructive_hint: false,
+        idempotent_hint: false,
+        open_world_hint: false,
+    };
+    /// Overwrites or removes existing data.
+    pub const DESTRUCTIVE: Self = Self {
+        read_only_hint: false,
+        destructive_hint: true,
+        idempotent_hint: false,
+        open_world_hint: false,
+    };
+}
+
+/// A single content block inside a tool result.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Content {
+    Text { text: String },
+}
+
+/// Result of `tools/call`. Tool failures are reported here with `is_error`
+/// rather than as JSON-RPC errors, so the model can see and react to them.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallToolResult {
+    pub content: Vec<Content>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<Value>,
+    pub is_error: bool,
+}
+
+impl CallToolResult {
+    /// Wraps a structured tool output, mirroring it as text for clients
+    /// that only render `content`.
+    pub fn structured(value: Value) -> Self {
+        Self {
+            content: vec![Content::Text {
+                text: value.to_string(),
+            }],
+            structured_content: Some(value),
+            is_error: false,
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            content: vec![Content::Text {
+                text: message.into(),
+            }],
+            structured_content: None,
+            is_error: true,
+        }
+    }
+
+    /// An error result that also carries machine-readable details.
+    pub fn structured_error(message: impl Into<String>, details: Value) -> Self {
+        Self {
+            structured_content: Some(details),
+            ..Self::error(message)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_error_result_serialization() {
+        let result = CallToolResult::error("boom");
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(
+            json,
+            json!({ "content": [{ "type": "text", "text": "boom" }], "isError": true })
+        );
+    }
+
+    #[test]
+    fn test_notification_has_no_id() {
+        let msg: IncomingMessage =
+            serde_json::from_str(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#)
+                .unwrap();
+        assert!(msg.id.is_none());
+    }
+}
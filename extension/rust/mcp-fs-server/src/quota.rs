@@ -0,0 +1,134 @@
+//! Limits on how much mutating tools may change, so a runaway client cannot
+//! fill the disk or rewrite a whole tree in one burst.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Configured limits; `None` means unlimited.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Limits {
+    pub max_write_bytes: Option<u64>,
+    pub max_files_per_call: Option<u64>,
+    pub max_ops_per_minute: Option<u64>,
+}
+
+/// Returned when a call would exceed one of the [`Limits`].
+#[derive(Debug)]
+pub struct QuotaExceeded {
+    pub quota: &'static str,
+    pub limit: u64,
+    pub requested: u64,
+}
+
+impl QuotaExceeded {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "error": "quota_exceeded",
+            "quota": self.quota,
+            "limit": self.limit,
+            "requested": self.requested,
+        })
+    }
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Quota exceeded: {} is limited to {} (requested {})",
+            self.quota, self.limit, self.requested
+        )
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+/// Enforces [`Limits`]. Clones share the operation rate window, so the rate
+/// limit covers every session of the process.
+#[derive(Clone, Default)]
+pub struct Quotas {
+    limits: Limits,
+    recent: Arc<Mutex<VecDeque<Instant>>>,
+}
+
+fn check(quota: &'static str, limit: Option<u64>, requested: u64) -> Result<(), QuotaExceeded> {
+    match limit {
+        Some(limit) if requested > limit => Err(QuotaExceeded {
+            quota,
+            limit,
+            requested,
+        }),
+        _ => Ok(()),
+    }
+}
+
+impl Quotas {
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            ..Self::default()
+        }
+    }
+
+    /// Checks the size of one file about to be written.
+    pub fn check_write(&self, bytes: usize) -> Result<(), QuotaExceeded> {
+        check("max_write_bytes", self.limits.max_write_bytes, bytes as u64)
+    }
+
+    /// Checks how many files a single call is about to change.
+    pub fn check_files(&self, files: usize) -> Result<(), QuotaExceeded> {
+        check(
+            "max_files_per_call",
+            self.limits.max_files_per_call,
+            files as u64,
+        )
+    }
+
+    /// Counts one mutating operation against the per-minute rate.
+    pub fn take_operation(&self) -> Result<(), QuotaExceeded> {
+        let Some(limit) = self.limits.max_ops_per_minute else {
+            return Ok(());
+        };
+        let mut recent = self.recent.lock().unwrap();
+        let now = Instant::now();
+        while recent
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= WINDOW)
+        {
+            recent.pop_front();
+        }
+        check("max_ops_per_minute", Some(limit), recent.len() as u64 + 1)?;
+        recent.push_back(now);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_are_enforced() {
+        let quotas = Quotas::new(Limits {
+            max_write_bytes: Some(10),
+            max_files_per_call: None,
+            max_ops_per_minute: Some(2),
+        });
+        assert!(quotas.check_write(10).is_ok());
+        assert_eq!(quotas.check_write(11).unwrap_err().quota, "max_write_bytes");
+        assert!(quotas.check_files(1_000_000).is_ok());
+
+        let shared = quotas.clone();
+        assert!(quotas.take_operation().is_ok());
+        assert!(shared.take_operation().is_ok());
+        let error = quotas.take_operation().unwrap_err();
+        assert_eq!(error.to_json()["requested"], 3);
+    }
+}
@@ -0,0 +1,251 @@
+use anyhow::{bail, Context as _, Result};
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+use crate::mime;
+use crate::state::State;
+use crate::uri::{path_to_uri, percent_decode, uri_to_path};
+use crate::workspace::Workspace;
+
+/// Number of resources returned per `resources/list` page.
+const PAGE_SIZE: usize = 500;
+
+/// Scheme of the search results resource; the rest of the URI is the query.
+const SEARCH_SCHEME: &str = "search://";
+
+/// Parameters of `resources/list`.
+#[derive(Debug, Deserialize)]
+pub struct ListParams {
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+/// Parameters of `resources/read`.
+#[derive(Debug, Deserialize)]
+pub struct ReadParams {
+    pub uri: String,
+}
+
+/// Lists files under every root as `file://` resources, leaving out those
+/// matching the roots' configured ignore patterns. The cursor is the offset
+/// of the next entry, encoded as a string.
+pub fn list(workspace: &Workspace, params: ListParams) -> Result<Value> {
+    let offset = match params.cursor.as_deref() {
+        Some(cursor) => cursor
+            .parse::<usize>()
+            .with_context(|| format!("Invalid cursor: {}", cursor))?,
+        None => 0,
+    };
+
+    let mut files = workspace
+        .roots()
+        .iter()
+        .flat_map(|root| {
+            WalkDir::new(root)
+                .sort_by_file_name()
+                .into_iter()
+                .filter_entry(|e| {
+                    e.file_name() != ".git"
+                        && !workspace.is_excluded(e.path(), e.file_type().is_dir())
+                })
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .skip(offset);
+
+    let mut resources = Vec::new();
+    for entry in files.by_ref().take(PAGE_SIZE) {
+        let path = entry.path();
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        resources.push(json!({
+            "uri": path_to_uri(path),
+            "name": workspace.display_path(path),
+            "mimeType": mime::guess_from_path(path),
+            "size": size,
+        }));
+    }
+
+    let mut result = json!({ "resources": resources });
+    if files.next().is_some() {
+        result["nextCursor"] = json!((offset + PAGE_SIZE).to_string());
+    }
+    Ok(result)
+}
+
+/// Lists the URI templates `resources/read` accepts. The search template is
+/// only offered while the `search_content` tool it runs is available.
+pub fn templates(search: bool) -> Value {
+    let mut templates = vec![json!({
+        "uriTemplate": "file://{+path}",
+        "name": "File",
+        "description": "A file inside the roots, by absolute path",
+    })];
+    if search {
+        templates.push(json!({
+            "uriTemplate": "search://{query}",
+            "name": "Content search",
+            "description": "Lines matching a regular expression in files under the roots, as \
+                            search_content returns them",
+            "mimeType": "application/json",
+        }));
+    }
+    json!({ "resourceTemplates": templates })
+}
+
+/// The query of a `search://` URI, or `None` for any other scheme.
+pub fn search_query(uri: &str) -> Option<Result<String>> {
+    let query = uri.strip_prefix(SEARCH_SCHEME)?;
+    Some(
+        percent_decode(query, uri).and_then(|query| match query.is_empty() {
+            true => bail!("Search URI has no query: {}", uri),
+            false => Ok(query),
+        }),
+    )
+}
+
+/// Wraps a search result as the contents of the resource at `uri`.
+pub fn search_contents(uri: &str, result: &Value) -> Value {
+    json!({
+        "contents": [{
+            "uri": uri,
+            "mimeType": "application/json",
+            "text": result.to_string(),
+        }]
+    })
+}
+
+/// Parameters of `resources/subscribe` and `resources/unsubscribe`.
+#[derive(Debug, Deserialize)]
+pub struct SubscribeParams {
+    pub uri: String,
+}
+
+/// Maps a `file://` URI to a path inside the workspace.
+fn workspace_path(workspace: &Workspace, uri: &str) -> Result<PathBuf> {
+    let path = uri_to_path(uri)?;
+    workspace.resolve(&path.to_string_lossy())
+}
+
+/// Reads one `file://` resource: as `text` if it is valid UTF-8, otherwise
+/// as a base64 `blob`.
+pub fn read(workspace: &Workspace, params: ReadParams) -> Result<Value> {
+    let path = workspace_path(workspace, &params.uri)?;
+    let bytes = fs::read(&path).with_context(|| format!("Failed to read {}", params.uri))?;
+    let mut content = json!({
+        "uri": params.uri,
+        "mimeType": mime::guess_from_path(&path),
+    });
+    match String::from_utf8(bytes) {
+        Ok(text) => content["text"] = json!(text),
+        Err(e) => {
+            let blob = base64::engine::general_purpose::STANDARD.encode(e.into_bytes());
+            content["blob"] = json!(blob);
+        }
+    }
+    Ok(json!({ "contents": [content] }))
+}
+
+/// Starts sending `notifications/resources/updated` for a resource.
+pub fn subscribe(workspace: &Workspace, state: &State, params: SubscribeParams) -> Result<Value> {
+    let path = workspace_path(workspace, &params.uri)?;
+    if !path.exists() {
+        bail!("Resource not found: {}", params.uri);
+    }
+    state.watches.watch(&path, false, &state.outbound)?;
+    Ok(json!({}))
+}
+
+/// Stops update notifications for a resource. Unknown URIs are ignored.
+pub fn unsubscribe(workspace: &Workspace, state: &State, params: SubscribeParams) -> Result<Value> {
+    let path = workspace_path(workspace, &params.uri)?;
+    state.watches.unwatch(&path);
+    Ok(json!({}))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_list_and_read_resources() {
+        let env = TestEnv::new("resources");
+        env.write(".git/HEAD", "ref");
+        env.write("notes.md", "# hi");
+        let workspace = &env.workspace;
+
+        let listing = list(workspace, ListParams { cursor: None }).unwrap();
+        let resources = listing["resources"].as_array().unwrap();
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0]["mimeType"], "text/markdown");
+        assert!(listing.get("nextCursor").is_none());
+
+        let uri = resources[0]["uri"].as_str().unwrap().to_string();
+        let content = read(workspace, ReadParams { uri }).unwrap();
+        assert_eq!(content["contents"][0]["text"], "# hi");
+
+        let outside = path_to_uri(&std::env::temp_dir().join("elsewhere.txt"));
+        assert!(read(workspace, ReadParams { uri: outside }).is_err());
+    }
+
+    #[test]
+    fn test_binary_resources_are_read_as_blobs() {
+        let env = TestEnv::new("resources-blob");
+        let path = env.write("logo.png", [0x89, b'P', b'N', b'G', 0xff, 0x00]);
+
+        let content = read(
+            &env.workspace,
+            ReadParams {
+                uri: path_to_uri(&path),
+            },
+        )
+        .unwrap();
+        let content = &content["contents"][0];
+        assert_eq!(content["mimeType"], "image/png");
+        assert_eq!(content["blob"], "iVBOR/8A");
+        assert!(content.get("text").is_none());
+    }
+
+    #[test]
+    fn test_configured_ignores_are_not_listed() {
+        let env = TestEnv::new("resources-ignore");
+        env.write("notes.md", "");
+        env.write("id.pem", "");
+        env.write("secrets/token.txt", "");
+        let workspace = env
+            .workspace
+            .clone()
+            .with_ignores(env.path(), &["*.pem".into(), "secrets/".into()])
+            .unwrap();
+
+        let listing = list(&workspace, ListParams { cursor: None }).unwrap();
+        let names: Vec<&Value> = listing["resources"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| &r["name"])
+            .collect();
+        assert_eq!(names, [&json!("notes.md")]);
+    }
+
+    #[test]
+    fn test_search_uris_carry_a_decoded_query() {
+        assert_eq!(
+            search_query("search://fn%20main").unwrap().unwrap(),
+            "fn main"
+        );
+        assert!(search_query("search://").unwrap().is_err());
+        assert!(search_query("file:///etc/hosts").is_none());
+        let names: Vec<Value> = templates(false)["resourceTemplates"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["uriTemplate"].clone())
+            .collect();
+        assert_eq!(names, [json!("file://{+path}")]);
+    }
+}
@@ -0,0 +1,134 @@
+//! Splits source files into overlapping, line-addressed chunks for
+//! embedding. Cuts prefer the start of a top-level item so a chunk tends to
+//! hold whole functions or sections rather than halves of two.
+
+use std::path::Path;
+
+/// Preferred chunk length in lines, matching the TypeScript indexer.
+pub const CHUNK_LINES: usize = 50;
+/// Lines repeated at the start of the next chunk after a forced cut.
+pub const CHUNK_OVERLAP: usize = 10;
+/// Chunks shorter than this many characters are merged or dropped.
+const MIN_CHUNK_CHARS: usize = 50;
+
+/// A run of lines; `start_line` and `end_line` are 1-based and inclusive.
+#[derive(Debug, PartialEq)]
+pub struct Chunk {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub content: String,
+}
+
+/// Line prefixes that open a top-level item, by file extension.
+fn boundaries(path: &Path) -> &'static [&'static str] {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    match extension {
+        "rs" => &[
+            "fn ",
+            "pub ",
+            "impl",
+            "struct ",
+            "enum ",
+            "trait ",
+            "mod ",
+            "#[",
+            "///",
+            "const ",
+            "static ",
+            "type ",
+            "macro_rules!",
+        ],
+        "ts" | "tsx" | "js" | "jsx" | "mjs" | "cjs" => &[
+            "function ",
+            "export ",
+            "class ",
+            "interface ",
+            "type ",
+            "const ",
+            "let ",
+            "async function ",
+            "/**",
+        ],
+        "py" => &["def ", "class ", "async def ", "@"],
+        "go" => &["func ", "type ", "var ", "const ", "//"],
+        "java" | "kt" | "cs" | "scala" => &["public ", "private ", "protected ", "class ", "@"],
+        "c" | "h" | "cc" | "cpp" | "hpp" => &["#", "static ", "struct ", "typedef ", "void "],
+        "md" | "markdown" => &["#"],
+        _ => &[],
+    }
+}
+
+/// Splits `text` into chunks, cutting at item boundaries when a chunk
+/// reaches [`CHUNK_LINES`] and forcing a cut with overlap at twice that.
+pub fn split(path: &Path, text: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = text.lines().collect();
+    let starts = boundaries(path);
+    let is_boundary = |line: &str| starts.iter().any(|s| line.starts_with(s));
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let mut end = start + 1;
+        while end < lines.len() {
+            let len = end - start;
+            if len >= CHUNK_LINES && is_boundary(lines[end]) && !is_boundary(lines[end - 1]) {
+                break;
+            }
+            if len >= CHUNK_LINES * 2 || (starts.is_empty() && len >= CHUNK_LINES) {
+                break;
+            }
+            end += 1;
+        }
+        let content = lines[start..end].join("\n");
+        if content.trim().len() >= MIN_CHUNK_CHARS || chunks.is_empty() {
+            chunks.push(Chunk {
+                start_line: start + 1,
+                end_line: end,
+                content,
+            });
+        }
+        if end >= lines.len() {
+            break;
+        }
+        // A cut at an item boundary needs no overlap; a forced one does.
+        start = if is_boundary(lines[end]) {
+            end
+        } else {
+            end.saturating_sub(CHUNK_OVERLAP).max(start + 1)
+        };
+    }
+    chunks.retain(|c| !c.content.trim().is_empty());
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cuts_at_item_boundaries_and_overlaps_forced_cuts() {
+        let mut source = String::new();
+        for f in 0..3 {
+            source.push_str(&format!("fn item_{}() {{\n", f));
+            for i in 0..40 {
+                source.push_str(&format!("    let value_{} = {};\n", i, i));
+            }
+            source.push_str("}\n");
+        }
+        let chunks = split(Path::new("lib.rs"), &source);
+        assert_eq!(chunks[0].start_line, 1);
+        assert_eq!(chunks[0].end_line, 84);
+        assert!(chunks[1].content.starts_with("fn item_2"));
+        assert_eq!(chunks[1].end_line, 126);
+
+        let prose: String = (1..=120)
+            .map(|i| format!("plain text line number {}\n", i))
+            .collect();
+        let chunks = split(Path::new("notes.txt"), &prose);
+        assert_eq!((chunks[0].start_line, chunks[0].end_line), (1, 50));
+        assert_eq!(chunks[1].start_line, 41);
+    }
+}
@@ -0,0 +1,46 @@
+use anyhow::{Context as _, Result};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+use ureq::Agent;
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Turns text into vectors with an Ollama embedding model.
+pub struct Embedder {
+    agent: Agent,
+    url: String,
+    model: String,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    embedding: Vec<f32>,
+}
+
+impl Embedder {
+    pub fn new(url: &str, model: &str) -> Self {
+        let agent = Agent::config_builder()
+            .timeout_global(Some(TIMEOUT))
+            .build()
+            .into();
+        Self {
+            agent,
+            url: url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+        }
+    }
+
+    pub fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let url = format!("{}/api/embeddings", self.url);
+        let response: EmbeddingResponse = self
+            .agent
+            .post(&url)
+            .send_json(json!({ "model": self.model, "prompt": text }))
+            .with_context(|| format!("Embedding request to {} failed", self.url))?
+            .body_mut()
+            .read_json()
+            .context("Invalid embedding response")?;
+        Ok(response.embedding)
+    }
+}
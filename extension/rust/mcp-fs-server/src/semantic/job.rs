@@ -0,0 +1,485 @@
+//! Background jobs that chunk, embed, and upsert workspace files into
+//! Qdrant. Files whose stored `contentHash` still matches are skipped, so an
+//! interrupted job resumes where it left off when run again. A watching job
+//! then keeps the collection current as files change.
+
+use anyhow::{Context as _, Result};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::chunk;
+use super::Backend;
+use crate::digest;
+use crate::index::{is_ignored, root_ignores};
+use crate::logging::Level;
+use crate::outbound::Outbound;
+use crate::progress::Progress as Reporter;
+use crate::text;
+use crate::workspace::Workspace;
+
+/// Notification method carrying indexing progress.
+pub const PROGRESS_NOTIFICATION: &str = "notifications/index_workspace/progress";
+
+/// Files larger than this are not embedded, as in the TypeScript indexer.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+/// Progress is reported at most this often, plus once at the end.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+/// A watching job re-embeds changed files once edits have paused this long.
+const CHANGE_DEBOUNCE: Duration = Duration::from_secs(1);
+
+/// What to index and where to report.
+pub struct IndexRequest {
+    pub backend: Arc<Backend>,
+    pub collection: String,
+    pub roots: Vec<PathBuf>,
+    pub workspace: Workspace,
+    /// Re-embed files even if their stored hash matches.
+    pub force: bool,
+    /// Keep running after the initial pass, following file changes.
+    pub watch: bool,
+    pub outbound: Outbound,
+    /// Receives the initial pass's `notifications/progress`, if the caller
+    /// is waiting on it.
+    pub reporter: Reporter,
+    /// Receives the progress summary once the initial pass is over.
+    pub initial_pass: Option<Sender<Value>>,
+}
+
+/// Running jobs, stopped when their session ends.
+#[derive(Default)]
+pub struct IndexJobs {
+    next_id: AtomicU64,
+    active: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+}
+
+impl IndexJobs {
+    /// Starts a job and returns its id.
+    pub fn spawn(&self, request: IndexRequest) -> String {
+        let id = format!("index-{}", self.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let stop = Arc::new(AtomicBool::new(false));
+        self.active
+            .lock()
+            .unwrap()
+            .insert(id.clone(), Arc::clone(&stop));
+
+        let active = Arc::clone(&self.active);
+        let job_id = id.clone();
+        thread::spawn(move || {
+            let outbound = request.outbound.clone();
+            let mut progress = Progress::new(&job_id, outbound.clone(), request.reporter.clone());
+            progress.initial_pass = request.initial_pass.clone();
+            let outcome = run(request, &stop, &mut progress);
+            let mut last = progress.to_json();
+            match outcome {
+                Ok(()) if stop.load(Ordering::Relaxed) => last["finished"] = json!("stopped"),
+                Ok(()) => last["finished"] = json!("completed"),
+                Err(e) => {
+                    last["finished"] = json!("failed");
+                    last["error"] = json!(format!("{:#}", e));
+                }
+            }
+            progress.end_initial_pass(&last);
+            outbound.notify(PROGRESS_NOTIFICATION, last);
+            active.lock().unwrap().remove(&job_id);
+        });
+        id
+    }
+}
+
+impl IndexJobs {
+    /// Asks a job to stop; returns false if no such job is running.
+    pub fn stop(&self, id: &str) -> bool {
+        match self.active.lock().unwrap().get(id) {
+            Some(flag) => {
+                flag.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Drop for IndexJobs {
+    fn drop(&mut self) {
+        for flag in self.active.lock().unwrap().values() {
+            flag.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+struct Progress {
+    job_id: String,
+    outbound: Outbound,
+    files_total: usize,
+    files_done: usize,
+    files_indexed: usize,
+    files_skipped: usize,
+    files_failed: usize,
+    files_removed: usize,
+    chunks: usize,
+    last_error: Option<String>,
+    last_sent: Instant,
+    reporter: Reporter,
+    initial_pass: Option<Sender<Value>>,
+}
+
+impl Progress {
+    fn new(job_id: &str, outbound: Outbound, reporter: Reporter) -> Self {
+        Self {
+            job_id: job_id.to_string(),
+            outbound,
+            files_total: 0,
+            files_done: 0,
+            files_indexed: 0,
+            files_skipped: 0,
+            files_failed: 0,
+            files_removed: 0,
+            chunks: 0,
+            last_error: None,
+            last_sent: Instant::now(),
+            reporter,
+            initial_pass: None,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "job_id": self.job_id,
+            "files_total": self.files_total,
+            "files_done": self.files_done,
+            "files_indexed": self.files_indexed,
+            "files_skipped": self.files_skipped,
+            "files_failed": self.files_failed,
+            "files_removed": self.files_removed,
+            "chunks": self.chunks,
+            "last_error": self.last_error,
+        })
+    }
+
+    fn tick(&mut self) {
+        if self.reporter.is_requested() {
+            let message = format!("{} of {} files", self.files_done, self.files_total);
+            let total = Some(self.files_total as u64);
+            self.reporter
+                .report(self.files_done as u64, total, &message);
+        }
+        if self.last_sent.elapsed() >= PROGRESS_INTERVAL {
+            self.send(None);
+        }
+    }
+
+    fn send(&mut self, phase: Option<&str>) {
+        let mut params = self.to_json();
+        if let Some(phase) = phase {
+            params["phase"] = json!(phase);
+        }
+        self.outbound.notify(PROGRESS_NOTIFICATION, params);
+        self.last_sent = Instant::now();
+    }
+
+    /// Hands the summary to a waiting caller and stops reporting against
+    /// its progress token, whose request is about to complete.
+    fn end_initial_pass(&mut self, summary: &Value) {
+        if let Some(waiter) = self.initial_pass.take() {
+            let _ = waiter.send(summary.clone());
+        }
+        self.reporter = Reporter::default();
+    }
+
+    fn record(&mut self, display: &str, outcome: Result<Option<usize>>) {
+        match outcome {
+            Ok(Some(chunks)) => {
+                self.files_indexed += 1;
+                self.chunks += chunks;
+            }
+            Ok(None) => self.files_skipped += 1,
+            Err(e) => {
+                self.files_failed += 1;
+                self.last_error = Some(format!("{}: {:#}", display, e));
+            }
+        }
+    }
+}
+
+/// A Qdrant point id (which must be a UUID or integer) derived from the
+/// chunk's location, so re-indexing a file overwrites its points.
+fn point_id(file: &str, start_line: usize) -> String {
+    let hash = digest::of_bytes(format!("{}:{}", file, start_line).as_bytes());
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hash[0..8],
+        &hash[8..12],
+        &hash[12..16],
+        &hash[16..20],
+        &hash[20..32]
+    )
+}
+
+fn run(request: IndexRequest, stop: &AtomicBool, progress: &mut Progress) -> Result<()> {
+    let IndexRequest {
+        backend,
+        collection,
+        roots,
+        workspace,
+        force,
+        watch,
+        ..
+    } = request;
+
+    // Subscribe before the initial pass so edits made during it are not lost.
+    let (tx, events) = mpsc::channel();
+    let mut watcher = None;
+    if watch {
+        let outbound = progress.outbound.clone();
+        let mut w = notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                let _ = tx.send(event.paths);
+            }
+            Ok(_) => {}
+            Err(e) => outbound.log(
+                Level::Error,
+                "index_workspace",
+                json!({ "message": e.to_string(), "paths": e.paths }),
+            ),
+        })
+        .context("Failed to start the indexing watcher")?;
+        for root in &roots {
+            w.watch(root, RecursiveMode::Recursive)
+                .with_context(|| format!("Failed to watch {}", root.display()))?;
+        }
+        watcher = Some(w);
+    }
+
+    let files = files_to_index(&workspace, &roots);
+    progress.files_total = files.len();
+
+    let mut collection_ready = backend.qdrant.collection_exists(&collection)?;
+    for path in files {
+        if stop.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let display = workspace.display_path(&path);
+        let outcome = index_file(
+            &backend,
+            &collection,
+            &path,
+            &display,
+            force,
+            &mut collection_ready,
+        );
+        progress.record(&display, outcome);
+        progress.files_done += 1;
+        progress.tick();
+    }
+    if watcher.is_none() {
+        return Ok(());
+    }
+    progress.end_initial_pass(&progress.to_json());
+    progress.send(Some("watching"));
+
+    // Changed paths are batched until the tree has been quiet for a moment.
+    let ignores: Vec<_> = roots.iter().map(|r| (r.clone(), root_ignores(r))).collect();
+    let mut pending = BTreeSet::new();
+    while !stop.load(Ordering::Relaxed) {
+        match events.recv_timeout(CHANGE_DEBOUNCE) {
+            Ok(paths) => pending.extend(
+                paths
+                    .into_iter()
+                    .filter(|p| !is_ignored(&ignores, p) && !workspace.is_excluded(p, p.is_dir())),
+            ),
+            Err(RecvTimeoutError::Timeout) if !pending.is_empty() => {
+                let mut changed = BTreeSet::new();
+                let mut removed = Vec::new();
+                for path in std::mem::take(&mut pending) {
+                    if path.is_file() {
+                        if fs::metadata(&path).is_ok_and(|m| m.len() <= MAX_FILE_BYTES) {
+                            changed.insert(path);
+                        }
+                    } else if path.is_dir() {
+                        // A directory moved into place brings files no event named.
+                        changed.extend(files_to_index(&workspace, &[path]));
+                    } else if !path.exists() {
+                        removed.push(workspace.display_path(&path));
+                    }
+                }
+                for path in changed {
+                    let display = workspace.display_path(&path);
+                    let outcome = index_file(
+                        &backend,
+                        &collection,
+                        &path,
+                        &display,
+                        false,
+                        &mut collection_ready,
+                    );
+                    progress.record(&display, outcome);
+                }
+                if !removed.is_empty() && collection_ready {
+                    match remove_files(&backend, &collection, &removed) {
+                        Ok(count) => progress.files_removed += count,
+                        Err(e) => {
+                            progress.files_failed += removed.len();
+                            progress.last_error = Some(format!("{}: {:#}", removed.join(", "), e));
+                        }
+                    }
+                }
+                progress.send(Some("watching"));
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    Ok(())
+}
+
+/// Deletes the points of every indexed file at or under one of `removed`,
+/// which may have been files or whole directories, and returns how many
+/// files that was.
+fn remove_files(backend: &Backend, collection: &str, removed: &[String]) -> Result<usize> {
+    let files: Vec<String> = backend
+        .qdrant
+        .indexed_files(collection)?
+        .into_iter()
+        .filter(|file| removed.iter().any(|path| is_at_or_under(file, path)))
+        .collect();
+    if !files.is_empty() {
+        backend.qdrant.delete_files(collection, &files)?;
+    }
+    Ok(files.len())
+}
+
+/// Whether the display path `file` is `path` or lies beneath it. A root
+/// displays as the empty path.
+fn is_at_or_under(file: &str, path: &str) -> bool {
+    path.is_empty()
+        || file
+            .strip_prefix(path)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// The files under `roots` small enough to embed.
+fn files_to_index(workspace: &Workspace, roots: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for root in roots {
+        for entry in workspace.walker(root, false).build().filter_map(|e| e.ok()) {
+            let is_file = entry.file_type().is_some_and(|t| t.is_file());
+            if is_file && entry.metadata().is_ok_and(|m| m.len() <= MAX_FILE_BYTES) {
+                files.push(entry.into_path());
+            }
+        }
+    }
+    files
+}
+
+/// What an initial pass over `roots` would embed, without embedding or
+/// storing anything: each file that would be (re-)indexed with its chunk
+/// count.
+pub fn plan_index(
+    backend: &Backend,
+    collection: &str,
+    workspace: &Workspace,
+    roots: &[PathBuf],
+    force: bool,
+) -> Result<Value> {
+    let collection_ready = backend.qdrant.collection_exists(collection)?;
+    let (mut files, mut chunks) = (Vec::new(), 0);
+    for path in files_to_index(workspace, roots) {
+        let display = workspace.display_path(&path);
+        let Some((text, hash)) = text_to_index(&path)? else {
+            continue;
+        };
+        if !force
+            && collection_ready
+            && backend.qdrant.stored_hash(collection, &display)?.as_deref() == Some(hash.as_str())
+        {
+            continue;
+        }
+        let count = chunk::split(&path, &text).len();
+        chunks += count;
+        files.push(json!({ "path": display, "chunks": count }));
+    }
+    Ok(json!({
+        "collection": collection,
+        "dry_run": true,
+        "would_index": files,
+        "chunks": chunks,
+        "create_collection": !collection_ready,
+    }))
+}
+
+/// The decoded text of `path` and the hash of its bytes, or `None` if it is
+/// not text.
+fn text_to_index(path: &Path) -> Result<Option<(String, String)>> {
+    let bytes = fs::read(path)?;
+    if text::is_binary(&bytes) {
+        return Ok(None);
+    }
+    Ok(text::decode(&bytes).map(|decoded| (decoded.text, digest::of_bytes(&bytes))))
+}
+
+/// Embeds and stores one file, returning its chunk count, or `None` if it
+/// is not text or is already indexed with the same content.
+fn index_file(
+    backend: &Backend,
+    collection: &str,
+    path: &Path,
+    display: &str,
+    force: bool,
+    collection_ready: &mut bool,
+) -> Result<Option<usize>> {
+    let Some((text, hash)) = text_to_index(path)? else {
+        return Ok(None);
+    };
+    if !force
+        && *collection_ready
+        && backend.qdrant.stored_hash(collection, display)?.as_deref() == Some(hash.as_str())
+    {
+        return Ok(None);
+    }
+
+    let mut points = Vec::new();
+    for chunk in chunk::split(path, &text) {
+        let prompt = format!(
+            "File: {}\nLines: {}-{}\n\n{}",
+            display, chunk.start_line, chunk.end_line, chunk.content
+        );
+        let vector = backend.embedder.embed(&prompt)?;
+        if !*collection_ready {
+            backend
+                .qdrant
+                .create_collection(collection, vector.len(), "Cosine")?;
+            *collection_ready = true;
+        }
+        points.push(json!({
+            "id": point_id(display, chunk.start_line),
+            "vector": vector,
+            "payload": {
+                "content": chunk.content,
+                "filePath": display,
+                "startLine": chunk.start_line,
+                "endLine": chunk.end_line,
+                "fullContext": format!(
+                    "File: {} ({}-{})\n{}",
+                    display, chunk.start_line, chunk.end_line, chunk.content
+                ),
+                "contentHash": hash,
+            },
+        }));
+    }
+    let count = points.len();
+    if *collection_ready {
+        backend.qdrant.delete_file(collection, display)?;
+    }
+    if !points.is_empty() {
+        backend.qdrant.upsert(collection, points)?;
+    }
+    Ok(Some(count))
+}
@@ -0,0 +1,47 @@
+//! Semantic search over a Qdrant collection, with embeddings from Ollama.
+//! Configured through the same environment variables as the TypeScript
+//! indexer so both sides share one collection.
+
+use std::env;
+
+mod chunk;
+mod embed;
+mod job;
+mod qdrant;
+
+pub use embed::Embedder;
+pub use job::{plan_index, IndexJobs, IndexRequest, PROGRESS_NOTIFICATION};
+pub use qdrant::Qdrant;
+
+const DEFAULT_QDRANT_URL: &str = "http://localhost:6333";
+const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+const DEFAULT_MODEL: &str = "nomic-embed-text";
+const DEFAULT_COLLECTION: &str = "codebase_context";
+
+/// The vector store and embedding model used by the semantic tools.
+pub struct Backend {
+    pub qdrant: Qdrant,
+    pub embedder: Embedder,
+    /// Collection used when a tool call does not name one.
+    pub collection: String,
+}
+
+impl Backend {
+    /// Reads `QDRANT_URL`, `QDRANT_API_KEY`, `QDRANT_COLLECTION`,
+    /// `OLLAMA_HOST`, and `OLLAMA_MODEL`. Nothing is contacted until a tool
+    /// needs it.
+    pub fn from_env() -> Self {
+        let var = |name: &str| env::var(name).ok().filter(|v| !v.is_empty());
+        Self {
+            qdrant: Qdrant::new(
+                var("QDRANT_URL").as_deref().unwrap_or(DEFAULT_QDRANT_URL),
+                var("QDRANT_API_KEY"),
+            ),
+            embedder: Embedder::new(
+                var("OLLAMA_HOST").as_deref().unwrap_or(DEFAULT_OLLAMA_URL),
+                var("OLLAMA_MODEL").as_deref().unwrap_or(DEFAULT_MODEL),
+            ),
+            collection: var("QDRANT_COLLECTION").unwrap_or_else(|| DEFAULT_COLLECTION.into()),
+        }
+    }
+}
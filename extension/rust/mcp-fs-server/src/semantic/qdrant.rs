@@ -0,0 +1,200 @@
+use anyhow::{anyhow, bail, Context as _, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::time::Duration;
+use ureq::Agent;
+
+use crate::error::{ErrorCode, Failure};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// Points fetched per request when scrolling through a collection.
+const SCROLL_PAGE: usize = 1000;
+
+/// A minimal client for the Qdrant REST API.
+pub struct Qdrant {
+    agent: Agent,
+    url: String,
+    api_key: Option<String>,
+}
+
+/// One search hit. `payload` is whatever the indexer stored with the point.
+#[derive(Debug, Deserialize)]
+pub struct ScoredPoint {
+    pub id: Value,
+    pub score: f32,
+    #[serde(default)]
+    pub payload: Value,
+}
+
+impl Qdrant {
+    pub fn new(url: &str, api_key: Option<String>) -> Self {
+        let agent = Agent::config_builder()
+            .timeout_global(Some(TIMEOUT))
+            .http_status_as_error(false)
+            .build()
+            .into();
+        Self {
+            agent,
+            url: url.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+
+    /// Sends a request and returns the `result` member of the reply.
+    fn call(&self, method: &str, path: &str, body: Option<Value>) -> Result<Value> {
+        let url = format!("{}{}", self.url, path);
+        let mut request = ureq::http::Request::builder().method(method).uri(&url);
+        if let Some(key) = &self.api_key {
+            request = request.header("api-key", key);
+        }
+        let body = body.map(|b| b.to_string()).unwrap_or_default();
+        let request = request
+            .header("Content-Type", "application/json")
+            .body(body)?;
+        let mut response = self
+            .agent
+            .run(request)
+            .with_context(|| format!("Qdrant is not reachable at {}", self.url))?;
+        let status = response.status();
+        let reply: Value = response
+            .body_mut()
+            .read_json()
+            .with_context(|| format!("Invalid reply from Qdrant for {} {}", method, path))?;
+        if !status.is_success() {
+            let message = reply["status"]["error"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| reply.to_string());
+            let code = match status.as_u16() {
+                404 => ErrorCode::NotFound,
+                _ => ErrorCode::Upstream,
+            };
+            bail!(Failure::new(
+                code,
+                format!(
+                    "Qdrant {} {} failed ({}): {}",
+                    method, path, status, message
+                )
+            ));
+        }
+        reply
+            .get("result")
+            .cloned()
+            .ok_or_else(|| anyhow!("Qdrant reply has no result: {}", reply))
+    }
+
+    pub fn collection_exists(&self, collection: &str) -> Result<bool> {
+        let result = self.call("GET", &format!("/collections/{}/exists", collection), None)?;
+        Ok(result["exists"].as_bool().unwrap_or(false))
+    }
+
+    /// Creates `collection` for `size`-dimensional vectors compared by
+    /// `distance` (`Cosine`, `Dot`, `Euclid`, or `Manhattan`).
+    pub fn create_collection(&self, collection: &str, size: usize, distance: &str) -> Result<()> {
+        let body = json!({ "vectors": { "size": size, "distance": distance } });
+        self.call("PUT", &format!("/collections/{}", collection), Some(body))?;
+        Ok(())
+    }
+
+    /// Deletes `collection` and every point in it.
+    pub fn delete_collection(&self, collection: &str) -> Result<()> {
+        self.call("DELETE", &format!("/collections/{}", collection), None)?;
+        Ok(())
+    }
+
+    pub fn list_collections(&self) -> Result<Vec<String>> {
+        let result = self.call("GET", "/collections", None)?;
+        Ok(result["collections"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|c| c["name"].as_str().map(str::to_string))
+            .collect())
+    }
+
+    /// The raw collection description: status, point counts, and config.
+    pub fn collection_info(&self, collection: &str) -> Result<Value> {
+        self.call("GET", &format!("/collections/{}", collection), None)
+    }
+
+    /// Inserts or replaces points, waiting until they are searchable.
+    pub fn upsert(&self, collection: &str, points: Vec<Value>) -> Result<()> {
+        let path = format!("/collections/{}/points?wait=true", collection);
+        self.call("PUT", &path, Some(json!({ "points": points })))?;
+        Ok(())
+    }
+
+    /// Removes every point whose `filePath` payload equals `file`.
+    pub fn delete_file(&self, collection: &str, file: &str) -> Result<()> {
+        let path = format!("/collections/{}/points/delete?wait=true", collection);
+        self.call("POST", &path, Some(json!({ "filter": file_filter(file) })))?;
+        Ok(())
+    }
+
+    /// Removes every point whose `filePath` payload is one of `files`.
+    pub fn delete_files(&self, collection: &str, files: &[String]) -> Result<()> {
+        let path = format!("/collections/{}/points/delete?wait=true", collection);
+        let filter = json!({ "must": [{ "key": "filePath", "match": { "any": files } }] });
+        self.call("POST", &path, Some(json!({ "filter": filter })))?;
+        Ok(())
+    }
+
+    /// The `filePath` of every file with points in `collection`.
+    pub fn indexed_files(&self, collection: &str) -> Result<BTreeSet<String>> {
+        let path = format!("/collections/{}/points/scroll", collection);
+        let mut files = BTreeSet::new();
+        let mut offset = Value::Null;
+        loop {
+            let body = json!({
+                "limit": SCROLL_PAGE,
+                "offset": offset,
+                "with_payload": ["filePath"],
+                "with_vector": false,
+            });
+            let result = self.call("POST", &path, Some(body))?;
+            let points = result["points"].as_array().into_iter().flatten();
+            files.extend(
+                points
+                    .filter_map(|p| p["payload"]["filePath"].as_str())
+                    .map(str::to_string),
+            );
+            offset = result["next_page_offset"].clone();
+            if offset.is_null() {
+                return Ok(files);
+            }
+        }
+    }
+
+    /// The `contentHash` stored with any chunk of `file`, if it is indexed.
+    pub fn stored_hash(&self, collection: &str, file: &str) -> Result<Option<String>> {
+        let path = format!("/collections/{}/points/scroll", collection);
+        let body = json!({
+            "filter": file_filter(file),
+            "limit": 1,
+            "with_payload": ["contentHash"],
+            "with_vector": false,
+        });
+        let result = self.call("POST", &path, Some(body))?;
+        Ok(result["points"][0]["payload"]["contentHash"]
+            .as_str()
+            .map(str::to_string))
+    }
+
+    /// The `limit` points of `collection` closest to `vector`.
+    pub fn search(
+        &self,
+        collection: &str,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<ScoredPoint>> {
+        let path = format!("/collections/{}/points/search", collection);
+        let body = json!({ "vector": vector, "limit": limit, "with_payload": true });
+        let result = self.call("POST", &path, Some(body))?;
+        serde_json::from_value(result).context("Unexpected Qdrant search result")
+    }
+}
+
+fn file_filter(file: &str) -> Value {
+    json!({ "must": [{ "key": "filePath", "match": { "value": file } }] })
+}
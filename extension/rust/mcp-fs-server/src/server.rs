@@ -0,0 +1,664 @@
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::time::Instant;
+
+use crate::audit::{self, Entry};
+use crate::cancel::{Cancellations, CANCELLED_NOTIFICATION};
+use crate::completion;
+use crate::error;
+use crate::logging::{Level, SetLevelParams};
+use crate::metrics::Call;
+use crate::progress::Progress;
+use crate::protocol::{
+    CallToolParams, CallToolResult, IncomingMessage, InitializeParams, ListRootsResult, Response,
+    RpcError, INVALID_PARAMS, INVALID_REQUEST, LATEST_PROTOCOL_VERSION, METHOD_NOT_FOUND,
+    PARSE_ERROR, RESOURCE_NOT_FOUND, SUPPORTED_PROTOCOL_VERSIONS,
+};
+use crate::quota::QuotaExceeded;
+use crate::resources::{self, ReadParams};
+use crate::state::State;
+use crate::tools::{self, Context, Tool};
+use crate::transfer::Transfer;
+use crate::uri;
+use crate::workspace::{AccessDenied, NotDeletable, ReadOnly, Workspace};
+
+/// Id of the `roots/list` request the server sends to clients with the
+/// `roots` capability.
+const ROOTS_REQUEST_ID: &str = "roots/list";
+
+/// Transport-independent MCP request dispatcher.
+pub struct Server {
+    /// Roots configured at startup; client roots can only narrow these.
+    configured: Workspace,
+    workspace: Workspace,
+    state: State,
+    tools: Vec<Tool>,
+    client_roots: bool,
+}
+
+impl Server {
+    pub fn new(workspace: Workspace, state: State) -> Self {
+        Self {
+            configured: workspace.clone(),
+            workspace,
+            state,
+            tools: tools::registry(),
+            client_roots: false,
+        }
+    }
+
+    /// Handles one raw JSON-RPC message and returns the serialized response,
+    /// or `None` for notifications.
+    pub fn handle_line(&mut self, line: &str) -> Option<String> {
+        let response = match serde_json::from_str::<IncomingMessage>(line) {
+            Ok(msg) => self.handle_message(msg)?,
+            Err(e) => Response::failure(Value::Null, RpcError::new(PARSE_ERROR, e.to_string())),
+        };
+        serde_json::to_string(&response).ok()
+    }
+
+    /// In-flight request registry, for transports that read ahead while a
+    /// tool is running.
+    pub fn cancellations(&self) -> Cancellations {
+        self.state.cancellations.clone()
+    }
+
+    fn handle_message(&mut self, msg: IncomingMessage) -> Option<Response> {
+        let Some(method) = msg.method else {
+            self.handle_response(msg.id, msg.result, msg.error);
+            return None;
+        };
+        // Notifications (no id) never get a response.
+        let Some(id) = msg.id else {
+            self.handle_notification(&method, msg.params);
+            return None;
+        };
+        if msg.jsonrpc != "2.0" {
+            return Some(Response::failure(
+                id,
+                RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""),
+            ));
+        }
+        let params = msg.params.unwrap_or(Value::Null);
+        let result = match method.as_str() {
+            "initialize" => self.initialize(params),
+            "ping" => Ok(json!({})),
+            "logging/setLevel" => self.set_log_level(params),
+            "tools/list" => Ok(self.list_tools()),
+            "tools/call" => self.call_tool(&id, params),
+            "resources/list" => self.list_resources(params),
+            "resources/read" => self.read_resource(&id, params),
+            "resources/templates/list" => Ok(self.list_resource_templates()),
+            "completion/complete" => self.complete(params),
+            "resources/subscribe" => self.subscribe_resource(params),
+            "resources/unsubscribe" => self.unsubscribe_resource(params),
+            other => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method: {}", other),
+            )),
+        };
+        Some(match result {
+            Ok(value) => Response::success(id, value),
+            Err(error) => Response::failure(id, error),
+        })
+    }
+
+    fn handle_notification(&mut self, method: &str, params: Option<Value>) {
+        match method {
+            CANCELLED_NOTIFICATION => {
+                if let Some(id) = params.as_ref().and_then(|p| p.get("requestId")) {
+                    self.state.cancellations.cancel(id);
+                }
+            }
+            "notifications/initialized" | "notifications/roots/list_changed"
+                if self.client_roots =>
+            {
+                self.request_roots()
+            }
+            _ => {}
+        }
+    }
+
+    /// Asks the client for its roots; the reply arrives as a response
+    /// message and is applied in [`Server::apply_roots`].
+    fn request_roots(&self) {
+        self.state.outbound.send_raw(
+            json!({ "jsonrpc": "2.0", "id": ROOTS_REQUEST_ID, "method": "roots/list" }).to_string(),
+        );
+    }
+
+    fn handle_response(&mut self, id: Option<Value>, result: Option<Value>, error: Option<Value>) {
+        if id.as_ref().and_then(Value::as_str) != Some(ROOTS_REQUEST_ID) {
+            return;
+        }
+        if let Some(error) = error {
+            self.log_roots_problem(format!("roots/list failed: {}", error));
+            return;
+        }
+        match serde_json::from_value::<ListRootsResult>(result.unwrap_or(Value::Null)) {
+            Ok(list) => self.apply_roots(list),
+            Err(e) => self.log_roots_problem(format!("invalid roots/list result: {}", e)),
+        }
+    }
+
+    /// Reports a problem with the client's roots on stderr and in the
+    /// client's log, since nothing else tells the client its roots were not
+    /// applied.
+    fn log_roots_problem(&self, message: String) {
+        eprintln!("mcp-fs-server: {}", message);
+        self.state
+            .outbound
+            .log(Level::Error, "roots", json!({ "message": message }));
+    }
+
+    /// Narrows the sandbox to the client's roots. Roots that are not local
+    /// directories inside the configured roots are ignored.
+    fn apply_roots(&mut self, list: ListRootsResult) {
+        let mut requested = Vec::new();
+        for root in list.roots {
+            match uri::uri_to_path(&root.uri) {
+                Ok(path) => requested.push(path),
+                Err(e) => self.log_roots_problem(format!("ignoring client root: {:#}", e)),
+            }
+        }
+        let (workspace, rejected) = self.configured.with_client_roots(&requested);
+        for path in rejected {
+            self.log_roots_problem(format!(
+                "ignoring client root outside the allowed roots: {}",
+                path.display()
+            ));
+        }
+        self.workspace = workspace;
+    }
+
+    fn initialize(&mut self, params: Value) -> Result<Value, RpcError> {
+        let params: InitializeParams = parse_params(params)?;
+        self.client_roots = params.capabilities.roots.is_some();
+        let version = params
+            .protocol_version
+            .filter(|v| SUPPORTED_PROTOCOL_VERSIONS.contains(&v.as_str()))
+            .unwrap_or_else(|| LATEST_PROTOCOL_VERSION.to_string());
+
+        Ok(json!({
+            "protocolVersion": version,
+            "capabilities": {
+                "tools": { "listChanged": false },
+                "logging": {},
+                "resources": { "subscribe": true, "listChanged": false },
+                "completions": {}
+            },
+            "serverInfo": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION")
+            }
+        }))
+    }
+
+    /// Tools available under the current policy; mutating tools are
+    /// withheld entirely when no root is writable, and tools the
+    /// configuration disabled always are.
+    fn available_tools(&self) -> impl Iterator<Item = &Tool> {
+        let read_only = self.workspace.is_read_only();
+        let disabled = &self.state.disabled_tools;
+        self.tools
+            .iter()
+            .filter(move |t| !(read_only && t.mutates || disabled.contains(t.name)))
+    }
+
+    fn list_tools(&self) -> Value {
+        let definitions: Vec<_> = self.available_tools().map(Tool::definition).collect();
+        json!({ "tools": definitions })
+    }
+
+    fn call_tool(&mut self, id: &Value, params: Value) -> Result<Value, RpcError> {
+        let params: CallToolParams = parse_params(params)?;
+        let tool = self
+            .available_tools()
+            .find(|t| t.name == params.name)
+            .ok_or_else(|| {
+                RpcError::new(INVALID_PARAMS, format!("Unknown tool: {}", params.name))
+            })?;
+
+        let token = params.meta.and_then(|meta| meta.progress_token);
+        let ctx = Context {
+            workspace: &self.workspace,
+            state: &self.state,
+            progress: Progress::new(token, self.state.outbound.clone()),
+            cancel: self.state.cancellations.start(id),
+            transfer: Transfer::default(),
+        };
+        let arguments = params.arguments.unwrap_or(Value::Null);
+        let audited = tool.mutates && !tools::is_dry_run(&arguments);
+        let started = Instant::now();
+        let outcome = match audited {
+            true => self.state.quotas.take_operation().map_err(Into::into),
+            false => Ok(()),
+        }
+        .and_then(|()| (tool.handler)(&ctx, arguments.clone()));
+        self.state.cancellations.finish(id);
+        if let Err(e) = &outcome {
+            self.log_denial(tool.name, e);
+        }
+        self.state.metrics.record(Call {
+            tool: tool.name,
+            elapsed: started.elapsed(),
+            succeeded: outcome.is_ok(),
+            bytes_read: ctx.transfer.bytes_read(),
+            bytes_written: ctx.transfer.bytes_written(),
+        });
+        if audited {
+            let entry = Entry {
+                tool: tool.name,
+                arguments: &arguments,
+                paths: audit::affected_paths(&arguments, outcome.as_ref().ok()),
+                bytes_written: outcome.as_ref().ok().map(|_| ctx.transfer.bytes_written()),
+                error: outcome.as_ref().err().map(|e| format!("{:#}", e)),
+            };
+            if let Err(e) = self.state.audit.record(entry) {
+                eprintln!("mcp-fs-server: {:#}", e);
+            }
+        }
+        let result = match outcome {
+            Ok(value) => CallToolResult::structured(value),
+            Err(e) => CallToolResult::structured_error(format!("{:#}", e), error::details(&e)),
+        };
+        Ok(serde_json::to_value(result).expect("tool result is always serializable"))
+    }
+
+    fn set_log_level(&self, params: Value) -> Result<Value, RpcError> {
+        let params: SetLevelParams = parse_params(params)?;
+        self.state.outbound.set_log_level(params.level);
+        Ok(json!({}))
+    }
+
+    /// Surfaces policy refusals in the client's log as well as the result,
+    /// since the agent that caused them may not pass the result on.
+    fn log_denial(&self, tool: &str, error: &anyhow::Error) {
+        let logger =
+            if error.is::<AccessDenied>() || error.is::<ReadOnly>() || error.is::<NotDeletable>() {
+                "sandbox"
+            } else if error.is::<QuotaExceeded>() {
+                "quota"
+            } else {
+                return;
+            };
+        self.state.outbound.log(
+            Level::Warning,
+            logger,
+            json!({
+                "tool": tool,
+                "message": format!("{:#}", error),
+                "details": error::details(error),
+            }),
+        );
+    }
+
+    fn list_resources(&self, params: Value) -> Result<Value, RpcError> {
+        resources::list(&self.workspace, parse_params(params)?)
+            .map_err(|e| RpcError::new(INVALID_PARAMS, format!("{:#}", e)))
+    }
+
+    fn list_resource_templates(&self) -> Value {
+        resources::templates(self.search_tool().is_some())
+    }
+
+    /// The tool behind `search://` resources, unless it is disabled.
+    fn search_tool(&self) -> Option<&Tool> {
+        self.available_tools().find(|t| t.name == "search_content")
+    }
+
+    fn read_resource(&self, id: &Value, params: Value) -> Result<Value, RpcError> {
+        let params: ReadParams = parse_params(params)?;
+        let Some(query) = resources::search_query(&params.uri) else {
+            return resources::read(&self.workspace, params)
+                .map_err(|e| RpcError::new(RESOURCE_NOT_FOUND, format!("{:#}", e)));
+        };
+        let query = query.map_err(|e| RpcError::new(INVALID_PARAMS, format!("{:#}", e)))?;
+        let tool = self
+            .search_tool()
+            .ok_or_else(|| RpcError::new(RESOURCE_NOT_FOUND, "Search resources are disabled"))?;
+        let ctx = Context {
+            workspace: &self.workspace,
+            state: &self.state,
+            progress: Progress::new(None, self.state.outbound.clone()),
+            cancel: self.state.cancellations.start(id),
+            transfer: Transfer::default(),
+        };
+        let outcome = (tool.handler)(&ctx, json!({ "pattern": query }));
+        self.state.cancellations.finish(id);
+        outcome
+            .map(|result| resources::search_contents(&params.uri, &result))
+            .map_err(|e| RpcError::new(INVALID_PARAMS, format!("{:#}", e)))
+    }
+
+    fn complete(&self, params: Value) -> Result<Value, RpcError> {
+        completion::complete(&self.workspace, parse_params(params)?)
+            .map_err(|e| RpcError::new(INVALID_PARAMS, format!("{:#}", e)))
+    }
+
+    fn subscribe_resource(&self, params: Value) -> Result<Value, RpcError> {
+        resources::subscribe(&self.workspace, &self.state, parse_params(params)?)
+            .map_err(|e| RpcError::new(RESOURCE_NOT_FOUND, format!("{:#}", e)))
+    }
+
+    fn unsubscribe_resource(&self, params: Value) -> Result<Value, RpcError> {
+        resources::unsubscribe(&self.workspace, &self.state, parse_params(params)?)
+            .map_err(|e| RpcError::new(RESOURCE_NOT_FOUND, format!("{:#}", e)))
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outbound::Outbound;
+    use crate::test_support::TestEnv;
+
+    fn request(server: &mut Server, body: Value) -> Value {
+        let line = server
+            .handle_line(&body.to_string())
+            .expect("expected a response");
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[test]
+    fn test_initialize_negotiates_known_version() {
+        let mut server = Server::new(Workspace::new(vec![]), State::default());
+        let response = request(
+            &mut server,
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize",
+                    "params": { "protocolVersion": "2024-11-05" } }),
+        );
+        assert_eq!(response["result"]["protocolVersion"], "2024-11-05");
+
+        let response = request(
+            &mut server,
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "initialize",
+                    "params": { "protocolVersion": "1999-01-01" } }),
+        );
+        assert_eq!(
+            response["result"]["protocolVersion"],
+            LATEST_PROTOCOL_VERSION
+        );
+    }
+
+    #[test]
+    fn test_unknown_method_and_notifications() {
+        let mut server = Server::new(Workspace::new(vec![]), State::default());
+        let response = request(
+            &mut server,
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "nope" }),
+        );
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert!(server.handle_line(&notification.to_string()).is_none());
+    }
+
+    #[test]
+    fn test_client_roots_narrow_the_sandbox() {
+        let env = TestEnv::new("client-roots");
+        env.write("app/main.rs", "fn main() {}");
+        env.write("secret.txt", "hidden");
+        let (outbound, rx) = Outbound::channel();
+        let state = State {
+            outbound,
+            ..State::default()
+        };
+        let mut server = Server::new(env.workspace.clone(), state);
+
+        request(
+            &mut server,
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize",
+                    "params": { "capabilities": { "roots": { "listChanged": true } } } }),
+        );
+        let initialized = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert!(server.handle_line(&initialized.to_string()).is_none());
+        let sent: Value = serde_json::from_str(&rx.try_recv().unwrap()).unwrap();
+        assert_eq!(sent["method"], "roots/list");
+
+        let roots = json!({ "jsonrpc": "2.0", "id": sent["id"], "result": { "roots": [
+            { "uri": crate::uri::path_to_uri(&env.path().join("app")) },
+            { "uri": "file:///" }
+        ] } });
+        assert!(server.handle_line(&roots.to_string()).is_none());
+        assert_eq!(server.workspace.roots(), &[env.path().join("app")]);
+
+        let call = |path: &str| {
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/call",
+                    "params": { "name": "read_file", "arguments": { "path": path } } })
+        };
+        let response = request(&mut server, call("main.rs"));
+        assert_eq!(response["result"]["isError"], false);
+        let outside = env.path().join("secret.txt");
+        let response = request(&mut server, call(outside.to_str().unwrap()));
+        assert_eq!(response["result"]["isError"], true);
+        let details = &response["result"]["structuredContent"];
+        assert_eq!(details["error"], "outside_root");
+        assert!(details["hint"].is_string());
+    }
+
+    #[test]
+    fn test_progress_token_tags_notifications() {
+        let env = TestEnv::new("progress-token");
+        env.write("a.txt", "needle");
+        let (outbound, rx) = Outbound::channel();
+        let state = State {
+            outbound,
+            ..State::default()
+        };
+        let mut server = Server::new(env.workspace.clone(), state);
+
+        let response = request(
+            &mut server,
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/call",
+                    "params": { "name": "search_content", "arguments": { "pattern": "needle" },
+                                "_meta": { "progressToken": "search-1" } } }),
+        );
+        assert_eq!(response["result"]["isError"], false);
+        let sent: Value = serde_json::from_str(&rx.try_recv().unwrap()).unwrap();
+        assert_eq!(sent["method"], "notifications/progress");
+        assert_eq!(sent["params"]["progressToken"], "search-1");
+        assert_eq!(sent["params"]["progress"], 1);
+    }
+
+    #[test]
+    fn test_cancelled_call_reports_cancelled() {
+        let env = TestEnv::new("cancelled-call");
+        env.write("a.txt", "needle");
+        let mut server = Server::new(env.workspace.clone(), State::default());
+
+        // Transports flag the id as soon as the notification is read, which
+        // may be before the request itself is dispatched.
+        let cancel = json!({ "jsonrpc": "2.0", "method": "notifications/cancelled",
+                             "params": { "requestId": 7 } });
+        assert!(server.cancellations().intercept(&cancel.to_string()));
+        let response = request(
+            &mut server,
+            json!({ "jsonrpc": "2.0", "id": 7, "method": "tools/call",
+                    "params": { "name": "search_content", "arguments": { "pattern": "needle" } } }),
+        );
+        assert_eq!(response["result"]["isError"], true);
+        assert_eq!(response["result"]["structuredContent"]["cancelled"], true);
+
+        let response = request(
+            &mut server,
+            json!({ "jsonrpc": "2.0", "id": 8, "method": "tools/call",
+                    "params": { "name": "search_content", "arguments": { "pattern": "needle" } } }),
+        );
+        assert_eq!(response["result"]["isError"], false);
+    }
+
+    #[test]
+    fn test_sandbox_denials_are_logged_at_the_client_level() {
+        let env = TestEnv::new("log-denials");
+        let (outbound, rx) = Outbound::channel();
+        let state = State {
+            outbound,
+            ..State::default()
+        };
+        let mut server = Server::new(env.workspace.clone(), state);
+        let outside = std::env::temp_dir().join("outside.txt");
+        let call = json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/call",
+                           "params": { "name": "read_file",
+                                       "arguments": { "path": outside.to_str().unwrap() } } });
+
+        assert_eq!(
+            request(&mut server, call.clone())["result"]["isError"],
+            true
+        );
+        let sent: Value = serde_json::from_str(&rx.try_recv().unwrap()).unwrap();
+        assert_eq!(sent["method"], "notifications/message");
+        assert_eq!(sent["params"]["level"], "warning");
+        assert_eq!(sent["params"]["logger"], "sandbox");
+        assert_eq!(sent["params"]["data"]["tool"], "read_file");
+
+        let set_level = json!({ "jsonrpc": "2.0", "id": 2, "method": "logging/setLevel",
+                                "params": { "level": "error" } });
+        assert!(request(&mut server, set_level)["result"].is_object());
+        request(&mut server, call);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_audit_records_bytes_written_by_any_tool() {
+        let env = TestEnv::new("audit-bytes");
+        env.write("notes.txt", "hello world\n");
+        let state = State {
+            audit: crate::audit::AuditLog::new(Some(env.path().join("audit.jsonl"))),
+            ..State::default()
+        };
+        let mut server = Server::new(env.workspace.clone(), state);
+        let edit =
+            json!({ "path": "notes.txt", "edits": [{ "search": "world", "replace": "there" }] });
+        let call = json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/call",
+                           "params": { "name": "edit_file", "arguments": edit } });
+
+        assert_eq!(request(&mut server, call)["result"]["isError"], false);
+        let entries = server.state.audit.entries().unwrap();
+        assert_eq!(entries[0]["tool"], "edit_file");
+        assert_eq!(entries[0]["bytes_written"], 12);
+    }
+
+    #[test]
+    fn test_metrics_count_bytes_read_by_any_tool() {
+        let env = TestEnv::new("metrics-bytes");
+        env.write("notes.txt", "one\ntwo\nthree\n");
+        let mut server = Server::new(env.workspace.clone(), State::default());
+        let read = json!({ "path": "notes.txt", "start": 2 });
+        let call = json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/call",
+                           "params": { "name": "read_lines", "arguments": read } });
+
+        assert_eq!(request(&mut server, call)["result"]["isError"], false);
+        let text = server.state.metrics.render();
+        assert!(text.contains("mcp_fs_bytes_read_total{tool=\"read_lines\"} 14\n"));
+    }
+
+    #[test]
+    fn test_root_problems_are_logged_to_the_client() {
+        let env = TestEnv::new("log-roots");
+        let (outbound, rx) = Outbound::channel();
+        let state = State {
+            outbound,
+            ..State::default()
+        };
+        let mut server = Server::new(env.workspace.clone(), state);
+        let id = Some(json!(ROOTS_REQUEST_ID));
+        let next = || serde_json::from_str::<Value>(&rx.try_recv().unwrap()).unwrap();
+
+        server.handle_response(id.clone(), None, Some(json!({ "code": -32601 })));
+        let sent = next();
+        assert_eq!(sent["method"], "notifications/message");
+        assert_eq!(sent["params"]["level"], "error");
+        assert_eq!(sent["params"]["logger"], "roots");
+
+        let outside = uri::path_to_uri(&std::env::temp_dir());
+        let roots = json!({ "roots": [{ "uri": outside }, { "uri": "https://example.com" }] });
+        server.handle_response(id, Some(roots), None);
+        let messages = [next(), next()];
+        assert!(messages.iter().all(|m| m["params"]["data"]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("ignoring")));
+    }
+
+    #[test]
+    fn test_tools_carry_annotations() {
+        let mut server = Server::new(Workspace::new(vec![std::env::temp_dir()]), State::default());
+        let response = request(
+            &mut server,
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }),
+        );
+        let tools = response["result"]["tools"].as_array().unwrap();
+        let annotations =
+            |name: &str| tools.iter().find(|t| t["name"] == name).unwrap()["annotations"].clone();
+        assert_eq!(annotations("read_file")["readOnlyHint"], true);
+        assert_eq!(annotations("delete_path")["destructiveHint"], true);
+        assert_eq!(annotations("create_directory")["idempotentHint"], true);
+        assert_eq!(annotations("semantic_search")["openWorldHint"], true);
+        // Anything that writes files must not claim to be read-only.
+        for tool in tools
+            .iter()
+            .filter(|t| t["inputSchema"]["properties"]["dry_run"].is_object())
+        {
+            assert_eq!(
+                tool["annotations"]["readOnlyHint"], false,
+                "{}",
+                tool["name"]
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_only_hides_mutating_tools() {
+        let workspace =
+            Workspace::new(vec![std::env::temp_dir()]).with_read_only(vec![std::env::temp_dir()]);
+        let mut server = Server::new(workspace, State::default());
+        let response = request(
+            &mut server,
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }),
+        );
+        let names: Vec<_> = response["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap().to_string())
+            .collect();
+        assert!(names.contains(&"read_file".to_string()));
+        assert!(!names.contains(&"write_file".to_string()));
+
+        let response = request(
+            &mut server,
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/call",
+                    "params": { "name": "write_file", "arguments": { "path": "x", "content": "" } } }),
+        );
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_resource_templates_include_search() {
+        let env = TestEnv::new("resource-templates");
+        env.write("src/main.rs", "fn main() {}\n");
+        let mut server = Server::new(env.workspace.clone(), State::default());
+
+        let response = request(
+            &mut server,
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "resources/templates/list" }),
+        );
+        let templates = response["result"]["resourceTemplates"].as_array().unwrap();
+        assert_eq!(templates[1]["uriTemplate"], "search://{query}");
+
+        let response = request(
+            &mut server,
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "resources/read",
+                    "params": { "uri": "search://fn%20main" } }),
+        );
+        let text = response["result"]["contents"][0]["text"].as_str().unwrap();
+        let result: Value = serde_json::from_str(text).unwrap();
+        assert_eq!(result["matches"][0]["line"], 1);
+    }
+}
@@ -0,0 +1,169 @@
+//! In-memory manifests of file hashes, compared later to report what changed.
+
+use anyhow::Result;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::digest;
+use crate::tools::Context;
+
+/// Oldest snapshots are evicted beyond this many.
+const MAX_SNAPSHOTS: usize = 20;
+/// A manifest stops growing at this many files and is marked truncated.
+pub const MAX_FILES: usize = 100_000;
+
+/// Size and blake3 digest of one file.
+#[derive(Clone, PartialEq)]
+pub struct Entry {
+    pub size: u64,
+    pub blake3: String,
+}
+
+pub struct Manifest {
+    pub files: BTreeMap<PathBuf, Entry>,
+    pub truncated: bool,
+}
+
+impl Manifest {
+    /// Hashes every file under `roots` that the walker does not skip,
+    /// reporting progress and stopping early if the call is cancelled.
+    pub fn build(ctx: &Context, roots: &[PathBuf], include_ignored: bool) -> Result<Self> {
+        let mut files = BTreeMap::new();
+        let mut truncated = false;
+        'walk: for root in roots {
+            for entry in ctx
+                .workspace
+                .walker(root, include_ignored)
+                .build()
+                .filter_map(|e| e.ok())
+            {
+                ctx.cancel.check()?;
+                if !entry.file_type().is_some_and(|t| t.is_file()) {
+                    continue;
+                }
+                if files.len() >= MAX_FILES {
+                    truncated = true;
+                    break 'walk;
+                }
+                if let Ok((size, blake3)) = digest::of_file(entry.path()) {
+                    ctx.transfer.read(size as usize);
+                    files.insert(entry.into_path(), Entry { size, blake3 });
+                    ctx.progress
+                        .report(files.len() as u64, None, "files hashed");
+                }
+            }
+        }
+        Ok(Self { files, truncated })
+    }
+}
+
+/// A manifest together with what it covered, so a diff can rebuild the
+/// same view of the tree.
+pub struct Snapshot {
+    pub id: u64,
+    pub roots: Vec<PathBuf>,
+    pub include_ignored: bool,
+    pub taken_at: SystemTime,
+    pub manifest: Manifest,
+}
+
+/// Paths that differ between two manifests.
+#[derive(Default)]
+pub struct Changes<'a> {
+    pub added: Vec<&'a Path>,
+    pub removed: Vec<&'a Path>,
+    pub modified: Vec<(&'a Path, &'a Entry, &'a Entry)>,
+    pub unchanged: usize,
+}
+
+pub fn compare<'a>(before: &'a Manifest, after: &'a Manifest) -> Changes<'a> {
+    let mut changes = Changes::default();
+    for (path, old) in &before.files {
+        match after.files.get(path) {
+            None => changes.removed.push(path),
+            Some(new) if new != old => changes.modified.push((path, old, new)),
+            Some(_) => changes.unchanged += 1,
+        }
+    }
+    changes.added = after
+        .files
+        .keys()
+        .filter(|path| !before.files.contains_key(*path))
+        .map(PathBuf::as_path)
+        .collect();
+    changes
+}
+
+#[derive(Default)]
+pub struct Snapshots {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    next_id: u64,
+    entries: VecDeque<Arc<Snapshot>>,
+}
+
+impl Snapshots {
+    /// Stores a manifest and returns the snapshot it became.
+    pub fn record(
+        &self,
+        roots: Vec<PathBuf>,
+        include_ignored: bool,
+        manifest: Manifest,
+    ) -> Arc<Snapshot> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_id += 1;
+        let snapshot = Arc::new(Snapshot {
+            id: inner.next_id,
+            roots,
+            include_ignored,
+            taken_at: SystemTime::now(),
+            manifest,
+        });
+        inner.entries.push_back(Arc::clone(&snapshot));
+        while inner.entries.len() > MAX_SNAPSHOTS {
+            inner.entries.pop_front();
+        }
+        snapshot
+    }
+
+    pub fn get(&self, id: u64) -> Option<Arc<Snapshot>> {
+        let inner = self.inner.lock().unwrap();
+        inner.entries.iter().find(|s| s.id == id).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(blake3: &str) -> Entry {
+        Entry {
+            size: 1,
+            blake3: blake3.into(),
+        }
+    }
+
+    #[test]
+    fn test_compare_classifies_paths() {
+        let manifest = |files: &[(&str, &str)]| Manifest {
+            files: files
+                .iter()
+                .map(|(path, hash)| (PathBuf::from(path), entry(hash)))
+                .collect(),
+            truncated: false,
+        };
+        let before = manifest(&[("a", "1"), ("b", "2"), ("c", "3")]);
+        let after = manifest(&[("a", "1"), ("b", "changed"), ("d", "4")]);
+        let changes = compare(&before, &after);
+        assert_eq!(changes.added, vec![Path::new("d")]);
+        assert_eq!(changes.removed, vec![Path::new("c")]);
+        assert_eq!(changes.modified.len(), 1);
+        assert_eq!(changes.modified[0].0, Path::new("b"));
+        assert_eq!(changes.unchanged, 1);
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use crate::audit::AuditLog;
+use crate::backup::Backups;
+use crate::cancel::Cancellations;
+use crate::confirm::Confirmations;
+use crate::follow::Followers;
+use crate::index::SearchIndex;
+use crate::metrics::Metrics;
+use crate::outbound::Outbound;
+use crate::quota::Quotas;
+use crate::semantic::{Backend, IndexJobs};
+use crate::snapshot::Snapshots;
+use crate::template::Templates;
+use crate::watch::Watches;
+
+/// Mutable server-wide state shared by tool invocations.
+#[derive(Default)]
+pub struct State {
+    pub audit: AuditLog,
+    pub backups: Backups,
+    /// Requests in flight, so `notifications/cancelled` can reach them.
+    pub cancellations: Cancellations,
+    pub confirmations: Confirmations,
+    /// Tools turned off by the configuration file; they are neither listed
+    /// nor callable.
+    pub disabled_tools: Arc<BTreeSet<String>>,
+    pub followers: Followers,
+    /// The shared full-text index, when enabled with `--fast-search`.
+    pub index: Option<Arc<SearchIndex>>,
+    /// Background `index_workspace` jobs.
+    pub indexing: IndexJobs,
+    pub metrics: Metrics,
+    pub outbound: Outbound,
+    pub quotas: Quotas,
+    /// Qdrant and embedding settings for the semantic tools.
+    pub semantic: Option<Arc<Backend>>,
+    pub snapshots: Snapshots,
+    /// Scaffolding templates, when a directory was given with `--templates`.
+    pub templates: Option<Arc<Templates>>,
+    pub watches: Watches,
+}
@@ -0,0 +1,189 @@
+//! Scaffolding templates for `create_from_template`. Every entry of the
+//! `--templates` directory is a template named after it: a file becomes one
+//! file, a directory becomes a tree. `{{name}}` placeholders are replaced in
+//! file contents and path components, optionally through a case filter such
+//! as `{{name | snake}}`.
+
+use anyhow::{bail, Context as _, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+const FILTERS: [&str; 5] = ["lower", "upper", "snake", "kebab", "pascal"];
+
+/// The directory templates are read from.
+pub struct Templates {
+    dir: PathBuf,
+}
+
+/// One file of a template, its path relative to the template's root. A
+/// single-file template has one file with an empty path.
+pub struct TemplateFile {
+    pub path: PathBuf,
+    pub content: Vec<u8>,
+}
+
+impl Templates {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Template names, sorted. Hidden entries are skipped.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter(|name| !name.starts_with('.'))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Reads every file of template `name`.
+    pub fn load(&self, name: &str) -> Result<Vec<TemplateFile>> {
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            bail!("Invalid template name: {:?}", name);
+        }
+        let root = self.dir.join(name);
+        if !root.exists() {
+            bail!(
+                "Unknown template {:?}; available templates are {}",
+                name,
+                self.names().join(", ")
+            );
+        }
+        let mut files = Vec::new();
+        for entry in WalkDir::new(&root).sort_by_file_name() {
+            let entry = entry.with_context(|| format!("Failed to read template {}", name))?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let content = fs::read(entry.path())
+                .with_context(|| format!("Failed to read {}", entry.path().display()))?;
+            let path = entry.path().strip_prefix(&root).unwrap().to_path_buf();
+            files.push(TemplateFile { path, content });
+        }
+        Ok(files)
+    }
+}
+
+/// Replaces every placeholder in `text`. Placeholders naming a variable not
+/// in `variables` are collected and reported together.
+pub fn render(text: &str, variables: &BTreeMap<String, String>) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut missing = BTreeSet::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let inner = &rest[start + 2..start + 2 + len];
+        rest = &rest[start + 2 + len + 2..];
+        let (name, filter) = match inner.split_once('|') {
+            Some((name, filter)) => (name.trim(), Some(filter.trim())),
+            None => (inner.trim(), None),
+        };
+        let Some(value) = variables.get(name) else {
+            missing.insert(name.to_string());
+            continue;
+        };
+        match filter {
+            None => out.push_str(value),
+            Some(filter) => out.push_str(&apply(filter, value)?),
+        }
+    }
+    out.push_str(rest);
+    if !missing.is_empty() {
+        let missing: Vec<String> = missing.into_iter().collect();
+        bail!("Template uses undefined variables: {}", missing.join(", "));
+    }
+    Ok(out)
+}
+
+/// Renders each component of a template path, refusing results that would
+/// leave the destination.
+pub fn render_path(path: &Path, variables: &BTreeMap<String, String>) -> Result<PathBuf> {
+    let rendered = PathBuf::from(render(&path.to_string_lossy(), variables)?);
+    for component in rendered.components() {
+        if !matches!(component, Component::Normal(_)) {
+            bail!(
+                "Template path {} renders to {}, which leaves the destination",
+                path.display(),
+                rendered.display()
+            );
+        }
+    }
+    Ok(rendered)
+}
+
+fn apply(filter: &str, value: &str) -> Result<String> {
+    let words = || words(value);
+    Ok(match filter {
+        "lower" => value.to_lowercase(),
+        "upper" => value.to_uppercase(),
+        "snake" => words().join("_"),
+        "kebab" => words().join("-"),
+        "pascal" => words()
+            .iter()
+            .map(|word| {
+                let mut chars = word.chars();
+                chars.next().map_or(String::new(), |first| {
+                    first.to_uppercase().chain(chars).collect()
+                })
+            })
+            .collect(),
+        _ => bail!(
+            "Unknown template filter {:?}; use one of {}",
+            filter,
+            FILTERS.join(", ")
+        ),
+    })
+}
+
+/// Splits `value` into lowercase words at separators and case changes, so
+/// `MyApp`, `my-app` and `my_app` all give `["my", "app"]`.
+fn words(value: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut previous_lower = false;
+    for c in value.chars() {
+        if !c.is_alphanumeric() {
+            words.push(String::new());
+            previous_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && previous_lower || words.is_empty() {
+            words.push(String::new());
+        }
+        words.last_mut().unwrap().extend(c.to_lowercase());
+        previous_lower = c.is_lowercase() || c.is_numeric();
+    }
+    words.retain(|word| !word.is_empty());
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_substitutes_and_filters() {
+        let variables = BTreeMap::from([
+            ("project".to_string(), "MyApp".to_string()),
+            ("module".to_string(), "http-client".to_string()),
+        ]);
+        let text = "{{project}}: {{ module | snake }} {{module|pascal}} {{project | kebab}}";
+        assert_eq!(
+            render(text, &variables).unwrap(),
+            "MyApp: http_client HttpClient my-app"
+        );
+        let err = render("{{a}} {{project}} {{b}}", &variables).unwrap_err();
+        assert_eq!(err.to_string(), "Template uses undefined variables: a, b");
+        assert!(render("{{project | shout}}", &variables).is_err());
+
+        let escape = BTreeMap::from([("name".to_string(), "../x".to_string())]);
+        assert!(render_path(Path::new("src/{{name}}.rs"), &escape).is_err());
+    }
+}
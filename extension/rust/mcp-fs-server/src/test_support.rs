@@ -0,0 +1,87 @@
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cancel::CancelToken;
+use crate::progress::Progress;
+use crate::state::State;
+use crate::tools::Context;
+use crate::transfer::Transfer;
+use crate::workspace::Workspace;
+
+/// A scratch workspace under the system temp dir, removed on drop.
+pub struct TestEnv {
+    path: PathBuf,
+    pub workspace: Workspace,
+    pub state: State,
+}
+
+impl TestEnv {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("mcp-fs-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        let path = path.canonicalize().unwrap();
+        Self {
+            workspace: Workspace::new(vec![path.clone()]),
+            state: State::default(),
+            path,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes `content` to `relative`, creating parent directories.
+    pub fn write(&self, relative: &str, content: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.path.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    pub fn ctx(&self) -> Context<'_> {
+        Context {
+            workspace: &self.workspace,
+            state: &self.state,
+            progress: Progress::default(),
+            cancel: CancelToken::default(),
+            transfer: Transfer::default(),
+        }
+    }
+}
+
+/// Serves canned JSON on a random local port and returns its base URL. Each
+/// request's method, path, and parsed JSON body are passed to `reply`, whose
+/// return value becomes the response.
+pub fn mock_http(reply: impl Fn(&str, &str, Value) -> Value + Send + 'static) -> String {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+    std::thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let mut body = String::new();
+            let _ = request.as_reader().read_to_string(&mut body);
+            let body = serde_json::from_str(&body).unwrap_or(Value::Null);
+            let path = request
+                .url()
+                .split('?')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            let reply = reply(request.method().as_str(), &path, body);
+            let content_type = tiny_http::Header::from_bytes("Content-Type", "application/json")
+                .expect("static header is valid");
+            let response =
+                tiny_http::Response::from_string(reply.to_string()).with_header(content_type);
+            let _ = request.respond(response);
+        }
+    });
+    url
+}
+
+impl Drop for TestEnv {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,212 @@
+//! Cheap content sniffing shared by the read, search, and stat tools, plus
+//! charset-aware decoding so legacy-encoded files survive a round trip.
+
+use anyhow::{bail, Result};
+use chardetng::{EncodingDetector, Iso2022JpDetection, Utf8Detection};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// How many leading bytes are inspected when classifying content.
+pub const SNIFF_BYTES: usize = 8 * 1024;
+
+/// Content is treated as binary if its first few KB contain a NUL byte.
+/// UTF-16 text is recognised by its BOM first so it is not misclassified.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    if bom_encoding(bytes).is_some() {
+        return false;
+    }
+    bytes[..bytes.len().min(SNIFF_BYTES)].contains(&0)
+}
+
+/// Encoding announced by a byte-order mark, if any.
+pub fn bom_encoding(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        Some("utf-8-bom")
+    } else if bytes.starts_with(&[0xFF, 0xFE]) {
+        Some("utf-16le")
+    } else if bytes.starts_with(&[0xFE, 0xFF]) {
+        Some("utf-16be")
+    } else {
+        None
+    }
+}
+
+/// Best-effort encoding label for a file's leading bytes.
+pub fn detect_encoding(bytes: &[u8]) -> &'static str {
+    if let Some(bom) = bom_encoding(bytes) {
+        return bom;
+    }
+    if is_binary(bytes) {
+        return "binary";
+    }
+    let sample = &bytes[..bytes.len().min(SNIFF_BYTES)];
+    match std::str::from_utf8(sample) {
+        Ok(s) if s.is_ascii() => "ascii",
+        Ok(_) => "utf-8",
+        // A multi-byte sequence cut off by the sample boundary is still UTF-8.
+        Err(e) if e.error_len().is_none() => "utf-8",
+        Err(_) => "unknown",
+    }
+}
+
+/// Classifies the line terminators used in `bytes`.
+pub fn detect_line_endings(bytes: &[u8]) -> &'static str {
+    let mut crlf = 0;
+    let mut lf = 0;
+    for (i, byte) in bytes.iter().enumerate() {
+        if *byte == b'\n' {
+            if i > 0 && bytes[i - 1] == b'\r' {
+                crlf += 1;
+            } else {
+                lf += 1;
+            }
+        }
+    }
+    match (lf, crlf) {
+        (0, 0) => "none",
+        (_, 0) => "lf",
+        (0, _) => "crlf",
+        _ => "mixed",
+    }
+}
+
+/// Text decoded from a file, with what is needed to write it back unchanged.
+pub struct Decoded {
+    pub text: String,
+    pub encoding: &'static Encoding,
+    pub bom: bool,
+}
+
+impl Decoded {
+    /// Lower-case charset name, e.g. `utf-8`, `shift_jis`, `windows-1252`.
+    pub fn label(&self) -> String {
+        label(self.encoding)
+    }
+}
+
+pub fn label(encoding: &'static Encoding) -> String {
+    encoding.name().to_ascii_lowercase()
+}
+
+/// Looks up an encoding by WHATWG label (`latin1`, `sjis`, `utf-16le`, ...).
+pub fn encoding_for_label(label: &str) -> Result<&'static Encoding> {
+    match Encoding::for_label(label.trim().as_bytes()) {
+        Some(encoding) => Ok(encoding),
+        None => bail!("Unknown encoding: {}", label),
+    }
+}
+
+/// Decodes `bytes` to UTF-8, honouring a BOM, then trying UTF-8, then
+/// guessing among legacy charsets. Returns `None` for binary content.
+pub fn decode(bytes: &[u8]) -> Option<Decoded> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return Some(Decoded {
+            text: text.into_owned(),
+            encoding,
+            bom: true,
+        });
+    }
+    if is_binary(bytes) {
+        return None;
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Some(Decoded {
+            text: text.to_string(),
+            encoding: UTF_8,
+            bom: false,
+        });
+    }
+    let mut detector = EncodingDetector::new(Iso2022JpDetection::Deny);
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, Utf8Detection::Deny);
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    Some(Decoded {
+        text: text.into_owned(),
+        encoding,
+        bom: false,
+    })
+}
+
+/// Encodes `text` for writing, failing rather than substituting characters
+/// the target charset cannot represent.
+pub fn encode(text: &str, encoding: &'static Encoding, bom: bool) -> Result<Vec<u8>> {
+    // encoding_rs only encodes to UTF-8 for the UTF-16 family, so do it by hand.
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let little = encoding == UTF_16LE;
+        let mut out = Vec::with_capacity(text.len() * 2 + 2);
+        let units = bom.then_some(0xFEFF).into_iter().chain(text.encode_utf16());
+        for unit in units {
+            let bytes = if little {
+                unit.to_le_bytes()
+            } else {
+                unit.to_be_bytes()
+            };
+            out.extend_from_slice(&bytes);
+        }
+        return Ok(out);
+    }
+    let mut out = Vec::with_capacity(text.len() + 3);
+    if bom && encoding == UTF_8 {
+        out.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+    }
+    let (bytes, _, had_errors) = encoding.encode(text);
+    if had_errors {
+        bail!(
+            "Content contains characters that cannot be represented in {}",
+            label(encoding)
+        );
+    }
+    out.extend_from_slice(&bytes);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_encoding_and_line_endings() {
+        assert_eq!(detect_encoding(b"plain\n"), "ascii");
+        assert_eq!(detect_encoding("naïve".as_bytes()), "utf-8");
+        assert_eq!(detect_encoding(&[0xFF, 0xFE, b'a', 0]), "utf-16le");
+        assert_eq!(detect_encoding(b"\x89PNG\r\n\x1a\n\0\0"), "binary");
+        assert_eq!(detect_encoding(&[b'a', 0xE9, b'b']), "unknown");
+
+        assert_eq!(detect_line_endings(b"a\nb\n"), "lf");
+        assert_eq!(detect_line_endings(b"a\r\nb\r\n"), "crlf");
+        assert_eq!(detect_line_endings(b"a\r\nb\n"), "mixed");
+        assert_eq!(detect_line_endings(b"ab"), "none");
+    }
+
+    #[test]
+    fn test_decode_and_encode_round_trip_legacy_charsets() {
+        let utf16 = [0xFF, 0xFE, b'h', 0, b'i', 0];
+        let decoded = decode(&utf16).unwrap();
+        assert_eq!(
+            (decoded.text.as_str(), decoded.label(), decoded.bom),
+            ("hi", "utf-16le".into(), true)
+        );
+        assert_eq!(
+            encode(&decoded.text, decoded.encoding, true).unwrap(),
+            utf16
+        );
+
+        let latin1 = b"caf\xE9 cr\xE8me br\xFBl\xE9e";
+        let decoded = decode(latin1).unwrap();
+        assert_eq!(decoded.text, "café crème brûlée");
+        assert_eq!(decoded.label(), "windows-1252");
+        assert_eq!(
+            encode(&decoded.text, decoded.encoding, false).unwrap(),
+            latin1
+        );
+
+        let sjis = encoding_rs::SHIFT_JIS
+            .encode("これは日本語のテキストです。")
+            .0;
+        let decoded = decode(&sjis).unwrap();
+        assert_eq!(decoded.label(), "shift_jis");
+        assert_eq!(decoded.text, "これは日本語のテキストです。");
+
+        assert!(encode("日本", encoding_for_label("latin1").unwrap(), false).is_err());
+    }
+}
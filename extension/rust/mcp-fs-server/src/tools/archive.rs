@@ -0,0 +1,374 @@
+use anyhow::{bail, Context as _, Result};
+use globset::Glob;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{parse_args, Context, Tool};
+use crate::archive::{self, Format, Source};
+use crate::atomic;
+use crate::error::{ErrorCode, Failure};
+use crate::protocol::ToolAnnotations;
+
+pub fn tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "create_archive",
+            description: "Pack files into a zip or tar.gz archive. `paths` names files or \
+                          directories, taken recursively; `glob` adds root-relative matches. \
+                          Entries are named by their path relative to their root. Honors \
+                          .gitignore. The format follows the destination's extension unless \
+                          `format` is given.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "destination": { "type": "string", "description": "Archive file to write, e.g. dist.zip" },
+                    "paths": { "type": "array", "items": { "type": "string" } },
+                    "glob": { "type": "string", "description": "e.g. \"build/**/*.js\"" },
+                    "format": { "type": "string", "enum": ["zip", "tar.gz"] },
+                    "overwrite": { "type": "boolean", "default": false }
+                },
+                "required": ["destination"]
+            }),
+            mutates: true,
+            annotations: ToolAnnotations::ADDITIVE,
+            handler: create_archive,
+        },
+        Tool {
+            name: "extract_archive",
+            description: "Unpack a zip or tar.gz archive into a directory inside the roots. \
+                          Entries whose names would land outside the destination are refused, \
+                          as are archives that expand beyond 1 GiB; symlinks and other special \
+                          entries are skipped and listed. Existing files are left alone unless \
+                          `overwrite` is set.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "archive": { "type": "string", "description": "Archive file to read" },
+                    "destination": { "type": "string", "description": "Directory to extract into" },
+                    "format": { "type": "string", "enum": ["zip", "tar.gz"] },
+                    "overwrite": { "type": "boolean", "default": false }
+                },
+                "required": ["archive", "destination"]
+            }),
+            mutates: true,
+            annotations: ToolAnnotations::ADDITIVE,
+            handler: extract_archive,
+        },
+    ]
+}
+
+#[derive(Deserialize)]
+struct CreateArgs {
+    destination: String,
+    #[serde(default)]
+    paths: Vec<String>,
+    #[serde(default)]
+    glob: Option<String>,
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    overwrite: bool,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Deserialize)]
+struct ExtractArgs {
+    archive: String,
+    destination: String,
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    overwrite: bool,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+fn format(explicit: Option<&str>, path: &str) -> Result<Format> {
+    match explicit {
+        Some(name) => Format::from_name(name),
+        None => Format::from_path(path),
+    }
+}
+
+fn create_archive(ctx: &Context, args: Value) -> Result<Value> {
+    let args: CreateArgs = parse_args(args)?;
+    if args.paths.is_empty() && args.glob.is_none() {
+        bail!("Either paths or glob is required");
+    }
+    let format = format(args.format.as_deref(), &args.destination)?;
+    let destination = ctx.workspace.resolve_writable(&args.destination)?;
+    if destination.exists() && !args.overwrite {
+        bail!(Failure::new(
+            ErrorCode::Conflict,
+            format!(
+                "Refusing to overwrite {}; pass overwrite to replace it",
+                args.destination
+            )
+        ));
+    }
+
+    // Keyed by entry name, so a file named twice is packed once.
+    let mut files: BTreeMap<String, PathBuf> = BTreeMap::new();
+    let mut add = |path: &Path| -> Result<()> {
+        let name = ctx.workspace.display_path(path);
+        match files.get(&name) {
+            Some(packed) if packed != path => bail!(Failure::new(
+                ErrorCode::Conflict,
+                format!(
+                    "{} and {} would both be packed as {}",
+                    packed.display(),
+                    path.display(),
+                    name
+                )
+            )),
+            _ => {
+                files.insert(name, path.to_path_buf());
+                Ok(())
+            }
+        }
+    };
+    for path in &args.paths {
+        let resolved = ctx.workspace.resolve(path)?;
+        if !resolved.exists() {
+            bail!(Failure::not_found(path));
+        }
+        for entry in ctx
+            .workspace
+            .walker(&resolved, false)
+            .build()
+            .filter_map(|e| e.ok())
+        {
+            if entry.file_type().is_some_and(|t| t.is_file()) && entry.path() != destination {
+                add(entry.path())?;
+            }
+        }
+    }
+    if let Some(pattern) = &args.glob {
+        let glob = Glob::new(pattern)
+            .with_context(|| format!("Invalid glob: {}", pattern))?
+            .compile_matcher();
+        for root in ctx.workspace.roots() {
+            for entry in ctx
+                .workspace
+                .walker(root, false)
+                .build()
+                .filter_map(|e| e.ok())
+            {
+                let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+                if entry.file_type().is_some_and(|t| t.is_file())
+                    && glob.is_match(relative)
+                    && entry.path() != destination
+                {
+                    add(entry.path())?;
+                }
+            }
+        }
+    }
+    if files.is_empty() {
+        bail!("No files matched");
+    }
+    ctx.state.quotas.check_files(files.len())?;
+    // Checked before anything is read; the archive itself is checked below.
+    let mut total = 0u64;
+    for (name, path) in &files {
+        total += fs::metadata(path)
+            .with_context(|| format!("Failed to read {}", name))?
+            .len();
+    }
+    ctx.state.quotas.check_write(total as usize)?;
+
+    let mut sources = Vec::new();
+    for (name, path) in &files {
+        ctx.cancel.check()?;
+        let modified = fs::metadata(path)?.modified()?;
+        let content = fs::read(path).with_context(|| format!("Failed to read {}", name))?;
+        ctx.transfer.read(content.len());
+        sources.push(Source {
+            name: name.clone(),
+            content,
+            modified,
+        });
+        ctx.progress
+            .report(sources.len() as u64, Some(files.len() as u64), "files read");
+    }
+    let bytes = archive::write(format, &sources)?;
+    ctx.state.quotas.check_write(bytes.len())?;
+    let names: Vec<&String> = files.keys().collect();
+    if args.dry_run {
+        return Ok(json!({
+            "destination": args.destination,
+            "format": format.name(),
+            "dry_run": true,
+            "would_pack": names,
+            "bytes": bytes.len(),
+        }));
+    }
+
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    ctx.state.backups.snapshot(&destination, "create_archive")?;
+    atomic::write(&destination, &bytes)
+        .with_context(|| format!("Failed to write {}", destination.display()))?;
+    ctx.transfer.wrote(bytes.len());
+    Ok(json!({
+        "destination": args.destination,
+        "format": format.name(),
+        "files": names,
+        "bytes": bytes.len(),
+    }))
+}
+
+fn extract_archive(ctx: &Context, args: Value) -> Result<Value> {
+    let args: ExtractArgs = parse_args(args)?;
+    let format = format(args.format.as_deref(), &args.archive)?;
+    let source = ctx.workspace.resolve(&args.archive)?;
+    let bytes = fs::read(&source).with_context(|| format!("Failed to read {}", args.archive))?;
+    ctx.transfer.read(bytes.len());
+    let contents = archive::read(format, &bytes)
+        .with_context(|| format!("Failed to read archive {}", args.archive))?;
+
+    // Every target goes through the workspace too, so a symlinked directory
+    // already inside the destination cannot carry an entry out of the roots.
+    let base = args.destination.trim_end_matches('/');
+    let mut plan = Vec::new();
+    for entry in contents.entries {
+        let display = format!(
+            "{}/{}",
+            base,
+            entry.name.to_string_lossy().replace('\\', "/")
+        );
+        let path = ctx.workspace.resolve_writable(&display)?;
+        if let Some(content) = &entry.content {
+            ctx.state.quotas.check_write(content.len())?;
+        }
+        plan.push((display, path, entry.content));
+    }
+    let files = plan
+        .iter()
+        .filter(|(_, _, content)| content.is_some())
+        .count();
+    ctx.state.quotas.check_files(files)?;
+    let existing: Vec<&str> = plan
+        .iter()
+        .filter(|(_, path, content)| content.is_some() && path.exists())
+        .map(|(display, _, _)| display.as_str())
+        .collect();
+    if !existing.is_empty() && !args.overwrite {
+        bail!(Failure::new(
+            ErrorCode::Conflict,
+            format!(
+                "Refusing to overwrite existing files: {}; pass overwrite to replace them",
+                existing.join(", ")
+            )
+        ));
+    }
+    let written: Vec<&str> = plan
+        .iter()
+        .filter(|(_, _, content)| content.is_some())
+        .map(|(display, _, _)| display.as_str())
+        .collect();
+    if args.dry_run {
+        return Ok(json!({
+            "archive": args.archive,
+            "destination": args.destination,
+            "dry_run": true,
+            "would_extract": written,
+            "skipped": contents.skipped,
+        }));
+    }
+
+    let mut total = 0;
+    for (display, path, content) in &plan {
+        ctx.cancel.check()?;
+        let Some(content) = content else {
+            fs::create_dir_all(path).with_context(|| format!("Failed to create {}", display))?;
+            continue;
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        ctx.state.backups.snapshot(path, "extract_archive")?;
+        atomic::write(path, content).with_context(|| format!("Failed to write {}", display))?;
+        ctx.transfer.wrote(content.len());
+        total += content.len();
+    }
+    Ok(json!({
+        "archive": args.archive,
+        "destination": args.destination,
+        "files": written,
+        "bytes": total,
+        "skipped": contents.skipped,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_archives_round_trip_into_a_new_directory() {
+        let env = TestEnv::new("archive");
+        env.write("build/app.js", "console.log(1)");
+        env.write("build/css/site.css", "body {}");
+        env.write("readme.md", "docs");
+        let ctx = env.ctx();
+
+        for name in ["out.zip", "out.tar.gz"] {
+            let created = create_archive(
+                &ctx,
+                json!({ "destination": name, "paths": ["build"], "glob": "readme.md" }),
+            )
+            .unwrap();
+            assert_eq!(
+                created["files"],
+                json!(["build/app.js", "build/css/site.css", "readme.md"])
+            );
+            let destination = format!("x-{}", name);
+            let extracted =
+                extract_archive(&ctx, json!({ "archive": name, "destination": destination }))
+                    .unwrap();
+            assert_eq!(extracted["files"].as_array().unwrap().len(), 3);
+            let css = env.path().join(destination).join("build/css/site.css");
+            assert_eq!(fs::read_to_string(css).unwrap(), "body {}");
+        }
+
+        let again = extract_archive(
+            &ctx,
+            json!({ "archive": "out.zip", "destination": "x-out.zip" }),
+        );
+        assert!(again
+            .unwrap_err()
+            .to_string()
+            .contains("Refusing to overwrite"));
+        let outside = extract_archive(&ctx, json!({ "archive": "out.zip", "destination": "/tmp" }));
+        assert!(outside.is_err());
+    }
+
+    #[test]
+    fn test_same_named_files_from_two_roots_are_refused() {
+        let mut env = TestEnv::new("archive-roots");
+        env.write("one/notes.txt", "first");
+        env.write("two/notes.txt", "second");
+        let roots = vec![env.path().join("one"), env.path().join("two")];
+        env.workspace = crate::workspace::Workspace::new(roots);
+        let ctx = env.ctx();
+
+        let error = create_archive(&ctx, json!({ "destination": "out.zip", "glob": "*.txt" }))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("would both be packed as notes.txt"),
+            "{}",
+            error
+        );
+        assert!(!env.path().join("one/out.zip").exists());
+    }
+}
@@ -0,0 +1,99 @@
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{parse_args, Context, Tool};
+use crate::protocol::ToolAnnotations;
+
+const DEFAULT_LIMIT: usize = 100;
+
+pub fn tools() -> Vec<Tool> {
+    vec![Tool {
+        name: "get_audit_log",
+        description: "Return recent entries from the audit log of mutating tool calls, newest \
+                      first: timestamp, tool, arguments hash, affected paths, bytes written, and \
+                      result. Requires the server to be started with `--audit-log`.",
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "limit": { "type": "integer", "minimum": 1, "default": DEFAULT_LIMIT },
+                "tool": { "type": "string", "description": "Only entries for this tool" },
+                "path": { "type": "string", "description": "Only entries that touched this path" }
+            }
+        }),
+        mutates: false,
+        annotations: ToolAnnotations::READ_ONLY,
+        handler: get_audit_log,
+    }]
+}
+
+#[derive(Deserialize)]
+struct AuditArgs {
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    tool: Option<String>,
+    #[serde(default)]
+    path: Option<String>,
+}
+
+fn get_audit_log(ctx: &Context, args: Value) -> Result<Value> {
+    let args: AuditArgs = parse_args(args)?;
+    if !ctx.state.audit.is_enabled() {
+        bail!("Audit logging is disabled; start the server with --audit-log FILE");
+    }
+    let entries = ctx.state.audit.entries()?;
+    let total = entries.len();
+    let matching: Vec<Value> = entries
+        .into_iter()
+        .rev()
+        .filter(|e| args.tool.as_ref().is_none_or(|t| e["tool"] == t.as_str()))
+        .filter(|e| {
+            args.path.as_ref().is_none_or(|p| {
+                e["paths"]
+                    .as_array()
+                    .is_some_and(|paths| paths.iter().any(|x| x == p.as_str()))
+            })
+        })
+        .take(args.limit.unwrap_or(DEFAULT_LIMIT))
+        .collect();
+    Ok(json!({ "entries": matching, "total": total }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{AuditLog, Entry};
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_records_are_filtered_newest_first() {
+        let mut env = TestEnv::new("audit");
+        env.state.audit = AuditLog::new(Some(env.path().join("audit.jsonl")));
+        for (tool, path) in [
+            ("write_file", "a.txt"),
+            ("edit_file", "b.txt"),
+            ("write_file", "c.txt"),
+        ] {
+            let arguments = json!({ "path": path, "content": "secret" });
+            env.state
+                .audit
+                .record(Entry {
+                    tool,
+                    arguments: &arguments,
+                    paths: vec![path.to_string()],
+                    bytes_written: Some(6),
+                    error: None,
+                })
+                .unwrap();
+        }
+        let ctx = env.ctx();
+
+        let log = get_audit_log(&ctx, json!({ "tool": "write_file" })).unwrap();
+        let entries = log["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["paths"], json!(["c.txt"]));
+        assert_eq!(log["total"], 3);
+        assert!(!log.to_string().contains("secret"));
+    }
+}
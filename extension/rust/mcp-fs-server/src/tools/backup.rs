@@ -0,0 +1,170 @@
+use anyhow::{Context as _, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+
+use super::{format_time, parse_args, Context, Tool};
+use crate::atomic;
+use crate::protocol::ToolAnnotations;
+
+pub fn tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "undo_last_change",
+            description: "Restore a file to its state before the most recent change made through \
+                          this server. Files created by that change are removed. Repeat to step \
+                          further back.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string" }
+                },
+                "required": ["path"]
+            }),
+            mutates: true,
+            annotations: ToolAnnotations::DESTRUCTIVE,
+            handler: undo_last_change,
+        },
+        Tool {
+            name: "list_backups",
+            description: "List the pre-change snapshots available to `undo_last_change`, newest \
+                          first. Snapshots are kept in memory for this session only.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Only list snapshots of this file" }
+                }
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: list_backups,
+        },
+    ]
+}
+
+#[derive(Deserialize)]
+struct UndoArgs {
+    path: String,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Deserialize)]
+struct ListBackupsArgs {
+    #[serde(default)]
+    path: Option<String>,
+}
+
+fn undo_last_change(ctx: &Context, args: Value) -> Result<Value> {
+    let args: UndoArgs = parse_args(args)?;
+    let path = ctx.workspace.resolve_writable(&args.path)?;
+    if args.dry_run {
+        let backup = ctx
+            .state
+            .backups
+            .latest(&path)
+            .with_context(|| format!("No backups recorded for {}", args.path))?;
+        return Ok(json!({
+            "path": args.path,
+            "dry_run": true,
+            "would_restore": backup.content.is_some(),
+            "would_remove": backup.content.is_none(),
+            "undone_tool": backup.tool,
+            "taken_at": format_time(backup.taken_at),
+        }));
+    }
+    // Undoing a tool that created the file removes it, which the root must
+    // allow as it would a delete_path.
+    if ctx
+        .state
+        .backups
+        .latest(&path)
+        .is_some_and(|b| b.content.is_none() && path.exists())
+    {
+        ctx.workspace.check_deletable(&args.path, &path)?;
+    }
+    let backup = ctx
+        .state
+        .backups
+        .take_latest(&path)
+        .with_context(|| format!("No backups recorded for {}", args.path))?;
+
+    match &backup.content {
+        Some(content) => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            atomic::write(&path, content)
+        }
+        None if path.exists() => fs::remove_file(&path),
+        None => Ok(()),
+    }
+    .with_context(|| format!("Failed to restore {}", path.display()))?;
+    ctx.transfer
+        .wrote(backup.content.as_ref().map_or(0, Vec::len));
+
+    Ok(json!({
+        "path": args.path,
+        "restored": backup.content.is_some(),
+        "removed": backup.content.is_none(),
+        "undone_tool": backup.tool,
+        "taken_at": format_time(backup.taken_at),
+    }))
+}
+
+fn list_backups(ctx: &Context, args: Value) -> Result<Value> {
+    let args: ListBackupsArgs = parse_args(args)?;
+    let path = match &args.path {
+        Some(path) => Some(ctx.workspace.resolve(path)?),
+        None => None,
+    };
+    let backups: Vec<Value> = ctx
+        .state
+        .backups
+        .list(path.as_deref())
+        .iter()
+        .map(|b| {
+            json!({
+                "id": b.id,
+                "path": ctx.workspace.display_path(&b.path),
+                "tool": b.tool,
+                "taken_at": format_time(b.taken_at),
+                "existed": b.content.is_some(),
+                "size": b.content.as_ref().map(Vec::len),
+            })
+        })
+        .collect();
+    Ok(json!({ "backups": backups }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_undo_steps_back_through_writes() {
+        let env = TestEnv::new("undo");
+        let ctx = env.ctx();
+        let write = |content: &str| {
+            let tool = crate::tools::registry()
+                .into_iter()
+                .find(|t| t.name == "write_file")
+                .unwrap();
+            (tool.handler)(&ctx, json!({ "path": "notes.txt", "content": content })).unwrap();
+        };
+        write("first");
+        write("second");
+
+        let listing = list_backups(&ctx, json!({ "path": "notes.txt" })).unwrap();
+        assert_eq!(listing["backups"].as_array().unwrap().len(), 2);
+
+        undo_last_change(&ctx, json!({ "path": "notes.txt" })).unwrap();
+        let file = env.path().join("notes.txt");
+        assert_eq!(fs::read_to_string(&file).unwrap(), "first");
+        let result = undo_last_change(&ctx, json!({ "path": "notes.txt" })).unwrap();
+        assert_eq!(result["removed"], true);
+        assert!(!file.exists());
+        assert!(undo_last_change(&ctx, json!({ "path": "notes.txt" })).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{confirmation_token_schema, parse_args, require_confirmation, Context, Tool};
+use crate::error::{ErrorCode, Failure};
+use crate::protocol::ToolAnnotations;
+use crate::semantic::Backend;
+
+const DISTANCES: [&str; 4] = ["Cosine", "Dot", "Euclid", "Manhattan"];
+
+pub fn tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "list_collections",
+            description: "List the Qdrant collections available to semantic_search.",
+            input_schema: json!({ "type": "object", "properties": {} }),
+            mutates: false,
+            annotations: ToolAnnotations {
+                open_world_hint: true,
+                ..ToolAnnotations::READ_ONLY
+            },
+            handler: list_collections,
+        },
+        Tool {
+            name: "collection_info",
+            description: "Describe a Qdrant collection: status, vector size, distance metric, \
+                          and point counts.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "description": "Collection; defaults to QDRANT_COLLECTION" }
+                }
+            }),
+            mutates: false,
+            annotations: ToolAnnotations {
+                open_world_hint: true,
+                ..ToolAnnotations::READ_ONLY
+            },
+            handler: collection_info,
+        },
+        Tool {
+            name: "create_collection",
+            description: "Create a Qdrant collection. Without vector_size, the embedding model \
+                          is asked for one vector to learn its dimension.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "vector_size": { "type": "integer", "minimum": 1 },
+                    "distance": { "type": "string", "enum": DISTANCES, "default": "Cosine" }
+                },
+                "required": ["name"]
+            }),
+            mutates: true,
+            annotations: ToolAnnotations {
+                open_world_hint: true,
+                ..ToolAnnotations::ADDITIVE
+            },
+            handler: create_collection,
+        },
+        Tool {
+            name: "drop_collection",
+            description: "Delete a Qdrant collection and every point in it. The first call \
+                          returns a confirmation token and the collection's size; call again \
+                          with the token to drop it.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "confirmation_token": confirmation_token_schema()
+                },
+                "required": ["name"]
+            }),
+            mutates: true,
+            annotations: ToolAnnotations {
+                idempotent_hint: true,
+                open_world_hint: true,
+                ..ToolAnnotations::DESTRUCTIVE
+            },
+            handler: drop_collection,
+        },
+    ]
+}
+
+fn backend<'a>(ctx: &'a Context) -> Result<&'a Backend> {
+    match &ctx.state.semantic {
+        Some(backend) => Ok(backend),
+        None => bail!("Semantic search is not configured"),
+    }
+}
+
+fn list_collections(ctx: &Context, _args: Value) -> Result<Value> {
+    let backend = backend(ctx)?;
+    let collections = backend.qdrant.list_collections()?;
+    Ok(json!({ "collections": collections, "default": backend.collection }))
+}
+
+#[derive(Deserialize)]
+struct InfoArgs {
+    #[serde(default)]
+    name: Option<String>,
+}
+
+fn collection_info(ctx: &Context, args: Value) -> Result<Value> {
+    let args: InfoArgs = parse_args(args)?;
+    let backend = backend(ctx)?;
+    let name = args.name.unwrap_or_else(|| backend.collection.clone());
+    let info = backend.qdrant.collection_info(&name)?;
+    Ok(summarize(&name, &info))
+}
+
+/// Flattens Qdrant's collection description. Collections with named vectors
+/// report each vector's parameters under `vectors`.
+fn summarize(name: &str, info: &Value) -> Value {
+    let vectors = &info["config"]["params"]["vectors"];
+    let mut summary = json!({
+        "name": name,
+        "status": info["status"],
+        "points_count": info["points_count"],
+        "indexed_vectors_count": info["indexed_vectors_count"],
+        "segments_count": info["segments_count"],
+    });
+    if vectors.get("size").is_some() {
+        summary["vector_size"] = vectors["size"].clone();
+        summary["distance"] = vectors["distance"].clone();
+    } else {
+        summary["vectors"] = vectors.clone();
+    }
+    summary
+}
+
+#[derive(Deserialize)]
+struct CreateArgs {
+    name: String,
+    #[serde(default)]
+    vector_size: Option<usize>,
+    #[serde(default)]
+    distance: Option<String>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+fn create_collection(ctx: &Context, args: Value) -> Result<Value> {
+    let args: CreateArgs = parse_args(args)?;
+    let backend = backend(ctx)?;
+    let distance = args.distance.as_deref().unwrap_or("Cosine");
+    if !DISTANCES.contains(&distance) {
+        bail!("distance must be one of {}", DISTANCES.join(", "));
+    }
+    if backend.qdrant.collection_exists(&args.name)? {
+        bail!(Failure::new(
+            ErrorCode::Conflict,
+            format!("Collection {} already exists", args.name)
+        ));
+    }
+    let vector_size = match args.vector_size {
+        Some(size) => size,
+        None => backend.embedder.embed("dimension probe")?.len(),
+    };
+    if args.dry_run {
+        return Ok(json!({
+            "name": args.name,
+            "dry_run": true,
+            "would_create": { "vector_size": vector_size, "distance": distance },
+        }));
+    }
+    backend
+        .qdrant
+        .create_collection(&args.name, vector_size, distance)?;
+    Ok(json!({
+        "name": args.name,
+        "created": true,
+        "vector_size": vector_size,
+        "distance": distance,
+    }))
+}
+
+#[derive(Deserialize)]
+struct DropArgs {
+    name: String,
+    #[serde(default)]
+    confirmation_token: Option<String>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+fn drop_collection(ctx: &Context, args: Value) -> Result<Value> {
+    let args: DropArgs = parse_args(args)?;
+    let backend = backend(ctx)?;
+    let info = backend.qdrant.collection_info(&args.name)?;
+    if args.dry_run {
+        return Ok(json!({
+            "name": args.name,
+            "dry_run": true,
+            "would_drop": summarize(&args.name, &info),
+        }));
+    }
+    let action = format!("drop_collection:{}", args.name);
+    let token = args.confirmation_token.as_deref();
+    if let Some(mut pending) =
+        require_confirmation(ctx, &action, token, summarize(&args.name, &info))?
+    {
+        pending["name"] = json!(args.name);
+        pending["dropped"] = json!(false);
+        return Ok(pending);
+    }
+    backend.qdrant.delete_collection(&args.name)?;
+    Ok(json!({ "name": args.name, "dropped": true }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::semantic::{Embedder, Qdrant};
+    use crate::test_support::{mock_http, TestEnv};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_create_collection_dry_run_creates_nothing() {
+        let url = mock_http(|method, path, _| match (method, path) {
+            ("GET", "/collections/new/exists") => json!({ "result": { "exists": false } }),
+            other => panic!("unexpected request {:?}", other),
+        });
+        let mut env = TestEnv::new("create-collection");
+        env.state.semantic = Some(Arc::new(Backend {
+            qdrant: Qdrant::new(&url, None),
+            embedder: Embedder::new(&url, "test-model"),
+            collection: "code".into(),
+        }));
+        let ctx = env.ctx();
+
+        let args = json!({ "name": "new", "vector_size": 4, "dry_run": true });
+        let planned = create_collection(&ctx, args).unwrap();
+        assert_eq!(planned["dry_run"], true);
+        assert_eq!(planned["would_create"]["vector_size"], 4);
+        assert!(crate::tools::registry()
+            .iter()
+            .any(|t| t.name == "create_collection" && t.mutates));
+    }
+
+    #[test]
+    fn test_drop_collection_needs_confirmation() {
+        let url = mock_http(|method, path, _| match (method, path) {
+            ("GET", "/collections/old") => json!({ "result": {
+                "status": "green", "points_count": 12,
+                "config": { "params": { "vectors": { "size": 768, "distance": "Cosine" } } }
+            } }),
+            ("DELETE", "/collections/old") => json!({ "result": true }),
+            other => panic!("unexpected request {:?}", other),
+        });
+        let mut env = TestEnv::new("drop-collection");
+        env.state.semantic = Some(Arc::new(Backend {
+            qdrant: Qdrant::new(&url, None),
+            embedder: Embedder::new(&url, "test-model"),
+            collection: "code".into(),
+        }));
+        let ctx = env.ctx();
+
+        let planned = drop_collection(&ctx, json!({ "name": "old", "dry_run": true })).unwrap();
+        assert_eq!(planned["would_drop"]["points_count"], 12);
+
+        let pending = drop_collection(&ctx, json!({ "name": "old" })).unwrap();
+        assert_eq!(pending["dropped"], false);
+        assert_eq!(pending["summary"]["points_count"], 12);
+        assert_eq!(pending["summary"]["vector_size"], 768);
+
+        let token = pending["confirmation_token"].clone();
+        let dropped =
+            drop_collection(&ctx, json!({ "name": "old", "confirmation_token": token })).unwrap();
+        assert_eq!(dropped["dropped"], true);
+    }
+}
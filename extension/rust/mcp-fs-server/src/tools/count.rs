@@ -0,0 +1,210 @@
+use anyhow::{bail, Context as _, Result};
+use globset::Glob;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{parse_args, Context, Tool};
+use crate::protocol::ToolAnnotations;
+use crate::text;
+
+const DEFAULT_MAX_FILES: usize = 1000;
+
+/// Rough characters per token for English prose and code alike; close enough
+/// to budget a context window, not to bill by.
+const CHARS_PER_TOKEN: usize = 4;
+
+pub fn tools() -> Vec<Tool> {
+    vec![Tool {
+        name: "count",
+        description: "Count the lines, words, and bytes of a file, of every file under a \
+                      directory, or of files matching a root-relative `glob`, with totals. \
+                      With `tokens`, also estimates LLM tokens at about four characters each. \
+                      Binary files are counted in bytes only. Honors .gitignore.",
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "File or directory to count; with glob, the directory it is matched under" },
+                "glob": { "type": "string", "description": "e.g. \"src/**/*.rs\"" },
+                "tokens": { "type": "boolean", "default": false },
+                "max_files": { "type": "integer", "minimum": 1, "default": DEFAULT_MAX_FILES }
+            }
+        }),
+        mutates: false,
+        annotations: ToolAnnotations::READ_ONLY,
+        handler: count,
+    }]
+}
+
+#[derive(Deserialize)]
+struct CountArgs {
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    glob: Option<String>,
+    #[serde(default)]
+    tokens: bool,
+    #[serde(default)]
+    max_files: Option<usize>,
+}
+
+#[derive(Default)]
+struct Counts {
+    lines: u64,
+    words: u64,
+    bytes: u64,
+    chars: u64,
+}
+
+impl Counts {
+    fn of(path: &Path) -> Result<(Counts, bool)> {
+        let bytes = fs::read(path)?;
+        let mut counts = Counts {
+            bytes: bytes.len() as u64,
+            ..Counts::default()
+        };
+        let Some(decoded) = text::decode(&bytes) else {
+            return Ok((counts, true));
+        };
+        counts.lines = decoded.text.lines().count() as u64;
+        counts.words = decoded.text.split_whitespace().count() as u64;
+        counts.chars = decoded.text.chars().count() as u64;
+        Ok((counts, false))
+    }
+
+    fn add(&mut self, other: &Counts) {
+        self.lines += other.lines;
+        self.words += other.words;
+        self.bytes += other.bytes;
+        self.chars += other.chars;
+    }
+
+    fn to_json(&self, tokens: bool) -> Value {
+        let mut value = json!({
+            "lines": self.lines,
+            "words": self.words,
+            "bytes": self.bytes,
+        });
+        if tokens {
+            value["tokens"] = json!(self.chars.div_ceil(CHARS_PER_TOKEN as u64));
+        }
+        value
+    }
+}
+
+fn count(ctx: &Context, args: Value) -> Result<Value> {
+    let args: CountArgs = parse_args(args)?;
+    if args.path.is_none() && args.glob.is_none() {
+        bail!("Either path or glob is required");
+    }
+    let max_files = args.max_files.unwrap_or(DEFAULT_MAX_FILES).max(1);
+    let glob = match &args.glob {
+        Some(pattern) => Some(
+            Glob::new(pattern)
+                .with_context(|| format!("Invalid glob: {}", pattern))?
+                .compile_matcher(),
+        ),
+        None => None,
+    };
+
+    let roots = match &args.path {
+        Some(path) => vec![ctx.workspace.resolve(path)?],
+        None => ctx.workspace.search_roots(None)?,
+    };
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let mut truncated = false;
+    'walk: for root in &roots {
+        if root.is_file() {
+            paths.push(root.clone());
+            continue;
+        }
+        for entry in ctx
+            .workspace
+            .walker(root, false)
+            .build()
+            .filter_map(|e| e.ok())
+        {
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            if glob.as_ref().is_some_and(|glob| !glob.is_match(relative)) {
+                continue;
+            }
+            if paths.len() >= max_files {
+                truncated = true;
+                break 'walk;
+            }
+            paths.push(entry.into_path());
+        }
+    }
+    paths.sort();
+
+    let mut total = Counts::default();
+    let mut files = Vec::new();
+    for path in &paths {
+        ctx.cancel.check()?;
+        let display = ctx.workspace.display_path(path);
+        let mut entry = match Counts::of(path) {
+            Ok((counts, binary)) => {
+                ctx.transfer.read(counts.bytes as usize);
+                total.add(&counts);
+                let mut entry = counts.to_json(args.tokens);
+                if binary {
+                    entry["binary"] = json!(true);
+                }
+                entry
+            }
+            Err(e) => json!({ "error": e.to_string() }),
+        };
+        entry["path"] = json!(display);
+        files.push(entry);
+        ctx.progress.report(
+            files.len() as u64,
+            Some(paths.len() as u64),
+            "files counted",
+        );
+    }
+
+    Ok(json!({
+        "files": files,
+        "total": total.to_json(args.tokens),
+        "truncated": truncated,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_counts_files_and_totals() {
+        let env = TestEnv::new("count");
+        env.write("src/main.rs", "fn main() {\n    run();\n}\n");
+        env.write("src/lib.rs", "pub mod a;");
+        env.write("notes.md", "two words\n");
+        env.write("src/blob.bin", b"\0\x01\x02");
+        let ctx = env.ctx();
+
+        let result = count(&ctx, json!({ "glob": "src/*.rs", "tokens": true })).unwrap();
+        assert_eq!(result["files"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            result["total"],
+            json!({ "lines": 4, "words": 8, "bytes": 35, "tokens": 9 })
+        );
+
+        let result = count(&ctx, json!({ "path": "src" })).unwrap();
+        assert_eq!(result["files"][0]["binary"], true);
+        assert_eq!(result["total"]["bytes"], 38);
+        let result = count(&ctx, json!({ "path": "notes.md" })).unwrap();
+        assert_eq!(
+            (
+                result["total"]["words"].as_u64(),
+                result["total"].get("tokens")
+            ),
+            (Some(2), None)
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use anyhow::{bail, Context as _, Result};
+use regex::{NoExpand, Regex};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+
+use super::{expected_hash_schema, parse_args, Context, Tool};
+use crate::atomic;
+use crate::digest;
+use crate::patch::unified_diff;
+use crate::protocol::ToolAnnotations;
+use crate::text;
+
+pub fn tools() -> Vec<Tool> {
+    vec![Tool {
+        name: "edit_file",
+        description: "Apply one or more search/replace edits to a text file, in order. Each edit \
+                      must match at least once. With `preview: true` the file is left untouched \
+                      and a unified diff of the result is returned instead. The file's charset \
+                      and BOM are preserved.",
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string" },
+                "edits": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "search": { "type": "string", "description": "Literal text, or a regex when `regex` is true" },
+                            "replace": { "type": "string", "description": "Replacement; `$1`-style groups expand in regex mode" },
+                            "regex": { "type": "boolean", "default": false },
+                            "max_replacements": { "type": "integer", "minimum": 1, "description": "Replace at most this many occurrences; default all" }
+                        },
+                        "required": ["search", "replace"]
+                    }
+                },
+                "preview": { "type": "boolean", "default": false },
+                "expected_hash": expected_hash_schema()
+            },
+            "required": ["path", "edits"]
+        }),
+        mutates: true,
+        annotations: ToolAnnotations::DESTRUCTIVE,
+        handler: edit_file,
+    }]
+}
+
+#[derive(Deserialize)]
+struct Edit {
+    search: String,
+    replace: String,
+    #[serde(default)]
+    regex: bool,
+    #[serde(default)]
+    max_replacements: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct EditFileArgs {
+    path: String,
+    edits: Vec<Edit>,
+    #[serde(default)]
+    dry_run: bool,
+    #[serde(default)]
+    preview: bool,
+    #[serde(default)]
+    expected_hash: Option<String>,
+}
+
+/// Applies one edit, returning the new text and how many occurrences were replaced.
+fn apply_edit(text: &str, edit: &Edit) -> Result<(String, usize)> {
+    if edit.search.is_empty() {
+        bail!("search must not be empty");
+    }
+    // `replacen` treats a limit of 0 as "replace all".
+    let limit = edit.max_replacements.unwrap_or(0);
+    if edit.regex {
+        let re =
+            Regex::new(&edit.search).with_context(|| format!("Invalid regex: {}", edit.search))?;
+        let found = re.find_iter(text).count();
+        let count = if limit == 0 { found } else { found.min(limit) };
+        let replaced = re.replacen(text, limit, edit.replace.as_str()).into_owned();
+        Ok((replaced, count))
+    } else {
+        let found = text.matches(edit.search.as_str()).count();
+        let count = if limit == 0 { found } else { found.min(limit) };
+        let re = Regex::new(&regex::escape(&edit.search))?;
+        let replaced = re
+            .replacen(text, limit, NoExpand(&edit.replace))
+            .into_owned();
+        Ok((replaced, count))
+    }
+}
+
+fn edit_file(ctx: &Context, args: Value) -> Result<Value> {
+    let args: EditFileArgs = parse_args(args)?;
+    if args.edits.is_empty() {
+        bail!("At least one edit is required");
+    }
+    let path = ctx.workspace.resolve(&args.path)?;
+    let bytes = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    ctx.transfer.read(bytes.len());
+    digest::check_expected(&args.path, Some(&bytes), args.expected_hash.as_deref())?;
+    let decoded =
+        text::decode(&bytes).with_context(|| format!("{} is not a text file", args.path))?;
+    let original = decoded.text.clone();
+
+    let mut text = original.clone();
+    let mut replacements = Vec::new();
+    for (index, edit) in args.edits.iter().enumerate() {
+        let (next, count) = apply_edit(&text, edit)?;
+        if count == 0 {
+            bail!("Edit {} matched nothing: {:?}", index, edit.search);
+        }
+        replacements.push(count);
+        text = next;
+    }
+
+    let diff = unified_diff(&args.path, &original, &text);
+    let preview = args.preview || args.dry_run;
+    let mut result = json!({
+        "path": args.path,
+        "replacements": replacements,
+        "diff": diff,
+        "written": !preview,
+        "encoding": decoded.label(),
+    });
+    if !preview {
+        ctx.workspace.check_writable(&args.path, &path)?;
+        let encoded = text::encode(&text, decoded.encoding, decoded.bom)?;
+        ctx.state.quotas.check_write(encoded.len())?;
+        result["blake3"] = json!(digest::of_bytes(&encoded));
+        ctx.state.backups.record(&path, "edit_file", Some(bytes));
+        let written = encoded.len();
+        atomic::write(&path, encoded)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        ctx.transfer.wrote(written);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_preview_then_apply_with_limits() {
+        let env = TestEnv::new("edit");
+        let file = env.write("main.rs", "let a = 1;\nlet b = 1;\nlet c = 1;\n");
+        let ctx = env.ctx();
+
+        let args = json!({
+            "path": "main.rs",
+            "preview": true,
+            "edits": [
+                { "search": "= 1", "replace": "= $2", "max_replacements": 2 },
+                { "search": r"let (\w)", "replace": "const ${1}_X", "regex": true }
+            ]
+        });
+        let result = edit_file(&ctx, args.clone()).unwrap();
+        assert_eq!(result["replacements"], json!([2, 3]));
+        assert!(result["diff"]
+            .as_str()
+            .unwrap()
+            .contains("+const a_X = $2;"));
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "let a = 1;\nlet b = 1;\nlet c = 1;\n"
+        );
+
+        let mut args = args;
+        args["preview"] = json!(false);
+        edit_file(&ctx, args).unwrap();
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "const a_X = $2;\nconst b_X = $2;\nconst c_X = 1;\n"
+        );
+
+        let missing = json!({ "path": "main.rs", "edits": [{ "search": "nope", "replace": "" }] });
+        assert!(edit_file(&ctx, missing).is_err());
+    }
+
+    #[test]
+    fn test_edit_preserves_legacy_encoding() {
+        let env = TestEnv::new("edit-latin1");
+        let file = env.write("menu.txt", b"caf\xE9 au lait\n");
+        let ctx = env.ctx();
+
+        let args =
+            json!({ "path": "menu.txt", "edits": [{ "search": "au lait", "replace": "crème" }] });
+        let result = edit_file(&ctx, args).unwrap();
+        assert_eq!(result["encoding"], "windows-1252");
+        assert_eq!(fs::read(&file).unwrap(), b"caf\xE9 cr\xE8me\n");
+    }
+
+    #[test]
+    fn test_stale_expected_hash_is_a_conflict() {
+        let env = TestEnv::new("edit-conflict");
+        env.write("notes.txt", "draft\n");
+        let ctx = env.ctx();
+        let seen = digest::of_bytes(b"draft\n");
+        env.write("notes.txt", "user edit\n");
+
+        let args = json!({ "path": "notes.txt", "expected_hash": seen,
+                           "edits": [{ "search": "edit", "replace": "change" }] });
+        let error = edit_file(&ctx, args).unwrap_err();
+        let conflict = error.downcast_ref::<digest::Conflict>().unwrap();
+        assert_eq!(conflict.current, Some(digest::of_bytes(b"user edit\n")));
+        assert_eq!(
+            fs::read_to_string(env.path().join("notes.txt")).unwrap(),
+            "user edit\n"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+use super::{parse_args, Context, Tool};
+use crate::protocol::ToolAnnotations;
+use crate::text;
+
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 200;
+/// Matching lines returned per hit.
+const MAX_LINES_PER_HIT: usize = 3;
+
+pub fn tools() -> Vec<Tool> {
+    vec![Tool {
+        name: "fast_search",
+        description: "Search the full-text index of the workspace (requires the server to run \
+                      with --fast-search). Much faster than `search_content` on large trees; \
+                      the query uses tantivy syntax: words, \"phrases\", AND/OR/NOT, prefix*. \
+                      Returns ranked files with their first matching lines.",
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string" },
+                "path": { "type": "string", "description": "Only return files under this directory" },
+                "limit": { "type": "integer", "minimum": 1, "maximum": MAX_LIMIT, "default": DEFAULT_LIMIT }
+            },
+            "required": ["query"]
+        }),
+        mutates: false,
+        annotations: ToolAnnotations::READ_ONLY,
+        handler: fast_search,
+    }]
+}
+
+#[derive(Deserialize)]
+struct FastSearchArgs {
+    query: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+/// Lowercased words of the query, used to pick out matching lines.
+pub(super) fn query_terms(query: &str) -> Vec<String> {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty() && !matches!(*w, "AND" | "OR" | "NOT"))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// The first few lines of `path` containing any of `terms`.
+pub(super) fn matching_lines(ctx: &Context, path: &Path, terms: &[String]) -> Vec<Value> {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| {
+            ctx.transfer.read(bytes.len());
+            text::decode(&bytes)
+        })
+        .map(|decoded| {
+            decoded
+                .text
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| {
+                    let line = line.to_lowercase();
+                    terms.iter().any(|t| line.contains(t.as_str()))
+                })
+                .take(MAX_LINES_PER_HIT)
+                .map(|(i, line)| json!({ "line": i + 1, "text": line }))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn fast_search(ctx: &Context, args: Value) -> Result<Value> {
+    let args: FastSearchArgs = parse_args(args)?;
+    let Some(index) = &ctx.state.index else {
+        bail!("The full-text index is disabled; start the server with --fast-search");
+    };
+    let limit = args.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let scope = match &args.path {
+        Some(path) => Some(ctx.workspace.resolve(path)?),
+        None => None,
+    };
+
+    let terms = query_terms(&args.query);
+    // Over-fetch so hits outside the session's roots or `path` can be dropped.
+    let mut hits = Vec::new();
+    for hit in index.search(&args.query, limit * 4)? {
+        if hits.len() >= limit {
+            break;
+        }
+        let in_scope = scope.as_ref().is_none_or(|s| hit.path.starts_with(s));
+        if !in_scope || !ctx.workspace.contains(&hit.path) {
+            continue;
+        }
+        let lines = matching_lines(ctx, &hit.path, &terms);
+        hits.push(json!({
+            "path": ctx.workspace.display_path(&hit.path),
+            "score": hit.score,
+            "lines": lines,
+        }));
+    }
+
+    Ok(json!({
+        "query": args.query,
+        "hits": hits,
+        "indexed_documents": index.num_documents(),
+        "index_ready": index.is_ready(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::SearchIndex;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_requires_index_and_reports_lines() {
+        let mut env = TestEnv::new("fast-search");
+        env.write("a.rs", "use std::fs;\nfn parse_config() {}\n");
+        assert!(fast_search(&env.ctx(), json!({ "query": "config" })).is_err());
+
+        let index = SearchIndex::start(env.workspace.clone()).unwrap();
+        while !index.is_ready() {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        env.state.index = Some(index);
+        let result = fast_search(&env.ctx(), json!({ "query": "config" })).unwrap();
+        assert_eq!(result["hits"][0]["path"], "a.rs");
+        assert_eq!(
+            result["hits"][0]["lines"],
+            json!([{ "line": 2, "text": "fn parse_config() {}" }])
+        );
+    }
+}
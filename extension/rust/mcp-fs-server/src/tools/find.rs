@@ -0,0 +1,155 @@
+use anyhow::{bail, Context as _, Result};
+use globset::{Glob, GlobSetBuilder};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::SystemTime;
+
+use super::{format_time, include_ignored_schema, parse_args, Context, Tool};
+use crate::protocol::ToolAnnotations;
+
+const DEFAULT_MAX_RESULTS: usize = 500;
+
+pub fn tools() -> Vec<Tool> {
+    vec![Tool {
+        name: "find_files",
+        description: "Find files whose root-relative path matches any of the given glob patterns \
+                      (e.g. \"**/*.rs\"), honoring .gitignore and .ignore unless `include_ignored` is set.",
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "patterns": { "type": "array", "items": { "type": "string" }, "minItems": 1 },
+                "path": { "type": "string", "description": "Directory to search; defaults to all roots" },
+                "max_results": { "type": "integer", "minimum": 1, "default": DEFAULT_MAX_RESULTS },
+                "include_ignored": include_ignored_schema(),
+                "sort": {
+                    "type": "string",
+                    "enum": ["path", "mtime", "size"],
+                    "default": "path",
+                    "description": "path: ascending; mtime: newest first; size: largest first"
+                }
+            },
+            "required": ["patterns"]
+        }),
+        mutates: false,
+        annotations: ToolAnnotations::READ_ONLY,
+        handler: find_files,
+    }]
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    #[default]
+    Path,
+    Mtime,
+    Size,
+}
+
+#[derive(Deserialize)]
+struct FindArgs {
+    patterns: Vec<String>,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    max_results: Option<usize>,
+    #[serde(default)]
+    sort: SortOrder,
+    #[serde(default)]
+    include_ignored: bool,
+}
+
+struct Found {
+    path: String,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+fn find_files(ctx: &Context, args: Value) -> Result<Value> {
+    let args: FindArgs = parse_args(args)?;
+    if args.patterns.is_empty() {
+        bail!("At least one glob pattern is required");
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in &args.patterns {
+        builder.add(Glob::new(pattern).with_context(|| format!("Invalid glob: {}", pattern))?);
+    }
+    let globs = builder.build()?;
+    let max_results = args.max_results.unwrap_or(DEFAULT_MAX_RESULTS).max(1);
+
+    let mut found = Vec::new();
+    let mut scanned = 0;
+    for root in ctx.workspace.search_roots(args.path.as_deref())? {
+        for entry in ctx
+            .workspace
+            .walker(&root, args.include_ignored)
+            .build()
+            .filter_map(|e| e.ok())
+        {
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            ctx.cancel.check()?;
+            scanned += 1;
+            ctx.progress.report(scanned, None, "files scanned");
+            let relative = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+            if !globs.is_match(relative) {
+                continue;
+            }
+            let metadata = entry.metadata().ok();
+            found.push(Found {
+                path: ctx.workspace.display_path(entry.path()),
+                size: metadata.as_ref().map_or(0, |m| m.len()),
+                modified: metadata.and_then(|m| m.modified().ok()),
+            });
+        }
+    }
+
+    match args.sort {
+        SortOrder::Path => found.sort_by(|a, b| a.path.cmp(&b.path)),
+        SortOrder::Mtime => found.sort_by_key(|f| std::cmp::Reverse(f.modified)),
+        SortOrder::Size => found.sort_by_key(|f| std::cmp::Reverse(f.size)),
+    }
+
+    let total = found.len();
+    let files: Vec<Value> = found
+        .into_iter()
+        .take(max_results)
+        .map(|f| {
+            json!({
+                "path": f.path,
+                "size": f.size,
+                "modified": f.modified.map(format_time),
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "files": files,
+        "total_matched": total,
+        "truncated": total > max_results,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_find_files_by_glob_sorted_by_size() {
+        let env = TestEnv::new("find");
+        env.write("src/small.rs", "a");
+        env.write("src/nested/big.rs", "abcdef");
+        env.write("README.md", "docs");
+        let ctx = env.ctx();
+
+        let result = find_files(
+            &ctx,
+            json!({ "patterns": ["**/*.rs"], "sort": "size", "max_results": 1 }),
+        )
+        .unwrap();
+        assert_eq!(result["total_matched"], 2);
+        assert_eq!(result["truncated"], true);
+        assert_eq!(result["files"][0]["path"], "src/nested/big.rs");
+    }
+}
@@ -0,0 +1,792 @@
+use anyhow::{bail, Context as _, Result};
+use base64::Engine;
+use chrono::DateTime;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs::{self, FileTimes};
+use std::path::Path;
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+use super::{
+    confirmation_token_schema, expected_hash_schema, format_time, include_ignored_schema,
+    parse_args, require_confirmation, Context, Tool,
+};
+use crate::atomic;
+use crate::digest;
+use crate::error::{ErrorCode, Failure};
+use crate::mime;
+use crate::patch::unified_diff;
+use crate::protocol::ToolAnnotations;
+use crate::text;
+
+/// Largest file `read_file` inlines unless the caller raises `max_bytes`.
+const DEFAULT_MAX_READ_BYTES: u64 = 5 * 1024 * 1024;
+
+pub fn tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "read_file",
+            description: "Read the complete contents of a file. Text is returned as UTF-8; binary \
+                          content is returned base64-encoded with a MIME type detected from its \
+                          magic bytes. Files above `max_bytes` are refused; page through them \
+                          with `read_lines` or `read_bytes` instead.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "File to read" },
+                    "max_bytes": { "type": "integer", "minimum": 1, "default": DEFAULT_MAX_READ_BYTES }
+                },
+                "required": ["path"]
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: read_file,
+        },
+        Tool {
+            name: "write_file",
+            description: "Create a file or atomically replace it with the given content. Content \
+                          is written as UTF-8 unless `encoding` names another charset, or is \
+                          `preserve` to keep the existing file's charset and BOM.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "File to write" },
+                    "content": { "type": "string", "description": "New file content" },
+                    "encoding": { "type": "string", "default": "utf-8", "description": "Charset label such as utf-8, utf-16le, latin1, shift_jis, or `preserve`" },
+                    "bom": { "type": "boolean", "default": false, "description": "Write a byte-order mark; ignored with `preserve`" },
+                    "expected_hash": expected_hash_schema()
+                },
+                "required": ["path", "content"]
+            }),
+            mutates: true,
+            annotations: ToolAnnotations {
+                idempotent_hint: true,
+                ..ToolAnnotations::DESTRUCTIVE
+            },
+            handler: write_file,
+        },
+        Tool {
+            name: "list_directory",
+            description: "List the entries of a directory with their type, size, and \
+                          modification time. Entries excluded by .gitignore/.ignore are left \
+                          out unless `include_ignored` is set. `extensions` and the size bounds \
+                          keep only matching files; `modified_since` applies to every entry. \
+                          Results are sorted by `sort` and paged: pass `limit`, then \
+                          `next_cursor` as `cursor` for the rest.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Directory to list" },
+                    "include_ignored": include_ignored_schema(),
+                    "extensions": { "type": "array", "items": { "type": "string" }, "description": "e.g. [\"rs\", \"toml\"]" },
+                    "min_size": { "type": "integer", "minimum": 0 },
+                    "max_size": { "type": "integer", "minimum": 0 },
+                    "modified_since": { "type": "string", "description": "RFC 3339 timestamp, e.g. 2024-05-01T00:00:00Z" },
+                    "sort": {
+                        "type": "string",
+                        "enum": ["name", "mtime", "size"],
+                        "default": "name",
+                        "description": "name: ascending; mtime: newest first; size: largest first"
+                    },
+                    "limit": { "type": "integer", "minimum": 1 },
+                    "cursor": { "type": "string", "description": "next_cursor from the previous page" }
+                },
+                "required": ["path"]
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: list_directory,
+        },
+        Tool {
+            name: "create_directory",
+            description: "Create a directory, including any missing parents.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Directory to create" }
+                },
+                "required": ["path"]
+            }),
+            mutates: true,
+            annotations: ToolAnnotations {
+                idempotent_hint: true,
+                ..ToolAnnotations::ADDITIVE
+            },
+            handler: create_directory,
+        },
+        Tool {
+            name: "touch",
+            description: "Create an empty file, or set an existing file's modification and \
+                          access times to now without changing its content. Missing parent \
+                          directories are created unless `create_parents` is false.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "File to create or touch" },
+                    "create_parents": { "type": "boolean", "default": true }
+                },
+                "required": ["path"]
+            }),
+            mutates: true,
+            annotations: ToolAnnotations {
+                idempotent_hint: true,
+                ..ToolAnnotations::ADDITIVE
+            },
+            handler: touch,
+        },
+        Tool {
+            name: "delete_path",
+            description: "Delete a file or directory by moving it to the OS trash. With \
+                          `permanent: true` the first call returns a `confirmation_token` and a \
+                          summary; call again with that token to delete irrecoverably.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "File or directory to delete" },
+                    "permanent": { "type": "boolean", "default": false, "description": "Bypass the trash" },
+                    "confirmation_token": confirmation_token_schema()
+                },
+                "required": ["path"]
+            }),
+            mutates: true,
+            annotations: ToolAnnotations {
+                idempotent_hint: true,
+                ..ToolAnnotations::DESTRUCTIVE
+            },
+            handler: delete_path,
+        },
+    ]
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    #[default]
+    Name,
+    Mtime,
+    Size,
+}
+
+#[derive(Deserialize)]
+struct ListDirectoryArgs {
+    path: String,
+    #[serde(default)]
+    include_ignored: bool,
+    #[serde(default)]
+    extensions: Vec<String>,
+    #[serde(default)]
+    min_size: Option<u64>,
+    #[serde(default)]
+    max_size: Option<u64>,
+    #[serde(default)]
+    modified_since: Option<String>,
+    #[serde(default)]
+    sort: SortOrder,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    cursor: Option<String>,
+}
+
+struct Listed {
+    name: String,
+    kind: &'static str,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+#[derive(Deserialize)]
+struct CreateDirectoryArgs {
+    path: String,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Deserialize)]
+struct TouchArgs {
+    path: String,
+    #[serde(default)]
+    create_parents: Option<bool>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Deserialize)]
+struct ReadFileArgs {
+    path: String,
+    #[serde(default)]
+    max_bytes: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct WriteFileArgs {
+    path: String,
+    content: String,
+    #[serde(default)]
+    encoding: Option<String>,
+    #[serde(default)]
+    bom: bool,
+    #[serde(default)]
+    expected_hash: Option<String>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Deserialize)]
+struct DeleteArgs {
+    path: String,
+    #[serde(default)]
+    permanent: bool,
+    #[serde(default)]
+    confirmation_token: Option<String>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+fn read_file(ctx: &Context, args: Value) -> Result<Value> {
+    let args: ReadFileArgs = parse_args(args)?;
+    let path = ctx.workspace.resolve(&args.path)?;
+    let size = fs::metadata(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?
+        .len();
+    let limit = args.max_bytes.unwrap_or(DEFAULT_MAX_READ_BYTES);
+    if size > limit {
+        bail!(Failure::new(
+            ErrorCode::TooLarge,
+            format!(
+                "{} is {} bytes, above the {} byte limit; raise max_bytes or use read_lines/read_bytes",
+                args.path, size, limit
+            )
+        ));
+    }
+    let bytes = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    ctx.transfer.read(bytes.len());
+
+    let sniffed = mime::sniff(&bytes);
+    let decoded = match sniffed {
+        Some(_) => None,
+        None => text::decode(&bytes),
+    };
+    Ok(match decoded {
+        Some(decoded) => json!({
+            "path": args.path,
+            "size": size,
+            "blake3": digest::of_bytes(&bytes),
+            "mime_type": mime::guess_from_path(&path),
+            "encoding": "utf-8",
+            "charset": decoded.label(),
+            "bom": decoded.bom,
+            "content": decoded.text,
+        }),
+        None => json!({
+            "path": args.path,
+            "size": size,
+            "blake3": digest::of_bytes(&bytes),
+            "mime_type": sniffed.unwrap_or_else(|| mime::guess_from_path(&path)),
+            "encoding": "base64",
+            "content": base64::engine::general_purpose::STANDARD.encode(bytes),
+        }),
+    })
+}
+
+fn write_file(ctx: &Context, args: Value) -> Result<Value> {
+    let args: WriteFileArgs = parse_args(args)?;
+    let path = ctx.workspace.resolve_writable(&args.path)?;
+    let existing = fs::read(&path).ok();
+    digest::check_expected(
+        &args.path,
+        existing.as_deref(),
+        args.expected_hash.as_deref(),
+    )?;
+    let (encoding, bom) = match args.encoding.as_deref() {
+        Some("preserve") => existing
+            .as_deref()
+            .and_then(text::decode)
+            .map_or((encoding_rs::UTF_8, false), |d| (d.encoding, d.bom)),
+        Some(label) => (text::encoding_for_label(label)?, args.bom),
+        None => (encoding_rs::UTF_8, args.bom),
+    };
+    let bytes = text::encode(&args.content, encoding, bom)?;
+    ctx.state.quotas.check_write(bytes.len())?;
+    if args.dry_run {
+        let before = existing
+            .as_deref()
+            .and_then(text::decode)
+            .map(|d| d.text)
+            .unwrap_or_default();
+        return Ok(json!({
+            "path": args.path,
+            "dry_run": true,
+            "would_create": existing.is_none(),
+            "bytes": bytes.len(),
+            "encoding": text::label(encoding),
+            "diff": unified_diff(&args.path, &before, &args.content),
+        }));
+    }
+    ctx.state.backups.snapshot(&path, "write_file")?;
+    atomic::write(&path, &bytes).with_context(|| format!("Failed to write {}", path.display()))?;
+    ctx.transfer.wrote(bytes.len());
+    Ok(json!({
+        "path": args.path,
+        "bytes_written": bytes.len(),
+        "encoding": text::label(encoding),
+        "bom": bom,
+        "blake3": digest::of_bytes(&bytes),
+    }))
+}
+
+fn list_directory(ctx: &Context, args: Value) -> Result<Value> {
+    let args: ListDirectoryArgs = parse_args(args)?;
+    let path = ctx.workspace.resolve(&args.path)?;
+    if !path.is_dir() {
+        bail!("Failed to list {}: not a directory", path.display());
+    }
+    let since = match args.modified_since.as_deref() {
+        Some(since) => Some(SystemTime::from(
+            DateTime::parse_from_rfc3339(since).with_context(|| {
+                format!("modified_since is not an RFC 3339 timestamp: {}", since)
+            })?,
+        )),
+        None => None,
+    };
+    let offset = match args.cursor.as_deref() {
+        Some(cursor) => cursor
+            .parse::<usize>()
+            .with_context(|| format!("Invalid cursor: {}", cursor))?,
+        None => 0,
+    };
+    let extensions: Vec<&str> = args
+        .extensions
+        .iter()
+        .map(|e| e.trim_start_matches('.'))
+        .collect();
+    let filters_files =
+        !extensions.is_empty() || args.min_size.is_some() || args.max_size.is_some();
+
+    let mut entries = Vec::new();
+    let walk = ctx
+        .workspace
+        .walker(&path, args.include_ignored)
+        .hidden(false)
+        .max_depth(Some(1))
+        .build();
+    for entry in walk {
+        let entry = entry.with_context(|| format!("Failed to list {}", path.display()))?;
+        if entry.depth() == 0 {
+            continue;
+        }
+        let Some(file_type) = entry.file_type() else {
+            continue;
+        };
+        let kind = if file_type.is_symlink() {
+            "symlink"
+        } else if file_type.is_dir() {
+            "directory"
+        } else {
+            "file"
+        };
+        let metadata = entry.metadata()?;
+        let size = if file_type.is_file() {
+            metadata.len()
+        } else {
+            0
+        };
+        let modified = metadata.modified().ok();
+        if filters_files {
+            let extension = entry.path().extension().and_then(|e| e.to_str());
+            let keep = file_type.is_file()
+                && (extensions.is_empty()
+                    || extension.is_some_and(|e| {
+                        extensions.iter().any(|want| want.eq_ignore_ascii_case(e))
+                    }))
+                && args.min_size.is_none_or(|min| size >= min)
+                && args.max_size.is_none_or(|max| size <= max);
+            if !keep {
+                continue;
+            }
+        }
+        if since.is_some_and(|since| modified.is_none_or(|m| m < since)) {
+            continue;
+        }
+        entries.push(Listed {
+            name: entry.file_name().to_string_lossy().to_string(),
+            kind,
+            size,
+            modified,
+        });
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    match args.sort {
+        SortOrder::Name => {}
+        SortOrder::Mtime => entries.sort_by_key(|e| std::cmp::Reverse(e.modified)),
+        SortOrder::Size => entries.sort_by_key(|e| std::cmp::Reverse(e.size)),
+    }
+
+    let total = entries.len();
+    let limit = args.limit.unwrap_or(usize::MAX).max(1);
+    let page: Vec<Value> = entries
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|e| {
+            json!({
+                "name": e.name,
+                "type": e.kind,
+                "size": e.size,
+                "modified": e.modified.map(format_time),
+            })
+        })
+        .collect();
+    let mut result = json!({
+        "path": args.path,
+        "access": ctx.workspace.access(&path),
+        "entries": page,
+        "total": total,
+    });
+    if offset.saturating_add(limit) < total {
+        result["next_cursor"] = json!((offset + limit).to_string());
+    }
+    Ok(result)
+}
+
+fn create_directory(ctx: &Context, args: Value) -> Result<Value> {
+    let args: CreateDirectoryArgs = parse_args(args)?;
+    let path = ctx.workspace.resolve_writable(&args.path)?;
+    let existed = path.is_dir();
+    if args.dry_run {
+        let missing: Vec<String> = path
+            .ancestors()
+            .take_while(|p| !p.exists())
+            .map(|p| ctx.workspace.display_path(p))
+            .collect();
+        return Ok(json!({
+            "path": args.path,
+            "dry_run": true,
+            "would_create": missing.into_iter().rev().collect::<Vec<_>>(),
+        }));
+    }
+    fs::create_dir_all(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+    Ok(json!({ "path": args.path, "created": !existed }))
+}
+
+fn touch(ctx: &Context, args: Value) -> Result<Value> {
+    let args: TouchArgs = parse_args(args)?;
+    let path = ctx.workspace.resolve_writable(&args.path)?;
+    if path.is_dir() {
+        bail!("{} is a directory", args.path);
+    }
+    let existed = path.exists();
+    let parents: Vec<&Path> = path
+        .ancestors()
+        .skip(1)
+        .take_while(|p| !p.exists())
+        .collect();
+    if !parents.is_empty() && !args.create_parents.unwrap_or(true) {
+        bail!(
+            "Parent directory of {} does not exist; pass create_parents to create it",
+            args.path
+        );
+    }
+    if !existed {
+        ctx.state.quotas.check_files(1)?;
+    }
+    if args.dry_run {
+        return Ok(json!({
+            "path": args.path,
+            "dry_run": true,
+            "would_create": !existed,
+            "would_create_parents": parents
+                .iter()
+                .rev()
+                .map(|p| ctx.workspace.display_path(p))
+                .collect::<Vec<_>>(),
+        }));
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    if !existed {
+        ctx.state.backups.snapshot(&path, "touch")?;
+    }
+    let now = SystemTime::now();
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|file| file.set_times(FileTimes::new().set_accessed(now).set_modified(now)))
+        .with_context(|| format!("Failed to touch {}", path.display()))?;
+    Ok(json!({ "path": args.path, "created": !existed }))
+}
+
+/// Counts the files, directories, and bytes that deleting `path` would remove.
+/// A symlink counts as one file: deleting it leaves its target alone.
+fn summarize(path: &Path) -> Value {
+    let (mut files, mut directories, mut bytes) = (0u64, 0u64, 0u64);
+    let walk = WalkDir::new(path).follow_root_links(false);
+    for entry in walk.into_iter().filter_map(|e| e.ok()) {
+        if entry.file_type().is_dir() {
+            directories += 1;
+        } else {
+            files += 1;
+            bytes += entry.metadata().map(|m| m.len()).unwrap_or(0);
+        }
+    }
+    json!({ "files": files, "directories": directories, "bytes": bytes })
+}
+
+fn delete_path(ctx: &Context, args: Value) -> Result<Value> {
+    let args: DeleteArgs = parse_args(args)?;
+    let path = ctx.workspace.resolve_entry(&args.path)?;
+    ctx.workspace.check_deletable(&args.path, &path)?;
+    let metadata = fs::symlink_metadata(&path)
+        .with_context(|| format!("Path not found: {}", path.display()))?;
+    let summary = summarize(&path);
+    let files = summary["files"].as_u64().unwrap_or(0);
+    ctx.state.quotas.check_files(files as usize)?;
+
+    if args.dry_run {
+        return Ok(json!({
+            "path": args.path,
+            "dry_run": true,
+            "method": if args.permanent { "permanent" } else { "trash" },
+            "would_delete": summary,
+        }));
+    }
+    if !args.permanent {
+        trash::delete(&path)
+            .with_context(|| format!("Failed to move {} to the trash", path.display()))?;
+        return Ok(json!({ "path": args.path, "deleted": true, "method": "trash" }));
+    }
+
+    let action = format!("delete_path:{}", path.display());
+    let token = args.confirmation_token.as_deref();
+    if let Some(mut pending) = require_confirmation(ctx, &action, token, summary)? {
+        pending["path"] = json!(args.path);
+        pending["deleted"] = json!(false);
+        return Ok(pending);
+    }
+
+    ctx.state.backups.snapshot(&path, "delete_path")?;
+    if metadata.is_dir() {
+        fs::remove_dir_all(&path)
+    } else {
+        fs::remove_file(&path)
+    }
+    .with_context(|| format!("Failed to delete {}", path.display()))?;
+    Ok(json!({ "path": args.path, "deleted": true, "method": "permanent" }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_write_then_read_round_trip() {
+        let env = TestEnv::new("roundtrip");
+        let ctx = env.ctx();
+
+        write_file(&ctx, json!({ "path": "a.txt", "content": "hello" })).unwrap();
+        let result = read_file(&ctx, json!({ "path": "a.txt" })).unwrap();
+        assert_eq!(result["content"], "hello");
+
+        let listing = list_directory(&ctx, json!({ "path": "." })).unwrap();
+        assert_eq!(listing["entries"][0]["name"], "a.txt");
+    }
+
+    #[test]
+    fn test_list_directory_honors_gitignore() {
+        let env = TestEnv::new("list-ignored");
+        env.write(".gitignore", "node_modules/\n");
+        env.write("node_modules/dep/index.js", "");
+        env.write("index.js", "");
+        let ctx = env.ctx();
+
+        let names = |listing: Value| -> Vec<String> {
+            listing["entries"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| e["name"].as_str().unwrap().to_string())
+                .collect()
+        };
+        let listing = list_directory(&ctx, json!({ "path": "." })).unwrap();
+        assert_eq!(names(listing), [".gitignore", "index.js"]);
+        let listing =
+            list_directory(&ctx, json!({ "path": ".", "include_ignored": true })).unwrap();
+        assert_eq!(names(listing), [".gitignore", "index.js", "node_modules"]);
+    }
+
+    #[test]
+    fn test_list_directory_filters_sorts_and_pages() {
+        let env = TestEnv::new("list-filtered");
+        let old = env.write("old.rs", "fn a() {}");
+        env.write("big.rs", "x".repeat(100));
+        env.write("notes.md", "");
+        env.write("src/lib.rs", "");
+        fs::File::options()
+            .write(true)
+            .open(old)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        let ctx = env.ctx();
+
+        let names = |listing: &Value| -> Vec<String> {
+            listing["entries"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| e["name"].as_str().unwrap().to_string())
+                .collect()
+        };
+        let args = json!({ "path": ".", "extensions": [".rs"], "sort": "size", "limit": 1 });
+        let first = list_directory(&ctx, args).unwrap();
+        assert_eq!(
+            (names(&first), first["total"].as_u64()),
+            (vec!["big.rs".to_string()], Some(2))
+        );
+        let cursor = first["next_cursor"].clone();
+        let args = json!({ "path": ".", "extensions": ["rs"], "sort": "size", "limit": 1, "cursor": cursor });
+        let second = list_directory(&ctx, args).unwrap();
+        assert_eq!(names(&second), ["old.rs"]);
+        assert!(second.get("next_cursor").is_none());
+
+        let args =
+            json!({ "path": ".", "modified_since": "2000-01-01T00:00:00Z", "sort": "mtime" });
+        let recent = list_directory(&ctx, args).unwrap();
+        assert!(!names(&recent).contains(&"old.rs".to_string()));
+        let args = json!({ "path": ".", "max_size": 10 });
+        assert_eq!(
+            names(&list_directory(&ctx, args).unwrap()),
+            ["notes.md", "old.rs"]
+        );
+    }
+
+    #[test]
+    fn test_read_binary_as_base64_with_limit() {
+        let env = TestEnv::new("read-binary");
+        env.write("logo.dat", b"\x89PNG\r\n\x1a\n\0\x01");
+        let ctx = env.ctx();
+
+        let result = read_file(&ctx, json!({ "path": "logo.dat" })).unwrap();
+        assert_eq!(result["encoding"], "base64");
+        assert_eq!(result["mime_type"], "image/png");
+        assert_eq!(result["content"], "iVBORw0KGgoAAQ==");
+
+        assert!(read_file(&ctx, json!({ "path": "logo.dat", "max_bytes": 4 })).is_err());
+    }
+
+    #[test]
+    fn test_text_starting_with_a_short_magic_is_read_as_text() {
+        let env = TestEnv::new("read-bm25");
+        env.write("notes.txt", "BM25 beats TF-IDF here");
+        let result = read_file(&env.ctx(), json!({ "path": "notes.txt" })).unwrap();
+        assert_eq!(result["encoding"], "utf-8");
+        assert_eq!(result["content"], "BM25 beats TF-IDF here");
+    }
+
+    #[test]
+    fn test_dry_run_changes_nothing() {
+        let env = TestEnv::new("dry-run");
+        let file = env.write("keep.txt", "old\n");
+        let ctx = env.ctx();
+
+        let plan = write_file(
+            &ctx,
+            json!({ "path": "keep.txt", "content": "new\n", "dry_run": true }),
+        )
+        .unwrap();
+        assert_eq!(plan["would_create"], false);
+        assert!(plan["diff"].as_str().unwrap().contains("+new"));
+
+        let plan = create_directory(&ctx, json!({ "path": "a/b", "dry_run": true })).unwrap();
+        assert_eq!(plan["would_create"], json!(["a", "a/b"]));
+
+        let plan = delete_path(&ctx, json!({ "path": "keep.txt", "dry_run": true })).unwrap();
+        assert_eq!(plan["would_delete"]["files"], 1);
+
+        assert_eq!(fs::read_to_string(&file).unwrap(), "old\n");
+        assert!(!env.path().join("a").exists());
+    }
+
+    #[test]
+    fn test_touch_creates_parents_and_keeps_content() {
+        let env = TestEnv::new("touch");
+        let existing = env.write("notes.txt", "keep");
+        let ctx = env.ctx();
+
+        let result = touch(&ctx, json!({ "path": "a/b/empty.txt" })).unwrap();
+        assert_eq!(result["created"], true);
+        assert_eq!(fs::read(env.path().join("a/b/empty.txt")).unwrap(), b"");
+        let refused = json!({ "path": "c/new.txt", "create_parents": false });
+        assert!(touch(&ctx, refused).is_err());
+
+        let old = SystemTime::UNIX_EPOCH;
+        fs::File::options()
+            .write(true)
+            .open(&existing)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        let result = touch(&ctx, json!({ "path": "notes.txt" })).unwrap();
+        assert_eq!(result["created"], false);
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "keep");
+        assert!(fs::metadata(&existing).unwrap().modified().unwrap() > old);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_delete_summary_does_not_follow_a_linked_directory() {
+        let env = TestEnv::new("delete-link");
+        let outside = env.path().with_extension("outside");
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("big.bin"), vec![0u8; 1000]).unwrap();
+        std::os::unix::fs::symlink(&outside, env.path().join("link")).unwrap();
+        let ctx = env.ctx();
+
+        let result = delete_path(&ctx, json!({ "path": "link", "dry_run": true })).unwrap();
+        assert_eq!(result["would_delete"]["files"], 1);
+        assert_eq!(result["would_delete"]["directories"], 0);
+        assert!(result["would_delete"]["bytes"].as_u64().unwrap() < 1000);
+        let _ = fs::remove_dir_all(&outside);
+    }
+
+    #[test]
+    fn test_touch_refused_by_quota_creates_nothing() {
+        let mut env = TestEnv::new("touch-quota");
+        env.state.quotas = crate::quota::Quotas::new(crate::quota::Limits {
+            max_files_per_call: Some(0),
+            ..Default::default()
+        });
+        let ctx = env.ctx();
+
+        assert!(touch(&ctx, json!({ "path": "a/b/new.txt" })).is_err());
+        assert!(!env.path().join("a").exists());
+    }
+
+    #[test]
+    fn test_permanent_delete_requires_confirmation() {
+        let env = TestEnv::new("delete");
+        env.write("sub/file.txt", "xyz");
+        let ctx = env.ctx();
+
+        let first = delete_path(&ctx, json!({ "path": "sub", "permanent": true })).unwrap();
+        assert_eq!(first["deleted"], false);
+        assert_eq!(first["summary"]["files"], 1);
+        assert_eq!(first["summary"]["bytes"], 3);
+        assert!(env.path().join("sub").exists());
+
+        let bogus = json!({ "path": "sub", "permanent": true, "confirmation_token": "nope" });
+        assert!(delete_path(&ctx, bogus).is_err());
+
+        let token = first["confirmation_token"].clone();
+        let args = json!({ "path": "sub", "permanent": true, "confirmation_token": token });
+        let second = delete_path(&ctx, args).unwrap();
+        assert_eq!(second["deleted"], true);
+        assert!(!env.path().join("sub").exists());
+    }
+}
@@ -0,0 +1,427 @@
+use anyhow::{bail, Context as _, Result};
+use globset::Glob;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use super::{parse_args, Context, Tool};
+use crate::digest;
+use crate::protocol::ToolAnnotations;
+
+const DEFAULT_MAX_FILES: usize = 1000;
+const DEFAULT_MAX_SETS: usize = 100;
+
+pub fn tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "hash_files",
+            description:
+                "Return the blake3 digest and size of files named by `paths` or matched by \
+                          a root-relative `glob`, so clients can tell which files changed since \
+                          they last read them without downloading the content again.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "paths": { "type": "array", "items": { "type": "string" } },
+                    "glob": { "type": "string", "description": "e.g. \"src/**/*.rs\"; honors .gitignore" },
+                    "path": { "type": "string", "description": "Directory the glob is matched under; defaults to all roots" },
+                    "max_files": { "type": "integer", "minimum": 1, "default": DEFAULT_MAX_FILES }
+                }
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: hash_files,
+        },
+        Tool {
+            name: "find_duplicates",
+            description: "Find files with identical content under `root`, returned as sets of \
+                          paths with the bytes each set wastes, most wasteful first. Files are \
+                          grouped by size and only same-size files are hashed (blake3). Honors \
+                          .gitignore.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "root": { "type": "string", "description": "Directory to search; defaults to all roots" },
+                    "min_size": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "default": 1,
+                        "description": "Ignore files smaller than this many bytes"
+                    },
+                    "max_sets": { "type": "integer", "minimum": 1, "default": DEFAULT_MAX_SETS }
+                }
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: find_duplicates,
+        },
+        Tool {
+            name: "verify_checksums",
+            description: "Check files against expected blake3 digests and report which match, \
+                          which differ, and which are missing. `manifest` is a list of \
+                          `{ path, blake3 }` objects, as hash_files returns, or text in b3sum's \
+                          `<digest>  <path>` format.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "manifest": {
+                        "oneOf": [
+                            {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "properties": {
+                                        "path": { "type": "string" },
+                                        "blake3": { "type": "string" }
+                                    },
+                                    "required": ["path", "blake3"]
+                                }
+                            },
+                            { "type": "string" }
+                        ]
+                    },
+                    "base": { "type": "string", "description": "Directory manifest paths are relative to; defaults to the first root" }
+                },
+                "required": ["manifest"]
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: verify_checksums,
+        },
+    ]
+}
+
+#[derive(Deserialize)]
+struct HashArgs {
+    #[serde(default)]
+    paths: Vec<String>,
+    #[serde(default)]
+    glob: Option<String>,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    max_files: Option<usize>,
+}
+
+fn hash_files(ctx: &Context, args: Value) -> Result<Value> {
+    let args: HashArgs = parse_args(args)?;
+    if args.paths.is_empty() && args.glob.is_none() {
+        bail!("Either paths or glob is required");
+    }
+    let max_files = args.max_files.unwrap_or(DEFAULT_MAX_FILES).max(1);
+
+    // Named paths that cannot be hashed are reported per file, so a deleted
+    // file shows up as such instead of failing the whole call.
+    let mut files = Vec::new();
+    for path in &args.paths {
+        let entry = match ctx.workspace.resolve(path) {
+            Ok(resolved) => match digest::of_file(&resolved) {
+                Ok((size, hash)) => {
+                    ctx.transfer.read(size as usize);
+                    json!({ "path": path, "size": size, "blake3": hash })
+                }
+                Err(e) => json!({ "path": path, "error": e.to_string() }),
+            },
+            Err(e) => json!({ "path": path, "error": format!("{:#}", e) }),
+        };
+        files.push(entry);
+        ctx.progress.report(
+            files.len() as u64,
+            Some(args.paths.len() as u64),
+            "files hashed",
+        );
+    }
+
+    let mut truncated = false;
+    if let Some(pattern) = &args.glob {
+        let glob = Glob::new(pattern)
+            .with_context(|| format!("Invalid glob: {}", pattern))?
+            .compile_matcher();
+        'walk: for root in ctx.workspace.search_roots(args.path.as_deref())? {
+            for entry in ctx
+                .workspace
+                .walker(&root, false)
+                .build()
+                .filter_map(|e| e.ok())
+            {
+                if !entry.file_type().is_some_and(|t| t.is_file()) {
+                    continue;
+                }
+                let relative = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+                if !glob.is_match(relative) {
+                    continue;
+                }
+                ctx.cancel.check()?;
+                if files.len() >= max_files {
+                    truncated = true;
+                    break 'walk;
+                }
+                let Ok((size, hash)) = digest::of_file(entry.path()) else {
+                    continue;
+                };
+                ctx.transfer.read(size as usize);
+                files.push(json!({
+                    "path": ctx.workspace.display_path(entry.path()),
+                    "size": size,
+                    "blake3": hash,
+                }));
+                ctx.progress
+                    .report(files.len() as u64, None, "files hashed");
+            }
+        }
+    }
+
+    Ok(json!({ "files": files, "truncated": truncated }))
+}
+
+#[derive(Deserialize)]
+struct DuplicateArgs {
+    #[serde(default)]
+    root: Option<String>,
+    #[serde(default)]
+    min_size: Option<u64>,
+    #[serde(default)]
+    max_sets: Option<usize>,
+}
+
+fn find_duplicates(ctx: &Context, args: Value) -> Result<Value> {
+    let args: DuplicateArgs = parse_args(args)?;
+    let min_size = args.min_size.unwrap_or(1);
+    let max_sets = args.max_sets.unwrap_or(DEFAULT_MAX_SETS).max(1);
+
+    let mut by_size: BTreeMap<u64, Vec<PathBuf>> = BTreeMap::new();
+    for root in ctx.workspace.search_roots(args.root.as_deref())? {
+        for entry in ctx
+            .workspace
+            .walker(&root, false)
+            .build()
+            .filter_map(|e| e.ok())
+        {
+            ctx.cancel.check()?;
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.len() >= min_size {
+                by_size
+                    .entry(metadata.len())
+                    .or_default()
+                    .push(entry.into_path());
+            }
+        }
+    }
+
+    let candidates: Vec<(u64, Vec<PathBuf>)> = by_size
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect();
+    let total = candidates.iter().map(|(_, paths)| paths.len() as u64).sum();
+    let mut hashed = 0;
+    let mut sets = Vec::new();
+    for (size, paths) in candidates {
+        let mut by_hash: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for path in paths {
+            ctx.cancel.check()?;
+            hashed += 1;
+            ctx.progress.report(hashed, Some(total), "files hashed");
+            // A file that vanished or cannot be read has no duplicates.
+            if let Ok((size, hash)) = digest::of_file(&path) {
+                ctx.transfer.read(size as usize);
+                by_hash
+                    .entry(hash)
+                    .or_default()
+                    .push(ctx.workspace.display_path(&path));
+            }
+        }
+        sets.extend(
+            by_hash
+                .into_iter()
+                .filter(|(_, paths)| paths.len() > 1)
+                .map(|(hash, mut paths)| {
+                    paths.sort();
+                    (size, hash, paths)
+                }),
+        );
+    }
+
+    let wasted = |(size, _, paths): &(u64, String, Vec<String>)| size * (paths.len() as u64 - 1);
+    sets.sort_by(|a, b| wasted(b).cmp(&wasted(a)).then_with(|| a.2.cmp(&b.2)));
+    let total_wasted: u64 = sets.iter().map(wasted).sum();
+    let truncated = sets.len() > max_sets;
+    sets.truncate(max_sets);
+    let sets: Vec<Value> = sets
+        .iter()
+        .map(|set| {
+            json!({
+                "size": set.0,
+                "blake3": set.1,
+                "wasted_bytes": wasted(set),
+                "paths": set.2,
+            })
+        })
+        .collect();
+    Ok(json!({
+        "sets": sets,
+        "wasted_bytes": total_wasted,
+        "truncated": truncated,
+    }))
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Manifest {
+    Entries(Vec<ManifestEntry>),
+    Text(String),
+}
+
+#[derive(Deserialize)]
+struct ManifestEntry {
+    path: String,
+    blake3: String,
+}
+
+#[derive(Deserialize)]
+struct VerifyArgs {
+    manifest: Manifest,
+    #[serde(default)]
+    base: Option<String>,
+}
+
+/// The entries of a b3sum-style manifest. A `*` before the path, which
+/// marks binary mode in sha256sum output, is dropped.
+fn parse_manifest(text: &str) -> Result<Vec<ManifestEntry>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let Some((digest, path)) = line.trim_end().split_once(char::is_whitespace) else {
+                bail!(
+                    "Manifest line {} is not `<digest>  <path>`: {}",
+                    i + 1,
+                    line
+                );
+            };
+            let path = path.trim_start();
+            Ok(ManifestEntry {
+                path: path.strip_prefix('*').unwrap_or(path).to_string(),
+                blake3: digest.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn verify_checksums(ctx: &Context, args: Value) -> Result<Value> {
+    let args: VerifyArgs = parse_args(args)?;
+    let entries = match args.manifest {
+        Manifest::Entries(entries) => entries,
+        Manifest::Text(text) => parse_manifest(&text)?,
+    };
+    if entries.is_empty() {
+        bail!("Manifest lists no files");
+    }
+    let base = args.base.as_deref().map(|base| base.trim_end_matches('/'));
+
+    let (mut matched, mut mismatched, mut missing) = (Vec::new(), Vec::new(), Vec::new());
+    for (i, entry) in entries.iter().enumerate() {
+        ctx.cancel.check()?;
+        let path = match base {
+            Some(base) => format!("{}/{}", base, entry.path),
+            None => entry.path.clone(),
+        };
+        let expected = entry.blake3.to_lowercase();
+        match ctx
+            .workspace
+            .resolve(&path)
+            .and_then(|resolved| Ok(digest::of_file(&resolved)?))
+            .inspect(|(size, _)| ctx.transfer.read(*size as usize))
+        {
+            Ok((_, actual)) if actual == expected => matched.push(json!(path)),
+            Ok((_, actual)) => mismatched.push(json!({
+                "path": path,
+                "expected": expected,
+                "actual": actual,
+            })),
+            Err(e) => missing.push(json!({ "path": path, "error": format!("{:#}", e) })),
+        }
+        ctx.progress
+            .report(i as u64 + 1, Some(entries.len() as u64), "files verified");
+    }
+    Ok(json!({
+        "ok": mismatched.is_empty() && missing.is_empty(),
+        "checked": entries.len(),
+        "matched": matched,
+        "mismatched": mismatched,
+        "missing": missing,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_hash_by_paths_and_glob() {
+        let env = TestEnv::new("hash");
+        env.write("src/lib.rs", "pub fn a() {}");
+        env.write("src/main.rs", "fn main() {}");
+        env.write("README.md", "docs");
+        let ctx = env.ctx();
+
+        let result = hash_files(&ctx, json!({ "paths": ["README.md", "gone.txt"] })).unwrap();
+        assert_eq!(result["files"][0]["size"], 4);
+        assert_eq!(
+            result["files"][0]["blake3"],
+            blake3::hash(b"docs").to_hex().as_str()
+        );
+        assert!(result["files"][1]["error"].is_string());
+
+        let result = hash_files(&ctx, json!({ "glob": "src/*.rs" })).unwrap();
+        assert_eq!(result["files"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_duplicates_are_grouped_by_content() {
+        let env = TestEnv::new("duplicates");
+        env.write("a/logo.png", "same bytes");
+        env.write("b/logo-copy.png", "same bytes");
+        env.write("c/other.png", "diff bytes");
+        env.write("empty1", "");
+        env.write("empty2", "");
+        let ctx = env.ctx();
+
+        let result = find_duplicates(&ctx, json!({})).unwrap();
+        let sets = result["sets"].as_array().unwrap();
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0]["wasted_bytes"], 10);
+        let paths = sets[0]["paths"].as_array().unwrap();
+        assert!(paths[0].as_str().unwrap().ends_with("a/logo.png"));
+        assert!(paths[1].as_str().unwrap().ends_with("b/logo-copy.png"));
+
+        let result = find_duplicates(&ctx, json!({ "min_size": 0 })).unwrap();
+        assert_eq!(result["sets"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_verify_reports_mismatches_and_missing_files() {
+        let env = TestEnv::new("verify");
+        env.write("dist/app.js", "built");
+        env.write("dist/app.css", "styled");
+        let ctx = env.ctx();
+        let good = blake3::hash(b"built").to_hex().to_string();
+
+        let manifest = json!([{ "path": "dist/app.js", "blake3": good.to_uppercase() }]);
+        let result = verify_checksums(&ctx, json!({ "manifest": manifest })).unwrap();
+        assert_eq!(result["ok"], true);
+
+        let text = format!("{}  app.js\n{} *app.css\n{}  gone.js\n", good, good, good);
+        let result = verify_checksums(&ctx, json!({ "manifest": text, "base": "dist" })).unwrap();
+        assert_eq!(result["ok"], false);
+        assert_eq!(result["matched"], json!(["dist/app.js"]));
+        assert_eq!(result["mismatched"][0]["path"], "dist/app.css");
+        assert_eq!(result["missing"][0]["path"], "dist/gone.js");
+    }
+}
@@ -0,0 +1,257 @@
+use anyhow::{bail, Result};
+use regex::RegexBuilder;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use super::fast_search::{matching_lines, query_terms};
+use super::{parse_args, Context, Tool};
+use crate::protocol::ToolAnnotations;
+use crate::text;
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 100;
+/// The usual reciprocal rank fusion constant; damps the weight of top ranks.
+const RRF_K: f64 = 60.0;
+/// Each source contributes this many candidates per requested result.
+const CANDIDATES_PER_RESULT: usize = 4;
+
+pub fn tools() -> Vec<Tool> {
+    vec![Tool {
+        name: "hybrid_search",
+        description: "Search by both exact terms and meaning. Lexical hits come from the \
+                      full-text index (or a scan of the workspace without --fast-search), \
+                      vector hits from Qdrant; the two rankings are merged with reciprocal \
+                      rank fusion so exact identifier matches are not lost. Each result \
+                      reports its rank in either source.",
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string" },
+                "path": { "type": "string", "description": "Only return files under this directory" },
+                "collection": { "type": "string", "description": "Qdrant collection; defaults to QDRANT_COLLECTION" },
+                "limit": { "type": "integer", "minimum": 1, "maximum": MAX_LIMIT, "default": DEFAULT_LIMIT }
+            },
+            "required": ["query"]
+        }),
+        mutates: false,
+        annotations: ToolAnnotations {
+            open_world_hint: true,
+            ..ToolAnnotations::READ_ONLY
+        },
+        handler: hybrid_search,
+    }]
+}
+
+#[derive(Deserialize)]
+struct HybridArgs {
+    query: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    collection: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+/// A file's standing in the fused ranking.
+#[derive(Default)]
+struct Fused {
+    score: f64,
+    lexical_rank: Option<usize>,
+    vector_rank: Option<usize>,
+    /// The best-ranked chunk from the vector side, if any.
+    chunk: Option<Value>,
+}
+
+fn hybrid_search(ctx: &Context, args: Value) -> Result<Value> {
+    let args: HybridArgs = parse_args(args)?;
+    if args.query.trim().is_empty() {
+        bail!("query must not be empty");
+    }
+    let limit = args.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let candidates = limit * CANDIDATES_PER_RESULT;
+    let roots = ctx.workspace.search_roots(args.path.as_deref())?;
+    let in_scope = |path: &PathBuf| roots.iter().any(|root| path.starts_with(root));
+
+    let mut fused: HashMap<String, Fused> = HashMap::new();
+    let lexical = lexical_ranking(ctx, &args.query, &roots, candidates)?;
+    for (rank, path) in lexical.iter().enumerate() {
+        let entry = fused.entry(ctx.workspace.display_path(path)).or_default();
+        entry.score += 1.0 / (RRF_K + rank as f64 + 1.0);
+        entry.lexical_rank = Some(rank + 1);
+    }
+
+    // Qdrant being unreachable degrades to lexical results rather than failing.
+    let mut vector_error = None;
+    if let Some(backend) = &ctx.state.semantic {
+        let collection = args.collection.as_deref().unwrap_or(&backend.collection);
+        let points = backend
+            .embedder
+            .embed(&args.query)
+            .and_then(|vector| backend.qdrant.search(collection, &vector, candidates));
+        match points {
+            Ok(points) => {
+                let mut rank = 0;
+                for point in points {
+                    let Some(file) = point.payload["filePath"].as_str() else {
+                        continue;
+                    };
+                    let resolved = ctx.workspace.resolve(file).ok();
+                    if !resolved.as_ref().is_some_and(in_scope) {
+                        continue;
+                    }
+                    let entry = fused.entry(file.to_string()).or_default();
+                    if entry.vector_rank.is_some() {
+                        continue;
+                    }
+                    rank += 1;
+                    entry.score += 1.0 / (RRF_K + rank as f64);
+                    entry.vector_rank = Some(rank);
+                    entry.chunk = Some(json!({
+                        "start_line": point.payload["startLine"],
+                        "end_line": point.payload["endLine"],
+                        "content": point.payload["content"],
+                        "similarity": point.score,
+                    }));
+                }
+            }
+            Err(e) => vector_error = Some(format!("{:#}", e)),
+        }
+    }
+
+    let mut ranked: Vec<(String, Fused)> = fused.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.score.total_cmp(&a.1.score).then_with(|| a.0.cmp(&b.0)));
+    let terms = query_terms(&args.query);
+    let results: Vec<Value> = ranked
+        .into_iter()
+        .take(limit)
+        .map(|(path, fused)| {
+            let lines = match ctx.workspace.resolve(&path) {
+                Ok(resolved) => matching_lines(ctx, &resolved, &terms),
+                Err(_) => Vec::new(),
+            };
+            json!({
+                "path": path,
+                "score": fused.score,
+                "lexical_rank": fused.lexical_rank,
+                "vector_rank": fused.vector_rank,
+                "lines": lines,
+                "chunk": fused.chunk,
+            })
+        })
+        .collect();
+
+    let mut result = json!({
+        "query": args.query,
+        "results": results,
+        "lexical_source": if ctx.state.index.is_some() { "index" } else { "scan" },
+    });
+    if ctx.state.semantic.is_none() {
+        result["vector_error"] = json!("Semantic search is not configured");
+    } else if let Some(error) = vector_error {
+        result["vector_error"] = json!(error);
+    }
+    Ok(result)
+}
+
+/// Files matching the query's terms, best first: the tantivy ranking when the
+/// index is enabled, otherwise files ordered by how many lines match.
+fn lexical_ranking(
+    ctx: &Context,
+    query: &str,
+    roots: &[PathBuf],
+    limit: usize,
+) -> Result<Vec<PathBuf>> {
+    if let Some(index) = &ctx.state.index {
+        return Ok(index
+            .search(query, limit * 2)?
+            .into_iter()
+            .map(|hit| hit.path)
+            .filter(|path| ctx.workspace.contains(path))
+            .filter(|path| roots.iter().any(|root| path.starts_with(root)))
+            .take(limit)
+            .collect());
+    }
+
+    let terms = query_terms(query);
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+    let alternatives: Vec<String> = terms.iter().map(|t| regex::escape(t)).collect();
+    let regex = RegexBuilder::new(&alternatives.join("|"))
+        .case_insensitive(true)
+        .build()?;
+    let mut counts = Vec::new();
+    for root in roots {
+        for entry in ctx
+            .workspace
+            .walker(root, false)
+            .build()
+            .filter_map(|e| e.ok())
+        {
+            ctx.cancel.check()?;
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let Ok(bytes) = fs::read(entry.path()) else {
+                continue;
+            };
+            ctx.transfer.read(bytes.len());
+            if text::is_binary(&bytes) {
+                continue;
+            }
+            let text = String::from_utf8_lossy(&bytes);
+            let matching = text.lines().filter(|line| regex.is_match(line)).count();
+            if matching > 0 {
+                counts.push((matching, entry.into_path()));
+            }
+        }
+    }
+    counts.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    Ok(counts
+        .into_iter()
+        .take(limit)
+        .map(|(_, path)| path)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::semantic::{Backend, Embedder, Qdrant};
+    use crate::test_support::{mock_http, TestEnv};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_fuses_lexical_and_vector_rankings() {
+        let url = mock_http(|_, path, _| match path {
+            "/api/embeddings" => json!({ "embedding": [1.0, 0.0] }),
+            "/collections/code/points/search" => json!({ "result": [
+                { "id": 1, "score": 0.9, "payload": { "filePath": "login.rs", "startLine": 1, "endLine": 3, "content": "fn sign_in() {}" } },
+                { "id": 2, "score": 0.8, "payload": { "filePath": "session.rs", "startLine": 1, "endLine": 2, "content": "struct Session;" } }
+            ] }),
+            other => panic!("unexpected request to {}", other),
+        });
+        let mut env = TestEnv::new("hybrid");
+        env.write("session.rs", "struct Session;\nfn parse_token() {}\n");
+        env.write("login.rs", "fn sign_in() {}\n");
+        env.write("token.rs", "// parse_token helpers\n");
+        env.state.semantic = Some(Arc::new(Backend {
+            qdrant: Qdrant::new(&url, None),
+            embedder: Embedder::new(&url, "test-model"),
+            collection: "code".into(),
+        }));
+
+        let result = hybrid_search(&env.ctx(), json!({ "query": "parse_token" })).unwrap();
+        let results = result["results"].as_array().unwrap();
+        // session.rs is found by both sources, so it outranks either single-source hit.
+        assert_eq!(results[0]["path"], "session.rs");
+        assert_eq!(results[0]["vector_rank"], 2);
+        assert!(results[0]["lexical_rank"].is_number());
+        assert_eq!(results.len(), 3);
+        assert_eq!(result["lexical_source"], "scan");
+    }
+}
@@ -0,0 +1,207 @@
+use anyhow::{bail, Context as _, Result};
+use chrono::{DateTime, Utc};
+use ignore::WalkBuilder;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::cancel::CancelToken;
+use crate::progress::Progress;
+use crate::protocol::{ToolAnnotations, ToolDefinition};
+use crate::state::State;
+use crate::transfer::Transfer;
+use crate::workspace::Workspace;
+
+mod archive;
+mod audit;
+mod backup;
+mod collections;
+mod count;
+mod edit;
+mod fast_search;
+mod find;
+mod fs;
+mod hash;
+mod hybrid;
+mod patch;
+mod read;
+mod search;
+mod semantic;
+mod snapshot;
+mod stat;
+mod tail;
+mod template;
+mod tree;
+mod usage;
+mod watch;
+
+/// State handed to every tool invocation.
+pub struct Context<'a> {
+    pub workspace: &'a Workspace,
+    pub state: &'a State,
+    /// Progress reporting for this call; a no-op unless the client sent a token.
+    pub progress: Progress,
+    /// Set when the client cancels this call; long loops should poll it.
+    pub cancel: CancelToken,
+    /// File bytes this call has written.
+    pub transfer: Transfer,
+}
+
+/// A tool exposed through `tools/list` and `tools/call`.
+pub struct Tool {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub input_schema: Value,
+    /// Whether the tool can change files or collections; such tools are hidden
+    /// in read-only mode.
+    pub mutates: bool,
+    pub annotations: ToolAnnotations,
+    pub handler: fn(&Context, Value) -> Result<Value>,
+}
+
+impl Tool {
+    /// The advertised definition. Every mutating tool accepts `dry_run`, so
+    /// it is added to their schemas here rather than repeated in each one.
+    pub fn definition(&self) -> ToolDefinition {
+        let mut input_schema = self.input_schema.clone();
+        if self.mutates {
+            if let Some(properties) = input_schema
+                .get_mut("properties")
+                .and_then(Value::as_object_mut)
+            {
+                properties.insert(
+                    "dry_run".into(),
+                    json!({
+                        "type": "boolean",
+                        "default": false,
+                        "description": "Report the planned changes without performing them"
+                    }),
+                );
+            }
+        }
+        ToolDefinition {
+            name: self.name,
+            description: self.description,
+            input_schema,
+            annotations: self.annotations,
+        }
+    }
+}
+
+/// Every tool the server knows about, in the order they are advertised.
+pub fn registry() -> Vec<Tool> {
+    let mut tools = Vec::new();
+    tools.extend(fs::tools());
+    tools.extend(read::tools());
+    tools.extend(tail::tools());
+    tools.extend(watch::tools());
+    tools.extend(edit::tools());
+    tools.extend(search::tools());
+    tools.extend(fast_search::tools());
+    tools.extend(semantic::tools());
+    tools.extend(hybrid::tools());
+    tools.extend(collections::tools());
+    tools.extend(find::tools());
+    tools.extend(tree::tools());
+    tools.extend(stat::tools());
+    tools.extend(hash::tools());
+    tools.extend(usage::tools());
+    tools.extend(count::tools());
+    tools.extend(patch::tools());
+    tools.extend(template::tools());
+    tools.extend(archive::tools());
+    tools.extend(backup::tools());
+    tools.extend(snapshot::tools());
+    tools.extend(audit::tools());
+    tools
+}
+
+/// Deserializes tool arguments, treating a missing `arguments` object as `{}`.
+pub(crate) fn parse_args<T: DeserializeOwned>(args: Value) -> Result<T> {
+    let args = if args.is_null() {
+        Value::Object(Default::default())
+    } else {
+        args
+    };
+    serde_json::from_value(args).context("Invalid tool arguments")
+}
+
+/// Whether a mutating call only asked for its plan.
+pub(crate) fn is_dry_run(args: &Value) -> bool {
+    args.get("dry_run")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// Schema of the flag that turns off .gitignore/.ignore filtering.
+pub(crate) fn include_ignored_schema() -> Value {
+    json!({
+        "type": "boolean",
+        "default": false,
+        "description": "Also return entries excluded by .gitignore/.ignore files"
+    })
+}
+
+/// Schema of the optimistic-concurrency guard accepted by write tools.
+pub(crate) fn expected_hash_schema() -> Value {
+    json!({
+        "type": "string",
+        "description": "blake3 hex digest the file must still have (as returned by read_file or \
+                        hash_files); otherwise the call fails with a `conflict` error"
+    })
+}
+
+/// Schema of the token that confirms a destructive operation.
+pub(crate) fn confirmation_token_schema() -> Value {
+    json!({
+        "type": "string",
+        "description": "Token returned by a previous call for the same operation; required to \
+                        actually destroy data"
+    })
+}
+
+/// Two-phase confirmation for operations that destroy data. Without a token
+/// one is issued for `action` and the returned response, carrying it and
+/// `summary`, should be handed back to the client instead of proceeding. A
+/// token issued for the same `action` yields `None` and is consumed.
+pub(crate) fn require_confirmation(
+    ctx: &Context,
+    action: &str,
+    token: Option<&str>,
+    summary: Value,
+) -> Result<Option<Value>> {
+    match token {
+        Some(token) if ctx.state.confirmations.redeem(token, action) => Ok(None),
+        Some(_) => {
+            bail!("Confirmation token is invalid, expired, or was issued for a different operation")
+        }
+        None => Ok(Some(json!({
+            "requires_confirmation": true,
+            "confirmation_token": ctx.state.confirmations.issue(action),
+            "summary": summary,
+        }))),
+    }
+}
+
+/// A walker over `root` that skips hidden entries and anything excluded by
+/// .gitignore/.ignore files, whether or not `root` is inside a git
+/// repository. With `include_ignored` only hidden entries are skipped.
+pub(crate) fn walker(root: &Path, include_ignored: bool) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(root);
+    builder.require_git(false);
+    if include_ignored {
+        builder
+            .git_ignore(false)
+            .git_exclude(false)
+            .git_global(false)
+            .ignore(false)
+            .parents(false);
+    }
+    builder
+}
+
+/// Formats a filesystem timestamp as RFC 3339 in UTC.
+pub(crate) fn format_time(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339()
+}
@@ -0,0 +1,294 @@
+use anyhow::{bail, Context as _, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{confirmation_token_schema, parse_args, require_confirmation, Context, Tool};
+use crate::atomic;
+use crate::digest;
+use crate::error::{ErrorCode, Failure};
+use crate::patch::{self, unified_diff, FilePatch};
+use crate::protocol::ToolAnnotations;
+
+pub fn tools() -> Vec<Tool> {
+    vec![Tool {
+        name: "apply_patch",
+        description: "Apply a unified diff to one or more files. Every hunk is validated against \
+                      the current contents first; if any hunk fails nothing is written. A patch \
+                      that deletes files first returns a `confirmation_token` and the files it \
+                      would delete; call again with that token to apply it.",
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "patch": { "type": "string", "description": "Unified diff text (git diff or diff -u output)" },
+                "confirmation_token": confirmation_token_schema(),
+                "expected_hashes": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "Map from a patched file's path, as written in the diff, to the blake3 digest it must still have"
+                }
+            },
+            "required": ["patch"]
+        }),
+        mutates: true,
+        annotations: ToolAnnotations::DESTRUCTIVE,
+        handler: apply_patch,
+    }]
+}
+
+#[derive(Deserialize)]
+struct ApplyPatchArgs {
+    patch: String,
+    #[serde(default)]
+    expected_hashes: HashMap<String, String>,
+    #[serde(default)]
+    confirmation_token: Option<String>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// A single file change: `None` on either side means "does not exist".
+struct Change {
+    path: PathBuf,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+fn read_existing(path: &Path) -> Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    fs::read_to_string(path)
+        .map(Some)
+        .with_context(|| format!("Failed to read {}", path.display()))
+}
+
+fn write_state(path: &Path, content: &Option<String>) -> std::io::Result<()> {
+    match content {
+        Some(text) => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            atomic::write(path, text)
+        }
+        None if path.exists() => fs::remove_file(path),
+        None => Ok(()),
+    }
+}
+
+/// Plans the changes for one file section, returning them with a JSON report.
+fn plan(
+    ctx: &Context,
+    file: &FilePatch,
+    expected_hashes: &HashMap<String, String>,
+) -> Result<(Vec<Change>, Value, bool)> {
+    let source = match &file.old_path {
+        Some(old) => Some(ctx.workspace.resolve_writable(old)?),
+        None => None,
+    };
+    let target = match &file.new_path {
+        Some(new) => Some(ctx.workspace.resolve_writable(new)?),
+        None => None,
+    };
+    // Deleting or renaming a file removes the source path.
+    if let (Some(old), Some(src)) = (&file.old_path, &source) {
+        if target.as_ref() != Some(src) {
+            ctx.workspace.check_deletable(old, src)?;
+        }
+    }
+
+    let original = match &source {
+        Some(path) => read_existing(path)?
+            .with_context(|| format!("File to patch does not exist: {}", path.display()))?,
+        None => {
+            if let Some(path) = target.as_ref().filter(|p| p.exists()) {
+                bail!(Failure::new(
+                    ErrorCode::Conflict,
+                    format!("Patch creates {} but it already exists", path.display())
+                ));
+            }
+            String::new()
+        }
+    };
+    ctx.transfer.read(original.len());
+    if let Some(old) = &file.old_path {
+        let expected = expected_hashes.get(old).map(String::as_str);
+        digest::check_expected(old, Some(original.as_bytes()), expected)?;
+    }
+
+    let (patched, outcomes) = patch::apply(&original, file);
+    let ok = outcomes.iter().all(|o| o.error.is_none());
+    let status = match (&source, &target) {
+        (None, _) => "created",
+        (_, None) => "deleted",
+        (Some(a), Some(b)) if a != b => "renamed",
+        _ => "modified",
+    };
+    let hunks: Vec<Value> = outcomes
+        .iter()
+        .enumerate()
+        .map(|(i, o)| json!({ "index": i, "applied": o.error.is_none(), "offset": o.offset, "error": o.error }))
+        .collect();
+    let report = json!({ "path": file.target(), "status": status, "hunks": hunks });
+
+    let mut changes = Vec::new();
+    match (source, target) {
+        (Some(src), Some(dst)) if src != dst => {
+            changes.push(Change {
+                before: read_existing(&dst)?,
+                path: dst,
+                after: patched,
+            });
+            changes.push(Change {
+                path: src,
+                before: Some(original),
+                after: None,
+            });
+        }
+        (src, dst) => {
+            let path = dst.or(src).expect("a file patch always names a path");
+            let before = if file.old_path.is_some() {
+                Some(original)
+            } else {
+                None
+            };
+            changes.push(Change {
+                path,
+                before,
+                after: patched,
+            });
+        }
+    }
+    Ok((changes, report, ok))
+}
+
+fn apply_patch(ctx: &Context, args: Value) -> Result<Value> {
+    let args: ApplyPatchArgs = parse_args(args)?;
+    let files = patch::parse(&args.patch)?;
+
+    let mut changes = Vec::new();
+    let mut reports = Vec::new();
+    let mut all_ok = true;
+    for file in &files {
+        let (file_changes, report, ok) = plan(ctx, file, &args.expected_hashes)?;
+        changes.extend(file_changes);
+        reports.push(report);
+        all_ok &= ok;
+    }
+    if !all_ok {
+        return Ok(json!({ "applied": false, "files": reports }));
+    }
+    ctx.state.quotas.check_files(changes.len())?;
+    for change in &changes {
+        if let Some(after) = &change.after {
+            ctx.state.quotas.check_write(after.len())?;
+        }
+    }
+    if args.dry_run {
+        let diff: String = changes
+            .iter()
+            .map(|c| {
+                unified_diff(
+                    &ctx.workspace.display_path(&c.path),
+                    c.before.as_deref().unwrap_or(""),
+                    c.after.as_deref().unwrap_or(""),
+                )
+            })
+            .collect();
+        return Ok(json!({ "applied": false, "dry_run": true, "files": reports, "diff": diff }));
+    }
+
+    // Renames also remove their source, but lose nothing.
+    let deleted: Vec<&Value> = reports
+        .iter()
+        .filter(|r| r["status"] == "deleted")
+        .map(|r| &r["path"])
+        .collect();
+    if !deleted.is_empty() {
+        let action = format!("apply_patch:{}", digest::of_bytes(args.patch.as_bytes()));
+        let token = args.confirmation_token.as_deref();
+        let summary = json!({ "deletes": deleted });
+        if let Some(mut pending) = require_confirmation(ctx, &action, token, summary)? {
+            pending["applied"] = json!(false);
+            pending["files"] = json!(reports);
+            return Ok(pending);
+        }
+    }
+
+    // Write everything; on the first failure restore what was already written.
+    for (index, change) in changes.iter().enumerate() {
+        if let Err(e) = write_state(&change.path, &change.after) {
+            for done in changes[..index].iter().rev() {
+                let _ = write_state(&done.path, &done.before);
+            }
+            bail!(
+                "Failed to write {}: {} (changes rolled back)",
+                change.path.display(),
+                e
+            );
+        }
+    }
+    for change in changes {
+        ctx.transfer
+            .wrote(change.after.as_ref().map_or(0, String::len));
+        let before = change.before.map(String::into_bytes);
+        ctx.state
+            .backups
+            .record(&change.path, "apply_patch", before);
+    }
+    Ok(json!({ "applied": true, "files": reports }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_failed_hunk_leaves_all_files_untouched() {
+        let env = TestEnv::new("apply-patch");
+        env.write("a.txt", "one\ntwo\n");
+        env.write("b.txt", "alpha\n");
+        let ctx = env.ctx();
+
+        let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n one\n-two\n+2\n\
+                    --- a/b.txt\n+++ b/b.txt\n@@ -1 +1 @@\n-beta\n+gamma\n";
+        let result = apply_patch(&ctx, json!({ "patch": diff })).unwrap();
+        assert_eq!(result["applied"], false);
+        assert_eq!(result["files"][0]["hunks"][0]["applied"], true);
+        assert_eq!(result["files"][1]["hunks"][0]["applied"], false);
+        assert_eq!(
+            fs::read_to_string(env.path().join("a.txt")).unwrap(),
+            "one\ntwo\n"
+        );
+
+        let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n one\n-two\n+2\n";
+        let result = apply_patch(&ctx, json!({ "patch": diff })).unwrap();
+        assert_eq!(result["applied"], true);
+        assert_eq!(
+            fs::read_to_string(env.path().join("a.txt")).unwrap(),
+            "one\n2\n"
+        );
+    }
+
+    #[test]
+    fn test_deleting_patch_needs_confirmation() {
+        let env = TestEnv::new("apply-patch-delete");
+        env.write("old.txt", "bye\n");
+        let ctx = env.ctx();
+
+        let diff = "--- a/old.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-bye\n";
+        let first = apply_patch(&ctx, json!({ "patch": diff })).unwrap();
+        assert_eq!(first["applied"], false);
+        assert_eq!(first["summary"]["deletes"], json!(["old.txt"]));
+        assert!(env.path().join("old.txt").exists());
+
+        let token = first["confirmation_token"].clone();
+        let second =
+            apply_patch(&ctx, json!({ "patch": diff, "confirmation_token": token })).unwrap();
+        assert_eq!(second["applied"], true);
+        assert!(!env.path().join("old.txt").exists());
+    }
+}
@@ -0,0 +1,337 @@
+use anyhow::{bail, Context as _, Result};
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+
+use super::{parse_args, Context, Tool};
+use crate::mime;
+use crate::protocol::ToolAnnotations;
+use crate::text;
+
+/// Upper bound on lines returned by one `read_lines` call.
+const MAX_LINES_PER_CALL: usize = 10_000;
+
+/// Upper bound on bytes returned by one `read_bytes` call.
+const MAX_BYTES_PER_CALL: u64 = 1024 * 1024;
+
+/// Lines `head_file` returns when neither `lines` nor `bytes` is given.
+const DEFAULT_HEAD_LINES: usize = 20;
+
+pub fn tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "read_lines",
+            description: "Read an inclusive, 1-based range of lines from a text file without \
+                          loading the whole file. Useful for paging through large logs.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string" },
+                    "start": { "type": "integer", "minimum": 1, "description": "First line (1-based)" },
+                    "end": { "type": "integer", "minimum": 1, "description": "Last line, inclusive; defaults to start + 199" }
+                },
+                "required": ["path", "start"]
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: read_lines,
+        },
+        Tool {
+            name: "read_bytes",
+            description: "Read a byte range from a file. Returns UTF-8 text when the range \
+                          decodes cleanly, otherwise base64.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string" },
+                    "offset": { "type": "integer", "minimum": 0, "default": 0 },
+                    "length": { "type": "integer", "minimum": 1, "maximum": MAX_BYTES_PER_CALL }
+                },
+                "required": ["path", "length"]
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: read_bytes,
+        },
+        Tool {
+            name: "head_file",
+            description: "Preview the start of a file: its first `lines` lines, 20 by default, \
+                          or its first `bytes` bytes. The charset is detected and the text \
+                          decoded as `read_file` does; binary content comes back base64-encoded \
+                          with its MIME type. Cheap enough to call on many candidates.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string" },
+                    "lines": { "type": "integer", "minimum": 1, "default": DEFAULT_HEAD_LINES },
+                    "bytes": { "type": "integer", "minimum": 1, "maximum": MAX_BYTES_PER_CALL }
+                },
+                "required": ["path"]
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: head_file,
+        },
+    ]
+}
+
+#[derive(Deserialize)]
+struct ReadLinesArgs {
+    path: String,
+    start: usize,
+    #[serde(default)]
+    end: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct ReadBytesArgs {
+    path: String,
+    #[serde(default)]
+    offset: u64,
+    length: u64,
+}
+
+#[derive(Deserialize)]
+struct HeadArgs {
+    path: String,
+    #[serde(default)]
+    lines: Option<usize>,
+    #[serde(default)]
+    bytes: Option<u64>,
+}
+
+fn read_lines(ctx: &Context, args: Value) -> Result<Value> {
+    let args: ReadLinesArgs = parse_args(args)?;
+    if args.start == 0 {
+        bail!("start is 1-based and must be at least 1");
+    }
+    let end = args.end.unwrap_or(args.start + 199);
+    if end < args.start {
+        bail!("end ({}) must not be before start ({})", end, args.start);
+    }
+    let end = end.min(args.start + MAX_LINES_PER_CALL - 1);
+
+    let path = ctx.workspace.resolve(&args.path)?;
+    let file = File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut reader = BufReader::new(file);
+
+    let mut lines = Vec::new();
+    let mut buf = Vec::new();
+    let mut number = 0;
+    let mut eof = false;
+    while number < end {
+        ctx.cancel.check()?;
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            eof = true;
+            break;
+        }
+        ctx.transfer.read(buf.len());
+        number += 1;
+        if number >= args.start {
+            let line = String::from_utf8_lossy(&buf);
+            lines.push(line.trim_end_matches(['\n', '\r']).to_string());
+        }
+    }
+    if !eof {
+        eof = reader.fill_buf()?.is_empty();
+    }
+
+    Ok(json!({
+        "path": args.path,
+        "start": args.start,
+        "end": args.start + lines.len().saturating_sub(1),
+        "lines": lines,
+        "eof": eof,
+    }))
+}
+
+fn read_bytes(ctx: &Context, args: Value) -> Result<Value> {
+    let args: ReadBytesArgs = parse_args(args)?;
+    if args.length > MAX_BYTES_PER_CALL {
+        bail!(
+            "length may not exceed {} bytes per call",
+            MAX_BYTES_PER_CALL
+        );
+    }
+    let path = ctx.workspace.resolve(&args.path)?;
+    let mut file =
+        File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let file_size = file.metadata()?.len();
+    file.seek(SeekFrom::Start(args.offset))?;
+
+    let mut data = Vec::new();
+    file.take(args.length).read_to_end(&mut data)?;
+    ctx.transfer.read(data.len());
+
+    let mut result = json!({
+        "path": args.path,
+        "offset": args.offset,
+        "length": data.len(),
+        "file_size": file_size,
+        "eof": args.offset + data.len() as u64 >= file_size,
+    });
+    match String::from_utf8(data) {
+        Ok(text) => {
+            result["encoding"] = json!("utf-8");
+            result["content"] = json!(text);
+        }
+        Err(e) => {
+            result["encoding"] = json!("base64");
+            result["content"] =
+                json!(base64::engine::general_purpose::STANDARD.encode(e.into_bytes()));
+        }
+    }
+    Ok(result)
+}
+
+/// Reads from `file` until `lines` `\n` bytes have been seen or `limit`
+/// bytes read.
+fn read_head(file: File, lines: usize, limit: u64) -> Result<Vec<u8>> {
+    let mut reader = BufReader::new(file.take(limit));
+    let mut head = Vec::new();
+    let mut seen = 0;
+    while seen < lines {
+        let read = reader.read_until(b'\n', &mut head)?;
+        if read == 0 {
+            break;
+        }
+        seen += 1;
+    }
+    Ok(head)
+}
+
+fn head_file(ctx: &Context, args: Value) -> Result<Value> {
+    let args: HeadArgs = parse_args(args)?;
+    if args.lines.is_some() && args.bytes.is_some() {
+        bail!("Pass either lines or bytes, not both");
+    }
+    if args.lines == Some(0) || args.bytes == Some(0) {
+        bail!("lines and bytes must be at least 1");
+    }
+    let limit = args
+        .bytes
+        .unwrap_or(MAX_BYTES_PER_CALL)
+        .min(MAX_BYTES_PER_CALL);
+    let lines = args.lines.unwrap_or(match args.bytes {
+        Some(_) => usize::MAX,
+        None => DEFAULT_HEAD_LINES,
+    });
+
+    let path = ctx.workspace.resolve(&args.path)?;
+    let mut file =
+        File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let size = file.metadata()?.len();
+    // Classified by a full sample, so a few bytes of an image are not taken
+    // for text.
+    let mut sample = Vec::new();
+    (&file)
+        .take(text::SNIFF_BYTES as u64)
+        .read_to_end(&mut sample)?;
+    file.rewind()?;
+    let mut head = read_head(file, lines, limit)?;
+    ctx.transfer.read(head.len().max(sample.len()));
+    let truncated = (head.len() as u64) < size;
+    // Leave out a character cut in half by the limit rather than have it
+    // spoil charset detection. UTF-16 stops at the first byte of a `\n`.
+    if truncated && text::bom_encoding(&head).is_some_and(|bom| bom.starts_with("utf-16")) {
+        head.truncate(head.len() & !1);
+    } else if truncated {
+        if let Err(e) = std::str::from_utf8(&head) {
+            if e.error_len().is_none() {
+                head.truncate(e.valid_up_to());
+            }
+        }
+    }
+
+    let sniffed = mime::sniff(&sample);
+    let decoded = match sniffed {
+        Some(_) => None,
+        None if text::is_binary(&sample) => None,
+        None => text::decode(&head),
+    };
+    Ok(match decoded {
+        Some(decoded) => {
+            let mut content: Vec<&str> = decoded.text.split_inclusive('\n').collect();
+            content.truncate(lines);
+            json!({
+                "path": args.path,
+                "size": size,
+                "encoding": "utf-8",
+                "charset": decoded.label(),
+                "bom": decoded.bom,
+                "line_endings": text::detect_line_endings(&head),
+                "lines": content.len(),
+                "content": content.concat(),
+                "truncated": truncated,
+            })
+        }
+        None => json!({
+            "path": args.path,
+            "size": size,
+            "mime_type": sniffed.unwrap_or_else(|| mime::guess_from_path(&path)),
+            "encoding": "base64",
+            "bytes": head.len(),
+            "content": base64::engine::general_purpose::STANDARD.encode(&head),
+            "truncated": truncated,
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_read_line_and_byte_ranges() {
+        let env = TestEnv::new("read-range");
+        let log: String = (1..=50).map(|i| format!("line {}\r\n", i)).collect();
+        env.write("app.log", &log);
+        env.write("blob.bin", [0xFFu8, 0x00, 0x01]);
+        let ctx = env.ctx();
+
+        let result =
+            read_lines(&ctx, json!({ "path": "app.log", "start": 48, "end": 60 })).unwrap();
+        assert_eq!(result["lines"], json!(["line 48", "line 49", "line 50"]));
+        assert_eq!(result["end"], 50);
+        assert_eq!(result["eof"], true);
+
+        let result =
+            read_bytes(&ctx, json!({ "path": "app.log", "offset": 8, "length": 6 })).unwrap();
+        assert_eq!(result["content"], "line 2");
+        assert_eq!(result["eof"], false);
+
+        let result = read_bytes(&ctx, json!({ "path": "blob.bin", "length": 3 })).unwrap();
+        assert_eq!(result["encoding"], "base64");
+        assert_eq!(result["content"], "/wAB");
+    }
+
+    #[test]
+    fn test_head_file_previews_lines_or_bytes() {
+        let env = TestEnv::new("head");
+        let log: String = (1..=50).map(|i| format!("línea {}\n", i)).collect();
+        env.write("app.log", &log);
+        env.write("logo.png", b"\x89PNG\r\n\x1a\n\0\x01");
+        let ctx = env.ctx();
+
+        let result = head_file(&ctx, json!({ "path": "app.log", "lines": 2 })).unwrap();
+        assert_eq!(result["content"], "línea 1\nlínea 2\n");
+        assert_eq!(
+            (result["charset"].as_str(), result["truncated"].as_bool()),
+            (Some("utf-8"), Some(true))
+        );
+        let result = head_file(&ctx, json!({ "path": "app.log" })).unwrap();
+        assert_eq!(result["lines"], 20);
+
+        // The 'í' straddles byte 2 and is left out whole.
+        let result = head_file(&ctx, json!({ "path": "app.log", "bytes": 2 })).unwrap();
+        assert_eq!(result["content"], "l");
+
+        let result = head_file(&ctx, json!({ "path": "logo.png", "bytes": 4 })).unwrap();
+        assert_eq!(result["encoding"], "base64");
+        assert!(head_file(&ctx, json!({ "path": "app.log", "lines": 1, "bytes": 1 })).is_err());
+    }
+}
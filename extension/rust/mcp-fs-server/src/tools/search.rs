@@ -0,0 +1,163 @@
+use anyhow::{Context as _, Result};
+use regex::RegexBuilder;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+
+use super::{include_ignored_schema, parse_args, Context, Tool};
+use crate::protocol::ToolAnnotations;
+use crate::text;
+
+const DEFAULT_MAX_MATCHES: usize = 200;
+
+/// Lines longer than this are truncated in results to keep responses small.
+const MAX_LINE_CHARS: usize = 500;
+
+pub fn tools() -> Vec<Tool> {
+    vec![Tool {
+        name: "search_content",
+        description: "Search file contents with a regular expression, honoring .gitignore and \
+                      .ignore unless `include_ignored` is set. \
+                      Binary files are skipped. Returns matching lines with optional context.",
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "pattern": { "type": "string", "description": "Regular expression (Rust regex syntax)" },
+                "path": { "type": "string", "description": "Directory or file to search; defaults to all roots" },
+                "case_insensitive": { "type": "boolean", "default": false },
+                "context": { "type": "integer", "minimum": 0, "default": 0, "description": "Lines of context before and after each match" },
+                "max_matches": { "type": "integer", "minimum": 1, "default": DEFAULT_MAX_MATCHES },
+                "include_ignored": include_ignored_schema()
+            },
+            "required": ["pattern"]
+        }),
+        mutates: false,
+        annotations: ToolAnnotations::READ_ONLY,
+        handler: search_content,
+    }]
+}
+
+#[derive(Deserialize)]
+struct SearchArgs {
+    pattern: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    case_insensitive: bool,
+    #[serde(default)]
+    context: usize,
+    #[serde(default)]
+    max_matches: Option<usize>,
+    #[serde(default)]
+    include_ignored: bool,
+}
+
+fn search_content(ctx: &Context, args: Value) -> Result<Value> {
+    let args: SearchArgs = parse_args(args)?;
+    let regex = RegexBuilder::new(&args.pattern)
+        .case_insensitive(args.case_insensitive)
+        .build()
+        .with_context(|| format!("Invalid regex: {}", args.pattern))?;
+    let max_matches = args.max_matches.unwrap_or(DEFAULT_MAX_MATCHES).max(1);
+
+    let mut matches = Vec::new();
+    let mut files_searched = 0;
+    let mut truncated = false;
+
+    'walk: for root in ctx.workspace.search_roots(args.path.as_deref())? {
+        for entry in ctx.workspace.walker(&root, args.include_ignored).build() {
+            ctx.cancel.check()?;
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let bytes = match fs::read(entry.path()) {
+                Ok(bytes) => bytes,
+                Err(_) => continue,
+            };
+            ctx.transfer.read(bytes.len());
+            if text::is_binary(&bytes) {
+                continue;
+            }
+            files_searched += 1;
+            ctx.progress.report(files_searched, None, "files searched");
+
+            let text = String::from_utf8_lossy(&bytes);
+            let lines: Vec<&str> = text.lines().collect();
+            for (index, line) in lines.iter().enumerate() {
+                let Some(found) = regex.find(line) else {
+                    continue;
+                };
+                if matches.len() >= max_matches {
+                    truncated = true;
+                    break 'walk;
+                }
+                let before = &lines[index.saturating_sub(args.context)..index];
+                let after = &lines[index + 1..(index + 1 + args.context).min(lines.len())];
+                matches.push(json!({
+                    "path": ctx.workspace.display_path(entry.path()),
+                    "line": index + 1,
+                    "column": line[..found.start()].chars().count() + 1,
+                    "text": clip(line),
+                    "before": before.iter().map(|l| clip(l)).collect::<Vec<_>>(),
+                    "after": after.iter().map(|l| clip(l)).collect::<Vec<_>>(),
+                }));
+            }
+        }
+    }
+
+    Ok(json!({
+        "matches": matches,
+        "files_searched": files_searched,
+        "truncated": truncated,
+    }))
+}
+
+fn clip(line: &str) -> String {
+    if line.chars().count() <= MAX_LINE_CHARS {
+        line.to_string()
+    } else {
+        let mut clipped: String = line.chars().take(MAX_LINE_CHARS).collect();
+        clipped.push('…');
+        clipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_search_with_context_skips_binary_and_ignored() {
+        let env = TestEnv::new("search");
+        env.write(".ignore", "target/\n");
+        env.write("src.rs", "fn a() {}\nfn needle() {}\nfn b() {}\n");
+        env.write("target/out.rs", "fn needle() {}\n");
+        env.write("blob.bin", b"needle\0\x01");
+        let ctx = env.ctx();
+
+        let result = search_content(
+            &ctx,
+            json!({ "pattern": "NEEDLE", "case_insensitive": true, "context": 1 }),
+        )
+        .unwrap();
+        let matches = result["matches"].as_array().unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0]["path"], "src.rs");
+        assert_eq!(matches[0]["line"], 2);
+        assert_eq!(matches[0]["column"], 4);
+        assert_eq!(matches[0]["before"], json!(["fn a() {}"]));
+        assert_eq!(matches[0]["after"], json!(["fn b() {}"]));
+
+        let result = search_content(
+            &ctx,
+            json!({ "pattern": "needle", "include_ignored": true }),
+        )
+        .unwrap();
+        assert_eq!(result["matches"].as_array().unwrap().len(), 2);
+    }
+}
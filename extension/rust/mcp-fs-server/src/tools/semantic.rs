@@ -0,0 +1,409 @@
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{parse_args, Context, Tool};
+use crate::cancel::Cancelled;
+use crate::error::{ErrorCode, Failure};
+use crate::progress::Progress;
+use crate::protocol::ToolAnnotations;
+use crate::semantic::{plan_index, IndexRequest, PROGRESS_NOTIFICATION};
+
+const DEFAULT_LIMIT: usize = 5;
+const MAX_LIMIT: usize = 100;
+/// How often a waiting index_workspace call checks for cancellation.
+const CANCEL_POLL: Duration = Duration::from_millis(100);
+
+pub fn tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "semantic_search",
+            description: "Search the workspace by meaning: the query is embedded and matched \
+                      against a Qdrant collection of code chunks. Returns file paths, line \
+                      ranges, chunk text, and similarity scores.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Natural-language description of the code to find" },
+                    "collection": { "type": "string", "description": "Qdrant collection; defaults to QDRANT_COLLECTION" },
+                    "limit": { "type": "integer", "minimum": 1, "maximum": MAX_LIMIT, "default": DEFAULT_LIMIT }
+                },
+                "required": ["query"]
+            }),
+            mutates: false,
+            annotations: ToolAnnotations {
+                open_world_hint: true,
+                ..ToolAnnotations::READ_ONLY
+            },
+            handler: semantic_search,
+        },
+        Tool {
+            name: "index_workspace",
+            description: "Chunk the workspace's text files, embed each chunk, and upsert them \
+                          into a Qdrant collection for semantic_search. Runs in the background \
+                          and reports progress as notifications/index_workspace/progress. Files \
+                          whose content is unchanged since the last run are skipped, so an \
+                          interrupted job can simply be started again. With `watch`, the job \
+                          keeps following the tree and re-embeds changed files (and drops \
+                          deleted ones) until stop_index_workspace is called. With `wait`, \
+                          the call instead returns once the initial pass is done, reporting \
+                          notifications/progress along the way if given a progress token.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Directory to index; defaults to every root" },
+                    "collection": { "type": "string", "description": "Qdrant collection; defaults to QDRANT_COLLECTION" },
+                    "force": { "type": "boolean", "default": false, "description": "Re-embed files even if they are unchanged" },
+                    "watch": { "type": "boolean", "default": false, "description": "Keep the collection current as files change" },
+                    "wait": { "type": "boolean", "default": false, "description": "Return after the initial pass with its summary" }
+                }
+            }),
+            mutates: true,
+            annotations: ToolAnnotations {
+                idempotent_hint: true,
+                open_world_hint: true,
+                ..ToolAnnotations::ADDITIVE
+            },
+            handler: index_workspace,
+        },
+        Tool {
+            name: "stop_index_workspace",
+            description: "Stop an index_workspace job, including one that is watching for changes.",
+            input_schema: json!({
+                "type": "object",
+                "properties": { "job_id": { "type": "string" } },
+                "required": ["job_id"]
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: stop_index_workspace,
+        },
+    ]
+}
+
+#[derive(Deserialize)]
+struct SemanticSearchArgs {
+    query: String,
+    #[serde(default)]
+    collection: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+fn semantic_search(ctx: &Context, args: Value) -> Result<Value> {
+    let args: SemanticSearchArgs = parse_args(args)?;
+    let Some(backend) = &ctx.state.semantic else {
+        bail!("Semantic search is not configured");
+    };
+    if args.query.trim().is_empty() {
+        bail!("query must not be empty");
+    }
+    let collection = args.collection.as_deref().unwrap_or(&backend.collection);
+    let limit = args.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let vector = backend.embedder.embed(&args.query)?;
+    let points = backend.qdrant.search(collection, &vector, limit)?;
+    // Payload fields follow the TypeScript indexer's camelCase names.
+    let results: Vec<Value> = points
+        .into_iter()
+        .map(|point| {
+            json!({
+                "id": point.id,
+                "path": point.payload["filePath"],
+                "start_line": point.payload["startLine"],
+                "end_line": point.payload["endLine"],
+                "content": point.payload["content"],
+                "score": point.score,
+            })
+        })
+        .collect();
+    Ok(json!({ "collection": collection, "results": results }))
+}
+
+#[derive(Deserialize)]
+struct IndexWorkspaceArgs {
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    collection: Option<String>,
+    #[serde(default)]
+    force: bool,
+    #[serde(default)]
+    watch: bool,
+    #[serde(default)]
+    wait: bool,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+fn index_workspace(ctx: &Context, args: Value) -> Result<Value> {
+    let args: IndexWorkspaceArgs = parse_args(args)?;
+    let Some(backend) = &ctx.state.semantic else {
+        bail!("Semantic search is not configured");
+    };
+    let collection = args
+        .collection
+        .unwrap_or_else(|| backend.collection.clone());
+    let roots = ctx.workspace.search_roots(args.path.as_deref())?;
+    if args.dry_run {
+        return plan_index(backend, &collection, ctx.workspace, &roots, args.force);
+    }
+    let (waiter, initial_pass) = match args.wait {
+        true => {
+            let (tx, rx) = mpsc::channel();
+            (Some(tx), Some(rx))
+        }
+        false => (None, None),
+    };
+    let job_id = ctx.state.indexing.spawn(IndexRequest {
+        backend: Arc::clone(backend),
+        collection: collection.clone(),
+        roots,
+        workspace: ctx.workspace.clone(),
+        force: args.force,
+        watch: args.watch,
+        outbound: ctx.state.outbound.clone(),
+        reporter: match args.wait {
+            true => ctx.progress.clone(),
+            false => Progress::default(),
+        },
+        initial_pass: waiter,
+    });
+    let mut result = json!({
+        "job_id": job_id,
+        "collection": collection,
+        "notification": PROGRESS_NOTIFICATION,
+    });
+    if let Some(rx) = initial_pass {
+        // Cancelling the wait stops the job too, since the caller gave up on it.
+        let summary = loop {
+            match rx.recv_timeout(CANCEL_POLL) {
+                Ok(summary) => break summary,
+                Err(RecvTimeoutError::Timeout) if ctx.cancel.is_cancelled() => {
+                    ctx.state.indexing.stop(&job_id);
+                    return Err(Cancelled.into());
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    bail!("Index job ended without reporting its initial pass")
+                }
+            }
+        };
+        result["initial_pass"] = summary;
+    }
+    Ok(result)
+}
+
+#[derive(Deserialize)]
+struct StopArgs {
+    job_id: String,
+}
+
+fn stop_index_workspace(ctx: &Context, args: Value) -> Result<Value> {
+    let args: StopArgs = parse_args(args)?;
+    if !ctx.state.indexing.stop(&args.job_id) {
+        bail!(Failure::new(
+            ErrorCode::NotFound,
+            format!("No running index job with id {}", args.job_id)
+        ));
+    }
+    Ok(json!({ "job_id": args.job_id, "stopped": true }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outbound::Outbound;
+    use crate::semantic::{Backend, Embedder, Qdrant};
+    use crate::test_support::{mock_http, TestEnv};
+    use std::collections::BTreeSet;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[test]
+    fn test_embeds_query_and_maps_payloads() {
+        let url = mock_http(|_, path, body| match path {
+            "/api/embeddings" => {
+                assert_eq!(body["prompt"], "parse config");
+                json!({ "embedding": [0.1, 0.2] })
+            }
+            "/collections/code/points/search" => {
+                assert_eq!(body["limit"], 2);
+                json!({ "result": [{ "id": 1, "score": 0.9, "payload": {
+                    "filePath": "src/config.rs", "startLine": 10, "endLine": 20,
+                    "content": "fn parse() {}" } }] })
+            }
+            other => panic!("unexpected request to {}", other),
+        });
+        let mut env = TestEnv::new("semantic");
+        env.state.semantic = Some(Arc::new(Backend {
+            qdrant: Qdrant::new(&url, None),
+            embedder: Embedder::new(&url, "test-model"),
+            collection: "code".into(),
+        }));
+
+        let result =
+            semantic_search(&env.ctx(), json!({ "query": "parse config", "limit": 2 })).unwrap();
+        assert_eq!(result["results"][0]["path"], "src/config.rs");
+        assert_eq!(result["results"][0]["start_line"], 10);
+    }
+
+    #[test]
+    fn test_index_workspace_upserts_chunks_and_skips_unchanged_files() {
+        let stored = Arc::new(Mutex::new(None::<Value>));
+        let store = Arc::clone(&stored);
+        let url = mock_http(move |_, path, body| match path {
+            "/api/embeddings" => json!({ "embedding": [0.5, 0.5, 0.0] }),
+            "/collections/code/exists" => json!({ "result": { "exists": true } }),
+            "/collections/code/points/scroll" => {
+                let points: Vec<Value> = store.lock().unwrap().iter().cloned().collect();
+                json!({ "result": { "points": points } })
+            }
+            "/collections/code/points/delete" => json!({ "result": {} }),
+            "/collections/code/points" => {
+                *store.lock().unwrap() = Some(body["points"][0].clone());
+                json!({ "result": {} })
+            }
+            other => panic!("unexpected request to {}", other),
+        });
+        let mut env = TestEnv::new("index-workspace");
+        env.write(
+            "src/lib.rs",
+            "pub fn answer() -> u32 {\n    // the answer to everything\n    42\n}\n",
+        );
+        let (outbound, rx) = Outbound::channel();
+        env.state.outbound = outbound;
+        env.state.semantic = Some(Arc::new(Backend {
+            qdrant: Qdrant::new(&url, None),
+            embedder: Embedder::new(&url, "test-model"),
+            collection: "code".into(),
+        }));
+        let ctx = env.ctx();
+
+        let finished = |rx: &std::sync::mpsc::Receiver<String>| loop {
+            let message: Value =
+                serde_json::from_str(&rx.recv_timeout(Duration::from_secs(10)).unwrap()).unwrap();
+            if message["params"]["finished"].is_string() {
+                return message["params"].clone();
+            }
+        };
+        let planned = index_workspace(&ctx, json!({ "dry_run": true })).unwrap();
+        assert_eq!(
+            planned["would_index"],
+            json!([{ "path": "src/lib.rs", "chunks": 1 }])
+        );
+        assert!(stored.lock().unwrap().is_none());
+
+        index_workspace(&ctx, json!({})).unwrap();
+        let first = finished(&rx);
+        assert_eq!(first["finished"], "completed");
+        assert_eq!(first["files_indexed"], 1);
+        let point = stored.lock().unwrap().clone().unwrap();
+        assert_eq!(point["payload"]["filePath"], "src/lib.rs");
+        assert_eq!(point["payload"]["startLine"], 1);
+
+        index_workspace(&ctx, json!({})).unwrap();
+        let second = finished(&rx);
+        assert_eq!(second["files_indexed"], 0);
+        assert_eq!(second["files_skipped"], 1);
+    }
+
+    #[test]
+    fn test_watching_job_follows_edits_and_deletions() {
+        // Tracks which files have points, as Qdrant would.
+        let indexed = Arc::new(Mutex::new(BTreeSet::<String>::new()));
+        let files = Arc::clone(&indexed);
+        let url = mock_http(move |_, path, body| {
+            let mut files = files.lock().unwrap();
+            match path {
+                "/api/embeddings" => json!({ "embedding": [0.5, 0.5] }),
+                "/collections/code/exists" => json!({ "result": { "exists": true } }),
+                "/collections/code/points/scroll" => {
+                    let points: Vec<Value> = files
+                        .iter()
+                        .map(|f| json!({ "payload": { "filePath": f } }))
+                        .collect();
+                    json!({ "result": { "points": points, "next_page_offset": null } })
+                }
+                "/collections/code/points/delete" => {
+                    let matched = &body["filter"]["must"][0]["match"];
+                    match matched["any"].as_array() {
+                        Some(any) => any.iter().for_each(|f| {
+                            files.remove(f.as_str().unwrap());
+                        }),
+                        None => {
+                            files.remove(matched["value"].as_str().unwrap());
+                        }
+                    }
+                    json!({ "result": {} })
+                }
+                "/collections/code/points" => {
+                    for point in body["points"].as_array().unwrap() {
+                        files.insert(point["payload"]["filePath"].as_str().unwrap().into());
+                    }
+                    json!({ "result": {} })
+                }
+                other => panic!("unexpected request to {}", other),
+            }
+        });
+        let mut env = TestEnv::new("index-watch");
+        env.write("notes.md", "# Notes\n\nThe first version of these notes.\n");
+        env.write("docs/a.md", "# A\n\nThe first document.\n");
+        env.write("docs/b.md", "# B\n\nThe second document.\n");
+        let (outbound, rx) = Outbound::channel();
+        env.state.outbound = outbound;
+        env.state.semantic = Some(Arc::new(Backend {
+            qdrant: Qdrant::new(&url, None),
+            embedder: Embedder::new(&url, "test-model"),
+            collection: "code".into(),
+        }));
+        let ctx = env.ctx();
+
+        let next = |rx: &std::sync::mpsc::Receiver<String>, key: &str| loop {
+            let message: Value =
+                serde_json::from_str(&rx.recv_timeout(Duration::from_secs(10)).unwrap()).unwrap();
+            if !message["params"][key].is_null() {
+                return message["params"].clone();
+            }
+        };
+        // Waits for the watching report in which `done` holds.
+        let until = |rx: &std::sync::mpsc::Receiver<String>, done: fn(&Value) -> bool| loop {
+            let report = next(rx, "phase");
+            if done(&report) {
+                return report;
+            }
+        };
+        let started = index_workspace(&ctx, json!({ "watch": true })).unwrap();
+        assert_eq!(next(&rx, "phase")["files_indexed"], 3);
+
+        env.write(
+            "notes.md",
+            "# Notes\n\nA second, longer version of these notes.\n",
+        );
+        assert_eq!(next(&rx, "phase")["files_indexed"], 4);
+        std::fs::remove_file(env.path().join("notes.md")).unwrap();
+        assert_eq!(next(&rx, "phase")["files_removed"], 1);
+
+        std::fs::rename(env.path().join("docs"), env.path().join("guides")).unwrap();
+        let moved = until(&rx, |r| {
+            r["files_indexed"].as_u64() >= Some(6) && r["files_removed"].as_u64() >= Some(3)
+        });
+        assert_eq!(
+            (
+                moved["files_indexed"].as_u64(),
+                moved["files_removed"].as_u64()
+            ),
+            (Some(6), Some(3))
+        );
+        assert_eq!(
+            *indexed.lock().unwrap(),
+            BTreeSet::from(["guides/a.md".to_string(), "guides/b.md".to_string()])
+        );
+
+        stop_index_workspace(&ctx, json!({ "job_id": started["job_id"] })).unwrap();
+        assert_eq!(next(&rx, "finished")["finished"], "stopped");
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::{Context as _, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{format_time, include_ignored_schema, parse_args, Context, Tool};
+use crate::protocol::ToolAnnotations;
+use crate::snapshot::{compare, Manifest};
+
+pub fn tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "snapshot_workspace",
+            description: "Record the size and blake3 hash of every file in the workspace (or \
+                          under `path`) so diff_snapshot can later report what changed. \
+                          Snapshots are kept in memory for this session only.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Directory to snapshot; defaults to every root" },
+                    "include_ignored": include_ignored_schema()
+                }
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: snapshot_workspace,
+        },
+        Tool {
+            name: "diff_snapshot",
+            description: "List files added, removed, or modified since a snapshot_workspace call.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "integer", "description": "Snapshot id returned by snapshot_workspace" }
+                },
+                "required": ["id"]
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: diff_snapshot,
+        },
+    ]
+}
+
+#[derive(Deserialize)]
+struct SnapshotArgs {
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    include_ignored: bool,
+}
+
+fn snapshot_workspace(ctx: &Context, args: Value) -> Result<Value> {
+    let args: SnapshotArgs = parse_args(args)?;
+    let roots = ctx.workspace.search_roots(args.path.as_deref())?;
+    let manifest = Manifest::build(ctx, &roots, args.include_ignored)?;
+    let snapshot = ctx
+        .state
+        .snapshots
+        .record(roots, args.include_ignored, manifest);
+    let files = &snapshot.manifest.files;
+    Ok(json!({
+        "id": snapshot.id,
+        "files": files.len(),
+        "total_bytes": files.values().map(|e| e.size).sum::<u64>(),
+        "taken_at": format_time(snapshot.taken_at),
+        "truncated": snapshot.manifest.truncated,
+    }))
+}
+
+#[derive(Deserialize)]
+struct DiffArgs {
+    id: u64,
+}
+
+fn diff_snapshot(ctx: &Context, args: Value) -> Result<Value> {
+    let args: DiffArgs = parse_args(args)?;
+    let snapshot =
+        ctx.state.snapshots.get(args.id).with_context(|| {
+            format!("No snapshot with id {}; it may have been evicted", args.id)
+        })?;
+    let current = Manifest::build(ctx, &snapshot.roots, snapshot.include_ignored)?;
+    let changes = compare(&snapshot.manifest, &current);
+    let display = |path: &std::path::Path| ctx.workspace.display_path(path);
+    let modified: Vec<Value> = changes
+        .modified
+        .iter()
+        .map(|(path, old, new)| {
+            json!({
+                "path": display(path),
+                "old_size": old.size,
+                "new_size": new.size,
+                "old_blake3": old.blake3,
+                "new_blake3": new.blake3,
+            })
+        })
+        .collect();
+    Ok(json!({
+        "id": snapshot.id,
+        "taken_at": format_time(snapshot.taken_at),
+        "added": changes.added.iter().map(|p| display(p)).collect::<Vec<_>>(),
+        "removed": changes.removed.iter().map(|p| display(p)).collect::<Vec<_>>(),
+        "modified": modified,
+        "unchanged": changes.unchanged,
+        "truncated": snapshot.manifest.truncated || current.truncated,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_diff_reports_changes_since_snapshot() {
+        let env = TestEnv::new("snapshot");
+        env.write("keep.txt", "same");
+        env.write("edit.txt", "before");
+        env.write("gone.txt", "bye");
+        let ctx = env.ctx();
+
+        let snapshot = snapshot_workspace(&ctx, json!({})).unwrap();
+        assert_eq!(snapshot["files"], 3);
+
+        env.write("edit.txt", "after!");
+        env.write("new/file.txt", "hi");
+        std::fs::remove_file(env.path().join("gone.txt")).unwrap();
+        let diff = diff_snapshot(&ctx, json!({ "id": snapshot["id"] })).unwrap();
+        assert_eq!(diff["added"], json!(["new/file.txt"]));
+        assert_eq!(diff["removed"], json!(["gone.txt"]));
+        assert_eq!(diff["modified"][0]["path"], "edit.txt");
+        assert_eq!(diff["unchanged"], 1);
+    }
+}
@@ -0,0 +1,291 @@
+use anyhow::{bail, Context as _, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs::{self, File, Metadata, Permissions};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use super::{format_time, parse_args, Context, Tool};
+use crate::error::Failure;
+use crate::protocol::ToolAnnotations;
+use crate::{mime, text};
+
+pub fn tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "stat",
+            description: "Return metadata for a path: type, size, timestamps, permissions, \
+                          symlink target, and for files the detected encoding and line endings.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "File, directory, or symlink to inspect" }
+                },
+                "required": ["path"]
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: stat,
+        },
+        Tool {
+            name: "set_permissions",
+            description: "Change a path's permissions: Unix `mode` bits given in octal, such as \
+                          \"755\", or the `readonly` attribute, which on Windows is the only \
+                          one there is. Only the read, write, and execute bits can be set. \
+                          With `recursive`, applies to everything under a directory too.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "File or directory to change" },
+                    "mode": { "type": "string", "description": "Octal permission bits, e.g. \"644\"; Unix only" },
+                    "readonly": { "type": "boolean", "description": "Set or clear the read-only attribute" },
+                    "recursive": { "type": "boolean", "default": false }
+                },
+                "required": ["path"]
+            }),
+            mutates: true,
+            annotations: ToolAnnotations {
+                idempotent_hint: true,
+                ..ToolAnnotations::ADDITIVE
+            },
+            handler: set_permissions,
+        },
+    ]
+}
+
+#[derive(Deserialize)]
+struct StatArgs {
+    path: String,
+}
+
+#[cfg(unix)]
+fn mode_string(permissions: &Permissions) -> Option<String> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(format!("{:o}", permissions.mode() & 0o7777))
+}
+
+#[cfg(not(unix))]
+fn mode_string(_permissions: &Permissions) -> Option<String> {
+    None
+}
+
+#[derive(Deserialize)]
+struct PermissionsArgs {
+    path: String,
+    #[serde(default)]
+    mode: Option<String>,
+    #[serde(default)]
+    readonly: Option<bool>,
+    #[serde(default)]
+    recursive: bool,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Parses octal `mode`, refusing setuid, setgid, and sticky bits.
+fn parse_mode(mode: &str) -> Result<u32> {
+    let bits = u32::from_str_radix(mode.trim_start_matches("0o"), 8)
+        .with_context(|| format!("mode must be octal, such as \"755\", not {:?}", mode))?;
+    if bits > 0o777 {
+        bail!("mode {} sets special bits; only 0-777 is allowed", mode);
+    }
+    Ok(bits)
+}
+
+/// `metadata`'s permissions with the requested changes applied.
+fn changed(metadata: &Metadata, mode: Option<u32>, readonly: Option<bool>) -> Result<Permissions> {
+    let mut permissions = metadata.permissions();
+    if let Some(bits) = mode {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            permissions.set_mode(permissions.mode() & !0o777 | bits);
+        }
+        #[cfg(not(unix))]
+        bail!(
+            "mode bits are Unix only; use readonly on this system (asked for {:o})",
+            bits
+        );
+    }
+    if let Some(readonly) = readonly {
+        permissions.set_readonly(readonly);
+    }
+    Ok(permissions)
+}
+
+fn set_permissions(ctx: &Context, args: Value) -> Result<Value> {
+    let args: PermissionsArgs = parse_args(args)?;
+    if args.mode.is_none() && args.readonly.is_none() {
+        bail!("Either mode or readonly is required");
+    }
+    let mode = args.mode.as_deref().map(parse_mode).transpose()?;
+    let root = ctx.workspace.resolve_writable(&args.path)?;
+    if !root.exists() {
+        bail!(Failure::not_found(&args.path));
+    }
+    // Symlinks met on the walk are left out: changing one changes its
+    // target, which may be outside the roots.
+    let paths: Vec<PathBuf> = match args.recursive && root.is_dir() {
+        true => ctx
+            .workspace
+            .walker(&root, true)
+            .hidden(false)
+            .build()
+            .filter_map(|e| e.ok())
+            .filter(|e| !e.path_is_symlink())
+            .map(|e| e.into_path())
+            .collect(),
+        false => vec![root],
+    };
+    ctx.state.quotas.check_files(paths.len())?;
+
+    let mut changes = Vec::new();
+    for path in &paths {
+        ctx.cancel.check()?;
+        let metadata = fs::metadata(path)?;
+        let permissions = changed(&metadata, mode, args.readonly)?;
+        let before = metadata.permissions();
+        let display = ctx.workspace.display_path(path);
+        if !args.dry_run && permissions != before {
+            fs::set_permissions(path, permissions.clone())
+                .with_context(|| format!("Failed to change permissions of {}", display))?;
+        }
+        changes.push(json!({
+            "path": if display.is_empty() { ".".to_string() } else { display },
+            "mode_before": mode_string(&before),
+            "readonly_before": before.readonly(),
+            "mode": mode_string(&permissions),
+            "readonly": permissions.readonly(),
+        }));
+    }
+    Ok(json!({
+        "path": args.path,
+        "dry_run": args.dry_run,
+        "changes": changes,
+    }))
+}
+
+/// Reads at most `text::SNIFF_BYTES` from the start of a file.
+fn read_sample(path: &Path) -> Result<Vec<u8>> {
+    let mut sample = Vec::with_capacity(text::SNIFF_BYTES);
+    File::open(path)?
+        .take(text::SNIFF_BYTES as u64)
+        .read_to_end(&mut sample)?;
+    Ok(sample)
+}
+
+fn stat(ctx: &Context, args: Value) -> Result<Value> {
+    let args: StatArgs = parse_args(args)?;
+    let path = ctx.workspace.resolve_entry(&args.path)?;
+    let link_metadata =
+        fs::symlink_metadata(&path).with_context(|| format!("Not found: {}", path.display()))?;
+    let symlink_target = if link_metadata.is_symlink() {
+        fs::read_link(&path)
+            .ok()
+            .map(|t| t.to_string_lossy().into_owned())
+    } else {
+        None
+    };
+    // Report on what a symlink points at, falling back to the link itself when
+    // dangling or when the target lies outside the roots.
+    let metadata = match ctx.workspace.resolve(&args.path) {
+        Ok(target) => fs::metadata(target).unwrap_or(link_metadata),
+        Err(_) => link_metadata,
+    };
+
+    let kind = if symlink_target.is_some() && !path.exists() {
+        "symlink"
+    } else if metadata.is_dir() {
+        "directory"
+    } else {
+        "file"
+    };
+
+    let mut result = json!({
+        "path": args.path,
+        "type": kind,
+        "size": metadata.len(),
+        "modified": metadata.modified().ok().map(format_time),
+        "accessed": metadata.accessed().ok().map(format_time),
+        "created": metadata.created().ok().map(format_time),
+        "readonly": metadata.permissions().readonly(),
+        "mode": mode_string(&metadata.permissions()),
+        "symlink_target": symlink_target,
+        "access": ctx.workspace.access(&path),
+    });
+
+    if metadata.is_file() {
+        let sample =
+            read_sample(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        ctx.transfer.read(sample.len());
+        result["mime_type"] = json!(mime::guess_from_path(&path));
+        result["encoding"] = json!(text::detect_encoding(&sample));
+        result["line_endings"] = json!(text::detect_line_endings(&sample));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_stat_reports_encoding_and_line_endings() {
+        let env = TestEnv::new("stat");
+        env.write("dos.txt", "one\r\ntwo\r\n");
+        let ctx = env.ctx();
+
+        let result = stat(&ctx, json!({ "path": "dos.txt" })).unwrap();
+        assert_eq!(result["type"], "file");
+        assert_eq!(result["size"], 10);
+        assert_eq!(result["encoding"], "ascii");
+        assert_eq!(result["line_endings"], "crlf");
+        assert!(result["modified"].is_string());
+        assert_eq!(result["access"], json!(["read", "write", "delete"]));
+
+        let result = stat(&ctx, json!({ "path": "." })).unwrap();
+        assert_eq!(result["type"], "directory");
+        assert!(result.get("encoding").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_set_permissions_changes_mode_and_previews_in_dry_run() {
+        let env = TestEnv::new("permissions");
+        let script = env.write("bin/run.sh", "#!/bin/sh\n");
+        env.write("bin/lib.sh", "");
+        let ctx = env.ctx();
+
+        let plan = set_permissions(
+            &ctx,
+            json!({ "path": "bin/run.sh", "mode": "755", "dry_run": true }),
+        )
+        .unwrap();
+        assert_eq!(plan["changes"][0]["mode"], "755");
+        assert_ne!(
+            mode_string(&fs::metadata(&script).unwrap().permissions()).unwrap(),
+            "755"
+        );
+
+        let result = set_permissions(
+            &ctx,
+            json!({ "path": "bin", "readonly": true, "recursive": true }),
+        )
+        .unwrap();
+        assert_eq!(result["changes"].as_array().unwrap().len(), 3);
+        assert!(fs::metadata(&script).unwrap().permissions().readonly());
+        set_permissions(
+            &ctx,
+            json!({ "path": "bin", "mode": "755", "recursive": true }),
+        )
+        .unwrap();
+        assert_eq!(
+            mode_string(&fs::metadata(&script).unwrap().permissions()).unwrap(),
+            "755"
+        );
+
+        assert!(set_permissions(&ctx, json!({ "path": "bin/run.sh", "mode": "4755" })).is_err());
+        assert!(set_permissions(&ctx, json!({ "path": "bin/run.sh", "mode": "rwx" })).is_err());
+    }
+}
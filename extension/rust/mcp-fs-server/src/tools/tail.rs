@@ -0,0 +1,191 @@
+use anyhow::{bail, Context as _, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::time::Duration;
+
+use super::{parse_args, Context, Tool};
+use crate::error::{ErrorCode, Failure};
+use crate::follow::{FollowRequest, TAIL_NOTIFICATION};
+use crate::protocol::ToolAnnotations;
+
+const DEFAULT_LINES: usize = 20;
+const MAX_LINES: usize = 10_000;
+const DEFAULT_FOLLOW_SECONDS: u64 = 60;
+const MAX_FOLLOW_SECONDS: u64 = 3600;
+
+/// Chunk size used when scanning backwards for line breaks.
+const CHUNK: u64 = 8 * 1024;
+
+pub fn tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "tail_file",
+            description: "Return the last N lines of a file. With `follow: true`, lines appended \
+                          afterwards are pushed as `notifications/tail_file/lines` until \
+                          `stop_tail` is called or `follow_seconds` elapse.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string" },
+                    "lines": { "type": "integer", "minimum": 0, "maximum": MAX_LINES, "default": DEFAULT_LINES },
+                    "follow": { "type": "boolean", "default": false },
+                    "follow_seconds": { "type": "integer", "minimum": 1, "maximum": MAX_FOLLOW_SECONDS, "default": DEFAULT_FOLLOW_SECONDS }
+                },
+                "required": ["path"]
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: tail_file,
+        },
+        Tool {
+            name: "stop_tail",
+            description: "Stop following a file started with `tail_file { follow: true }`.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "follow_id": { "type": "string" }
+                },
+                "required": ["follow_id"]
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: stop_tail,
+        },
+    ]
+}
+
+#[derive(Deserialize)]
+struct TailArgs {
+    path: String,
+    #[serde(default)]
+    lines: Option<usize>,
+    #[serde(default)]
+    follow: bool,
+    #[serde(default)]
+    follow_seconds: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct StopTailArgs {
+    follow_id: String,
+}
+
+/// Reads the last `count` lines by scanning backwards from the end.
+fn last_lines(ctx: &Context, file: &mut File, len: u64, count: usize) -> Result<Vec<String>> {
+    let mut buf = Vec::new();
+    let mut pos = len;
+    while pos > 0 {
+        let newlines = buf.iter().filter(|b| **b == b'\n').count();
+        // A trailing newline terminates the last line rather than starting a new one.
+        let needed = if buf.ends_with(b"\n") {
+            count + 1
+        } else {
+            count
+        };
+        if newlines >= needed {
+            break;
+        }
+        let start = pos.saturating_sub(CHUNK);
+        let mut chunk = vec![0; (pos - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        ctx.transfer.read(chunk.len());
+        chunk.extend_from_slice(&buf);
+        buf = chunk;
+        pos = start;
+    }
+    let text = String::from_utf8_lossy(&buf);
+    let lines: Vec<&str> = text.lines().collect();
+    let skip = lines.len().saturating_sub(count);
+    Ok(lines[skip..].iter().map(|l| l.to_string()).collect())
+}
+
+fn tail_file(ctx: &Context, args: Value) -> Result<Value> {
+    let args: TailArgs = parse_args(args)?;
+    let count = args.lines.unwrap_or(DEFAULT_LINES);
+    if count > MAX_LINES {
+        bail!("lines may not exceed {}", MAX_LINES);
+    }
+    let path = ctx.workspace.resolve(&args.path)?;
+    let mut file =
+        File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let len = file.metadata()?.len();
+    let lines = last_lines(ctx, &mut file, len, count)?;
+
+    let mut result = json!({ "path": args.path, "lines": lines, "size": len });
+    if args.follow {
+        let seconds = args
+            .follow_seconds
+            .unwrap_or(DEFAULT_FOLLOW_SECONDS)
+            .clamp(1, MAX_FOLLOW_SECONDS);
+        let follow_id = ctx.state.followers.spawn(FollowRequest {
+            path,
+            display: args.path,
+            offset: len,
+            duration: Duration::from_secs(seconds),
+            outbound: ctx.state.outbound.clone(),
+        });
+        result["follow_id"] = json!(follow_id);
+        result["notification"] = json!(TAIL_NOTIFICATION);
+    }
+    Ok(result)
+}
+
+fn stop_tail(ctx: &Context, args: Value) -> Result<Value> {
+    let args: StopTailArgs = parse_args(args)?;
+    if !ctx.state.followers.stop(&args.follow_id) {
+        bail!(Failure::new(
+            ErrorCode::NotFound,
+            format!("No active follower with id {}", args.follow_id)
+        ));
+    }
+    Ok(json!({ "follow_id": args.follow_id, "stopped": true }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outbound::Outbound;
+    use crate::test_support::TestEnv;
+    use std::io::Write;
+
+    #[test]
+    fn test_tail_returns_last_lines_across_chunks() {
+        let env = TestEnv::new("tail");
+        let log: String = (1..=5000).map(|i| format!("entry {}\n", i)).collect();
+        env.write("big.log", &log);
+        let ctx = env.ctx();
+
+        let result = tail_file(&ctx, json!({ "path": "big.log", "lines": 3 })).unwrap();
+        assert_eq!(
+            result["lines"],
+            json!(["entry 4998", "entry 4999", "entry 5000"])
+        );
+    }
+
+    #[test]
+    fn test_follow_pushes_appended_lines() {
+        let mut env = TestEnv::new("tail-follow");
+        let (outbound, rx) = Outbound::channel();
+        env.state.outbound = outbound;
+        let file = env.write("app.log", "start\n");
+        let ctx = env.ctx();
+
+        let result = tail_file(&ctx, json!({ "path": "app.log", "follow": true })).unwrap();
+        let follow_id = result["follow_id"].as_str().unwrap().to_string();
+        let mut handle = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&file)
+            .unwrap();
+        writeln!(handle, "next line").unwrap();
+
+        let message: Value =
+            serde_json::from_str(&rx.recv_timeout(Duration::from_secs(5)).unwrap()).unwrap();
+        assert_eq!(message["method"], TAIL_NOTIFICATION);
+        assert_eq!(message["params"]["lines"], json!(["next line"]));
+
+        stop_tail(&ctx, json!({ "follow_id": follow_id })).unwrap();
+    }
+}
@@ -0,0 +1,208 @@
+use anyhow::{bail, Context as _, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use super::{parse_args, Context, Tool};
+use crate::atomic;
+use crate::error::{ErrorCode, Failure};
+use crate::protocol::ToolAnnotations;
+use crate::template::{self, Templates};
+
+pub fn tools() -> Vec<Tool> {
+    vec![Tool {
+        name: "create_from_template",
+        description: "Scaffold a file or directory tree from a named template in the server's \
+                      templates directory. `{{name}}` placeholders in file contents and paths \
+                      are replaced from `variables`; `{{name | snake}}` also accepts lower, \
+                      upper, kebab, and pascal. Existing files are left alone unless \
+                      `overwrite` is set. Without `template`, lists the available templates.",
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "template": { "type": "string", "description": "Template name" },
+                "destination": { "type": "string", "description": "File or directory to create" },
+                "variables": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "Values for the template's placeholders, e.g. project_name and module_name"
+                },
+                "overwrite": { "type": "boolean", "default": false }
+            }
+        }),
+        mutates: true,
+        annotations: ToolAnnotations::ADDITIVE,
+        handler: create_from_template,
+    }]
+}
+
+#[derive(Deserialize)]
+struct TemplateArgs {
+    #[serde(default)]
+    template: Option<String>,
+    #[serde(default)]
+    destination: Option<String>,
+    #[serde(default)]
+    variables: BTreeMap<String, String>,
+    #[serde(default)]
+    overwrite: bool,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// A file the template will write.
+struct Planned {
+    display: String,
+    path: PathBuf,
+    content: Vec<u8>,
+}
+
+fn templates<'a>(ctx: &'a Context) -> Result<&'a Templates> {
+    match &ctx.state.templates {
+        Some(templates) => Ok(templates),
+        None => bail!("No templates directory is configured; start the server with --templates"),
+    }
+}
+
+fn create_from_template(ctx: &Context, args: Value) -> Result<Value> {
+    let args: TemplateArgs = parse_args(args)?;
+    let templates = templates(ctx)?;
+    let Some(name) = args.template else {
+        return Ok(json!({ "templates": templates.names() }));
+    };
+    let Some(destination) = args.destination else {
+        bail!("create_from_template requires a destination");
+    };
+
+    // Render everything before touching the disk so a bad placeholder
+    // leaves no half-written tree behind.
+    let mut plan = Vec::new();
+    for file in templates.load(&name)? {
+        let relative = template::render_path(&file.path, &args.variables)?;
+        let display = match relative.as_os_str().is_empty() {
+            true => destination.clone(),
+            false => format!(
+                "{}/{}",
+                destination.trim_end_matches('/'),
+                relative.to_string_lossy().replace('\\', "/")
+            ),
+        };
+        let path = ctx.workspace.resolve_writable(&display)?;
+        let content = match String::from_utf8(file.content) {
+            Ok(text) => template::render(&text, &args.variables)
+                .with_context(|| format!("Failed to render {}", file.path.display()))?
+                .into_bytes(),
+            Err(binary) => binary.into_bytes(),
+        };
+        ctx.state.quotas.check_write(content.len())?;
+        plan.push(Planned {
+            display,
+            path,
+            content,
+        });
+    }
+    ctx.state.quotas.check_files(plan.len())?;
+    let existing: Vec<&str> = plan
+        .iter()
+        .filter(|file| file.path.exists())
+        .map(|file| file.display.as_str())
+        .collect();
+    if !existing.is_empty() && !args.overwrite {
+        bail!(Failure::new(
+            ErrorCode::Conflict,
+            format!(
+                "Refusing to overwrite existing files: {}; pass overwrite to replace them",
+                existing.join(", ")
+            )
+        ));
+    }
+    let files: Vec<Value> = plan
+        .iter()
+        .map(|file| {
+            json!({
+                "path": file.display,
+                "bytes": file.content.len(),
+                "existed": file.path.exists(),
+            })
+        })
+        .collect();
+    if args.dry_run {
+        return Ok(json!({
+            "template": name,
+            "destination": destination,
+            "dry_run": true,
+            "would_write": files,
+        }));
+    }
+
+    for file in &plan {
+        if let Some(parent) = file.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        ctx.state
+            .backups
+            .snapshot(&file.path, "create_from_template")?;
+        atomic::write(&file.path, &file.content)
+            .with_context(|| format!("Failed to write {}", file.path.display()))?;
+        ctx.transfer.wrote(file.content.len());
+    }
+    Ok(json!({
+        "template": name,
+        "destination": destination,
+        "files": files,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_scaffolds_a_tree_with_substitutions() {
+        let mut env = TestEnv::new("template");
+        let dir = std::env::temp_dir().join(format!("mcp-fs-templates-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("crate/src")).unwrap();
+        fs::write(
+            dir.join("crate/Cargo.toml"),
+            "[package]\nname = \"{{project | kebab}}\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("crate/src/{{module | snake}}.rs"),
+            "//! The {{module}} module.\n",
+        )
+        .unwrap();
+        env.state.templates = Some(Arc::new(Templates::new(dir.clone())));
+        let ctx = env.ctx();
+
+        let listing = create_from_template(&ctx, json!({})).unwrap();
+        assert_eq!(listing["templates"], json!(["crate"]));
+        let args = json!({
+            "template": "crate",
+            "destination": "app",
+            "variables": { "project": "MyApp", "module": "HttpClient" }
+        });
+        let result = create_from_template(&ctx, args.clone()).unwrap();
+        assert_eq!(result["files"][1]["path"], "app/src/http_client.rs");
+        let manifest = fs::read_to_string(env.path().join("app/Cargo.toml")).unwrap();
+        assert_eq!(manifest, "[package]\nname = \"my-app\"\n");
+
+        let err = create_from_template(&ctx, args).unwrap_err();
+        assert!(err.to_string().contains("app/Cargo.toml"));
+        assert_eq!(crate::error::classify(&err), ErrorCode::Conflict);
+        let err = create_from_template(
+            &ctx,
+            json!({ "template": "crate", "destination": "other", "variables": {} }),
+        )
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("undefined variables: project"));
+        assert!(!env.path().join("other").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
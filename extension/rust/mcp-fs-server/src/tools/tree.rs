@@ -0,0 +1,158 @@
+use anyhow::{Context as _, Result};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::fs::Metadata;
+
+use super::{format_time, parse_args, Context, Tool};
+use crate::protocol::ToolAnnotations;
+
+const DEFAULT_MAX_DEPTH: usize = 3;
+const DEFAULT_MAX_ENTRIES: usize = 1000;
+
+pub fn tools() -> Vec<Tool> {
+    vec![Tool {
+        name: "directory_tree",
+        description: "Return a nested tree of a directory with type, size, and modification time \
+                      per entry, capped by depth and total entry count. Honors .gitignore.",
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "Directory to describe; defaults to the first root" },
+                "max_depth": { "type": "integer", "minimum": 1, "default": DEFAULT_MAX_DEPTH },
+                "max_entries": { "type": "integer", "minimum": 1, "default": DEFAULT_MAX_ENTRIES }
+            }
+        }),
+        mutates: false,
+        annotations: ToolAnnotations::READ_ONLY,
+        handler: directory_tree,
+    }]
+}
+
+#[derive(Deserialize)]
+struct TreeArgs {
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    max_depth: Option<usize>,
+    #[serde(default)]
+    max_entries: Option<usize>,
+}
+
+fn node(name: String, metadata: Option<&Metadata>) -> Map<String, Value> {
+    let mut node = Map::new();
+    let is_dir = metadata.is_some_and(|m| m.is_dir());
+    node.insert("name".into(), json!(name));
+    node.insert(
+        "type".into(),
+        json!(match metadata {
+            Some(m) if m.is_symlink() => "symlink",
+            _ if is_dir => "directory",
+            _ => "file",
+        }),
+    );
+    if let Some(m) = metadata {
+        if !is_dir {
+            node.insert("size".into(), json!(m.len()));
+        }
+        if let Ok(modified) = m.modified() {
+            node.insert("modified".into(), json!(format_time(modified)));
+        }
+    }
+    if is_dir {
+        node.insert("children".into(), json!([]));
+    }
+    node
+}
+
+/// Pops the innermost open directory and appends it to its parent.
+fn close(stack: &mut Vec<Map<String, Value>>) {
+    let finished = stack.pop().expect("stack never closes the root");
+    if let Some(Value::Array(children)) = stack.last_mut().and_then(|p| p.get_mut("children")) {
+        children.push(Value::Object(finished));
+    }
+}
+
+fn directory_tree(ctx: &Context, args: Value) -> Result<Value> {
+    let args: TreeArgs = parse_args(args)?;
+    let root = ctx.workspace.resolve(args.path.as_deref().unwrap_or("."))?;
+    let metadata =
+        std::fs::metadata(&root).with_context(|| format!("Not found: {}", root.display()))?;
+    if !metadata.is_dir() {
+        anyhow::bail!("Not a directory: {}", root.display());
+    }
+    let max_depth = args.max_depth.unwrap_or(DEFAULT_MAX_DEPTH).max(1);
+    let max_entries = args.max_entries.unwrap_or(DEFAULT_MAX_ENTRIES).max(1);
+
+    let root_name = root
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| root.display().to_string());
+    // `stack[i]` is the open directory at depth `i`; the walk is depth-first
+    // pre-order, so an entry at depth `d` belongs to `stack[d - 1]`.
+    let mut stack = vec![node(root_name, Some(&metadata))];
+    let mut entries = 0;
+    let mut truncated = false;
+
+    let walker = ctx
+        .workspace
+        .walker(&root, false)
+        .max_depth(Some(max_depth))
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+    for entry in walker.filter_map(|e| e.ok()).filter(|e| e.depth() > 0) {
+        if entries >= max_entries {
+            truncated = true;
+            break;
+        }
+        ctx.cancel.check()?;
+        entries += 1;
+        while stack.len() > entry.depth() {
+            close(&mut stack);
+        }
+        let metadata = entry.metadata().ok();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        stack.push(node(name, metadata.as_ref()));
+        if !stack.last().unwrap().contains_key("children") {
+            close(&mut stack);
+        }
+    }
+    while stack.len() > 1 {
+        close(&mut stack);
+    }
+
+    Ok(json!({
+        "tree": stack.pop(),
+        "entries": entries,
+        "truncated": truncated,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_tree_respects_depth_and_nesting() {
+        let env = TestEnv::new("tree");
+        env.write("a/b/c/deep.txt", "deep");
+        env.write("a/file.txt", "12345");
+        env.write("top.txt", "");
+        let ctx = env.ctx();
+
+        let result = directory_tree(&ctx, json!({ "max_depth": 2 })).unwrap();
+        let children = result["tree"]["children"].as_array().unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0]["name"], "a");
+        assert_eq!(children[0]["type"], "directory");
+        let nested = children[0]["children"].as_array().unwrap();
+        assert_eq!(nested[0]["name"], "b");
+        assert_eq!(nested[0]["children"], json!([]));
+        assert_eq!(nested[1]["size"], 5);
+        assert_eq!(children[1]["name"], "top.txt");
+        assert_eq!(result["truncated"], false);
+
+        let capped = directory_tree(&ctx, json!({ "max_entries": 1 })).unwrap();
+        assert_eq!(capped["truncated"], true);
+    }
+}
@@ -0,0 +1,200 @@
+use anyhow::{bail, Context as _, Result};
+use ignore::WalkState;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::{parse_args, Context, Tool};
+use crate::protocol::ToolAnnotations;
+
+const DEFAULT_DEPTH: usize = 1;
+const DEFAULT_MAX_ENTRIES: usize = 100;
+
+pub fn tools() -> Vec<Tool> {
+    vec![Tool {
+        name: "disk_usage",
+        description: "Total the size and file count of a directory and of every directory under \
+                      it down to `depth`, largest first. The walk runs in parallel and counts \
+                      hidden and gitignored files, such as build output, unless \
+                      `include_ignored` is false.",
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "Directory to measure; defaults to the first root" },
+                "depth": { "type": "integer", "minimum": 0, "default": DEFAULT_DEPTH },
+                "include_ignored": {
+                    "type": "boolean",
+                    "default": true,
+                    "description": "Count hidden files and those excluded by .gitignore/.ignore files"
+                },
+                "max_entries": { "type": "integer", "minimum": 1, "default": DEFAULT_MAX_ENTRIES }
+            }
+        }),
+        mutates: false,
+        annotations: ToolAnnotations::READ_ONLY,
+        handler: disk_usage,
+    }]
+}
+
+#[derive(Deserialize)]
+struct UsageArgs {
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    depth: Option<usize>,
+    #[serde(default)]
+    include_ignored: Option<bool>,
+    #[serde(default)]
+    max_entries: Option<usize>,
+}
+
+/// Bytes and files per directory, relative to the measured one.
+type Totals = HashMap<PathBuf, (u64, u64)>;
+
+/// One walker thread's totals, folded into the shared ones when the thread
+/// is done so the threads do not contend on every file.
+struct Tally {
+    local: Totals,
+    shared: Arc<Mutex<Totals>>,
+}
+
+impl Drop for Tally {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        for (dir, (bytes, files)) in mem::take(&mut self.local) {
+            let total = shared.entry(dir).or_default();
+            total.0 += bytes;
+            total.1 += files;
+        }
+    }
+}
+
+impl Tally {
+    /// Adds a file to the directory it is in and each one above it, those
+    /// deeper than `depth` left out.
+    fn add(&mut self, relative: &Path, bytes: u64, depth: usize) {
+        let mut dir = PathBuf::new();
+        let parents: Vec<_> = relative
+            .parent()
+            .into_iter()
+            .flat_map(Path::components)
+            .collect();
+        for i in 0..=parents.len().min(depth) {
+            if i > 0 {
+                dir.push(parents[i - 1]);
+            }
+            let total = self.local.entry(dir.clone()).or_default();
+            total.0 += bytes;
+            total.1 += 1;
+        }
+    }
+}
+
+fn disk_usage(ctx: &Context, args: Value) -> Result<Value> {
+    let args: UsageArgs = parse_args(args)?;
+    let root = ctx.workspace.resolve(args.path.as_deref().unwrap_or("."))?;
+    let metadata =
+        std::fs::metadata(&root).with_context(|| format!("Not found: {}", root.display()))?;
+    if !metadata.is_dir() {
+        bail!("Not a directory: {}", root.display());
+    }
+    let depth = args.depth.unwrap_or(DEFAULT_DEPTH);
+    let max_entries = args.max_entries.unwrap_or(DEFAULT_MAX_ENTRIES).max(1);
+    let include_ignored = args.include_ignored.unwrap_or(true);
+
+    let shared = Arc::new(Mutex::new(Totals::new()));
+    ctx.workspace
+        .walker(&root, include_ignored)
+        .hidden(!include_ignored)
+        .build_parallel()
+        .run(|| {
+            let mut tally = Tally {
+                local: Totals::new(),
+                shared: shared.clone(),
+            };
+            let cancel = ctx.cancel.clone();
+            let root = root.clone();
+            Box::new(move |entry| {
+                if cancel.check().is_err() {
+                    return WalkState::Quit;
+                }
+                let Ok(entry) = entry else {
+                    return WalkState::Continue;
+                };
+                if entry.file_type().is_some_and(|t| t.is_file()) {
+                    let bytes = entry.metadata().map_or(0, |m| m.len());
+                    let relative = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+                    tally.add(relative, bytes, depth);
+                }
+                WalkState::Continue
+            })
+        });
+    ctx.cancel.check()?;
+
+    let totals = mem::take(&mut *shared.lock().unwrap());
+    let (bytes, files) = totals.get(Path::new("")).copied().unwrap_or_default();
+    let mut directories: Vec<(PathBuf, (u64, u64))> = totals
+        .into_iter()
+        .filter(|(dir, _)| !dir.as_os_str().is_empty())
+        .collect();
+    directories.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then_with(|| a.0.cmp(&b.0)));
+    let truncated = directories.len() > max_entries;
+    directories.truncate(max_entries);
+    let directories: Vec<Value> = directories
+        .into_iter()
+        .map(|(dir, (bytes, files))| {
+            json!({
+                "path": ctx.workspace.display_path(&root.join(&dir)),
+                "bytes": bytes,
+                "files": files,
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "path": ctx.workspace.display_path(&root),
+        "bytes": bytes,
+        "files": files,
+        "directories": directories,
+        "truncated": truncated,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_sizes_roll_up_to_the_requested_depth() {
+        let env = TestEnv::new("usage");
+        env.write("src/a/one.rs", "12345");
+        env.write("src/two.rs", "12");
+        env.write("target/out.bin", "1234567890");
+        env.write(".gitignore", "target/\n");
+        let ctx = env.ctx();
+
+        let result = disk_usage(&ctx, json!({})).unwrap();
+        assert_eq!(
+            (result["bytes"].as_u64(), result["files"].as_u64()),
+            (Some(25), Some(4))
+        );
+        let directories = result["directories"].as_array().unwrap();
+        assert_eq!(directories.len(), 2);
+        assert!(directories[0]["path"].as_str().unwrap().ends_with("target"));
+        assert_eq!(directories[1]["bytes"], 7);
+
+        let result = disk_usage(&ctx, json!({ "depth": 2, "include_ignored": false })).unwrap();
+        assert_eq!(result["bytes"], 7);
+        let paths: Vec<&str> = result["directories"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| d["path"].as_str().unwrap())
+            .collect();
+        assert!(paths[0].ends_with("src") && paths[1].ends_with("a"));
+    }
+}
@@ -0,0 +1,200 @@
+use anyhow::{bail, Context as _, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{parse_args, Context, Tool};
+use crate::error::{ErrorCode, Failure};
+use crate::protocol::ToolAnnotations;
+use crate::uri::path_to_uri;
+use crate::watch::UPDATED_NOTIFICATION;
+
+pub fn tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "watch_path",
+            description: "Subscribe to changes under a file or directory. Each change is pushed \
+                          as `notifications/resources/updated` with the changed file's URI, and \
+                          journaled under the returned `watch_id` for `poll_changes`. Pass \
+                          `unsubscribe: true` to stop watching.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string" },
+                    "recursive": { "type": "boolean", "default": true, "description": "Also watch subdirectories" },
+                    "unsubscribe": { "type": "boolean", "default": false }
+                },
+                "required": ["path"]
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: watch_path,
+        },
+        Tool {
+            name: "poll_changes",
+            description: "Return what changed under a watch_path watch since `since_cursor`, for \
+                          transports that cannot push notifications. Each path is listed once \
+                          with its latest kind of change: created, modified, or removed. Pass \
+                          the returned `cursor` to the next call; `overflowed` means some \
+                          changes were dropped and the client should rescan.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "watch_id": { "type": "integer", "description": "Id returned by watch_path" },
+                    "since_cursor": { "type": "string", "description": "Cursor from the previous poll; omit for every change so far" }
+                },
+                "required": ["watch_id"]
+            }),
+            mutates: false,
+            annotations: ToolAnnotations::READ_ONLY,
+            handler: poll_changes,
+        },
+    ]
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+struct WatchArgs {
+    path: String,
+    #[serde(default = "default_true")]
+    recursive: bool,
+    #[serde(default)]
+    unsubscribe: bool,
+}
+
+#[derive(Deserialize)]
+struct PollArgs {
+    watch_id: u64,
+    #[serde(default)]
+    since_cursor: Option<String>,
+}
+
+fn watch_path(ctx: &Context, args: Value) -> Result<Value> {
+    let args: WatchArgs = parse_args(args)?;
+    let path = ctx.workspace.resolve(&args.path)?;
+    if args.unsubscribe {
+        if !ctx.state.watches.unwatch(&path) {
+            bail!("Not watching {}", args.path);
+        }
+        return Ok(json!({ "path": args.path, "watching": false }));
+    }
+    if !path.exists() {
+        bail!(Failure::not_found(path.display()));
+    }
+    let id = ctx
+        .state
+        .watches
+        .watch(&path, args.recursive, &ctx.state.outbound)?;
+    Ok(json!({
+        "path": args.path,
+        "watch_id": id,
+        "uri": path_to_uri(&path),
+        "watching": true,
+        "recursive": args.recursive,
+        "notification": UPDATED_NOTIFICATION,
+    }))
+}
+
+fn poll_changes(ctx: &Context, args: Value) -> Result<Value> {
+    let args: PollArgs = parse_args(args)?;
+    let since = match args.since_cursor.as_deref() {
+        Some(cursor) => cursor
+            .parse::<u64>()
+            .with_context(|| format!("Invalid cursor: {}", cursor))?,
+        None => 0,
+    };
+    let Some(poll) = ctx.state.watches.poll(args.watch_id, since) else {
+        bail!(Failure::new(
+            ErrorCode::NotFound,
+            format!("No watch with id {}", args.watch_id)
+        ));
+    };
+    let changes: Vec<Value> = poll
+        .changes
+        .iter()
+        .map(|(path, kind)| {
+            json!({
+                "path": ctx.workspace.display_path(path),
+                "uri": path_to_uri(path),
+                "kind": kind,
+            })
+        })
+        .collect();
+    Ok(json!({
+        "watch_id": args.watch_id,
+        "changes": changes,
+        "cursor": poll.cursor.to_string(),
+        "overflowed": poll.overflowed,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outbound::Outbound;
+    use crate::test_support::TestEnv;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_watch_reports_changes_until_unsubscribed() {
+        let mut env = TestEnv::new("watch");
+        let (outbound, rx) = Outbound::channel();
+        env.state.outbound = outbound;
+        env.write("src/lib.rs", "");
+        let ctx = env.ctx();
+
+        watch_path(&ctx, json!({ "path": "src" })).unwrap();
+        let changed = env.write("src/new.rs", "fn main() {}");
+        let expected = path_to_uri(&changed);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut seen = false;
+        while let Ok(message) = rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        {
+            let message: Value = serde_json::from_str(&message).unwrap();
+            assert_eq!(message["method"], UPDATED_NOTIFICATION);
+            if message["params"]["uri"] == expected.as_str() {
+                seen = true;
+                break;
+            }
+        }
+        assert!(seen, "no notification for {}", expected);
+
+        watch_path(&ctx, json!({ "path": "src", "unsubscribe": true })).unwrap();
+        assert!(watch_path(&ctx, json!({ "path": "src", "unsubscribe": true })).is_err());
+    }
+
+    #[test]
+    fn test_poll_changes_batches_by_path_after_the_cursor() {
+        let env = TestEnv::new("poll");
+        env.write("src/lib.rs", "");
+        let ctx = env.ctx();
+
+        let watch = watch_path(&ctx, json!({ "path": "src" })).unwrap();
+        let id = watch["watch_id"].clone();
+        env.write("src/new.rs", "fn main() {}");
+        env.write("src/new.rs", "fn main() { run() }");
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let first = loop {
+            let poll = poll_changes(&ctx, json!({ "watch_id": id })).unwrap();
+            if !poll["changes"].as_array().unwrap().is_empty() || Instant::now() > deadline {
+                break poll;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        };
+        let changes = first["changes"].as_array().unwrap();
+        assert_eq!(changes.len(), 1, "{:?}", changes);
+        assert!(changes[0]["path"].as_str().unwrap().ends_with("new.rs"));
+        assert_eq!(first["overflowed"], false);
+
+        // Give the watcher time to deliver any trailing events first.
+        std::thread::sleep(Duration::from_millis(200));
+        let cursor = poll_changes(&ctx, json!({ "watch_id": id })).unwrap()["cursor"].clone();
+        let later = poll_changes(&ctx, json!({ "watch_id": id, "since_cursor": cursor })).unwrap();
+        assert_eq!(later["changes"], json!([]));
+        assert!(poll_changes(&ctx, json!({ "watch_id": 999 })).is_err());
+    }
+}
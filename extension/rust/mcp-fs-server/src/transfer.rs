@@ -0,0 +1,32 @@
+//! File bytes one tool call read and wrote. Tools report them as they go
+//! through [`crate::tools::Context::transfer`], and the server takes the
+//! totals for the metrics and the audit log, so no tool has to put them in
+//! its result.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Default)]
+pub struct Transfer {
+    read: AtomicU64,
+    written: AtomicU64,
+}
+
+impl Transfer {
+    /// Counts `bytes` of file content read.
+    pub fn read(&self, bytes: usize) {
+        self.read.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Counts `bytes` of file content written.
+    pub fn wrote(&self, bytes: usize) {
+        self.written.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.read.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_written(&self) -> u64 {
+        self.written.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,285 @@
+//! The MCP Streamable HTTP transport: clients POST JSON-RPC messages to
+//! [`ENDPOINT`] and may hold a GET open to receive server-initiated messages
+//! as server-sent events.
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Write;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tiny_http::{Header, Method, Request, Response, StatusCode};
+
+use super::{origin_allowed, NetworkConfig, Scheduler, StateFactory};
+use crate::cancel::Cancellations;
+use crate::metrics::Metrics;
+use crate::outbound::Outbound;
+use crate::server::Server;
+use crate::workspace::Workspace;
+
+/// Path of the single MCP endpoint.
+pub const ENDPOINT: &str = "/mcp";
+/// Prometheus scrape path, served alongside the MCP endpoint.
+pub const METRICS_PATH: &str = "/metrics";
+const SESSION_HEADER: &str = "Mcp-Session-Id";
+/// An SSE comment is sent this often so idle streams are not dropped by proxies.
+const KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// One initialized client. Its notifications go to the most recent event stream.
+struct Session {
+    server: Mutex<Server>,
+    /// Reachable without the server lock, which a running tool call holds.
+    cancellations: Cancellations,
+    stream: Arc<Mutex<Option<Sender<String>>>>,
+}
+
+struct App {
+    config: NetworkConfig,
+    metrics: Metrics,
+    scheduler: Scheduler,
+    workspace: Workspace,
+    factory: StateFactory,
+    sessions: Mutex<HashMap<String, Arc<Session>>>,
+}
+
+/// Serves the Streamable HTTP transport on `addr` until the process exits.
+/// `metrics` is exposed at [`METRICS_PATH`] behind the same bearer token.
+pub fn serve(
+    addr: &str,
+    config: NetworkConfig,
+    metrics: Metrics,
+    scheduler: Scheduler,
+    workspace: Workspace,
+    factory: StateFactory,
+) -> Result<()> {
+    let listener = match &config.tls {
+        Some(tls) => tiny_http::Server::https(
+            addr,
+            tiny_http::SslConfig {
+                certificate: tls.certificate.clone(),
+                private_key: tls.private_key.clone(),
+            },
+        ),
+        None => tiny_http::Server::http(addr),
+    }
+    .map_err(|e| anyhow!("Failed to listen on {}: {}", addr, e))?;
+    let bound = listener
+        .server_addr()
+        .to_ip()
+        .map_or_else(|| addr.to_string(), |a| a.to_string());
+    let scheme = if config.tls.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    eprintln!(
+        "mcp-fs-server: listening on {}://{}{}",
+        scheme, bound, ENDPOINT
+    );
+
+    let app = Arc::new(App {
+        config,
+        metrics,
+        scheduler,
+        workspace,
+        factory,
+        sessions: Mutex::new(HashMap::new()),
+    });
+    for request in listener.incoming_requests() {
+        let app = Arc::clone(&app);
+        thread::spawn(move || app.handle(request));
+    }
+    Ok(())
+}
+
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+fn respond(request: Request, status: u16, body: &str) {
+    let response = Response::from_string(body).with_status_code(StatusCode(status));
+    let _ = request.respond(response);
+}
+
+fn new_session_id() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    if let Ok(elapsed) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        hasher.write_u128(elapsed.as_nanos());
+    }
+    let high = hasher.finish();
+    hasher.write_u64(high);
+    format!("{:016x}{:016x}", high, hasher.finish())
+}
+
+impl App {
+    fn handle(&self, request: Request) {
+        let path = request.url().split('?').next().unwrap_or_default();
+        if path != ENDPOINT && path != METRICS_PATH {
+            return respond(request, 404, "Not found");
+        }
+        if !origin_allowed(header(&request, "Origin")) {
+            return respond(request, 403, "Origin not allowed");
+        }
+        if !self.config.authorized(header(&request, "Authorization")) {
+            let challenge =
+                Header::from_bytes("WWW-Authenticate", "Bearer").expect("static header is valid");
+            let response = Response::from_string("Missing or invalid bearer token")
+                .with_status_code(StatusCode(401))
+                .with_header(challenge);
+            let _ = request.respond(response);
+            return;
+        }
+        if path == METRICS_PATH {
+            return match request.method() {
+                Method::Get => self.scrape(request),
+                _ => respond(request, 405, "Method not allowed"),
+            };
+        }
+        match request.method() {
+            Method::Post => self.post(request),
+            Method::Get => self.stream(request),
+            Method::Delete => self.delete(request),
+            _ => respond(request, 405, "Method not allowed"),
+        }
+    }
+
+    fn scrape(&self, request: Request) {
+        let content_type = Header::from_bytes("Content-Type", "text/plain; version=0.0.4")
+            .expect("static header is valid");
+        let response = Response::from_string(self.metrics.render()).with_header(content_type);
+        let _ = request.respond(response);
+    }
+
+    fn session(&self, id: &str) -> Option<Arc<Session>> {
+        self.sessions.lock().unwrap().get(id).cloned()
+    }
+
+    fn create_session(&self) -> (String, Arc<Session>) {
+        let stream = Arc::new(Mutex::new(None::<Sender<String>>));
+        let sink = Arc::clone(&stream);
+        let outbound = Outbound::new(move |message| {
+            if let Some(tx) = sink.lock().unwrap().as_ref() {
+                let _ = tx.send(message);
+            }
+        });
+        let server = Server::new(self.workspace.clone(), (self.factory)(outbound));
+        let session = Arc::new(Session {
+            cancellations: server.cancellations(),
+            server: Mutex::new(server),
+            stream,
+        });
+        let id = new_session_id();
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(id.clone(), Arc::clone(&session));
+        (id, session)
+    }
+
+    fn post(&self, mut request: Request) {
+        let mut body = String::new();
+        if request.as_reader().read_to_string(&mut body).is_err() {
+            return respond(request, 400, "Request body must be UTF-8");
+        }
+        let is_initialize = serde_json::from_str::<Value>(&body)
+            .is_ok_and(|message| message["method"] == "initialize");
+        let (id, session) = match header(&request, SESSION_HEADER) {
+            Some(id) => match self.session(id) {
+                Some(session) => (id.to_string(), session),
+                None => return respond(request, 404, "Unknown session"),
+            },
+            None if is_initialize => self.create_session(),
+            None => return respond(request, 400, "Missing Mcp-Session-Id header"),
+        };
+
+        let reply = match session.cancellations.intercept(&body) {
+            true => None,
+            false => {
+                // Holding the session lock first keeps each session to one
+                // queued request, which is what makes the scheduler fair.
+                let mut server = session.server.lock().unwrap();
+                let _slot = self.scheduler.admit();
+                server.handle_line(&body)
+            }
+        };
+        let session_header = Header::from_bytes(SESSION_HEADER, id).expect("hex is a valid header");
+        let response = match reply {
+            Some(json) => Response::from_string(json)
+                .with_header(
+                    Header::from_bytes("Content-Type", "application/json")
+                        .expect("static header is valid"),
+                )
+                .with_header(session_header),
+            // Notifications and responses from the client are acknowledged only.
+            None => Response::from_string("")
+                .with_status_code(StatusCode(202))
+                .with_header(session_header),
+        };
+        let _ = request.respond(response);
+    }
+
+    /// Holds the connection open and relays the session's notifications as
+    /// server-sent events until the client goes away or the session ends.
+    fn stream(&self, request: Request) {
+        let accepts_events =
+            header(&request, "Accept").is_some_and(|a| a.contains("text/event-stream"));
+        if !accepts_events {
+            return respond(request, 405, "GET requires Accept: text/event-stream");
+        }
+        let Some(session) = header(&request, SESSION_HEADER).and_then(|id| self.session(id)) else {
+            return respond(request, 404, "Unknown session");
+        };
+
+        let (tx, rx) = mpsc::channel();
+        *session.stream.lock().unwrap() = Some(tx);
+        drop(session);
+
+        // tiny_http buffers chunked bodies, so write the response by hand and
+        // flush each event as it arrives.
+        let mut writer = request.into_writer();
+        let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+                    Cache-Control: no-cache\r\nConnection: close\r\n\r\n";
+        if writer
+            .write_all(head.as_bytes())
+            .and_then(|_| writer.flush())
+            .is_err()
+        {
+            return;
+        }
+        loop {
+            let event = match rx.recv_timeout(KEEPALIVE) {
+                Ok(message) => format!("event: message\ndata: {}\n\n", message),
+                Err(RecvTimeoutError::Timeout) => ": keepalive\n\n".to_string(),
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            if writer
+                .write_all(event.as_bytes())
+                .and_then(|_| writer.flush())
+                .is_err()
+            {
+                break;
+            }
+        }
+    }
+
+    fn delete(&self, request: Request) {
+        let removed = header(&request, SESSION_HEADER)
+            .and_then(|id| self.sessions.lock().unwrap().remove(id));
+        match removed {
+            Some(session) => {
+                // Dropping the sender ends any open event stream.
+                session.stream.lock().unwrap().take();
+                respond(request, 200, "")
+            }
+            None => respond(request, 404, "Unknown session"),
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Ways of carrying JSON-RPC messages between clients and [`Server`]s.
+//!
+//! [`Server`]: crate::server::Server
+
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::outbound::Outbound;
+use crate::state::State;
+
+pub mod http;
+#[cfg(unix)]
+pub mod socket;
+pub mod stdio;
+pub mod tls;
+pub mod ws;
+
+/// Builds the state for one client session, given where its server-initiated
+/// messages should go. Network transports call this once per session.
+pub type StateFactory = Arc<dyn Fn(Outbound) -> State + Send + Sync>;
+
+/// Settings shared by the network transports.
+#[derive(Clone, Default)]
+pub struct NetworkConfig {
+    /// Bearer token every request or connection must present. `None` only
+    /// when authentication was explicitly disabled.
+    pub token: Option<String>,
+    /// Serve `https://` and `wss://` instead of plaintext when set.
+    pub tls: Option<tls::TlsConfig>,
+}
+
+impl NetworkConfig {
+    /// Whether an `Authorization` header value carries the configured token.
+    fn authorized(&self, authorization: Option<&str>) -> bool {
+        let Some(expected) = &self.token else {
+            return true;
+        };
+        authorization
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| constant_time_eq(token.trim().as_bytes(), expected.as_bytes()))
+    }
+}
+
+/// Caps how many requests run at once across every session, admitting
+/// waiters in arrival order. Each session has at most one request waiting
+/// here at a time, so arrival order is round-robin between sessions and a
+/// busy client cannot starve the others.
+#[derive(Clone)]
+pub struct Scheduler {
+    limit: usize,
+    queue: Arc<(Mutex<Queue>, Condvar)>,
+}
+
+#[derive(Default)]
+struct Queue {
+    next_ticket: u64,
+    serving: u64,
+    running: usize,
+}
+
+/// A running request's slot, released on drop.
+pub struct Slot<'a>(&'a Scheduler);
+
+impl Scheduler {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit: limit.max(1),
+            queue: Arc::default(),
+        }
+    }
+
+    /// Waits for this caller's turn and a free slot.
+    pub fn admit(&self) -> Slot<'_> {
+        let (lock, turn) = &*self.queue;
+        let mut queue = lock.lock().unwrap();
+        let ticket = queue.next_ticket;
+        queue.next_ticket += 1;
+        while queue.serving != ticket || queue.running >= self.limit {
+            queue = turn.wait(queue).unwrap();
+        }
+        queue.serving += 1;
+        queue.running += 1;
+        turn.notify_all();
+        Slot(self)
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new(std::thread::available_parallelism().map_or(4, |n| n.get()))
+    }
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        let (lock, turn) = &*self.0.queue;
+        lock.lock().unwrap().running -= 1;
+        turn.notify_all();
+    }
+}
+
+/// Compares secrets without returning early on the first differing byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Browsers attach an Origin; only local pages may talk to the server, which
+/// blocks DNS-rebinding attacks from arbitrary websites.
+fn origin_allowed(origin: Option<&str>) -> bool {
+    let Some(origin) = origin else {
+        return true;
+    };
+    let host = origin
+        .split("://")
+        .nth(1)
+        .unwrap_or(origin)
+        .split('/')
+        .next()
+        .unwrap_or_default();
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]") || origin.starts_with("vscode-webview://")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_origin_allows_only_local_pages() {
+        assert!(origin_allowed(None));
+        assert!(origin_allowed(Some("http://localhost:3000")));
+        assert!(origin_allowed(Some("http://127.0.0.1")));
+        assert!(origin_allowed(Some("http://[::1]:8080")));
+        assert!(!origin_allowed(Some("https://evil.example")));
+        assert!(!origin_allowed(Some("http://localhost.evil.example")));
+    }
+
+    #[test]
+    fn test_scheduler_admits_in_arrival_order() {
+        let scheduler = Scheduler::new(1);
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let first = scheduler.admit();
+        let waiters: Vec<_> = (0..3)
+            .map(|n| {
+                let (scheduler, order) = (scheduler.clone(), Arc::clone(&order));
+                let waiter = std::thread::spawn(move || {
+                    let _slot = scheduler.admit();
+                    order.lock().unwrap().push(n);
+                });
+                // Let each waiter take its ticket before the next arrives.
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                waiter
+            })
+            .collect();
+        assert!(order.lock().unwrap().is_empty());
+        drop(first);
+        for waiter in waiters {
+            waiter.join().unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_bearer_token_must_match() {
+        let config = NetworkConfig {
+            token: Some("s3cret".into()),
+            tls: None,
+        };
+        assert!(config.authorized(Some("Bearer s3cret")));
+        assert!(!config.authorized(Some("Bearer s3cre")));
+        assert!(!config.authorized(Some("Basic s3cret")));
+        assert!(!config.authorized(None));
+        assert!(NetworkConfig::default().authorized(None));
+    }
+}
@@ -0,0 +1,96 @@
+//! Newline-delimited JSON-RPC over a Unix domain socket, as on stdio but for
+//! any number of local clients at once. Every connection is its own session.
+//! The socket file is created owner-only, which takes the place of a token.
+
+use anyhow::{bail, Context as _, Result};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+use super::{Scheduler, StateFactory};
+use crate::outbound::Outbound;
+use crate::server::Server;
+use crate::workspace::Workspace;
+
+/// Listens on `path` until the process exits. A stale socket left by an
+/// earlier run is replaced; any other file there is an error.
+pub fn serve(
+    path: &Path,
+    scheduler: Scheduler,
+    workspace: Workspace,
+    factory: StateFactory,
+) -> Result<()> {
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            bail!("{} exists and is not a socket", path.display());
+        }
+        fs::remove_file(path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    }
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Failed to listen on {}", path.display()))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    eprintln!("mcp-fs-server: listening on unix://{}", path.display());
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let scheduler = scheduler.clone();
+        let workspace = workspace.clone();
+        let factory = factory.clone();
+        thread::spawn(move || {
+            if let Err(e) = connection(stream, scheduler, workspace, factory) {
+                eprintln!("mcp-fs-server: socket connection failed: {:#}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn connection(
+    stream: UnixStream,
+    scheduler: Scheduler,
+    workspace: Workspace,
+    factory: StateFactory,
+) -> Result<()> {
+    let writer = Mutex::new(stream.try_clone()?);
+    let outbound = Outbound::new(move |message| {
+        let mut writer = writer.lock().unwrap();
+        let _ = writer
+            .write_all(message.as_bytes())
+            .and_then(|_| writer.write_all(b"\n"))
+            .and_then(|_| writer.flush());
+    });
+    let mut server = Server::new(workspace, factory(outbound.clone()));
+
+    // Requests run on a worker so cancellations are read while a tool runs.
+    let cancellations = server.cancellations();
+    let (requests, queue) = mpsc::channel::<String>();
+    let worker = thread::spawn(move || {
+        for line in queue {
+            let reply = {
+                let _slot = scheduler.admit();
+                server.handle_line(&line)
+            };
+            if let Some(reply) = reply {
+                outbound.send_raw(reply);
+            }
+        }
+        // Dropping the server stops this session's followers and watches.
+    });
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else { break };
+        if line.trim().is_empty() || cancellations.intercept(&line) {
+            continue;
+        }
+        if requests.send(line).is_err() {
+            break;
+        }
+    }
+    drop(requests);
+    let _ = worker.join();
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use anyhow::Result;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc;
+use std::thread;
+
+use super::StateFactory;
+use crate::outbound::Outbound;
+use crate::server::Server;
+use crate::workspace::Workspace;
+
+/// Serves a single client over stdin/stdout, one JSON-RPC message per line,
+/// until stdin closes. Stdin is read on its own thread so cancellations
+/// reach a tool call that is still running.
+pub fn serve(workspace: Workspace, factory: StateFactory) -> Result<()> {
+    // stdout carries protocol messages only; diagnostics go to stderr. The
+    // stdout lock keeps responses and background notifications from interleaving.
+    let outbound = Outbound::new(|message| {
+        let mut stdout = io::stdout().lock();
+        let _ = stdout
+            .write_all(message.as_bytes())
+            .and_then(|_| stdout.write_all(b"\n"))
+            .and_then(|_| stdout.flush());
+    });
+    let mut server = Server::new(workspace, factory(outbound.clone()));
+
+    let cancellations = server.cancellations();
+    let (tx, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    eprintln!("mcp-fs-server: stdin read error: {}", e);
+                    break;
+                }
+            };
+            if line.trim().is_empty() || cancellations.intercept(&line) {
+                continue;
+            }
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    for line in lines {
+        if let Some(response) = server.handle_line(&line) {
+            outbound.send_raw(response);
+        }
+    }
+    Ok(())
+}
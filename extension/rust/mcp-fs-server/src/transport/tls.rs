@@ -0,0 +1,123 @@
+//! TLS settings for the network transports.
+
+use anyhow::{bail, Context as _, Result};
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A PEM certificate chain and private key, loaded once at startup.
+#[derive(Clone)]
+pub struct TlsConfig {
+    pub certificate: Vec<u8>,
+    pub private_key: Vec<u8>,
+}
+
+impl TlsConfig {
+    pub fn load(cert: &Path, key: &Path) -> Result<Self> {
+        let config = Self {
+            certificate: std::fs::read(cert)
+                .with_context(|| format!("Failed to read certificate {}", cert.display()))?,
+            private_key: std::fs::read(key)
+                .with_context(|| format!("Failed to read private key {}", key.display()))?,
+        };
+        // Fail at startup rather than on the first connection.
+        config.server_config()?;
+        Ok(config)
+    }
+
+    /// The rustls configuration used for WebSocket connections; the HTTP
+    /// transport hands the PEM data to tiny_http, which builds its own.
+    pub fn server_config(&self) -> Result<Arc<rustls::ServerConfig>> {
+        let certs: Vec<rustls::Certificate> =
+            rustls_pemfile::certs(&mut self.certificate.as_slice())
+                .context("Invalid certificate PEM")?
+                .into_iter()
+                .map(rustls::Certificate)
+                .collect();
+        if certs.is_empty() {
+            bail!("Certificate file contains no certificates");
+        }
+        let mut keys = rustls_pemfile::pkcs8_private_keys(&mut self.private_key.as_slice())
+            .context("Invalid private key PEM")?;
+        if keys.is_empty() {
+            keys = rustls_pemfile::rsa_private_keys(&mut self.private_key.as_slice())
+                .context("Invalid private key PEM")?;
+        }
+        let Some(key) = keys.into_iter().next() else {
+            bail!("Private key file contains no PKCS#8 or RSA key");
+        };
+        let config = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(certs, rustls::PrivateKey(key))
+            .context("Certificate and private key do not match")?;
+        Ok(Arc::new(config))
+    }
+}
+
+/// A connection that may or may not be wrapped in TLS.
+pub enum Stream {
+    Plain(TcpStream),
+    Tls(Box<rustls::StreamOwned<rustls::ServerConnection, TcpStream>>),
+}
+
+impl Stream {
+    pub fn new(tcp: TcpStream, tls: Option<&Arc<rustls::ServerConfig>>) -> Result<Self> {
+        Ok(match tls {
+            Some(config) => {
+                let connection = rustls::ServerConnection::new(Arc::clone(config))?;
+                Stream::Tls(Box::new(rustls::StreamOwned::new(connection, tcp)))
+            }
+            None => Stream::Plain(tcp),
+        })
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            Stream::Plain(tcp) => tcp.set_read_timeout(timeout),
+            Stream::Tls(tls) => tls.sock.set_read_timeout(timeout),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(tcp) => tcp.read(buf),
+            Stream::Tls(tls) => tls.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(tcp) => tcp.write(buf),
+            Stream::Tls(tls) => tls.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Plain(tcp) => tcp.flush(),
+            Stream::Tls(tls) => tls.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_pem_without_certificates() {
+        let config = TlsConfig {
+            certificate: b"not a certificate".to_vec(),
+            private_key: b"not a key".to_vec(),
+        };
+        let error = config.server_config().unwrap_err();
+        assert!(error.to_string().contains("no certificates"));
+    }
+}
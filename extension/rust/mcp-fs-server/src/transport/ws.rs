@@ -0,0 +1,157 @@
+//! JSON-RPC over WebSocket, one text frame per message. Every connection is
+//! its own session with its own state.
+
+use anyhow::{Context as _, Result};
+use std::io::ErrorKind;
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::http::StatusCode;
+use tungstenite::{Error, Message};
+
+use super::tls::{Stream, TlsConfig};
+use super::{origin_allowed, NetworkConfig, Scheduler, StateFactory};
+use crate::outbound::Outbound;
+use crate::server::Server;
+use crate::workspace::Workspace;
+
+/// How long a read may block before queued notifications are flushed.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Accepts WebSocket connections on `addr` until the process exits.
+pub fn serve(
+    addr: &str,
+    config: NetworkConfig,
+    scheduler: Scheduler,
+    workspace: Workspace,
+    factory: StateFactory,
+) -> Result<()> {
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("Failed to listen on {}", addr))?;
+    let tls = config
+        .tls
+        .as_ref()
+        .map(TlsConfig::server_config)
+        .transpose()?;
+    let scheme = if tls.is_some() { "wss" } else { "ws" };
+    eprintln!(
+        "mcp-fs-server: listening on {}://{}",
+        scheme,
+        listener.local_addr()?
+    );
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let config = config.clone();
+        let scheduler = scheduler.clone();
+        let tls = tls.clone();
+        let workspace = workspace.clone();
+        let factory = factory.clone();
+        thread::spawn(move || {
+            let result = Stream::new(stream, tls.as_ref())
+                .and_then(|stream| connection(stream, config, scheduler, workspace, factory));
+            if let Err(e) = result {
+                eprintln!("mcp-fs-server: websocket connection failed: {:#}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn reject(status: StatusCode, reason: &str) -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some(reason.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+/// Browser WebSocket APIs cannot set headers, so the token may also arrive
+/// as an `access_token` query parameter.
+fn query_token(request: &Request) -> Option<String> {
+    request
+        .uri()
+        .query()?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("access_token="))
+        .map(|token| format!("Bearer {}", token))
+}
+
+fn connection(
+    stream: Stream,
+    config: NetworkConfig,
+    scheduler: Scheduler,
+    workspace: Workspace,
+    factory: StateFactory,
+) -> Result<()> {
+    // The error type is dictated by tungstenite's handshake callback.
+    #[allow(clippy::result_large_err)]
+    let check = move |request: &Request, response: Response| {
+        let origin = request
+            .headers()
+            .get("Origin")
+            .and_then(|v| v.to_str().ok());
+        let authorization = request
+            .headers()
+            .get("Authorization")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .or_else(|| query_token(request));
+        if !origin_allowed(origin) {
+            Err(reject(StatusCode::FORBIDDEN, "Origin not allowed"))
+        } else if !config.authorized(authorization.as_deref()) {
+            Err(reject(
+                StatusCode::UNAUTHORIZED,
+                "Missing or invalid bearer token",
+            ))
+        } else {
+            Ok(response)
+        }
+    };
+    let mut socket =
+        tungstenite::accept_hdr(stream, check).context("WebSocket handshake failed")?;
+    socket.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+
+    let (tx, rx) = mpsc::channel();
+    let outbound = Outbound::new(move |message| {
+        let _ = tx.send(message);
+    });
+    let mut server = Server::new(workspace, factory(outbound.clone()));
+
+    // Requests run on a worker so the socket keeps being read, and
+    // cancellations seen, while a tool call is in progress.
+    let cancellations = server.cancellations();
+    let (requests, queue) = mpsc::channel::<String>();
+    thread::spawn(move || {
+        for line in queue {
+            let reply = {
+                let _slot = scheduler.admit();
+                server.handle_line(&line)
+            };
+            if let Some(reply) = reply {
+                outbound.send_raw(reply);
+            }
+        }
+        // Dropping the server stops this session's followers and watches.
+    });
+
+    loop {
+        while let Ok(message) = rx.try_recv() {
+            socket.send(Message::text(message))?;
+        }
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                if !cancellations.intercept(text.as_str()) {
+                    let _ = requests.send(text.as_str().to_string());
+                }
+            }
+            // Pings are answered and close frames acknowledged by tungstenite.
+            Ok(_) => {}
+            Err(Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(Error::ConnectionClosed | Error::AlreadyClosed) => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use anyhow::{anyhow, bail, Result};
+use std::path::{Path, PathBuf};
+
+/// Converts an absolute path into a percent-encoded `file://` URI.
+pub fn path_to_uri(path: &Path) -> String {
+    let raw = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    // Windows drive paths ("C:/...") need the extra slash of an empty authority.
+    if !raw.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in raw.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Parses a `file://` URI back into a filesystem path.
+pub fn uri_to_path(uri: &str) -> Result<PathBuf> {
+    let rest = uri
+        .strip_prefix("file://")
+        .ok_or_else(|| anyhow!("Unsupported URI scheme: {}", uri))?;
+    let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+    if !rest.starts_with('/') {
+        bail!("Only local file URIs are supported: {}", uri);
+    }
+
+    let decoded = percent_decode(rest, uri)?;
+
+    // "/C:/dir" -> "C:/dir" on Windows.
+    if cfg!(windows) && decoded.as_bytes().get(2) == Some(&b':') {
+        return Ok(PathBuf::from(&decoded[1..]));
+    }
+    Ok(PathBuf::from(decoded))
+}
+
+/// Decodes the `%XX` escapes in `encoded`, a part of `uri`.
+pub fn percent_decode(encoded: &str, uri: &str) -> Result<String> {
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut iter = encoded.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex: Vec<u8> = iter.by_ref().take(2).collect();
+            let value = std::str::from_utf8(&hex)
+                .ok()
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+                .ok_or_else(|| anyhow!("Invalid percent-encoding in URI: {}", uri))?;
+            bytes.push(value);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| anyhow!("URI is not valid UTF-8: {}", uri))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uri_round_trip_with_special_characters() {
+        let path = if cfg!(windows) {
+            PathBuf::from(r"C:\work space\naïve#1.txt")
+        } else {
+            PathBuf::from("/work space/naïve#1.txt")
+        };
+        let uri = path_to_uri(&path);
+        assert!(uri.contains("work%20space"));
+        assert!(uri.contains("%231"));
+        assert_eq!(uri_to_path(&uri).unwrap(), path);
+    }
+
+    #[test]
+    fn test_rejects_other_schemes() {
+        assert!(uri_to_path("https://example.com/a").is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! Filesystem watches that push `notifications/resources/updated`, and keep
+//! a journal of the same changes for clients that cannot receive pushes and
+//! poll instead.
+
+use anyhow::{Context as _, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::logging::Level;
+use crate::outbound::Outbound;
+use crate::uri::path_to_uri;
+
+/// Notification method sent when a watched resource changes.
+pub const UPDATED_NOTIFICATION: &str = "notifications/resources/updated";
+
+/// Changes journaled per watch before the oldest are dropped.
+const MAX_JOURNALED: usize = 10_000;
+
+/// Active watches keyed by absolute path. The underlying watcher is created
+/// on first use so servers that never watch anything spawn no threads.
+#[derive(Default)]
+pub struct Watches {
+    inner: Mutex<Inner>,
+    journals: Arc<Mutex<HashMap<u64, Journal>>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    watcher: Option<RecommendedWatcher>,
+    /// Whether each path is watched recursively, and its watch id.
+    paths: HashMap<PathBuf, (bool, u64)>,
+    next_id: u64,
+}
+
+/// One watch's changes, numbered from 1 in the order they arrived.
+struct Journal {
+    path: PathBuf,
+    recursive: bool,
+    changes: VecDeque<(u64, PathBuf, &'static str)>,
+    last: u64,
+}
+
+impl Journal {
+    fn covers(&self, path: &Path) -> bool {
+        match self.recursive {
+            true => path.starts_with(&self.path),
+            false => path == self.path || path.parent() == Some(&self.path),
+        }
+    }
+}
+
+/// Changes since a cursor, one per path, in the order each path last changed.
+pub struct Poll {
+    pub changes: Vec<(PathBuf, &'static str)>,
+    pub cursor: u64,
+    /// Changes after the cursor were dropped to bound the journal.
+    pub overflowed: bool,
+}
+
+impl Watches {
+    /// Starts watching `path` and returns its watch id. Re-watching an
+    /// existing path updates its mode and keeps its id.
+    pub fn watch(&self, path: &Path, recursive: bool, outbound: &Outbound) -> Result<u64> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.watcher.is_none() {
+            let outbound = outbound.clone();
+            let journals = self.journals.clone();
+            let watcher =
+                notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+                    Ok(event) => publish(&outbound, &journals, event),
+                    Err(e) => outbound.log(
+                        Level::Error,
+                        "watcher",
+                        json!({ "message": e.to_string(), "paths": e.paths }),
+                    ),
+                })
+                .context("Failed to start file watcher")?;
+            inner.watcher = Some(watcher);
+        }
+        let mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        let existing = inner.paths.get(path).map(|(_, id)| *id);
+        let watcher = inner.watcher.as_mut().expect("watcher was just created");
+        if existing.is_some() {
+            let _ = watcher.unwatch(path);
+        }
+        watcher
+            .watch(path, mode)
+            .with_context(|| format!("Failed to watch {}", path.display()))?;
+        let id = existing.unwrap_or_else(|| {
+            inner.next_id += 1;
+            inner.next_id
+        });
+        inner.paths.insert(path.to_path_buf(), (recursive, id));
+        let mut journals = self.journals.lock().unwrap();
+        let journal = journals.entry(id).or_insert_with(|| Journal {
+            path: path.to_path_buf(),
+            recursive,
+            changes: VecDeque::new(),
+            last: 0,
+        });
+        journal.recursive = recursive;
+        Ok(id)
+    }
+
+    /// The changes `id` has seen after `since`, or `None` for an unknown id.
+    pub fn poll(&self, id: u64, since: u64) -> Option<Poll> {
+        let journals = self.journals.lock().unwrap();
+        let journal = journals.get(&id)?;
+        let oldest = journal.changes.front().map_or(journal.last + 1, |c| c.0);
+        let mut changes: Vec<(PathBuf, &'static str)> = Vec::new();
+        for (_, path, kind) in journal.changes.iter().filter(|c| c.0 > since) {
+            let mut kind = *kind;
+            if let Some(index) = changes.iter().position(|(seen, _)| seen == path) {
+                // A file created and then written is still new to the client.
+                if changes[index].1 == "created" && kind == "modified" {
+                    kind = "created";
+                }
+                changes.remove(index);
+            }
+            changes.push((path.clone(), kind));
+        }
+        Some(Poll {
+            changes,
+            cursor: journal.last.max(since),
+            overflowed: since + 1 < oldest,
+        })
+    }
+
+    /// Stops watching `path`. Returns false if it was not being watched.
+    pub fn unwatch(&self, path: &Path) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        let Some((_, id)) = inner.paths.remove(path) else {
+            return false;
+        };
+        self.journals.lock().unwrap().remove(&id);
+        if let Some(watcher) = inner.watcher.as_mut() {
+            let _ = watcher.unwatch(path);
+        }
+        true
+    }
+}
+
+fn publish(outbound: &Outbound, journals: &Mutex<HashMap<u64, Journal>>, event: Event) {
+    let kind = match event.kind {
+        EventKind::Create(_) => "created",
+        EventKind::Remove(_) => "removed",
+        // Reads and metadata-only access never change content.
+        EventKind::Access(_) => return,
+        _ => "modified",
+    };
+    let mut journals = journals.lock().unwrap();
+    for path in &event.paths {
+        outbound.notify(UPDATED_NOTIFICATION, json!({ "uri": path_to_uri(path) }));
+        for journal in journals.values_mut().filter(|j| j.covers(path)) {
+            journal.last += 1;
+            journal
+                .changes
+                .push_back((journal.last, path.clone(), kind));
+            if journal.changes.len() > MAX_JOURNALED {
+                journal.changes.pop_front();
+            }
+        }
+    }
+}
@@ -0,0 +1,445 @@
+use anyhow::{bail, Context as _, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::WalkBuilder;
+use serde_json::{json, Value};
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use crate::config::Permission;
+use crate::tools::walker;
+
+/// Returned when a path resolves outside every root, whether directly, via
+/// `..`, or through a symlink.
+#[derive(Debug)]
+pub struct AccessDenied {
+    pub path: String,
+    pub resolved: PathBuf,
+}
+
+impl AccessDenied {
+    /// Structured form returned to clients alongside the error text.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "error": "outside_root",
+            "path": self.path,
+            "resolved": self.resolved.display().to_string(),
+        })
+    }
+}
+
+impl fmt::Display for AccessDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Access denied: {} resolves to {}, which is outside the allowed roots",
+            self.path,
+            self.resolved.display()
+        )
+    }
+}
+
+impl std::error::Error for AccessDenied {}
+
+/// Returned when a mutating tool targets a read-only root.
+#[derive(Debug)]
+pub struct ReadOnly {
+    pub path: String,
+    pub root: PathBuf,
+}
+
+impl ReadOnly {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "error": "access_denied",
+            "reason": "read_only",
+            "path": self.path,
+            "root": self.root.display().to_string(),
+        })
+    }
+}
+
+impl fmt::Display for ReadOnly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Read-only: {} is inside {}, which does not allow writes",
+            self.path,
+            self.root.display()
+        )
+    }
+}
+
+impl std::error::Error for ReadOnly {}
+
+/// Returned when a tool would delete a path in a root that forbids it.
+#[derive(Debug)]
+pub struct NotDeletable {
+    pub path: String,
+    pub root: PathBuf,
+}
+
+impl NotDeletable {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "error": "access_denied",
+            "reason": "delete_denied",
+            "path": self.path,
+            "root": self.root.display().to_string(),
+        })
+    }
+}
+
+impl fmt::Display for NotDeletable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Delete denied: {} is inside {}, which does not allow deletes",
+            self.path,
+            self.root.display()
+        )
+    }
+}
+
+impl std::error::Error for NotDeletable {}
+
+/// Canonicalizes `path`, which need not exist yet. The longest existing
+/// prefix is resolved on disk; the missing remainder cannot contain symlinks,
+/// so it is normalized lexically.
+fn canonicalize_lenient(path: &Path) -> Result<PathBuf> {
+    let mut missing: Vec<OsString> = Vec::new();
+    let mut existing = path;
+    let base = loop {
+        match existing.canonicalize() {
+            Ok(base) => break base,
+            Err(_) if existing.symlink_metadata().is_ok() => {
+                bail!(
+                    "Refusing to follow dangling symlink: {}",
+                    existing.display()
+                )
+            }
+            Err(e) => {
+                let (Some(parent), Some(last)) =
+                    (existing.parent(), existing.components().next_back())
+                else {
+                    return Err(e).with_context(|| format!("Failed to resolve {}", path.display()));
+                };
+                missing.push(last.as_os_str().to_os_string());
+                existing = parent;
+            }
+        }
+    };
+    let mut resolved = base;
+    for part in missing.iter().rev() {
+        match Path::new(part).components().next() {
+            Some(Component::ParentDir) => {
+                resolved.pop();
+            }
+            Some(Component::CurDir) | None => {}
+            _ => resolved.push(part),
+        }
+    }
+    Ok(resolved)
+}
+
+/// The set of directories the server exposes to the MCP client.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    roots: Vec<PathBuf>,
+    read_only: Vec<PathBuf>,
+    undeletable: Vec<PathBuf>,
+    /// Configured ignore patterns, by the root they are relative to.
+    ignores: Arc<Vec<(PathBuf, Gitignore)>>,
+}
+
+impl Workspace {
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        Self {
+            roots,
+            read_only: Vec::new(),
+            undeletable: Vec::new(),
+            ignores: Arc::default(),
+        }
+    }
+
+    /// Marks the given roots as read-only. Paths inside them can be read but
+    /// not modified.
+    pub fn with_read_only(mut self, roots: Vec<PathBuf>) -> Self {
+        self.read_only = roots;
+        self
+    }
+
+    /// Marks roots whose files may be written but not deleted.
+    pub fn with_undeletable(mut self, roots: Vec<PathBuf>) -> Self {
+        self.undeletable = roots;
+        self
+    }
+
+    /// Hides paths under `root` matching gitignore-style `patterns` from
+    /// every directory walk, even with `include_ignored`.
+    pub fn with_ignores(mut self, root: &Path, patterns: &[String]) -> Result<Self> {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in patterns {
+            builder
+                .add_line(None, pattern)
+                .with_context(|| format!("Invalid ignore pattern {:?}", pattern))?;
+        }
+        let matcher = builder
+            .build()
+            .with_context(|| format!("Invalid ignore patterns for {}", root.display()))?;
+        Arc::make_mut(&mut self.ignores).push((root.to_path_buf(), matcher));
+        Ok(self)
+    }
+
+    /// Whether no root accepts writes, in which case mutating tools are hidden.
+    pub fn is_read_only(&self) -> bool {
+        !self.roots.is_empty()
+            && self
+                .roots
+                .iter()
+                .all(|r| self.read_only.iter().any(|ro| r.starts_with(ro)))
+    }
+
+    /// This workspace narrowed to the roots a client reported. Requested
+    /// directories outside the configured roots are returned as rejected;
+    /// with no configured roots every existing directory is accepted.
+    pub fn with_client_roots(&self, requested: &[PathBuf]) -> (Workspace, Vec<PathBuf>) {
+        let mut roots = Vec::new();
+        let mut rejected = Vec::new();
+        for path in requested {
+            match path.canonicalize() {
+                Ok(root) if root.is_dir() && (self.roots.is_empty() || self.contains(&root)) => {
+                    roots.push(root)
+                }
+                _ => rejected.push(path.clone()),
+            }
+        }
+        let workspace = Workspace {
+            roots,
+            ..self.clone()
+        };
+        (workspace, rejected)
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Whether `path` lies under one of the roots.
+    pub fn contains(&self, path: &Path) -> bool {
+        self.roots.iter().any(|root| path.starts_with(root))
+    }
+
+    /// Renders `path` relative to the root containing it, using `/` separators.
+    pub fn display_path(&self, path: &Path) -> String {
+        let relative = self
+            .roots
+            .iter()
+            .find_map(|root| path.strip_prefix(root).ok())
+            .unwrap_or(path);
+        relative.to_string_lossy().replace('\\', "/")
+    }
+
+    /// Joins a client-supplied path onto the first root unless it is absolute.
+    fn absolute(&self, path: &str) -> Result<PathBuf> {
+        if path.trim().is_empty() {
+            bail!("Path must not be empty");
+        }
+        let candidate = Path::new(path);
+        if candidate.is_absolute() {
+            return Ok(candidate.to_path_buf());
+        }
+        match self.roots.first() {
+            Some(root) => Ok(root.join(candidate)),
+            None => bail!("No workspace roots configured"),
+        }
+    }
+
+    fn check(&self, path: &str, resolved: PathBuf) -> Result<PathBuf> {
+        if !self.contains(&resolved) {
+            return Err(AccessDenied {
+                path: path.to_string(),
+                resolved,
+            }
+            .into());
+        }
+        Ok(resolved)
+    }
+
+    /// Resolves a client-supplied path to its canonical location, following
+    /// symlinks and `..`, and fails with [`AccessDenied`] if that lies outside
+    /// the roots. Relative paths are taken relative to the first root.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf> {
+        let resolved = canonicalize_lenient(&self.absolute(path)?)?;
+        self.check(path, resolved)
+    }
+
+    /// Like [`Workspace::resolve`], but a symlink in the final component is
+    /// left unresolved so tools can inspect or remove the link itself.
+    pub fn resolve_entry(&self, path: &str) -> Result<PathBuf> {
+        let absolute = self.absolute(path)?;
+        let is_link = absolute.symlink_metadata().is_ok_and(|m| m.is_symlink());
+        match (absolute.parent(), absolute.file_name()) {
+            (Some(parent), Some(name)) if is_link => {
+                let resolved = canonicalize_lenient(parent)?.join(name);
+                self.check(path, resolved)
+            }
+            _ => self.resolve(path),
+        }
+    }
+
+    /// Fails with [`ReadOnly`] if `resolved` lies inside a read-only root.
+    pub fn check_writable(&self, path: &str, resolved: &Path) -> Result<()> {
+        match self
+            .read_only
+            .iter()
+            .find(|root| resolved.starts_with(root))
+        {
+            Some(root) => Err(ReadOnly {
+                path: path.to_string(),
+                root: root.clone(),
+            }
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails with [`NotDeletable`] if `resolved` lies inside a root that
+    /// forbids deletes. Read-only roots forbid them too.
+    pub fn check_deletable(&self, path: &str, resolved: &Path) -> Result<()> {
+        self.check_writable(path, resolved)?;
+        match self
+            .undeletable
+            .iter()
+            .find(|root| resolved.starts_with(root))
+        {
+            Some(root) => Err(NotDeletable {
+                path: path.to_string(),
+                root: root.clone(),
+            }
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    /// What the roots' policy allows on `resolved`, named as in the config's
+    /// `permissions`. Reading is always allowed inside the roots.
+    pub fn access(&self, resolved: &Path) -> Vec<Permission> {
+        let mut access = vec![Permission::Read];
+        if self.check_writable("", resolved).is_ok() {
+            access.push(Permission::Write);
+            if self.check_deletable("", resolved).is_ok() {
+                access.push(Permission::Delete);
+            }
+        }
+        access
+    }
+
+    /// Whether a configured ignore pattern hides `path`.
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        self.ignores.iter().any(|(root, matcher)| {
+            path.starts_with(root)
+                && matcher
+                    .matched_path_or_any_parents(path, is_dir)
+                    .is_ignore()
+        })
+    }
+
+    /// A directory walker for `root` honoring ignore files (unless
+    /// `include_ignored`) and, always, the configured ignore patterns.
+    pub fn walker(&self, root: &Path, include_ignored: bool) -> WalkBuilder {
+        let mut builder = walker(root, include_ignored);
+        if !self.ignores.is_empty() {
+            let workspace = self.clone();
+            builder.filter_entry(move |entry| {
+                let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                !workspace.is_excluded(entry.path(), is_dir)
+            });
+        }
+        builder
+    }
+
+    /// [`Workspace::resolve`] for a path that is about to be modified.
+    pub fn resolve_writable(&self, path: &str) -> Result<PathBuf> {
+        let resolved = self.resolve(path)?;
+        self.check_writable(path, &resolved)?;
+        Ok(resolved)
+    }
+
+    /// The directories a recursive tool should walk: the resolved `path` if
+    /// given, otherwise every root.
+    pub fn search_roots(&self, path: Option<&str>) -> Result<Vec<PathBuf>> {
+        match path {
+            Some(path) => Ok(vec![self.resolve(path)?]),
+            None => Ok(self.roots.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestEnv;
+
+    #[test]
+    fn test_resolve_rejects_escapes() {
+        let env = TestEnv::new("sandbox");
+        env.write("inside.txt", "ok");
+        let workspace = &env.workspace;
+
+        assert_eq!(
+            workspace.resolve("sub/../new.txt").unwrap(),
+            env.path().join("new.txt")
+        );
+        let escape = workspace.resolve("../outside.txt").unwrap_err();
+        assert!(escape.downcast_ref::<AccessDenied>().is_some());
+        assert!(workspace.resolve("/etc/passwd").is_err());
+
+        #[cfg(unix)]
+        {
+            let link = env.path().join("link");
+            std::os::unix::fs::symlink(std::env::temp_dir(), &link).unwrap();
+            let through = workspace.resolve("link/secret.txt").unwrap_err();
+            assert!(through.downcast_ref::<AccessDenied>().is_some());
+            assert_eq!(workspace.resolve_entry("link").unwrap(), link);
+        }
+    }
+
+    #[test]
+    fn test_configured_ignores_and_delete_policy() {
+        let env = TestEnv::new("policy");
+        env.write("src/main.rs", "");
+        env.write("keys/id.pem", "");
+        env.write("notes.pem", "");
+        let workspace = env
+            .workspace
+            .clone()
+            .with_undeletable(vec![env.path().to_path_buf()])
+            .with_ignores(env.path(), &["keys/".into(), "*.pem".into()])
+            .unwrap();
+
+        let walked: Vec<String> = workspace
+            .walker(env.path(), true)
+            .build()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+            .map(|e| workspace.display_path(e.path()))
+            .collect();
+        assert_eq!(walked, vec!["src/main.rs"]);
+
+        let target = env.path().join("src/main.rs");
+        assert!(workspace.check_writable("src/main.rs", &target).is_ok());
+        let denied = workspace
+            .check_deletable("src/main.rs", &target)
+            .unwrap_err();
+        assert!(denied.is::<NotDeletable>());
+        assert_eq!(
+            workspace.access(&target),
+            [Permission::Read, Permission::Write]
+        );
+        let read_only = workspace.with_read_only(vec![env.path().join("src")]);
+        assert_eq!(read_only.access(&target), [Permission::Read]);
+        assert_eq!(read_only.access(&env.path().join("notes.pem")).len(), 2);
+    }
+}
@@ -0,0 +1,265 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+
+#[test]
+fn test_initialize_list_and_call_over_stdio() {
+    let root = std::env::temp_dir().join(format!("mcp-fs-integration-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("hello.txt"), "hi there").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mcp-fs-server"))
+        .arg(&root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to start child process");
+
+    let mut stdin = child.stdin.take().expect("Failed to open stdin");
+    let stdout = child.stdout.take().expect("Failed to open stdout");
+    let mut reader = BufReader::new(stdout);
+
+    let requests = [
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-06-18","capabilities":{},"clientInfo":{"name":"test","version":"0"}}}"#,
+        r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+        r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"read_file","arguments":{"path":"hello.txt"}}}"#,
+    ];
+    for request in requests {
+        writeln!(stdin, "{}", request).unwrap();
+    }
+    drop(stdin);
+
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line).unwrap() > 0 {
+        lines.push(line.trim().to_string());
+        line.clear();
+    }
+    let _ = child.wait();
+    let _ = std::fs::remove_dir_all(&root);
+
+    // The notification gets no reply, so exactly three responses come back.
+    assert_eq!(lines.len(), 3, "Unexpected output: {:?}", lines);
+    assert!(
+        lines[0].contains(r#""protocolVersion":"2025-06-18""#),
+        "{}",
+        lines[0]
+    );
+    assert!(lines[1].contains(r#""name":"read_file""#), "{}", lines[1]);
+    assert!(lines[2].contains("hi there"), "{}", lines[2]);
+}
+
+const TOKEN: &str = "integration-token";
+const AUTH: (&str, &str) = ("Authorization", "Bearer integration-token");
+
+/// Starts the server on an ephemeral port and returns it with the bound
+/// `host:port`, parsed from the startup banner on stderr.
+fn spawn_listener(root: &std::path::Path, flag: &str) -> (std::process::Child, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mcp-fs-server"))
+        .arg(root)
+        .args([flag, "127.0.0.1:0"])
+        .env("MCP_FS_AUTH_TOKEN", TOKEN)
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start child process");
+    let mut banner = String::new();
+    BufReader::new(child.stderr.take().unwrap())
+        .read_line(&mut banner)
+        .unwrap();
+    let addr = banner
+        .trim()
+        .rsplit("://")
+        .next()
+        .map(|rest| rest.trim_end_matches("/mcp"))
+        .unwrap_or_else(|| panic!("Unexpected banner: {}", banner))
+        .to_string();
+    (child, addr)
+}
+
+/// Sends one HTTP/1.1 request and returns the raw response.
+fn http_request(
+    addr: &str,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: &str,
+) -> String {
+    use std::io::Read;
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    let mut request = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Length: {}\r\n",
+        method,
+        path,
+        addr,
+        body.len()
+    );
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    request.push_str("\r\n");
+    request.push_str(body);
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn test_streamable_http_sessions() {
+    let root = std::env::temp_dir().join(format!("mcp-fs-http-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+
+    let (mut child, addr) = spawn_listener(&root, "--http");
+
+    let init = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-06-18"}}"#;
+    let response = http_request(&addr, "POST", "/mcp", &[], init);
+    assert!(response.starts_with("HTTP/1.1 401"), "{}", response);
+    let response = http_request(&addr, "POST", "/mcp", &[AUTH], init);
+    let session = response
+        .lines()
+        .find_map(|l| l.strip_prefix("Mcp-Session-Id: "))
+        .unwrap_or_else(|| panic!("No session header: {}", response))
+        .trim()
+        .to_string();
+    assert!(response.contains("\"protocolVersion\""), "{}", response);
+
+    let list = r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#;
+    let response = http_request(
+        &addr,
+        "POST",
+        "/mcp",
+        &[AUTH, ("Mcp-Session-Id", &session)],
+        list,
+    );
+    assert!(response.contains("\"read_file\""), "{}", response);
+
+    let response = http_request(&addr, "POST", "/mcp", &[AUTH], list);
+    assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+
+    let call = r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"list_directory","arguments":{"path":"."}}}"#;
+    http_request(
+        &addr,
+        "POST",
+        "/mcp",
+        &[AUTH, ("Mcp-Session-Id", &session)],
+        call,
+    );
+    let response = http_request(&addr, "GET", "/metrics", &[], "");
+    assert!(response.starts_with("HTTP/1.1 401"), "{}", response);
+    let response = http_request(&addr, "GET", "/metrics", &[AUTH], "");
+    assert!(
+        response.contains("mcp_fs_tool_calls_total{tool=\"list_directory\"} 1"),
+        "{}",
+        response
+    );
+
+    let notification = r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#;
+    let response = http_request(
+        &addr,
+        "POST",
+        "/mcp",
+        &[AUTH, ("Mcp-Session-Id", &session)],
+        notification,
+    );
+    assert!(response.starts_with("HTTP/1.1 202"), "{}", response);
+
+    let response = http_request(
+        &addr,
+        "DELETE",
+        "/mcp",
+        &[AUTH, ("Mcp-Session-Id", &session)],
+        "",
+    );
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    let response = http_request(
+        &addr,
+        "POST",
+        "/mcp",
+        &[AUTH, ("Mcp-Session-Id", &session)],
+        list,
+    );
+    assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+
+    let _ = child.kill();
+    let _ = child.wait();
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_websocket_session() {
+    let root = std::env::temp_dir().join(format!("mcp-fs-ws-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("hello.txt"), "over websocket").unwrap();
+    let (mut child, addr) = spawn_listener(&root, "--ws");
+
+    assert!(tungstenite::connect(format!("ws://{}", addr)).is_err());
+    let url = format!("ws://{}/?access_token={}", addr, TOKEN);
+    let (mut socket, _) = tungstenite::connect(url).unwrap();
+    let call = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"read_file","arguments":{"path":"hello.txt"}}}"#;
+    socket.send(tungstenite::Message::text(call)).unwrap();
+    let reply = socket.read().unwrap();
+    assert!(
+        reply.to_text().unwrap().contains("over websocket"),
+        "{}",
+        reply
+    );
+    socket.close(None).unwrap();
+
+    let _ = child.kill();
+    let _ = child.wait();
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_socket_sessions_are_isolated() {
+    use std::os::unix::net::UnixStream;
+
+    let root = std::env::temp_dir().join(format!("mcp-fs-socket-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("a.txt"), "shared").unwrap();
+    let socket = root.join("mcp.sock");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mcp-fs-server"))
+        .arg(&root)
+        .arg("--socket")
+        .arg(&socket)
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start child process");
+    let mut banner = String::new();
+    BufReader::new(child.stderr.take().unwrap())
+        .read_line(&mut banner)
+        .unwrap();
+    assert!(banner.contains("unix://"), "{}", banner);
+
+    let call = |stream: &mut UnixStream, id: u32, name: &str, arguments: &str| {
+        let line = format!(
+            r#"{{"jsonrpc":"2.0","id":{},"method":"tools/call","params":{{"name":"{}","arguments":{}}}}}"#,
+            id, name, arguments
+        );
+        stream.write_all(line.as_bytes()).unwrap();
+        stream.write_all(b"\n").unwrap();
+        let mut reply = String::new();
+        BufReader::new(stream.try_clone().unwrap())
+            .read_line(&mut reply)
+            .unwrap();
+        reply
+    };
+    let mut first = UnixStream::connect(&socket).unwrap();
+    let mut second = UnixStream::connect(&socket).unwrap();
+    let taken = call(&mut first, 1, "snapshot_workspace", "{}");
+    assert!(taken.contains(r#""id":1,"taken_at""#), "{}", taken);
+    // Snapshots belong to the session that took them.
+    let other = call(&mut second, 1, "diff_snapshot", r#"{"id":1}"#);
+    assert!(other.contains(r#""isError":true"#), "{}", other);
+    let own = call(&mut first, 2, "diff_snapshot", r#"{"id":1}"#);
+    assert!(own.contains(r#""isError":false"#), "{}", own);
+
+    let _ = child.kill();
+    let _ = child.wait();
+    let _ = std::fs::remove_dir_all(&root);
+}
@@ -80,6 +80,7 @@ async function buildAll() {
   try {
     await buildBinary("clipboard-monitor");
     await buildBinary("clipboard-files");
+    await buildBinary("mcp-fs-server");
     console.log("🎉 All Rust binaries built successfully!");
   } catch (err) {
     console.error("❌ Build failed:", err.message);
@@ -33,7 +33,7 @@ export class ClipboardManager implements vscode.Disposable {
     private initialize() {
         // Listen to the specific trigger event from ClipboardService (always active for automations)
         this.disposables.push(
-            this.clipboardService.onTriggerXml((payloads) => this.handleXmlTrigger(payloads))
+            this.clipboardService.onTriggerXml(({ xmlPayloads, ids }) => this.handleXmlTrigger(xmlPayloads, ids))
         );
 
         // Listen for general clipboard updates
@@ -127,9 +127,9 @@ export class ClipboardManager implements vscode.Disposable {
         });
     }
 
-    private async handleXmlTrigger(xmlPayloads: string[]) {
-        // 1. Parse the raw XML into structured actions
-        const actions = this.xmlParser.parse(xmlPayloads);
+    private async handleXmlTrigger(xmlPayloads: string[], ids?: string[]) {
+        // 1. Parse the raw XML into structured actions, keeping the monitor's ids
+        const actions = this.xmlParser.parse(xmlPayloads, ids);
         
         if (actions.length === 0) return;
 
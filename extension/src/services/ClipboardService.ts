@@ -6,11 +6,94 @@ import * as vscode from "vscode";
 
 // Define the updated message types matching Rust protocol
 export interface ClipboardMessage {
-  type: "clipboard_update" | "error" | "ready" | "trigger_xml";
+  type:
+    | "accumulated"
+    | "auto_paused"
+    | "auto_resumed"
+    | "batch_result"
+    | "chunks"
+    | "clipboard_cleared"
+    | "clipboard_update"
+    | "clipboard_update_ref"
+    | "clipboard_written"
+    | "content_skipped"
+    | "diagnostics"
+    | "error"
+    | "file_read"
+    | "file_written"
+    | "files_copied"
+    | "history"
+    | "nack"
+    | "ready"
+    | "trigger_command"
+    | "trigger_conflict"
+    | "trigger_suppressed"
+    | "trigger_xml"
+    | "unsupported";
   content?: string;
+  path?: string; // File holding a clipboard_update_ref's content
+  size?: number; // Also the full size of a file_read's file
+  truncated?: boolean; // A file_read holds only the start of the file
+  bytes_written?: number;
+  hash?: string; // Names a recent entry, e.g. for chunkContent
+  chunks?: ClipboardChunk[];
+  entries?: ClipboardHistoryEntry[]; // Recent copies of a history reply, newest first
+  system_error?: string; // Why the Windows clipboard history could not be read
+  replies?: ClipboardMessage[]; // Replies of a batch_result, one per command, with its id
+  code?: string; // Set on errors the extension handles specially, e.g. "panic"
   message?: string;
+  raw_prefix?: string; // Start of a line a nack rejected
+  command?: string; // Command an older monitor reported unsupported
+  backtrace?: string;
+  reason?: string; // Why content was skipped, not cleared, or monitoring auto-paused
+  cleared?: boolean;
+  count?: number; // Copies joined by flush_accumulated, or files put on the clipboard
+  error?: string;
+  detail?: string;
   timestamp?: string;
+  ours?: string; // Sides of a trigger_conflict block
+  theirs?: string;
+  labels?: string[];
   xml_payloads?: string[]; // New field for XML trigger
+  xml_payload?: string; // One command of a split batch, with the fields below
+  id?: string;
+  index?: number;
+  total?: number;
+  batch_id?: string;
+  ids?: string[]; // Correlation id per payload, echoed by executor results
+  estimated_tokens?: number; // Approximate LLM token count of `content`
+  transforms_applied?: string[]; // Configured transforms that changed `content`
+  terminal_output?: boolean; // ANSI escapes were removed from `content`
+  raw_content?: string; // Terminal output as copied, escapes included
+  delta?: string; // Unified diff from the previous update's content
+  json_valid?: boolean; // Set when `content` looks like a JSON object or array
+  json_pretty?: string;
+  json_error?: { line: number; column: number; message: string };
+  base64_blobs?: { offset: number; length: number; decoded_bytes: number; kind: string }[];
+  formats?: string[]; // Formats on offer with the text, e.g. "text/html"
+}
+
+export interface ClipboardChunk {
+  content: string;
+  estimated_tokens: number;
+}
+
+export interface ClipboardHistoryEntry {
+  hash: string;
+  content: string;
+  source: "monitor" | "system";
+  pinned?: boolean; // Kept until unpinned and listed first
+}
+
+export interface TriggerConflictEvent {
+  ours: string;
+  theirs: string;
+  labels: string[];
+}
+
+export interface TriggerXmlEvent {
+  xmlPayloads: string[];
+  ids?: string[];
 }
 
 export class ClipboardService implements vscode.Disposable {
@@ -19,15 +102,26 @@ export class ClipboardService implements vscode.Disposable {
   private process: ChildProcessWithoutNullStreams | null = null;
   private disposables: vscode.Disposable[] = [];
   private isStarting: boolean = false;
+  // Replies awaited by request(), by the id sent with the command
+  private pending = new Map<string, { resolve: (msg: ClipboardMessage) => void; reject: (err: Error) => void }>();
+  private nextRequestId = 0;
   
   // Event Emitter for XML Triggers
-  private _onTriggerXml = new vscode.EventEmitter<string[]>();
+  private _onTriggerXml = new vscode.EventEmitter<TriggerXmlEvent>();
   public readonly onTriggerXml = this._onTriggerXml.event;
 
+  // Event Emitter for Git conflict blocks found in copies
+  private _onTriggerConflict = new vscode.EventEmitter<TriggerConflictEvent>();
+  public readonly onTriggerConflict = this._onTriggerConflict.event;
+
   // Event Emitter for General Clipboard Updates
   private _onClipboardUpdate = new vscode.EventEmitter<string>();
   public readonly onClipboardUpdate = this._onClipboardUpdate.event;
 
+  // Event Emitter for replies to chunkContent
+  private _onChunks = new vscode.EventEmitter<{ hash: string; chunks: ClipboardChunk[] }>();
+  public readonly onChunks = this._onChunks.event;
+
   constructor(
     context: vscode.ExtensionContext,
     outputChannel: vscode.OutputChannel
@@ -36,7 +130,11 @@ export class ClipboardService implements vscode.Disposable {
     this.outputChannel = outputChannel;
   }
 
-  public start(): void {
+  /**
+   * Starts the monitor. With `takeover`, a monitor already running for this
+   * user, e.g. from another window, is stopped first.
+   */
+  public start(takeover: boolean = false): void {
     if (this.process || this.isStarting) {
       return;
     }
@@ -50,8 +148,17 @@ export class ClipboardService implements vscode.Disposable {
       return;
     }
 
+    // The workspace folders are the only directories readFile/writeFile and
+    // clipboard commands may touch.
+    const args = takeover ? ["--takeover"] : [];
+    for (const folder of vscode.workspace.workspaceFolders ?? []) {
+      if (folder.uri.scheme === "file") {
+        args.push("--root", folder.uri.fsPath);
+      }
+    }
+
     try {
-      this.process = spawn(binPath, [], {
+      this.process = spawn(binPath, args, {
         cwd: path.dirname(binPath),
         env: { ...process.env },
       });
@@ -93,6 +200,142 @@ export class ClipboardService implements vscode.Disposable {
     }
   }
 
+  /**
+   * Sends `command` with an id and resolves with the monitor's reply to it,
+   * an "ack" for commands that have no other.
+   */
+  public request(command: { command: string; [key: string]: unknown }): Promise<ClipboardMessage> {
+    return new Promise((resolve, reject) => {
+      if (!this.process || !this.process.stdin.writable) {
+        reject(new Error("clipboard-monitor is not running"));
+        return;
+      }
+      const id = `req-${++this.nextRequestId}`;
+      this.pending.set(id, { resolve, reject });
+      this.process.stdin.write(JSON.stringify({ ...command, id }) + "\n");
+    });
+  }
+
+  /**
+   * Resolves with recent copies, newest first. With `includeSystem`, older
+   * copies from the Windows clipboard history follow the monitor's own.
+   */
+  public async getHistory(includeSystem: boolean = false): Promise<ClipboardHistoryEntry[]> {
+    const reply = await this.request({ command: "get_history", include_system: includeSystem });
+    if (reply.system_error) {
+      this.outputChannel.appendLine(`[WARN] Cannot read the system clipboard history: ${reply.system_error}`);
+    }
+    return reply.entries ?? [];
+  }
+
+  /**
+   * Pins or unpins the recent entry with `hash`; pinned entries are never
+   * evicted from the monitor's history.
+   */
+  public async setPinned(hash: string, pinned: boolean): Promise<void> {
+    const command = pinned ? "pin_history_item" : "unpin_history_item";
+    const reply = await this.request({ command, hash });
+    if (reply.type === "error") {
+      throw new Error(reply.message);
+    }
+  }
+
+  /**
+   * Sends several commands, e.g. the initial configuration after Ready, in
+   * one line; the monitor runs them in order and answers once.
+   */
+  public sendBatch(commands: object[]): void {
+    if (this.process && this.process.stdin.writable) {
+        this.process.stdin.write(JSON.stringify(commands) + "\n");
+    } else {
+        this.outputChannel.appendLine("[WARN] Cannot send commands: Process not running or stdin not writable");
+    }
+  }
+
+  /**
+   * In "triggers_only" mode the monitor neither sends nor keeps ordinary
+   * copies, whatever capture-all says; only XML triggers come through.
+   */
+  public setMode(mode: "normal" | "triggers_only"): void {
+    if (this.process && this.process.stdin.writable) {
+        this.process.stdin.write(JSON.stringify({ command: "set_mode", mode }) + "\n");
+        this.outputChannel.appendLine(`[CMD] Sent set_mode=${mode} to clipboard-monitor`);
+    } else {
+        this.outputChannel.appendLine("[WARN] Cannot set mode: Process not running or stdin not writable");
+    }
+  }
+
+  /**
+   * Replaces the process names (e.g. "KeePassXC") whose clipboard writes the
+   * monitor ignores entirely.
+   */
+  public setExcludedApps(apps: string[]): void {
+    if (this.process && this.process.stdin.writable) {
+        this.process.stdin.write(JSON.stringify({ command: "set_excluded_apps", apps }) + "\n");
+    } else {
+        this.outputChannel.appendLine("[WARN] Cannot set excluded apps: Process not running or stdin not writable");
+    }
+  }
+
+  /**
+   * Wipes the clipboard after `ms` milliseconds unless something else is
+   * copied first, e.g. after copying a secret.
+   */
+  public clearAfter(ms: number): void {
+    if (this.process && this.process.stdin.writable) {
+        this.process.stdin.write(JSON.stringify({ command: "clear_after", ms }) + "\n");
+    } else {
+        this.outputChannel.appendLine("[WARN] Cannot schedule clipboard clear: Process not running or stdin not writable");
+    }
+  }
+
+  /**
+   * Starts or stops appending every new copy to the monitor's buffer.
+   */
+  public setAccumulating(enabled: boolean): void {
+    if (this.process && this.process.stdin.writable) {
+        const command = enabled ? "start_accumulate" : "stop_accumulate";
+        this.process.stdin.write(JSON.stringify({ command }) + "\n");
+    } else {
+        this.outputChannel.appendLine("[WARN] Cannot change accumulate mode: Process not running or stdin not writable");
+    }
+  }
+
+  /**
+   * Puts the accumulated copies, joined by `separator`, on the clipboard.
+   */
+  public flushAccumulated(separator?: string): void {
+    if (this.process && this.process.stdin.writable) {
+        this.process.stdin.write(JSON.stringify({ command: "flush_accumulated", separator }) + "\n");
+    } else {
+        this.outputChannel.appendLine("[WARN] Cannot flush accumulated copies: Process not running or stdin not writable");
+    }
+  }
+
+  /**
+   * Splits the recent entry with `hash` into chunks of at most about
+   * `maxTokens` tokens; they arrive through `onChunks`.
+   */
+  public chunkContent(hash: string, maxTokens: number): void {
+    if (this.process && this.process.stdin.writable) {
+        this.process.stdin.write(JSON.stringify({ command: "chunk_content", hash, max_tokens: maxTokens }) + "\n");
+    } else {
+        this.outputChannel.appendLine("[WARN] Cannot chunk content: Process not running or stdin not writable");
+    }
+  }
+
+  /**
+   * Asks the monitor to describe its clipboard environment; the reply is
+   * written to the output channel.
+   */
+  public diagnose(): void {
+    if (this.process && this.process.stdin.writable) {
+        this.process.stdin.write(JSON.stringify({ command: "diagnose" }) + "\n");
+    } else {
+        this.outputChannel.appendLine("[WARN] Cannot diagnose: Process not running or stdin not writable");
+    }
+  }
+
   private handleStdout = async (data: string) => {
     for (const rawLine of data.split(/\r?\n/)) {
       const line = rawLine.trim();
@@ -100,6 +343,7 @@ export class ClipboardService implements vscode.Disposable {
 
       try {
         const msg = JSON.parse(line) as ClipboardMessage;
+        this.settleRequest(msg);
         await this.processMessage(msg);
       } catch {
         this.outputChannel.appendLine(`[clipboard-monitor raw]: ${line}`);
@@ -107,6 +351,19 @@ export class ClipboardService implements vscode.Disposable {
     }
   };
 
+  /** Resolves the request() waiting for `msg`, if it carries an awaited id. */
+  private settleRequest(msg: ClipboardMessage) {
+    const waiter = msg.id ? this.pending.get(msg.id) : undefined;
+    if (waiter && msg.id) {
+      this.pending.delete(msg.id);
+      if (msg.type === "nack") {
+        waiter.reject(new Error(msg.error));
+      } else {
+        waiter.resolve(msg);
+      }
+    }
+  }
+
   private async processMessage(msg: ClipboardMessage) {
     switch (msg.type) {
       case "ready":
@@ -120,23 +377,167 @@ export class ClipboardService implements vscode.Disposable {
         }
         break;
         
+      case "clipboard_update_ref":
+        if (msg.path) {
+            try {
+                const content = await fs.promises.readFile(msg.path, "utf8");
+                this.outputChannel.appendLine(`Clipboard copied: ${msg.size} bytes, read from ${msg.path}`);
+                this._onClipboardUpdate.fire(content);
+            } finally {
+                this.process?.stdin.write(JSON.stringify({ command: "release_ref", path: msg.path }) + "\n");
+            }
+        }
+        break;
+
       case "trigger_xml":
         if (msg.xml_payloads && msg.xml_payloads.length > 0) {
             this.outputChannel.appendLine(`[TRIGGER] Detected ${msg.xml_payloads.length} XML actions.`);
             // Fire the event for the ClipboardManager to handle
-            this._onTriggerXml.fire(msg.xml_payloads);
+            this._onTriggerXml.fire({ xmlPayloads: msg.xml_payloads, ids: msg.ids });
         }
         break;
         
+      case "accumulated":
+        this.outputChannel.appendLine(
+          msg.error
+            ? `Could not place ${msg.count} accumulated copies on the clipboard: ${msg.error}`
+            : `Placed ${msg.count} accumulated copies on the clipboard`
+        );
+        break;
+
+      case "trigger_command":
+        if (msg.xml_payload && msg.id) {
+            this.outputChannel.appendLine(`[TRIGGER] Command ${(msg.index ?? 0) + 1}/${msg.total} of batch ${msg.batch_id}.`);
+            this._onTriggerXml.fire({ xmlPayloads: [msg.xml_payload], ids: [msg.id] });
+        }
+        break;
+
+      case "trigger_conflict":
+        this.outputChannel.appendLine(`[TRIGGER] Detected a conflict block (${msg.labels?.join(" vs ")}).`);
+        this._onTriggerConflict.fire({ ours: msg.ours ?? "", theirs: msg.theirs ?? "", labels: msg.labels ?? [] });
+        break;
+
+      case "trigger_suppressed":
+        this.outputChannel.appendLine(`[TRIGGER] Skipped a repeated command (${msg.reason}, ${msg.hash}).`);
+        break;
+
+      case "auto_paused":
+      case "auto_resumed":
+        this.outputChannel.appendLine(`Clipboard monitor ${msg.type === "auto_paused" ? "paused" : "resumed"} (${msg.reason})`);
+        break;
+
+      case "batch_result":
+        for (const reply of msg.replies ?? []) {
+          this.settleRequest(reply);
+          await this.processMessage(reply);
+        }
+        break;
+
+      case "chunks":
+        if (msg.error) {
+          this.outputChannel.appendLine(`Cannot chunk clipboard entry: ${msg.error}`);
+        } else if (msg.hash && msg.chunks) {
+          this._onChunks.fire({ hash: msg.hash, chunks: msg.chunks });
+        }
+        break;
+
+      case "clipboard_cleared":
+        this.outputChannel.appendLine(
+          msg.cleared ? "Clipboard cleared" : `Clipboard not cleared (${msg.reason})`
+        );
+        break;
+
+      case "content_skipped":
+        this.outputChannel.appendLine(`Clipboard content skipped (${msg.reason}: ${msg.detail})`);
+        break;
+
+      case "diagnostics":
+        this.outputChannel.appendLine(`Clipboard diagnostics: ${JSON.stringify(msg, null, 2)}`);
+        break;
+
+      case "nack":
+        this.outputChannel.appendLine(`[WARN] clipboard-monitor rejected a command: ${msg.error} | ${msg.raw_prefix}`);
+        break;
+
+      case "unsupported":
+        this.outputChannel.appendLine(`clipboard-monitor does not support "${msg.command}"; update the binary to use it`);
+        break;
+
       case "error":
         this.outputChannel.appendLine(`Monitor Error: ${msg.message}`);
+        if (msg.code === "panic") {
+          // The monitor aborts right after reporting a panic.
+          if (msg.backtrace) {
+            this.outputChannel.appendLine(msg.backtrace);
+          }
+          const choice = await vscode.window.showErrorMessage(
+            `The clipboard monitor crashed: ${msg.message}`,
+            "Restart"
+          );
+          if (choice === "Restart") {
+            this.start();
+          }
+        } else if (msg.code === "already_running") {
+          // The monitor exits rather than double every update.
+          const choice = await vscode.window.showWarningMessage(
+            "The clipboard monitor is already running in another window.",
+            "Take Over"
+          );
+          if (choice === "Take Over") {
+            this.start(true);
+          }
+        }
         break;
     }
   }
 
   /**
-   * Copies the specified files to the system clipboard.
-   * Uses the `clipboard-files` helper binary.
+   * Reads a text file through the monitor, which only allows paths inside
+   * its workspace roots, as for `<qdrant-read>`.
+   */
+  public async readFile(filePath: string, maxBytes?: number): Promise<{ content: string; truncated: boolean }> {
+    const reply = await this.request({ command: "read_file", path: filePath, max_bytes: maxBytes });
+    if (reply.error) {
+      throw new Error(reply.error);
+    }
+    return { content: reply.content ?? "", truncated: reply.truncated ?? false };
+  }
+
+  /**
+   * Writes a text file through the monitor, under the same workspace rules.
+   */
+  public async writeFile(filePath: string, content: string, mode: "create" | "overwrite" | "append" = "overwrite"): Promise<void> {
+    const reply = await this.request({ command: "write_file", path: filePath, content, mode });
+    if (reply.error) {
+      throw new Error(reply.error);
+    }
+  }
+
+  /**
+   * Puts rich content, e.g. a highlighted snippet or a table, on the
+   * clipboard as HTML, with `text` for targets that only take plain text.
+   */
+  public async setClipboardHtml(html: string, text: string): Promise<void> {
+    const reply = await this.request({ command: "set_clipboard_html", html, text });
+    if (reply.error) {
+      throw new Error(reply.error);
+    }
+  }
+
+  /**
+   * Puts a PNG, given as base64 or a `data:` URL, on the clipboard as an
+   * image, e.g. a rendered diagram.
+   */
+  public async setClipboardImage(png: string, width?: number, height?: number): Promise<void> {
+    const reply = await this.request({ command: "set_clipboard_image", data: png, width, height });
+    if (reply.error) {
+      throw new Error(reply.error);
+    }
+  }
+
+  /**
+   * Copies the specified files to the system clipboard, through the running
+   * monitor when there is one and the `clipboard-files` helper otherwise.
    */
   public async copyFilesToClipboard(filePaths: string[]): Promise<void> {
     if (!filePaths.length) return;
@@ -160,6 +561,19 @@ export class ClipboardService implements vscode.Disposable {
         return;
     }
 
+    if (this.process) {
+        const reply = await this.request({ command: "copy_files", paths: validPaths });
+        if (reply.type === "files_copied") {
+            if (reply.error) {
+                this.outputChannel.appendLine(`[ERROR] ${reply.error}`);
+                throw new Error(reply.error);
+            }
+            this.outputChannel.appendLine(`[CLIPBOARD] Success! ${reply.count} files copied.`);
+            return;
+        }
+        // An older monitor answers "unsupported"; fall back to the helper.
+    }
+
     const binPath = this.getBinaryPath("clipboard-files");
     
     if (!binPath) {
@@ -240,12 +654,18 @@ export class ClipboardService implements vscode.Disposable {
       this.process.kill();
       this.process = null;
     }
+    for (const waiter of this.pending.values()) {
+      waiter.reject(new Error("clipboard-monitor exited"));
+    }
+    this.pending.clear();
   }
 
   public dispose(): void {
     this.cleanupProcess();
     this._onTriggerXml.dispose();
+    this._onTriggerConflict.dispose();
     this._onClipboardUpdate.dispose();
+    this._onChunks.dispose();
     for (const d of this.disposables) d.dispose();
   }
 }
\ No newline at end of file
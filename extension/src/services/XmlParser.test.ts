@@ -90,6 +90,14 @@ const x = 1;
         expect(result?.multiLineApprove).toBe(true);
     });
 
+    it('should keep correlation ids supplied by the monitor', () => {
+        const actions = parser.parse(
+            ['<qdrant-search>a</qdrant-search>', '<qdrant-read path="b.ts" />'],
+            ['0cc175b9-0', '0cc175b9-1']
+        );
+        expect(actions.map(a => a.id)).toEqual(['0cc175b9-0', '0cc175b9-1']);
+    });
+
     it('should return null for invalid xml', () => {
         const rawXml = 'just some text';
         const result = parser.parseSingle(rawXml, 7);
@@ -47,7 +47,7 @@ export class XmlParser {
   /**
    * Parses a single raw XML block into an actionable object.
    */
-  parseSingle(rawXml: string, index: number): ParsedAction | null {
+  parseSingle(rawXml: string, index: number, id?: string): ParsedAction | null {
     // 1. Identify command type and extract attributes from the opening tag
     const match = rawXml.match(/<qdrant-(file|search|read)([^>]*?)>/i);
     if (!match) {
@@ -76,7 +76,7 @@ export class XmlParser {
     }
 
     const parsedAction: ParsedAction = {
-      id: `${Date.now()}-${index}`,
+      id: id ?? `${Date.now()}-${index}`,
       type,
       status: "pending",
       rawXml,
@@ -131,10 +131,11 @@ export class XmlParser {
 
   /**
    * Parses an array of raw XML strings (from the Rust sidecar) into a list of actionable objects.
+   * When the sidecar supplies correlation ids, each action keeps the id of its payload.
    */
-  parse(xmlPayloads: string[]): ParsedAction[] {
+  parse(xmlPayloads: string[], ids?: string[]): ParsedAction[] {
     return xmlPayloads
-      .map((rawXml, index) => this.parseSingle(rawXml, index))
+      .map((rawXml, index) => this.parseSingle(rawXml, index, ids?.[index]))
       .filter((action): action is ParsedAction => action !== null);
   }
 }